@@ -0,0 +1,82 @@
+use std::f64::consts::{E, PI, TAU};
+
+use crate::types::AngleMode;
+
+/// Named constants usable in expressions. They cannot be assigned to.
+pub const CONSTANTS: &[(&str, f64)] = &[("pi", PI), ("tau", TAU), ("e", E)];
+
+/// Functions of one argument usable in expressions, e.g. `sin(x)` or `sqrt 2`.
+pub const FUNCTIONS: &[&str] = &[
+    "sin", "cos", "tan", "asin", "acos", "atan", "sqrt", "abs", "ln", "log", "exp",
+];
+
+pub fn constant(name: &str) -> Option<f64> {
+    CONSTANTS
+        .iter()
+        .find(|(constant, _)| *constant == name)
+        .map(|(_, value)| *value)
+}
+
+pub fn is_function(name: &str) -> bool {
+    FUNCTIONS.contains(&name)
+}
+
+/// One-line description of the built-in function `name`, for the function menu.
+pub fn function_doc(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "sin" => "Sine of an angle",
+        "cos" => "Cosine of an angle",
+        "tan" => "Tangent of an angle",
+        "asin" => "Angle whose sine is x",
+        "acos" => "Angle whose cosine is x",
+        "atan" => "Angle whose tangent is x",
+        "sqrt" => "Square root",
+        "abs" => "Absolute value",
+        "ln" => "Natural logarithm",
+        "log" => "Base-10 logarithm",
+        "exp" => "e raised to the power x",
+        _ => return None,
+    })
+}
+
+pub fn is_builtin(name: &str) -> bool {
+    is_function(name) || constant(name).is_some()
+}
+
+/// Length of the longest built-in name `text` starts with, if any.
+pub fn builtin_prefix_len(text: &str) -> Option<usize> {
+    FUNCTIONS
+        .iter()
+        .copied()
+        .chain(CONSTANTS.iter().map(|(name, _)| *name))
+        .filter(|name| text.starts_with(name))
+        .map(str::len)
+        .max()
+}
+
+/// Applies the function `name`, reading and producing angles in `angle_mode`.
+pub fn apply_function(name: &str, arg: f64, angle_mode: AngleMode) -> Option<f64> {
+    let to_radians = |x: f64| match angle_mode {
+        AngleMode::Radians => x,
+        AngleMode::Degrees => x.to_radians(),
+    };
+    let from_radians = |x: f64| match angle_mode {
+        AngleMode::Radians => x,
+        AngleMode::Degrees => x.to_degrees(),
+    };
+
+    Some(match name {
+        "sin" => to_radians(arg).sin(),
+        "cos" => to_radians(arg).cos(),
+        "tan" => to_radians(arg).tan(),
+        "asin" => from_radians(arg.asin()),
+        "acos" => from_radians(arg.acos()),
+        "atan" => from_radians(arg.atan()),
+        "sqrt" => arg.sqrt(),
+        "abs" => arg.abs(),
+        "ln" => arg.ln(),
+        "log" => arg.log10(),
+        "exp" => arg.exp(),
+        _ => return None,
+    })
+}
@@ -1,38 +1,278 @@
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Instant,
+};
 
-use crate::types::VariableEntry;
+use crate::{
+    builtins::{constant, is_function},
+    error::CalcError,
+    evaluator::Function,
+    numeric::Numeric,
+    types::{AngleMode, Associativity, Negation, PowerRules, VariableEntry},
+};
 
 pub fn calculate(
     tokens: Vec<&str>,
     variables: &HashMap<String, VariableEntry>,
-) -> Result<f64, String> {
+) -> Result<f64, CalcError> {
+    calculate_with_angle_mode(tokens, variables, AngleMode::Radians)
+}
+
+/// Like [`calculate`], with trigonometric functions working in `angle_mode`.
+pub fn calculate_with_angle_mode(
+    tokens: Vec<&str>,
+    variables: &HashMap<String, VariableEntry>,
+    angle_mode: AngleMode,
+) -> Result<f64, CalcError> {
+    calculate_with_limits(tokens, variables, angle_mode, &EvalLimits::default())
+}
+
+/// Evaluation steps allowed by default, so a runaway expression cannot hang the caller.
+pub const MAX_EVAL_STEPS: usize = 1_000_000;
+
+/// Nesting allowed by default, well within the stack of a worker thread.
+pub const MAX_EVAL_DEPTH: usize = 256;
+
+/// Bounds on a single evaluation; exceeding any of them aborts it with an error.
+#[derive(Clone, Debug)]
+pub struct EvalLimits {
+    pub deadline: Option<Instant>,
+    pub max_steps: usize,
+    /// Deepest nesting of parentheses, signs, functions, and exponents
+    pub max_depth: usize,
+    /// Set from another thread to stop the evaluation early
+    pub cancelled: Option<Arc<AtomicBool>>,
+}
+
+impl Default for EvalLimits {
+    fn default() -> Self {
+        Self {
+            deadline: None,
+            max_steps: MAX_EVAL_STEPS,
+            max_depth: MAX_EVAL_DEPTH,
+            cancelled: None,
+        }
+    }
+}
+
+/// Like [`calculate_with_angle_mode`], aborting once `limits` are exceeded.
+pub fn calculate_with_limits(
+    tokens: Vec<&str>,
+    variables: &HashMap<String, VariableEntry>,
+    angle_mode: AngleMode,
+    limits: &EvalLimits,
+) -> Result<f64, CalcError> {
+    evaluate(
+        &tokens,
+        variables,
+        angle_mode,
+        PowerRules::default(),
+        limits,
+        &HashMap::new(),
+    )
+}
+
+/// Evaluates `tokens`, looking up functions that are not built in in `functions`.
+pub(crate) fn evaluate(
+    tokens: &[&str],
+    variables: &HashMap<String, VariableEntry>,
+    angle_mode: AngleMode,
+    power: PowerRules,
+    limits: &EvalLimits,
+    functions: &HashMap<String, Function>,
+) -> Result<f64, CalcError> {
+    let node = parse(tokens, power, limits, functions)?;
+    node.eval(angle_mode, &|name| {
+        variables
+            .get(name)
+            .map(|var| var.value)
+            .or_else(|| constant(name))
+    })
+}
+
+/// Parses `tokens` into an expression tree, grouping `^` by `power`.
+pub(crate) fn parse(
+    tokens: &[&str],
+    power: PowerRules,
+    limits: &EvalLimits,
+    functions: &HashMap<String, Function>,
+) -> Result<Node, CalcError> {
     if tokens.is_empty() {
-        return Err("Expression could not be parsed".to_string());
+        return Err(CalcError::Unparsable);
     }
 
-    let mut parser = Parser::new(&tokens, variables);
-    let value = parser.parse_expr()?;
+    let mut parser = Parser {
+        tokens,
+        pos: 0,
+        functions,
+        power,
+        limits,
+        steps: 0,
+        depth: 0,
+    };
+    let node = parser.parse_expr()?;
 
     if let Some(tok) = parser.peek() {
-        return Err(format!("Unexpected token: {}", tok));
+        return Err(CalcError::UnexpectedToken(tok.to_string()));
+    }
+
+    Ok(node)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Pow,
+}
+
+impl BinaryOp {
+    pub(crate) fn apply<N: Numeric>(self, lhs: &N, rhs: &N) -> Result<N, CalcError> {
+        match self {
+            BinaryOp::Add => lhs.add(rhs),
+            BinaryOp::Sub => lhs.sub(rhs),
+            BinaryOp::Mul => lhs.mul(rhs),
+            BinaryOp::Div => lhs.div(rhs),
+            BinaryOp::Pow => lhs.pow(rhs),
+        }
+        .ok_or(CalcError::Undefined)
+    }
+}
+
+/// A parsed expression; identifiers are resolved when it is evaluated.
+#[derive(Clone)]
+pub(crate) enum Node {
+    /// A number literal, kept as written so exact number types can parse it
+    Number(String),
+    /// A variable or named constant
+    Identifier(String),
+    Negate(Box<Node>),
+    /// `first op1 rest1 op2 rest2 …`, evaluated left to right. Kept flat so long
+    /// sums and products do not make the tree deep.
+    Chain {
+        first: Box<Node>,
+        rest: Vec<(BinaryOp, Node)>,
+    },
+    Binary(BinaryOp, Box<Node>, Box<Node>),
+    /// A built-in function, or an evaluator function when `custom` is set
+    Call {
+        name: String,
+        custom: Option<Function>,
+        args: Vec<Node>,
+    },
+}
+
+impl Node {
+    /// Evaluates the tree in the number type `N`, resolving identifiers with `lookup`.
+    pub(crate) fn eval<N: Numeric>(
+        &self,
+        angle_mode: AngleMode,
+        lookup: &dyn Fn(&str) -> Option<N>,
+    ) -> Result<N, CalcError> {
+        match self {
+            Node::Number(text) => N::parse_literal(text).ok_or(CalcError::Undefined),
+            Node::Identifier(name) => {
+                lookup(name).ok_or_else(|| CalcError::UnknownVariable(name.clone()))
+            }
+            Node::Negate(operand) => Ok(operand.eval(angle_mode, lookup)?.neg()),
+            Node::Chain { first, rest } => {
+                let mut value = first.eval(angle_mode, lookup)?;
+                for (op, operand) in rest {
+                    value = op.apply(&value, &operand.eval(angle_mode, lookup)?)?;
+                }
+                Ok(value)
+            }
+            Node::Binary(op, lhs, rhs) => op.apply(
+                &lhs.eval(angle_mode, lookup)?,
+                &rhs.eval(angle_mode, lookup)?,
+            ),
+            Node::Call { name, custom, args } => {
+                let args = args
+                    .iter()
+                    .map(|arg| arg.eval(angle_mode, lookup))
+                    .collect::<Result<Vec<N>, _>>()?;
+                apply_call(name, custom.as_ref(), args, angle_mode)
+            }
+        }
+    }
+}
+
+/// Applies the built-in function `name`, or else `custom`, to `args`. Evaluator
+/// functions compute in `f64`.
+pub(crate) fn apply_call<N: Numeric>(
+    name: &str,
+    custom: Option<&Function>,
+    args: Vec<N>,
+    angle_mode: AngleMode,
+) -> Result<N, CalcError> {
+    if is_function(name) {
+        let [arg] = <[N; 1]>::try_from(args).map_err(|_| CalcError::Unparsable)?;
+        return arg
+            .apply_function(name, angle_mode)
+            .ok_or(CalcError::Undefined);
     }
+    let custom = custom.ok_or_else(|| CalcError::UnknownFunction(name.to_string()))?;
+    let args = args
+        .iter()
+        .map(Numeric::to_f64)
+        .collect::<Option<Vec<f64>>>()
+        .ok_or(CalcError::Undefined)?;
+    N::from_f64(custom.call(&args)).ok_or(CalcError::Undefined)
+}
 
-    Ok(value)
+fn chain(first: Node, rest: Vec<(BinaryOp, Node)>) -> Node {
+    if rest.is_empty() {
+        first
+    } else {
+        Node::Chain {
+            first: Box::new(first),
+            rest,
+        }
+    }
 }
 
 struct Parser<'a> {
     tokens: &'a [&'a str],
     pos: usize,
-    variables: &'a HashMap<String, VariableEntry>,
+    functions: &'a HashMap<String, Function>,
+    power: PowerRules,
+    limits: &'a EvalLimits,
+    steps: usize,
+    depth: usize,
 }
 
 impl<'a> Parser<'a> {
-    fn new(tokens: &'a [&'a str], variables: &'a HashMap<String, VariableEntry>) -> Self {
-        Self {
-            tokens,
-            pos: 0,
-            variables,
+    /// Counts one evaluation step, failing once the limits are exceeded. The
+    /// clock and cancel flag are only checked every 1024 steps.
+    fn step(&mut self) -> Result<(), CalcError> {
+        self.steps += 1;
+        if self.steps > self.limits.max_steps {
+            return Err(CalcError::StepLimit(self.limits.max_steps));
         }
+        if self.steps % 1024 == 1 {
+            if self
+                .limits
+                .cancelled
+                .as_ref()
+                .is_some_and(|cancelled| cancelled.load(Ordering::Relaxed))
+            {
+                return Err(CalcError::Cancelled);
+            }
+            if self
+                .limits
+                .deadline
+                .is_some_and(|deadline| Instant::now() >= deadline)
+            {
+                return Err(CalcError::TimedOut);
+            }
+        }
+        Ok(())
     }
 
     fn peek(&self) -> Option<&'a str> {
@@ -57,100 +297,175 @@ impl<'a> Parser<'a> {
     }
 
     // expr := add_sub
-    fn parse_expr(&mut self) -> Result<f64, String> {
+    fn parse_expr(&mut self) -> Result<Node, CalcError> {
         self.parse_add_sub()
     }
 
     // add_sub := mul_div (("+" | "-") mul_div)*
-    fn parse_add_sub(&mut self) -> Result<f64, String> {
-        let mut lhs = self.parse_mul_div()?;
+    fn parse_add_sub(&mut self) -> Result<Node, CalcError> {
+        let first = self.parse_mul_div()?;
+        let mut rest = Vec::new();
 
         loop {
-            if self.consume("+") {
-                let rhs = self.parse_mul_div()?;
-                lhs += rhs;
+            let op = if self.consume("+") {
+                BinaryOp::Add
             } else if self.consume("-") {
-                let rhs = self.parse_mul_div()?;
-                lhs -= rhs;
+                BinaryOp::Sub
             } else {
                 break;
-            }
+            };
+            rest.push((op, self.parse_mul_div()?));
         }
 
-        Ok(lhs)
+        Ok(chain(first, rest))
     }
 
     // mul_div := unary (("*" | "/") unary)*
-    fn parse_mul_div(&mut self) -> Result<f64, String> {
-        let mut lhs = self.parse_unary()?;
+    fn parse_mul_div(&mut self) -> Result<Node, CalcError> {
+        let first = self.parse_unary()?;
+        let mut rest = Vec::new();
 
         loop {
-            if self.consume("*") {
-                let rhs = self.parse_unary()?;
-                lhs *= rhs;
+            let op = if self.consume("*") {
+                BinaryOp::Mul
             } else if self.consume("/") {
-                let rhs = self.parse_unary()?;
-                lhs /= rhs;
+                BinaryOp::Div
             } else {
                 break;
-            }
+            };
+            rest.push((op, self.parse_unary()?));
         }
 
-        Ok(lhs)
+        Ok(chain(first, rest))
     }
 
-    // unary := ("+" | "-") unary | power
-    fn parse_unary(&mut self) -> Result<f64, String> {
-        if self.consume("+") {
-            return self.parse_unary();
+    /// Enters one level of nesting, failing past the depth limit.
+    fn descend(&mut self) -> Result<(), CalcError> {
+        self.depth += 1;
+        if self.depth > self.limits.max_depth {
+            return Err(CalcError::TooDeep(self.limits.max_depth));
         }
+        Ok(())
+    }
 
-        if self.consume("-") {
-            return Ok(-self.parse_unary()?);
+    // unary := signed(power)           when negation applies to the power
+    //        | power                   when it applies to the base
+    fn parse_unary(&mut self) -> Result<Node, CalcError> {
+        match self.power.negation {
+            Negation::Power => self.parse_signed(Self::parse_power),
+            Negation::Base => self.parse_power(),
         }
+    }
 
-        self.parse_power()
+    // signed(operand) := ("+" | "-") signed(operand) | operand
+    // Every level of nesting passes through here, so this is where depth is limited.
+    fn parse_signed(
+        &mut self,
+        operand: fn(&mut Self) -> Result<Node, CalcError>,
+    ) -> Result<Node, CalcError> {
+        self.descend()?;
+        let node = if self.consume("+") {
+            self.parse_signed(operand)
+        } else if self.consume("-") {
+            self.parse_signed(operand)
+                .map(|node| Node::Negate(Box::new(node)))
+        } else {
+            operand(self)
+        };
+        self.depth -= 1;
+        node
     }
 
-    // power := primary ("^" unary)?
-    // Right-associative because exponent is parsed via unary -> power.
-    fn parse_power(&mut self) -> Result<f64, String> {
-        let base = self.parse_primary()?;
+    // power := base ("^" unary)?       right-associative, via unary -> power
+    //        | base ("^" signed(primary))*   left-associative
+    // where base is signed(primary) when negation applies to the base.
+    fn parse_power(&mut self) -> Result<Node, CalcError> {
+        let mut node = match self.power.negation {
+            Negation::Power => self.parse_primary()?,
+            Negation::Base => self.parse_signed(Self::parse_primary)?,
+        };
 
-        if self.consume("^") {
-            let exponent = self.parse_unary()?;
-            Ok(base.powf(exponent))
-        } else {
-            Ok(base)
+        match self.power.associativity {
+            Associativity::Right => {
+                if self.consume("^") {
+                    let exponent = self.parse_unary()?;
+                    node = Node::Binary(BinaryOp::Pow, Box::new(node), Box::new(exponent));
+                }
+            }
+            Associativity::Left => {
+                // Each `^` nests the tree one level deeper on the left.
+                let depth = self.depth;
+                while self.consume("^") {
+                    self.descend()?;
+                    let exponent = self.parse_signed(Self::parse_primary)?;
+                    node = Node::Binary(BinaryOp::Pow, Box::new(node), Box::new(exponent));
+                }
+                self.depth = depth;
+            }
         }
+        Ok(node)
     }
 
-    // primary := NUMBER | IDENT | "(" expr ")"
-    fn parse_primary(&mut self) -> Result<f64, String> {
+    // primary := NUMBER | IDENT | FUNCTION ("(" expr ("," expr)* ")" | unary) | "(" expr ")"
+    fn parse_primary(&mut self) -> Result<Node, CalcError> {
+        self.step()?;
         let Some(tok) = self.next() else {
-            return Err("Expression could not be parsed".to_string());
+            return Err(CalcError::Unparsable);
         };
 
         if tok == "(" {
-            let value = self.parse_expr()?;
+            let node = self.parse_expr()?;
             if !self.consume(")") {
-                return Err("Missing closing ')'".to_string());
+                return Err(CalcError::MissingClosingParen);
             }
-            return Ok(value);
+            return Ok(node);
         }
 
         if tok == ")" {
-            return Err("Unexpected token: )".to_string());
+            return Err(CalcError::UnexpectedToken(")".to_string()));
         }
 
-        if let Ok(num) = tok.parse::<f64>() {
-            return Ok(num);
+        if tok.starts_with('#') {
+            return Err(CalcError::HistoryUnavailable(tok.to_string()));
         }
 
-        if let Some(var) = self.variables.get(tok) {
-            return Ok(var.value);
+        if tok.parse::<f64>().is_ok() {
+            return Ok(Node::Number(tok.to_string()));
+        }
+
+        let custom = self.functions.get(tok);
+        if is_function(tok) || custom.is_some() {
+            let args = if self.consume("(") {
+                let mut args = vec![self.parse_expr()?];
+                while self.consume(",") {
+                    args.push(self.parse_expr()?);
+                }
+                if !self.consume(")") {
+                    return Err(CalcError::MissingClosingParen);
+                }
+                args
+            } else {
+                vec![self.parse_unary()?]
+            };
+            // Built-ins win over evaluator functions of the same name.
+            let expected = match custom {
+                Some(custom) if !is_function(tok) => custom.arity,
+                _ => 1,
+            };
+            if args.len() != expected {
+                return Err(CalcError::ArgumentCount {
+                    name: tok.to_string(),
+                    expected,
+                    found: args.len(),
+                });
+            }
+            return Ok(Node::Call {
+                name: tok.to_string(),
+                custom: custom.cloned(),
+                args,
+            });
         }
 
-        Err(format!("Unknown variable: {}", tok))
+        Ok(Node::Identifier(tok.to_string()))
     }
 }
@@ -0,0 +1,434 @@
+use crate::{
+    io::is_valid_state_name,
+    plot::PlotDefinition,
+    theme::Theme,
+    types::{AngleMode, Associativity, ClearTarget, LayoutPreset, Negation, ValueFormat},
+    variables::is_valid_name,
+};
+
+/// Commands entered in the input area, prefixed with `:`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    /// Attach (or with `None`, remove) a label on the history entry with the given
+    /// 1-based number, as shown in the History pane.
+    Note { entry: usize, text: Option<String> },
+    /// Display a variable in the given format, or with `None` as a plain number
+    Format {
+        variable: String,
+        format: Option<ValueFormat>,
+    },
+    /// Label a variable with a unit, or with `None` remove it
+    Unit {
+        variable: String,
+        unit: Option<String>,
+    },
+    /// Switch between radians and degrees
+    Angle(AngleMode),
+    /// Whether a leading minus negates the power or the base in `-2^2`
+    Negation(Negation),
+    /// Whether `2^3^2` groups to the right or to the left
+    Associativity(Associativity),
+    /// Switch Reverse Polish input on or off
+    Rpn(bool),
+    /// Start, reset, or end tape mode
+    Tape(TapeAction),
+    /// Whether re-submitted inputs replace their earlier History entry
+    DedupHistory(bool),
+    /// Number of decimals shown for results; `None` restores the default
+    Precision(Option<usize>),
+    /// Longest an evaluation may run, in milliseconds; `None` restores the default
+    Timeout(Option<u64>),
+    /// Switch to the named theme, or to the next built-in one
+    Theme(Option<Theme>),
+    /// Switch to a different pane arrangement
+    Layout(LayoutPreset),
+    /// Plot a polar or parametric curve
+    Plot(PlotDefinition),
+    /// Plot the values of an imported list against their position
+    PlotList(String),
+    /// Keep the current plot under a name
+    SavePlot(String),
+    /// Display the saved plot with the given name, or with `None` pick one from a list
+    ShowPlot(Option<String>),
+    /// Write the current plot to a `.csv`, `.svg`, or `.png` file
+    ExportPlot(String),
+    /// Open, close, or switch between workspace tabs
+    Tab(TabAction),
+    /// Switch to the named session, or with `None` pick one from a list
+    Session(Option<String>),
+    /// Save the full state under a name, to come back to with `SnapshotRestore`
+    SnapshotSave(String),
+    /// Replace the current state with a saved snapshot
+    SnapshotRestore(String),
+    /// Wipe history, variables, or plots, after confirmation
+    Clear(ClearTarget),
+    /// Insert the named snippet, or with `None` pick one from a list
+    Snippet(Option<String>),
+    /// Summarize the results in History, or with a name an imported list: count,
+    /// sum, mean, extremes, and spread
+    Stats(Option<String>),
+    /// Read a 1-based column of a CSV file into a list
+    ImportCsv {
+        path: String,
+        column: usize,
+        name: String,
+    },
+    /// Remove repeated and old History entries and redundant plot samples
+    Compact,
+    /// Go back to the state before the last clear, variable overwrite, or
+    /// snapshot restore
+    Undo,
+    /// Save and exit the application
+    Quit,
+    /// Bind keys at runtime, given as `<context> <keys> <action>`
+    Map(String),
+    /// Append the mappings made with `Map` to the keymap file
+    MapSave,
+    /// Show (or hide, returning to the plot) a bar chart of the variables in the plot pane
+    VariableChart(bool),
+    /// Replace the input with the simplified form of an expression
+    Simplify(String),
+    /// Show how an expression is grouped, with every operation in parentheses
+    Parse(String),
+    /// Re-evaluate the stored expressions of all variables, or of one and those
+    /// depending on it
+    Recompute(Option<String>),
+}
+
+/// What `:tape` does with the running total.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TapeAction {
+    /// Turn tape mode on, continuing from the current total if it is already on
+    Start,
+    /// Start over from 0
+    Reset,
+    /// Turn tape mode off, discarding the total
+    Stop,
+}
+
+/// What `:tab` does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TabAction {
+    /// Open an empty workspace after the current one and switch to it
+    New,
+    /// Discard the current workspace
+    Close,
+    /// Switch to the workspace with the given 1-based number
+    Switch(usize),
+}
+
+/// Parses `input` as a command.
+///
+/// Returns `None` when the input is not a command at all, so the caller can
+/// evaluate it as an expression instead. `plot …` and `chart …` also work
+/// without the `:`.
+pub fn parse_command(input: &str) -> Option<Result<Command, String>> {
+    let input = input.trim();
+    let body = input.strip_prefix(':').or_else(|| {
+        ["plot ", "chart "]
+            .iter()
+            .any(|prefix| input.starts_with(prefix))
+            .then_some(input)
+    })?;
+    let (name, args) = match body.split_once(char::is_whitespace) {
+        Some((name, args)) => (name, args.trim()),
+        None => (body, ""),
+    };
+
+    Some(match name {
+        "q" | "quit" => Ok(Command::Quit),
+        "compact" => Ok(Command::Compact),
+        "undo" => Ok(Command::Undo),
+        "stats" => Ok(Command::Stats(
+            Some(args.to_string()).filter(|name| !name.is_empty()),
+        )),
+        "import" => parse_import(args),
+        "snippet" => Ok(Command::Snippet(
+            Some(args.to_string()).filter(|name| !name.is_empty()),
+        )),
+        "note" => parse_note(args),
+        "clear" => match args {
+            "history" => Ok(Command::Clear(ClearTarget::History)),
+            "vars" | "variables" => Ok(Command::Clear(ClearTarget::Variables)),
+            "plots" => Ok(Command::Clear(ClearTarget::Plots)),
+            _ => Err("Usage: :clear history|vars|plots".to_string()),
+        },
+        "angle" => parse_angle(args),
+        "negation" => match args {
+            "power" => Ok(Command::Negation(Negation::Power)),
+            "base" => Ok(Command::Negation(Negation::Base)),
+            _ => Err("Usage: :negation power|base".to_string()),
+        },
+        "associativity" => match args {
+            "right" => Ok(Command::Associativity(Associativity::Right)),
+            "left" => Ok(Command::Associativity(Associativity::Left)),
+            _ => Err("Usage: :associativity right|left".to_string()),
+        },
+        "format" => parse_format(args),
+        "unit" => match args.split_once(char::is_whitespace) {
+            Some((variable, unit)) => Ok(Command::Unit {
+                variable: variable.to_string(),
+                unit: Some(unit.trim().to_string()).filter(|unit| unit != "none"),
+            }),
+            None => Err("Usage: :unit <variable> <unit>|none".to_string()),
+        },
+        "tape" => match args {
+            "on" => Ok(Command::Tape(TapeAction::Start)),
+            "reset" => Ok(Command::Tape(TapeAction::Reset)),
+            "off" => Ok(Command::Tape(TapeAction::Stop)),
+            _ => Err("Usage: :tape on|reset|off".to_string()),
+        },
+        "tab" => match args {
+            "new" => Ok(Command::Tab(TabAction::New)),
+            "close" => Ok(Command::Tab(TabAction::Close)),
+            number => match number.parse::<usize>() {
+                Ok(number) if number > 0 => Ok(Command::Tab(TabAction::Switch(number))),
+                _ => Err("Usage: :tab new|close|<number>".to_string()),
+            },
+        },
+        "rpn" => match args {
+            "on" => Ok(Command::Rpn(true)),
+            "off" => Ok(Command::Rpn(false)),
+            _ => Err("Usage: :rpn on|off".to_string()),
+        },
+        "dedup" => match args {
+            "on" => Ok(Command::DedupHistory(true)),
+            "off" => Ok(Command::DedupHistory(false)),
+            _ => Err("Usage: :dedup on|off".to_string()),
+        },
+        "precision" => parse_precision(args),
+        "timeout" => parse_timeout(args),
+        "layout" => parse_layout(args),
+        "theme" => parse_theme(args),
+        "plot" => parse_plot(args),
+        "session" => parse_session(args),
+        "snapshot" => parse_snapshot(args),
+        "map" => match args {
+            "" => Err("Usage: :map <context> <keys> <action> | :map save".to_string()),
+            "save" => Ok(Command::MapSave),
+            mapping => Ok(Command::Map(mapping.to_string())),
+        },
+        "chart" => match args {
+            "vars" | "variables" => Ok(Command::VariableChart(true)),
+            "off" => Ok(Command::VariableChart(false)),
+            _ => Err("Usage: :chart vars|off".to_string()),
+        },
+        "simplify" => match args {
+            "" => Err("Usage: :simplify <expression>".to_string()),
+            expression => Ok(Command::Simplify(expression.to_string())),
+        },
+        "parse" => match args {
+            "" => Err("Usage: :parse <expression>".to_string()),
+            expression => Ok(Command::Parse(expression.to_string())),
+        },
+        "recompute" => match args {
+            "" => Ok(Command::Recompute(None)),
+            variable => Ok(Command::Recompute(Some(variable.to_string()))),
+        },
+        "" => Err("Missing command name after ':'".to_string()),
+        other => Err(format!("Unknown command: {}", other)),
+    })
+}
+
+fn parse_note(args: &str) -> Result<Command, String> {
+    let (entry, text) = match args.split_once(char::is_whitespace) {
+        Some((entry, text)) => (entry, text.trim()),
+        None => (args, ""),
+    };
+
+    let entry = entry
+        .trim_start_matches('#')
+        .parse::<usize>()
+        .map_err(|_| "Usage: :note <entry> <label>".to_string())?;
+
+    Ok(Command::Note {
+        entry,
+        text: (!text.is_empty()).then(|| text.to_string()),
+    })
+}
+
+fn parse_angle(args: &str) -> Result<Command, String> {
+    match args {
+        "deg" | "degrees" => Ok(Command::Angle(AngleMode::Degrees)),
+        "rad" | "radians" => Ok(Command::Angle(AngleMode::Radians)),
+        _ => Err("Usage: :angle deg|rad".to_string()),
+    }
+}
+
+fn parse_format(args: &str) -> Result<Command, String> {
+    let usage = || "Usage: :format <variable> currency|percent|sci|plain".to_string();
+    let (variable, name) = args.split_once(char::is_whitespace).ok_or_else(usage)?;
+    let format = match name.trim() {
+        "plain" | "none" => None,
+        name => Some(ValueFormat::named(name).ok_or_else(usage)?),
+    };
+    Ok(Command::Format {
+        variable: variable.to_string(),
+        format,
+    })
+}
+
+fn parse_precision(args: &str) -> Result<Command, String> {
+    if args == "auto" {
+        return Ok(Command::Precision(None));
+    }
+    args.parse::<usize>()
+        .map(|decimals| Command::Precision(Some(decimals)))
+        .map_err(|_| "Usage: :precision <decimals>|auto".to_string())
+}
+
+fn parse_timeout(args: &str) -> Result<Command, String> {
+    if args == "default" {
+        return Ok(Command::Timeout(None));
+    }
+    args.parse::<f64>()
+        .ok()
+        .filter(|seconds| *seconds > 0.0 && seconds.is_finite())
+        .map(|seconds| Command::Timeout(Some((seconds * 1000.0).round() as u64)))
+        .ok_or_else(|| "Usage: :timeout <seconds>|default".to_string())
+}
+
+fn parse_layout(args: &str) -> Result<Command, String> {
+    LayoutPreset::ALL
+        .into_iter()
+        .find(|preset| preset.name() == args)
+        .map(Command::Layout)
+        .ok_or_else(|| "Usage: :layout classic|plot|minimal".to_string())
+}
+
+fn parse_theme(args: &str) -> Result<Command, String> {
+    if args.is_empty() {
+        return Ok(Command::Theme(None));
+    }
+    Theme::named(args)
+        .map(|theme| Command::Theme(Some(theme)))
+        .ok_or_else(|| {
+            let names: Vec<&str> = Theme::BUILTIN.iter().map(|theme| theme.name).collect();
+            format!("Unknown theme: {} (available: {})", args, names.join(", "))
+        })
+}
+
+const PLOT_USAGE: &str = "Usage: :plot polar r=<expr> | :plot param (<x>, <y>) [for t in <from>..<to>] | :plot list <name> | :plot export <path> | :plot save|show <name>";
+
+/// `csv <path> [column=<n>] as <name>`, the column defaulting to the first.
+fn parse_import(args: &str) -> Result<Command, String> {
+    const USAGE: &str = "Usage: :import csv <path> [column=<n>] as <name>";
+    let (source, name) = args.rsplit_once(" as ").ok_or(USAGE)?;
+    let name = name.trim();
+    if !is_valid_name(name) {
+        return Err(format!("Invalid list name: {}", name));
+    }
+    let path = source.trim().strip_prefix("csv ").ok_or(USAGE)?.trim();
+    let (path, column) = match path.rsplit_once(" column=") {
+        Some((path, column)) => (
+            path.trim(),
+            column
+                .trim()
+                .parse::<usize>()
+                .ok()
+                .filter(|column| *column > 0)
+                .ok_or_else(|| format!("Invalid column: {}", column.trim()))?,
+        ),
+        None => (path, 1),
+    };
+    if path.is_empty() {
+        return Err(USAGE.to_string());
+    }
+    Ok(Command::ImportCsv {
+        path: path.to_string(),
+        column,
+        name: name.to_string(),
+    })
+}
+
+fn parse_plot(args: &str) -> Result<Command, String> {
+    let (kind, rest) = args.split_once(char::is_whitespace).unwrap_or((args, ""));
+    let rest = rest.trim();
+    let definition = match kind {
+        "polar" if !rest.is_empty() => PlotDefinition::Polar {
+            expression: rest.to_string(),
+        },
+        "param" => parse_parametric(rest).ok_or_else(|| PLOT_USAGE.to_string())?,
+        "list" if !rest.is_empty() => return Ok(Command::PlotList(rest.to_string())),
+        "export" if !rest.is_empty() => return Ok(Command::ExportPlot(rest.to_string())),
+        "save" if !rest.is_empty() => return Ok(Command::SavePlot(rest.to_string())),
+        "show" if !rest.is_empty() => return Ok(Command::ShowPlot(Some(rest.to_string()))),
+        "" => return Ok(Command::ShowPlot(None)),
+        _ => return Err(PLOT_USAGE.to_string()),
+    };
+    Ok(Command::Plot(definition))
+}
+
+fn parse_parametric(args: &str) -> Option<PlotDefinition> {
+    let (curve, range) = match args.split_once(" for ") {
+        Some((curve, range)) => (curve.trim(), Some(range.trim())),
+        None => (args, None),
+    };
+    let (x, y) = split_top_level_comma(curve.strip_prefix('(')?.strip_suffix(')')?)?;
+    let (from, to) = match range {
+        Some(range) => range.strip_prefix("t in ")?.split_once("..")?,
+        None => ("0", "2pi"),
+    };
+
+    let non_empty = |text: &str| {
+        let text = text.trim();
+        (!text.is_empty()).then(|| text.to_string())
+    };
+    Some(PlotDefinition::Parametric {
+        x: non_empty(x)?,
+        y: non_empty(y)?,
+        from: non_empty(from)?,
+        to: non_empty(to)?,
+    })
+}
+
+/// Splits `text` at its single comma outside of parentheses.
+fn split_top_level_comma(text: &str) -> Option<(&str, &str)> {
+    let mut depth = 0i32;
+    let mut split_at = None;
+    for (idx, ch) in text.char_indices() {
+        match ch {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                if split_at.is_some() {
+                    return None;
+                }
+                split_at = Some(idx);
+            }
+            _ => {}
+        }
+    }
+    let idx = split_at?;
+    Some((&text[..idx], &text[idx + 1..]))
+}
+
+fn parse_session(args: &str) -> Result<Command, String> {
+    if args.is_empty() {
+        return Ok(Command::Session(None));
+    }
+    if !is_valid_state_name(args) {
+        return Err(format!(
+            "Invalid session name: {} (use letters, digits, '-' and '_')",
+            args
+        ));
+    }
+    Ok(Command::Session(Some(args.to_string())))
+}
+
+fn parse_snapshot(args: &str) -> Result<Command, String> {
+    let usage = || "Usage: :snapshot save|restore <name>".to_string();
+    let (action, name) = args.split_once(char::is_whitespace).ok_or_else(usage)?;
+    let name = name.trim();
+    if !is_valid_state_name(name) {
+        return Err(format!(
+            "Invalid snapshot name: {} (use letters, digits, '-' and '_')",
+            name
+        ));
+    }
+    match action {
+        "save" => Ok(Command::SnapshotSave(name.to_string())),
+        "restore" => Ok(Command::SnapshotRestore(name.to_string())),
+        _ => Err(usage()),
+    }
+}
@@ -0,0 +1,44 @@
+use std::collections::HashMap;
+
+use crate::{
+    calculate::Node, error::CalcError, evaluator::round, numeric::Numeric, types::AngleMode,
+};
+
+/// An expression parsed once by [`Evaluator::compile`](crate::evaluator::Evaluator::compile)
+/// and evaluated many times, e.g. once per plot sample.
+#[derive(Clone)]
+pub struct CompiledExpr {
+    pub(crate) root: Node,
+    /// Values of the evaluator's variables when the expression was compiled
+    pub(crate) variables: HashMap<String, f64>,
+    pub(crate) angle_mode: AngleMode,
+    pub(crate) precision: Option<usize>,
+}
+
+impl CompiledExpr {
+    pub fn eval(&self) -> Result<f64, CalcError> {
+        self.eval_with(&[])
+    }
+
+    /// Evaluates with `bindings` taking precedence over the compiled-in variables.
+    pub fn eval_with(&self, bindings: &[(&str, f64)]) -> Result<f64, CalcError> {
+        let value = self.eval_as(bindings)?;
+        Ok(match self.precision {
+            Some(decimals) => round(value, decimals),
+            None => value,
+        })
+    }
+
+    /// Like [`eval_with`](Self::eval_with), computing in the number type `N`.
+    /// Precision rounding only applies to `f64` results.
+    pub fn eval_as<N: Numeric>(&self, bindings: &[(&str, N)]) -> Result<N, CalcError> {
+        self.root.eval(self.angle_mode, &|name| {
+            bindings
+                .iter()
+                .find(|(bound, _)| *bound == name)
+                .map(|(_, value)| value.clone())
+                .or_else(|| N::from_f64(*self.variables.get(name)?))
+                .or_else(|| N::constant(name))
+        })
+    }
+}
@@ -0,0 +1,78 @@
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Inline completion of the identifier before the cursor, cycled with Tab and
+/// Shift+Tab.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Completion {
+    /// Position (in characters) where the completed identifier starts
+    start: usize,
+    /// Matching names, sorted
+    candidates: Vec<String>,
+    /// Candidate currently inserted, `None` before the first cycle
+    index: Option<usize>,
+    /// Input and cursor right after the last completion; anything else starts over
+    expected: Option<(String, usize)>,
+}
+
+impl Completion {
+    /// Starts completing the identifier ending at `cursor` with the `names` it is a
+    /// prefix of. Returns `None` when there is no identifier or nothing matches.
+    pub fn new<'a>(
+        input: &str,
+        cursor: usize,
+        names: impl Iterator<Item = &'a str>,
+    ) -> Option<Self> {
+        let before: Vec<&str> = input.graphemes(true).take(cursor).collect();
+        let prefix_len = before
+            .iter()
+            .rev()
+            .take_while(|g| g.chars().all(|ch| ch.is_alphabetic() || ch == '_'))
+            .count();
+        if prefix_len == 0 {
+            return None;
+        }
+        let prefix = before[before.len() - prefix_len..].concat();
+
+        let mut candidates: Vec<String> = names
+            .filter(|name| name.starts_with(&prefix))
+            .map(String::from)
+            .collect();
+        candidates.sort();
+        candidates.dedup();
+        (!candidates.is_empty()).then_some(Self {
+            start: cursor - prefix_len,
+            candidates,
+            index: None,
+            expected: None,
+        })
+    }
+
+    pub fn start(&self) -> usize {
+        self.start
+    }
+
+    /// Whether `input` and `cursor` are still what the last completion left, so
+    /// Tab keeps cycling instead of starting over.
+    pub fn is_current(&self, input: &str, cursor: usize) -> bool {
+        self.expected
+            .as_ref()
+            .is_some_and(|(expected, at)| expected == input && *at == cursor)
+    }
+
+    pub fn set_expected(&mut self, input: String, cursor: usize) {
+        self.expected = Some((input, cursor));
+    }
+
+    /// Moves to the next (or previous) candidate, wrapping around, and returns it.
+    pub fn cycle(&mut self, forward: bool) -> &str {
+        let len = self.candidates.len();
+        let index = match (self.index, forward) {
+            (None, true) => 0,
+            (None, false) => len - 1,
+            (Some(index), true) => (index + 1) % len,
+            (Some(index), false) => (index + len - 1) % len,
+        };
+        self.index = Some(index);
+        &self.candidates[index]
+    }
+}
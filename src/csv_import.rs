@@ -0,0 +1,71 @@
+use std::mem;
+
+/// Reads the values of the 1-based `column` of CSV `data`.
+///
+/// A first row that is not numeric there is taken as a header and skipped, as are
+/// empty cells; any other cell that is not a number is an error.
+pub fn read_csv_column(data: &str, column: usize) -> Result<Vec<f64>, String> {
+    let index = column
+        .checked_sub(1)
+        .ok_or_else(|| "Columns are numbered from 1".to_string())?;
+    let mut values = Vec::new();
+    for (record_idx, (line, cells)) in records(data).into_iter().enumerate() {
+        if let [cell] = cells.as_slice()
+            && cell.trim().is_empty()
+        {
+            continue;
+        }
+        let cell = cells
+            .get(index)
+            .ok_or_else(|| format!("Line {} has no column {}", line, column))?
+            .trim();
+        if cell.is_empty() {
+            continue;
+        }
+        match cell.parse::<f64>() {
+            Ok(value) => values.push(value),
+            Err(_) if record_idx == 0 => {}
+            Err(_) => {
+                return Err(format!("Line {}: '{}' is not a number", line, cell));
+            }
+        }
+    }
+    Ok(values)
+}
+
+/// Splits CSV `data` into rows of cells, each with the line it starts on. Quoted
+/// cells may hold commas and line breaks, and `""` for a quote.
+fn records(data: &str) -> Vec<(usize, Vec<String>)> {
+    let mut records = Vec::new();
+    let mut cells = Vec::new();
+    let mut cell = String::new();
+    let (mut line, mut start) = (1, 1);
+    let mut quoted = false;
+    let mut chars = data.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                cell.push('"');
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => cells.push(mem::take(&mut cell)),
+            '\n' => {
+                line += 1;
+                if quoted {
+                    cell.push(c);
+                } else {
+                    cells.push(mem::take(&mut cell));
+                    records.push((start, mem::take(&mut cells)));
+                    start = line;
+                }
+            }
+            _ => cell.push(c),
+        }
+    }
+    if !cell.is_empty() || !cells.is_empty() {
+        cells.push(cell);
+        records.push((start, cells));
+    }
+    records
+}
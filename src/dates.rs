@@ -0,0 +1,30 @@
+use time::{Date, Duration, OffsetDateTime, UtcOffset};
+
+/// Current Unix time in seconds, as stored on history entries.
+pub fn now() -> i64 {
+    OffsetDateTime::now_utc().unix_timestamp()
+}
+
+/// The local UTC offset, falling back to UTC when it cannot be determined safely
+/// (e.g. once other threads are running).
+pub fn local_offset() -> UtcOffset {
+    UtcOffset::current_local_offset().unwrap_or(UtcOffset::UTC)
+}
+
+/// Calendar day of `timestamp` at `offset`.
+pub fn local_date(timestamp: i64, offset: UtcOffset) -> Option<Date> {
+    OffsetDateTime::from_unix_timestamp(timestamp)
+        .ok()
+        .map(|datetime| datetime.to_offset(offset).date())
+}
+
+/// "Today", "Yesterday", or the ISO date, e.g. "2024-05-01".
+pub fn day_label(day: Date, today: Date) -> String {
+    if day == today {
+        "Today".to_string()
+    } else if day == today - Duration::days(1) {
+        "Yesterday".to_string()
+    } else {
+        day.to_string()
+    }
+}
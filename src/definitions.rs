@@ -0,0 +1,76 @@
+use std::fmt;
+
+use crate::{
+    builtins::is_builtin,
+    error::CalcError,
+    tokenize::{split_comment, tokenize},
+    variables::is_valid_name,
+};
+
+/// A function defined with `f(x) = x^2+1`.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct FunctionDefinition {
+    pub name: String,
+    pub params: Vec<String>,
+    /// The expression after `=`, as typed
+    pub body: String,
+}
+
+/// `f(x) = x^2+1`
+impl fmt::Display for FunctionDefinition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}({}) = {}",
+            self.name,
+            self.params.join(", "),
+            self.body
+        )
+    }
+}
+
+/// Parses `input` as a function definition, or returns `None` when its left side
+/// is not shaped like `f(x)`.
+pub fn parse_definition(input: &str) -> Option<Result<FunctionDefinition, CalcError>> {
+    let (target, body) = input.split_once('=')?;
+    let target = tokenize(target);
+    let (name, params) = definition_shape(&target)?;
+    if let Some(reserved) = std::iter::once(name)
+        .chain(params.iter().map(String::as_str))
+        .find(|name| is_builtin(name))
+    {
+        return Some(Err(CalcError::ReservedName(reserved.to_string())));
+    }
+    let body = split_comment(body).0.trim();
+    if body.is_empty() {
+        return Some(Err(CalcError::Unparsable));
+    }
+    for (idx, param) in params.iter().enumerate() {
+        if params[..idx].contains(param) {
+            return Some(Err(CalcError::DuplicateParameter(param.clone())));
+        }
+    }
+    Some(Ok(FunctionDefinition {
+        name: name.to_string(),
+        params,
+        body: body.to_string(),
+    }))
+}
+
+/// The name and parameters of a left side shaped like `f(x)`, which tokenizes as
+/// `f * ( x )` unless `f` is already a function; `g(x, y)` comes in as
+/// `g * ( x , y )`.
+pub(crate) fn definition_shape<'a>(target: &[&'a str]) -> Option<(&'a str, Vec<String>)> {
+    let (name, rest) = target.split_first()?;
+    let rest = rest.strip_prefix(&["*"]).unwrap_or(rest);
+    let ["(", params @ .., ")"] = rest else {
+        return None;
+    };
+    let params: Vec<String> = params
+        .iter()
+        .filter(|token| !matches!(**token, "*" | ","))
+        .map(|param| param.to_string())
+        .collect();
+    (is_valid_name(name) && !params.is_empty() && params.iter().all(|p| is_valid_name(p)))
+        .then_some((name, params))
+}
@@ -0,0 +1,268 @@
+use std::fmt;
+
+use serde_json::{Map, Value};
+
+use crate::{
+    error::{CalcError, StateError},
+    evaluator::Evaluator,
+    inspect::inspect_unknown_variables,
+    io::StateFormat,
+    tokenize::Lexer,
+    types::{AppState, History, VariableEntry},
+    variables::parse_variables,
+};
+
+/// Top-level keys of [`AppState`]; others were written by a different version.
+const STATE_FIELDS: &[&str] = &[
+    "history",
+    "variables",
+    "plot_data",
+    "layout",
+    "plot_style",
+    "plot",
+    "plot_range",
+    "plots",
+    "settings",
+    "functions",
+    "tape_total",
+    "lists",
+    "workspaces",
+    "workspace",
+    "workspace_id",
+];
+
+/// Something `rcalc doctor` found wrong with a state file.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Problem {
+    /// A key that is not part of the state schema, dropped on the next save
+    UnknownField(String),
+    /// A History entry that does not match the schema, by 1-based number
+    InvalidHistoryEntry { number: usize, error: String },
+    /// A variable that does not match the schema
+    InvalidVariable { name: String, error: String },
+    /// A stored value that is NaN or infinite, or was saved as `null`
+    NonFiniteValue(String),
+    /// A variable whose expression uses variables that no longer exist
+    DanglingReference {
+        variable: String,
+        missing: Vec<String>,
+    },
+    /// A variable whose expression fails to evaluate
+    NotReevaluable { variable: String, error: String },
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Problem::UnknownField(key) => write!(f, "unknown field '{}'", key),
+            Problem::InvalidHistoryEntry { number, error } => {
+                write!(f, "History entry {} is invalid: {}", number, error)
+            }
+            Problem::InvalidVariable { name, error } => {
+                write!(f, "variable {} is invalid: {}", name, error)
+            }
+            Problem::NonFiniteValue(name) => write!(f, "variable {} has no finite value", name),
+            Problem::DanglingReference { variable, missing } => write!(
+                f,
+                "variable {} refers to undefined {}",
+                variable,
+                missing.join(", ")
+            ),
+            Problem::NotReevaluable { variable, error } => {
+                write!(f, "variable {} cannot be re-evaluated: {}", variable, error)
+            }
+        }
+    }
+}
+
+/// A state file read as far as possible, with what is wrong with it.
+#[derive(Debug, Clone)]
+pub struct Checkup {
+    /// The readable parts; invalid entries and unknown fields are left out
+    pub state: AppState,
+    pub problems: Vec<Problem>,
+}
+
+/// Reads a state file entry by entry, so one invalid variable or History entry
+/// does not make the whole file unreadable, and checks its variables.
+///
+/// Fails only when the file is not valid JSON or TOML, or its settings, layout,
+/// or plot do not match the schema.
+pub fn check_state(data: &str, format: StateFormat) -> Result<Checkup, StateError> {
+    let Value::Object(mut fields) = format.deserialize(data)? else {
+        // Let the schema report what is wrong.
+        let state: AppState = format.deserialize(data)?;
+        return Ok(Checkup {
+            state,
+            problems: Vec::new(),
+        });
+    };
+
+    let mut problems = Vec::new();
+    let mut unknown: Vec<String> = fields
+        .keys()
+        .filter(|key| !STATE_FIELDS.contains(&key.as_str()))
+        .cloned()
+        .collect();
+    unknown.sort();
+    for key in unknown {
+        fields.remove(&key);
+        problems.push(Problem::UnknownField(key));
+    }
+
+    let history = match fields.insert("history".to_string(), Value::Array(Vec::new())) {
+        Some(Value::Array(entries)) => entries,
+        _ => Vec::new(),
+    };
+    let variables = match fields.insert("variables".to_string(), Value::Object(Map::new())) {
+        Some(Value::Object(variables)) => variables,
+        _ => Map::new(),
+    };
+    let mut state: AppState = serde_json::from_value(Value::Object(fields))?;
+
+    for (index, entry) in history.into_iter().enumerate() {
+        match serde_json::from_value::<History>(entry) {
+            Ok(entry) => state.history.push(entry),
+            Err(err) => problems.push(Problem::InvalidHistoryEntry {
+                number: index + 1,
+                error: err.to_string(),
+            }),
+        }
+    }
+
+    for (name, mut entry) in variables {
+        // JSON has no NaN, so NaN values are written as `null`.
+        let was_null = entry.get("value").is_some_and(Value::is_null);
+        if was_null {
+            entry["value"] = Value::from(0.0);
+        }
+        match serde_json::from_value::<VariableEntry>(entry) {
+            Ok(mut entry) => {
+                if was_null {
+                    entry.value = f64::NAN;
+                }
+                state.variables.insert(name, entry);
+            }
+            Err(err) => problems.push(Problem::InvalidVariable {
+                name,
+                error: err.to_string(),
+            }),
+        }
+    }
+
+    problems.extend(check_variables(&state));
+    Ok(Checkup { state, problems })
+}
+
+/// Non-finite values, and expressions that reference undefined variables or no
+/// longer evaluate, sorted by variable name.
+pub fn check_variables(state: &AppState) -> Vec<Problem> {
+    let mut names: Vec<&String> = state.variables.keys().collect();
+    names.sort();
+
+    let mut evaluator = state_evaluator(state);
+    let functions: Vec<&str> = state.functions.iter().map(|f| f.name.as_str()).collect();
+    let mut problems = Vec::new();
+    for name in names {
+        let entry = &state.variables[name];
+        if !entry.value.is_finite() {
+            problems.push(Problem::NonFiniteValue(name.clone()));
+        }
+        let tokens = match value_tokens(&entry.expression, &functions) {
+            Ok(tokens) => tokens,
+            Err(err) => {
+                problems.push(Problem::NotReevaluable {
+                    variable: name.clone(),
+                    error: err.to_string(),
+                });
+                continue;
+            }
+        };
+        let mut missing = inspect_unknown_variables(&tokens, &state.variables);
+        missing.retain(|name| !functions.contains(&name.as_str()));
+        if !missing.is_empty() {
+            problems.push(Problem::DanglingReference {
+                variable: name.clone(),
+                missing,
+            });
+        } else if let Err(err) = evaluator.eval(&entry.expression) {
+            problems.push(Problem::NotReevaluable {
+                variable: name.clone(),
+                error: err.to_string(),
+            });
+        }
+    }
+    problems
+}
+
+/// An evaluator with the state's settings, variables, and functions.
+fn state_evaluator(state: &AppState) -> Evaluator {
+    let mut evaluator = Evaluator::new()
+        .with_angle_mode(state.settings.angle_mode)
+        .with_power_rules(state.settings.power)
+        .with_variables(state.variables.clone());
+    // Definitions that no longer register show up as undefined names.
+    let _ = evaluator.define_functions(&state.functions);
+    evaluator
+}
+
+/// Tokens of the value a variable is computed from. The TUI stores the whole
+/// assignment, such as `b=a*3`; the library only the right side.
+fn value_tokens<'a>(expression: &'a str, functions: &[&'a str]) -> Result<Vec<&'a str>, CalcError> {
+    let tokens: Vec<&str> = Lexer::new(expression)
+        .with_functions(functions.iter().copied())
+        .map(|token| token.text)
+        .collect();
+    if tokens.contains(&"=") {
+        Ok(parse_variables(tokens)?.tokens)
+    } else {
+        Ok(tokens)
+    }
+}
+
+impl Checkup {
+    /// The state with every problem repaired: non-finite values are re-evaluated,
+    /// expressions that no longer evaluate are replaced by their stored value, and
+    /// variables without either are removed. Unreadable entries stay dropped.
+    pub fn fix(self) -> AppState {
+        let mut state = self.state;
+        let mut evaluator = state_evaluator(&state);
+        for problem in &self.problems {
+            match problem {
+                Problem::NonFiniteValue(name) => {
+                    let value = state
+                        .variables
+                        .get(name)
+                        .and_then(|entry| evaluator.eval(&entry.expression).ok())
+                        .filter(|value| value.is_finite());
+                    match value {
+                        Some(value) => {
+                            if let Some(entry) = state.variables.get_mut(name) {
+                                entry.value = value;
+                            }
+                        }
+                        None => {
+                            state.variables.remove(name);
+                        }
+                    }
+                }
+                Problem::DanglingReference { variable, .. }
+                | Problem::NotReevaluable { variable, .. } => {
+                    if let Some(entry) = state.variables.get_mut(variable)
+                        && entry.value.is_finite()
+                    {
+                        // Keep the `name=` form of assignments saved by the TUI.
+                        entry.expression = match entry.expression.split_once('=') {
+                            Some((target, _)) => format!("{}={}", target.trim(), entry.value),
+                            None => entry.value.to_string(),
+                        };
+                    }
+                }
+                Problem::UnknownField(_)
+                | Problem::InvalidHistoryEntry { .. }
+                | Problem::InvalidVariable { .. } => {}
+            }
+        }
+        state
+    }
+}
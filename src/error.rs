@@ -0,0 +1,113 @@
+use thiserror::Error;
+
+/// Why an expression could not be evaluated.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum CalcError {
+    #[error("Expression could not be parsed")]
+    Unparsable,
+    #[error("Unexpected token: {0}")]
+    UnexpectedToken(String),
+    #[error("Missing closing ')'")]
+    MissingClosingParen,
+    /// A `(` that is never closed, at this byte offset of the input
+    #[error("'(' at position {} is never closed", .0 + 1)]
+    UnclosedParen(usize),
+    /// A `)` without a matching `(`, at this byte offset of the input
+    #[error("')' at position {} has no matching '('", .0 + 1)]
+    UnmatchedParen(usize),
+    /// An operator with nothing to apply to before it, such as the `*` of `*3`
+    #[error("'{operator}' at position {} is missing its left operand", .position + 1)]
+    MissingLeftOperand { operator: String, position: usize },
+    /// An operator with nothing after it, such as the `+` of `2+`
+    #[error("'{operator}' at position {} is missing its right operand", .position + 1)]
+    MissingRightOperand { operator: String, position: usize },
+    #[error("Unknown function: {0}")]
+    UnknownFunction(String),
+    #[error("{name} takes {expected} arguments, got {found}")]
+    ArgumentCount {
+        name: String,
+        expected: usize,
+        found: usize,
+    },
+    #[error("Unknown variable: {0}")]
+    UnknownVariable(String),
+    #[error("Unknown variables: {}", .0.join(", "))]
+    UnknownVariables(Vec<String>),
+    /// A `#3` History reference where there is no History to read it from
+    #[error("History references like {0} are unavailable here")]
+    HistoryUnavailable(String),
+    #[error("No assignment found")]
+    NoAssignment,
+    #[error("Missing variable name before '='")]
+    MissingVariableName,
+    #[error("'{0}' is not a valid variable name")]
+    InvalidVariableName(String),
+    /// Assigning to or defining a built-in constant or function
+    #[error("'{0}' is a built-in name and cannot be redefined")]
+    ReservedName(String),
+    /// More than a name before `=` that is not a function definition either
+    #[error("Cannot assign to '{0}': expected a variable name or a function like f(x)")]
+    InvalidAssignmentTarget(String),
+    /// A definition like `f(x) = …` where a variable assignment was expected
+    #[error("{0}(…) = … defines a function, not a value")]
+    FunctionDefinition(String),
+    /// Functions calling each other in a loop, starting and ending with the same name
+    #[error("Circular definition: {}", .0.join(" → "))]
+    CircularDefinition(Vec<String>),
+    #[error("Parameter {0} appears more than once")]
+    DuplicateParameter(String),
+    #[error("Functions take a single parameter, {name} has {count}")]
+    TooManyParameters { name: String, count: usize },
+    /// A Reverse Polish word applied to fewer values than it takes
+    #[error("'{word}' needs {needed} values on the stack")]
+    StackUnderflow { word: String, needed: usize },
+    /// Division by zero or overflow in a number type that cannot represent it
+    #[error("Result is undefined in this number type")]
+    Undefined,
+    #[error("Computation timed out")]
+    TimedOut,
+    #[error("Computation timed out after {0} steps")]
+    StepLimit(usize),
+    #[error("Expression is nested more than {0} levels deep")]
+    TooDeep(usize),
+    #[error("Computation cancelled")]
+    Cancelled,
+    /// The worker thread evaluating the expression stopped without a result
+    #[error("Evaluation failed unexpectedly")]
+    WorkerFailed,
+}
+
+impl CalcError {
+    /// Byte offset of the input the error is at, for errors tied to one place.
+    pub fn position(&self) -> Option<usize> {
+        match self {
+            CalcError::UnclosedParen(position)
+            | CalcError::UnmatchedParen(position)
+            | CalcError::MissingLeftOperand { position, .. }
+            | CalcError::MissingRightOperand { position, .. } => Some(*position),
+            _ => None,
+        }
+    }
+}
+
+/// Why saved state could not be read or written.
+#[derive(Debug, Error)]
+pub enum StateError {
+    #[error("HOME is not set")]
+    NoHome,
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error("Invalid state file: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Invalid state file: {0}")]
+    Toml(#[from] toml::de::Error),
+    #[error("Could not write state as TOML: {0}")]
+    TomlWrite(#[from] toml::ser::Error),
+}
+
+impl StateError {
+    /// Whether the file simply does not exist yet.
+    pub fn is_not_found(&self) -> bool {
+        matches!(self, StateError::Io(err) if err.kind() == std::io::ErrorKind::NotFound)
+    }
+}
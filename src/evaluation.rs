@@ -0,0 +1,82 @@
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, RecvTimeoutError, TryRecvError},
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{error::CalcError, evaluator::Evaluator};
+
+/// How long submitting waits for a result before handing the evaluation to the
+/// background, so quick results never flash the "computing…" indicator.
+pub const FOREGROUND_WAIT: Duration = Duration::from_millis(50);
+
+/// An expression being evaluated on a worker thread.
+///
+/// Dropping it cancels the evaluation: the worker stops and its result is discarded.
+pub struct Evaluation {
+    receiver: Receiver<Result<f64, CalcError>>,
+    cancelled: Arc<AtomicBool>,
+    pub started_at: Instant,
+}
+
+impl Evaluation {
+    /// Starts evaluating `tokens` with `evaluator`, giving up with a timeout error
+    /// after `timeout`.
+    pub fn spawn(mut evaluator: Evaluator, tokens: &[&str], timeout: Duration) -> Self {
+        let tokens: Vec<String> = tokens.iter().map(|token| token.to_string()).collect();
+        let started_at = Instant::now();
+        let cancelled = Arc::new(AtomicBool::new(false));
+        evaluator.limits.deadline = started_at.checked_add(timeout);
+        evaluator.limits.cancelled = Some(cancelled.clone());
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let tokens: Vec<&str> = tokens.iter().map(String::as_str).collect();
+            // The receiver is gone when the evaluation was cancelled.
+            let _ = sender.send(evaluator.eval_tokens(&tokens));
+        });
+        Self {
+            receiver,
+            cancelled,
+            started_at,
+        }
+    }
+
+    /// Waits up to `timeout` for the result.
+    pub fn wait(&self, timeout: Duration) -> Option<Result<f64, CalcError>> {
+        match self.receiver.recv_timeout(timeout) {
+            Ok(result) => Some(result),
+            Err(RecvTimeoutError::Timeout) => None,
+            Err(RecvTimeoutError::Disconnected) => Some(Err(CalcError::WorkerFailed)),
+        }
+    }
+
+    /// Blocks until the worker has finished, which it does by its deadline at the latest.
+    pub fn result(&self) -> Result<f64, CalcError> {
+        self.receiver.recv().unwrap_or(Err(CalcError::WorkerFailed))
+    }
+
+    /// Asks the worker to stop; it gives up with an error at its next check of
+    /// the limits.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// The result, if the worker has finished.
+    pub fn try_result(&self) -> Option<Result<f64, CalcError>> {
+        match self.receiver.try_recv() {
+            Ok(result) => Some(result),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => Some(Err(CalcError::WorkerFailed)),
+        }
+    }
+}
+
+impl Drop for Evaluation {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
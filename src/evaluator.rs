@@ -0,0 +1,322 @@
+use std::{collections::HashMap, sync::Arc};
+
+use num_complex::Complex64;
+use num_rational::BigRational;
+use rust_decimal::Decimal;
+
+use crate::{
+    builtins::constant,
+    calculate::{EvalLimits, evaluate, parse},
+    compiled::CompiledExpr,
+    definitions::FunctionDefinition,
+    error::CalcError,
+    expr::Expr,
+    inspect::{function_graph, inspect_unknown_variables, missing_operands, unmatched_parens},
+    numeric::Numeric,
+    tokenize::{Lexer, Token, TokenKind},
+    trace::Trace,
+    types::{AngleMode, PowerRules, VariableEntry},
+    variables::parse_variables,
+};
+
+type FunctionBody = dyn Fn(&[f64]) -> f64 + Send + Sync;
+
+/// A function added to an [`Evaluator`], taking `arity` arguments.
+#[derive(Clone)]
+pub struct Function {
+    pub arity: usize,
+    body: Arc<FunctionBody>,
+}
+
+impl Function {
+    /// Calls the function; `args` holds exactly `arity` values.
+    pub fn call(&self, args: &[f64]) -> f64 {
+        (self.body)(args)
+    }
+}
+
+/// Evaluates expressions with its own settings, functions, and variables.
+///
+/// Results are `f64`; [`eval_as`](Self::eval_as) computes in other [`Numeric`] types.
+#[derive(Clone, Default)]
+pub struct Evaluator {
+    pub angle_mode: AngleMode,
+    /// How `^` groups with signs and with itself
+    pub power: PowerRules,
+    /// Decimals results are rounded to; `None` keeps full precision
+    pub precision: Option<usize>,
+    pub limits: EvalLimits,
+    functions: HashMap<String, Function>,
+    variables: HashMap<String, VariableEntry>,
+}
+
+impl Evaluator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_angle_mode(mut self, angle_mode: AngleMode) -> Self {
+        self.angle_mode = angle_mode;
+        self
+    }
+
+    pub fn with_power_rules(mut self, power: PowerRules) -> Self {
+        self.power = power;
+        self
+    }
+
+    pub fn with_precision(mut self, precision: Option<usize>) -> Self {
+        self.precision = precision;
+        self
+    }
+
+    pub fn with_limits(mut self, limits: EvalLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    pub fn with_variables(mut self, variables: HashMap<String, VariableEntry>) -> Self {
+        self.variables = variables;
+        self
+    }
+
+    /// Adds a function callable as `name(x)` or `name x`. Built-in functions of the
+    /// same name take precedence.
+    pub fn with_function(
+        mut self,
+        name: &str,
+        function: impl Fn(f64) -> f64 + Send + Sync + 'static,
+    ) -> Self {
+        self.register_fn(name, 1, move |args| function(args[0]));
+        self
+    }
+
+    /// Adds a function of `arity` arguments, called as `name(a, b, …)`, replacing
+    /// one registered before under the same name. Calls with a different number
+    /// of arguments fail with [`CalcError::ArgumentCount`]. Built-in functions of
+    /// the same name take precedence.
+    pub fn register_fn(
+        &mut self,
+        name: &str,
+        arity: usize,
+        function: impl Fn(&[f64]) -> f64 + Send + Sync + 'static,
+    ) {
+        self.functions.insert(
+            name.to_string(),
+            Function {
+                arity,
+                body: Arc::new(function),
+            },
+        );
+    }
+
+    /// Adds a function defined as `f(x) = …`, like [`with_function`](Self::with_function).
+    /// Its body sees the variables and functions the evaluator has now.
+    pub fn define_function(&mut self, definition: &FunctionDefinition) -> Result<(), CalcError> {
+        let [param] = definition.params.as_slice() else {
+            return Err(CalcError::TooManyParameters {
+                name: definition.name.clone(),
+                count: definition.params.len(),
+            });
+        };
+        let tokens = self.tokenize(&definition.body)?;
+        let mut unknown = inspect_unknown_variables(&tokens, &self.variables);
+        unknown.retain(|name| name != param && !self.functions.contains_key(name));
+        match unknown.len() {
+            0 => {}
+            1 => return Err(CalcError::UnknownVariable(unknown.remove(0))),
+            _ => return Err(CalcError::UnknownVariables(unknown)),
+        }
+        let body = self.compile_tokens(&tokens)?;
+        let param = param.clone();
+        self.register_fn(&definition.name, 1, move |args| {
+            body.eval_with(&[(param.as_str(), args[0])])
+                .unwrap_or(f64::NAN)
+        });
+        Ok(())
+    }
+
+    /// Adds `definitions` like [`define_function`](Self::define_function), each
+    /// after the functions it calls, whatever order they are listed in.
+    pub fn define_functions(
+        &mut self,
+        definitions: &[FunctionDefinition],
+    ) -> Result<(), CalcError> {
+        let order = function_graph(definitions)
+            .topological_order()
+            .map_err(CalcError::CircularDefinition)?;
+        for name in order {
+            if let Some(definition) = definitions.iter().find(|d| d.name == name) {
+                self.define_function(definition)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn variables(&self) -> &HashMap<String, VariableEntry> {
+        &self.variables
+    }
+
+    pub fn set_variable(&mut self, name: &str, value: f64) {
+        self.variables.insert(
+            name.to_string(),
+            VariableEntry {
+                expression: value.to_string(),
+                value,
+                format: None,
+                unit: None,
+            },
+        );
+    }
+
+    /// Evaluates `input`. An assignment such as `x = 2+3` also stores the result
+    /// in this evaluator's variables.
+    pub fn eval(&mut self, input: &str) -> Result<f64, CalcError> {
+        let tokens = self.tokenize(input)?;
+        if !tokens.contains(&"=") {
+            return self.eval_tokens(&tokens);
+        }
+
+        let assignment = parse_variables(tokens)?;
+        let value = self.eval_tokens(&assignment.tokens)?;
+        let expression = input.split_once('=').map_or(input, |(_, rhs)| rhs.trim());
+        self.variables.insert(
+            assignment.var_name,
+            VariableEntry {
+                expression: expression.to_string(),
+                value,
+                format: None,
+                unit: None,
+            },
+        );
+        Ok(value)
+    }
+
+    /// Parses `input` once for repeated evaluation. Variables are looked up when it
+    /// is evaluated, so names bound later with [`CompiledExpr::eval_with`] work.
+    pub fn compile(&self, input: &str) -> Result<CompiledExpr, CalcError> {
+        self.compile_tokens(&self.tokenize(input)?)
+    }
+
+    pub fn compile_tokens(&self, tokens: &[&str]) -> Result<CompiledExpr, CalcError> {
+        Ok(CompiledExpr {
+            root: parse(tokens, self.power, &self.limits, &self.functions)?,
+            variables: self
+                .variables
+                .iter()
+                .map(|(name, entry)| (name.clone(), entry.value))
+                .collect(),
+            angle_mode: self.angle_mode,
+            precision: self.precision,
+        })
+    }
+
+    /// Evaluates `input` in the number type `N`, e.g. `Decimal` for exact decimal
+    /// arithmetic or `Complex64` for complex results. Assignments are evaluated but
+    /// not stored, since variables hold `f64` values.
+    pub fn eval_as<N: Numeric>(&self, input: &str) -> Result<N, CalcError> {
+        let mut tokens = self.tokenize(input)?;
+        if tokens.contains(&"=") {
+            tokens = parse_variables(tokens)?.tokens;
+        }
+        self.compile_tokens(&tokens)?.eval_as(&[])
+    }
+
+    /// Parses `input` with this evaluator's functions and [`PowerRules`], to show
+    /// how it is grouped. For an assignment only the value is parsed.
+    pub fn parse(&self, input: &str) -> Result<Expr, CalcError> {
+        let mut tokens = self.tokenize(input)?;
+        if tokens.contains(&"=") {
+            tokens = parse_variables(tokens)?.tokens;
+        }
+        Ok(Expr {
+            root: self.compile_tokens(&tokens)?.root,
+        })
+    }
+
+    /// Evaluates `input` like [`eval`](Self::eval), without storing assignments,
+    /// and records every operation on the way.
+    pub fn eval_trace(&self, input: &str) -> Result<Trace, CalcError> {
+        let mut tokens = self.tokenize(input)?;
+        if tokens.contains(&"=") {
+            tokens = parse_variables(tokens)?.tokens;
+        }
+        let compiled = self.compile_tokens(&tokens)?;
+        let mut steps = Vec::new();
+        let value = compiled.root.eval_traced(
+            self.angle_mode,
+            &|name| {
+                compiled
+                    .variables
+                    .get(name)
+                    .copied()
+                    .or_else(|| constant(name))
+            },
+            &mut steps,
+        )?;
+        Ok(Trace { value, steps })
+    }
+
+    /// Splits `input` into tokens, failing on the first unmatched parenthesis or
+    /// operator without operands.
+    fn tokenize<'a>(&'a self, input: &'a str) -> Result<Vec<&'a str>, CalcError> {
+        let tokens: Vec<Token> = Lexer::new(input)
+            .with_functions(self.functions.keys().map(String::as_str))
+            .collect();
+        let texts = || tokens.iter().map(|token| token.text).collect();
+        // A bad name such as the `+` of `+=3` is reported before its operands.
+        if tokens.iter().any(|token| token.kind == TokenKind::Equals) {
+            parse_variables(texts())?;
+        }
+        if let Some(error) = unmatched_parens(&tokens)
+            .into_iter()
+            .chain(missing_operands(&tokens))
+            .next()
+        {
+            return Err(error);
+        }
+        Ok(texts())
+    }
+
+    /// Evaluates already tokenized input, without assignments.
+    pub fn eval_tokens(&self, tokens: &[&str]) -> Result<f64, CalcError> {
+        let value = evaluate(
+            tokens,
+            &self.variables,
+            self.angle_mode,
+            self.power,
+            &self.limits,
+            &self.functions,
+        )?;
+        Ok(match self.precision {
+            Some(decimals) => round(value, decimals),
+            None => value,
+        })
+    }
+}
+
+pub(crate) fn round(value: f64, decimals: usize) -> f64 {
+    let scale = 10f64.powi(decimals.min(i32::MAX as usize) as i32);
+    if scale.is_finite() && (value * scale).is_finite() {
+        (value * scale).round() / scale
+    } else {
+        value
+    }
+}
+
+/// Entry point for fuzzers: evaluates and compiles arbitrary bytes, which must
+/// only ever produce errors, never panics.
+pub fn fuzz_eval(data: &[u8]) {
+    let input = String::from_utf8_lossy(data);
+    let mut evaluator = Evaluator::new().with_limits(EvalLimits {
+        max_steps: 10_000,
+        ..EvalLimits::default()
+    });
+    let _ = evaluator.eval(&input);
+    if let Ok(compiled) = evaluator.compile(&input) {
+        let _ = compiled.eval_with(&[("x", 1.0)]);
+    }
+    let _ = evaluator.eval_as::<Decimal>(&input);
+    let _ = evaluator.eval_as::<BigRational>(&input);
+    let _ = evaluator.eval_as::<Complex64>(&input);
+}
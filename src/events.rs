@@ -0,0 +1,74 @@
+use std::{collections::VecDeque, io, time::Duration};
+
+use ratatui::crossterm::event::{self, Event, KeyEvent, KeyEventKind};
+
+/// What the TUI reacts to, whatever it comes from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AppEvent {
+    /// A key press
+    Key(KeyEvent),
+    /// Text pasted in one piece
+    Paste(String),
+    /// The terminal was resized to the given columns and rows
+    Resize(u16, u16),
+    /// Time passed without input: toasts expire, autosave and finished
+    /// evaluations are picked up
+    Tick,
+    /// The background evaluation is done; the app waits for its result
+    EvalFinished,
+}
+
+/// Where the TUI reads its events from, e.g. the terminal or a script.
+pub trait EventSource {
+    /// The next event, or `None` when there are no more and the app should stop.
+    fn next_event(&mut self) -> io::Result<Option<AppEvent>>;
+}
+
+/// Events from the terminal, with a [`AppEvent::Tick`] whenever none arrives
+/// within `tick`.
+#[derive(Debug, Clone, Copy)]
+pub struct TerminalEvents {
+    pub tick: Duration,
+}
+
+impl Default for TerminalEvents {
+    fn default() -> Self {
+        Self {
+            tick: Duration::from_millis(16),
+        }
+    }
+}
+
+impl EventSource for TerminalEvents {
+    fn next_event(&mut self) -> io::Result<Option<AppEvent>> {
+        if !event::poll(self.tick)? {
+            return Ok(Some(AppEvent::Tick));
+        }
+        Ok(Some(match event::read()? {
+            Event::Key(key) if key.kind == KeyEventKind::Press => AppEvent::Key(key),
+            Event::Paste(text) => AppEvent::Paste(text),
+            Event::Resize(columns, rows) => AppEvent::Resize(columns, rows),
+            _ => AppEvent::Tick,
+        }))
+    }
+}
+
+/// A fixed sequence of events, for driving the app from tests or scripts.
+#[derive(Debug, Clone, Default)]
+pub struct ScriptedEvents {
+    events: VecDeque<AppEvent>,
+}
+
+impl ScriptedEvents {
+    pub fn new(events: impl IntoIterator<Item = AppEvent>) -> Self {
+        Self {
+            events: events.into_iter().collect(),
+        }
+    }
+}
+
+impl EventSource for ScriptedEvents {
+    fn next_event(&mut self) -> io::Result<Option<AppEvent>> {
+        Ok(self.events.pop_front())
+    }
+}
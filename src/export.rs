@@ -0,0 +1,267 @@
+use std::{fmt::Write as _, fs, path::Path};
+
+use crate::{types::PlotStyle, widgets::plot_block::axis_ticks};
+
+/// File formats a plot can be exported to, picked from the file extension.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv,
+    Svg,
+    Png,
+}
+
+impl ExportFormat {
+    pub fn from_path(path: &Path) -> Result<Self, String> {
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(str::to_ascii_lowercase);
+        match extension.as_deref() {
+            Some("csv") => Ok(ExportFormat::Csv),
+            Some("svg") => Ok(ExportFormat::Svg),
+            Some("png") => Ok(ExportFormat::Png),
+            _ => Err(format!(
+                "Unsupported export format for {}: use .csv, .svg, or .png",
+                path.display()
+            )),
+        }
+    }
+}
+
+/// Writes `data` to `path` in the format given by its extension.
+///
+/// Used by both `:plot export` in the TUI and `rcalc export plot`.
+pub fn export_plot(
+    path: &Path,
+    data: &[(f64, f64)],
+    label: &str,
+    style: PlotStyle,
+) -> Result<(), String> {
+    let bytes = match ExportFormat::from_path(path)? {
+        ExportFormat::Csv => plot_to_csv(data).into_bytes(),
+        ExportFormat::Svg => plot_to_svg(data, label, style).into_bytes(),
+        ExportFormat::Png => plot_to_png(data, style)?,
+    };
+    fs::write(path, bytes).map_err(|err| format!("Could not write {}: {}", path.display(), err))
+}
+
+pub fn plot_to_csv(data: &[(f64, f64)]) -> String {
+    let mut csv = String::from("x,y\n");
+    for (x, y) in data {
+        let _ = writeln!(csv, "{},{}", x, y);
+    }
+    csv
+}
+
+const WIDTH: u32 = 640;
+const HEIGHT: u32 = 480;
+const MARGIN: f64 = 48.0;
+
+/// Maps data coordinates onto an image of [`WIDTH`] x [`HEIGHT`] pixels.
+struct Viewport {
+    x_bounds: [f64; 2],
+    y_bounds: [f64; 2],
+    x_labels: Vec<String>,
+    y_labels: Vec<String>,
+}
+
+impl Viewport {
+    fn new(data: &[(f64, f64)]) -> Self {
+        let finite = || {
+            data.iter()
+                .copied()
+                .filter(|(x, y)| x.is_finite() && y.is_finite())
+        };
+        let min_max = |values: Vec<f64>| {
+            values
+                .iter()
+                .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), v| {
+                    (lo.min(*v), hi.max(*v))
+                })
+        };
+        let (x_min, x_max) = min_max(finite().map(|(x, _)| x).collect());
+        let (y_min, y_max) = min_max(finite().map(|(_, y)| y).collect());
+        let (x_bounds, x_labels) = axis_ticks(x_min, x_max);
+        let (y_bounds, y_labels) = axis_ticks(y_min, y_max);
+        Self {
+            x_bounds,
+            y_bounds,
+            x_labels,
+            y_labels,
+        }
+    }
+
+    fn to_pixel(&self, (x, y): (f64, f64)) -> (f64, f64) {
+        let [x_lo, x_hi] = self.x_bounds;
+        let [y_lo, y_hi] = self.y_bounds;
+        let plot_width = WIDTH as f64 - 2.0 * MARGIN;
+        let plot_height = HEIGHT as f64 - 2.0 * MARGIN;
+        (
+            MARGIN + (x - x_lo) / (x_hi - x_lo) * plot_width,
+            HEIGHT as f64 - MARGIN - (y - y_lo) / (y_hi - y_lo) * plot_height,
+        )
+    }
+}
+
+pub fn plot_to_svg(data: &[(f64, f64)], label: &str, style: PlotStyle) -> String {
+    let viewport = Viewport::new(data);
+    let (left, top) = (MARGIN, MARGIN);
+    let (right, bottom) = (WIDTH as f64 - MARGIN, HEIGHT as f64 - MARGIN);
+
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{WIDTH}" height="{HEIGHT}" viewBox="0 0 {WIDTH} {HEIGHT}" font-family="sans-serif" font-size="12">"#
+    );
+    let _ = writeln!(svg, r#"<rect width="100%" height="100%" fill="white"/>"#);
+    let _ = writeln!(
+        svg,
+        r#"<rect x="{left}" y="{top}" width="{}" height="{}" fill="none" stroke="gray"/>"#,
+        right - left,
+        bottom - top
+    );
+
+    let ticks = |labels: &[String]| labels.len().saturating_sub(1).max(1) as f64;
+    for (i, tick) in viewport.x_labels.iter().enumerate() {
+        let x = left + (right - left) * i as f64 / ticks(&viewport.x_labels);
+        let _ = writeln!(
+            svg,
+            r#"<text x="{x:.1}" y="{:.1}" text-anchor="middle" fill="gray">{}</text>"#,
+            bottom + 18.0,
+            escape_xml(tick)
+        );
+    }
+    for (i, tick) in viewport.y_labels.iter().enumerate() {
+        let y = bottom - (bottom - top) * i as f64 / ticks(&viewport.y_labels);
+        let _ = writeln!(
+            svg,
+            r#"<text x="{:.1}" y="{y:.1}" text-anchor="end" dominant-baseline="middle" fill="gray">{}</text>"#,
+            left - 6.0,
+            escape_xml(tick)
+        );
+    }
+
+    let points: Vec<(f64, f64)> = data
+        .iter()
+        .copied()
+        .filter(|(x, y)| x.is_finite() && y.is_finite())
+        .map(|point| viewport.to_pixel(point))
+        .collect();
+    match style {
+        PlotStyle::Line => {
+            let coordinates: Vec<String> = points
+                .iter()
+                .map(|(x, y)| format!("{x:.2},{y:.2}"))
+                .collect();
+            let _ = writeln!(
+                svg,
+                r#"<polyline points="{}" fill="none" stroke="steelblue" stroke-width="2"/>"#,
+                coordinates.join(" ")
+            );
+        }
+        PlotStyle::Scatter => {
+            for (x, y) in &points {
+                let _ = writeln!(
+                    svg,
+                    r#"<circle cx="{x:.2}" cy="{y:.2}" r="3" fill="steelblue"/>"#
+                );
+            }
+        }
+    }
+
+    let _ = writeln!(
+        svg,
+        r#"<text x="{}" y="{:.1}" text-anchor="middle" font-size="14">{}</text>"#,
+        WIDTH / 2,
+        top - 16.0,
+        escape_xml(label)
+    );
+    svg.push_str("</svg>\n");
+    svg
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Renders the plot into an RGB PNG image, without axis labels.
+pub fn plot_to_png(data: &[(f64, f64)], style: PlotStyle) -> Result<Vec<u8>, String> {
+    const BACKGROUND: [u8; 3] = [255, 255, 255];
+    const FRAME: [u8; 3] = [160, 160, 160];
+    const CURVE: [u8; 3] = [70, 130, 180];
+
+    let viewport = Viewport::new(data);
+    let mut canvas = Canvas {
+        pixels: vec![BACKGROUND; (WIDTH * HEIGHT) as usize],
+    };
+
+    let (left, top) = (MARGIN, MARGIN);
+    let (right, bottom) = (WIDTH as f64 - MARGIN, HEIGHT as f64 - MARGIN);
+    canvas.line((left, top), (right, top), FRAME);
+    canvas.line((right, top), (right, bottom), FRAME);
+    canvas.line((right, bottom), (left, bottom), FRAME);
+    canvas.line((left, bottom), (left, top), FRAME);
+
+    let points: Vec<(f64, f64)> = data
+        .iter()
+        .copied()
+        .filter(|(x, y)| x.is_finite() && y.is_finite())
+        .map(|point| viewport.to_pixel(point))
+        .collect();
+    match style {
+        PlotStyle::Line => {
+            for pair in points.windows(2) {
+                canvas.line(pair[0], pair[1], CURVE);
+            }
+        }
+        PlotStyle::Scatter => {
+            for &(x, y) in &points {
+                for dx in -2..=2 {
+                    for dy in -2..=2 {
+                        canvas.set(x as i64 + dx, y as i64 + dy, CURVE);
+                    }
+                }
+            }
+        }
+    }
+
+    encode_png(&canvas.pixels).map_err(|err| format!("Could not encode PNG: {}", err))
+}
+
+struct Canvas {
+    pixels: Vec<[u8; 3]>,
+}
+
+impl Canvas {
+    fn set(&mut self, x: i64, y: i64, color: [u8; 3]) {
+        if (0..WIDTH as i64).contains(&x) && (0..HEIGHT as i64).contains(&y) {
+            self.pixels[(y * WIDTH as i64 + x) as usize] = color;
+        }
+    }
+
+    fn line(&mut self, (x0, y0): (f64, f64), (x1, y1): (f64, f64), color: [u8; 3]) {
+        let steps = (x1 - x0).abs().max((y1 - y0).abs()).ceil().max(1.0) as i64;
+        for step in 0..=steps {
+            let t = step as f64 / steps as f64;
+            let x = x0 + (x1 - x0) * t;
+            let y = y0 + (y1 - y0) * t;
+            self.set(x.round() as i64, y.round() as i64, color);
+        }
+    }
+}
+
+/// Encodes RGB pixels as a PNG.
+fn encode_png(pixels: &[[u8; 3]]) -> Result<Vec<u8>, png::EncodingError> {
+    let mut png = Vec::new();
+    let mut encoder = png::Encoder::new(&mut png, WIDTH, HEIGHT);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.set_compression(png::Compression::High);
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(pixels.as_flattened())?;
+    writer.finish()?;
+    Ok(png)
+}
@@ -0,0 +1,304 @@
+use std::{collections::HashMap, fmt};
+
+use crate::{
+    calculate::{BinaryOp, EvalLimits, Node, parse},
+    error::CalcError,
+    tokenize::tokenize,
+    types::{AngleMode, PowerRules},
+};
+
+/// A parsed expression that can be rewritten and printed back as input.
+#[derive(Clone)]
+pub struct Expr {
+    pub(crate) root: Node,
+}
+
+impl Expr {
+    pub fn parse(input: &str) -> Result<Self, CalcError> {
+        let root = parse(
+            &tokenize(input),
+            PowerRules::default(),
+            &EvalLimits::default(),
+            &HashMap::new(),
+        )?;
+        Ok(Self { root })
+    }
+
+    /// The expression with every operation in parentheses, showing how it was
+    /// grouped: `-x^2 + 1` gives `(-(x^2)) + 1`.
+    pub fn grouped(&self) -> String {
+        let mut grouped = String::new();
+        self.root.write_grouped(&mut grouped);
+        grouped
+    }
+
+    /// Folds arithmetic on number literals, drops identity operations such as
+    /// `x*1`, `x+0`, and `x^1`, and moves signs out (`a - -b` becomes `a + b`).
+    /// Function calls and constants like `pi` are kept as written.
+    pub fn simplify(&self) -> Expr {
+        Expr {
+            root: self.root.clone().simplify(),
+        }
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.root.fmt(f)
+    }
+}
+
+fn number(value: f64) -> Node {
+    if value < 0.0 {
+        Node::Negate(Box::new(Node::Number((-value).to_string())))
+    } else {
+        Node::Number(value.to_string())
+    }
+}
+
+impl Node {
+    /// The value of a number literal, possibly negated.
+    fn literal(&self) -> Option<f64> {
+        match self {
+            Node::Number(text) => text.parse().ok(),
+            Node::Negate(operand) => operand.literal().map(|value| -value),
+            _ => None,
+        }
+    }
+
+    /// Whether the node only does arithmetic on number literals.
+    fn is_constant(&self) -> bool {
+        match self {
+            Node::Number(_) => true,
+            Node::Negate(operand) => operand.is_constant(),
+            Node::Chain { first, rest } => {
+                first.is_constant() && rest.iter().all(|(_, operand)| operand.is_constant())
+            }
+            Node::Binary(_, lhs, rhs) => lhs.is_constant() && rhs.is_constant(),
+            Node::Identifier(_) | Node::Call { .. } => false,
+        }
+    }
+
+    fn simplify(self) -> Node {
+        let node = match self {
+            Node::Negate(operand) => match operand.simplify() {
+                Node::Negate(inner) => *inner,
+                operand => Node::Negate(Box::new(operand)),
+            },
+            Node::Chain { first, rest } => {
+                let first = first.simplify();
+                let rest = rest
+                    .into_iter()
+                    .map(|(op, operand)| (op, operand.simplify()))
+                    .collect();
+                simplify_chain(first, rest)
+            }
+            Node::Binary(op, lhs, rhs) => {
+                let (lhs, rhs) = (lhs.simplify(), rhs.simplify());
+                match (op, rhs.literal()) {
+                    (BinaryOp::Pow, Some(1.0)) => lhs,
+                    (BinaryOp::Pow, Some(0.0)) => number(1.0),
+                    _ => Node::Binary(op, Box::new(lhs), Box::new(rhs)),
+                }
+            }
+            Node::Call { name, custom, args } => Node::Call {
+                name,
+                custom,
+                args: args.into_iter().map(Node::simplify).collect(),
+            },
+            node => node,
+        };
+
+        if node.is_constant()
+            && !matches!(node, Node::Number(_))
+            && let Ok(value) = node.eval::<f64>(AngleMode::Radians, &|_| None)
+            && value.is_finite()
+        {
+            return number(value);
+        }
+        node
+    }
+
+    fn write_grouped(&self, out: &mut String) {
+        let operand = |node: &Node, out: &mut String| {
+            if matches!(
+                node,
+                Node::Number(_) | Node::Identifier(_) | Node::Call { .. }
+            ) {
+                node.write_grouped(out);
+            } else {
+                out.push('(');
+                node.write_grouped(out);
+                out.push(')');
+            }
+        };
+        match self {
+            Node::Number(text) | Node::Identifier(text) => out.push_str(text),
+            Node::Negate(inner) => {
+                out.push('-');
+                operand(inner, out);
+            }
+            Node::Chain { first, rest } => {
+                operand(first, out);
+                for (op, node) in rest {
+                    out.push_str(op.symbol());
+                    operand(node, out);
+                }
+            }
+            Node::Binary(op, lhs, rhs) => {
+                operand(lhs, out);
+                out.push_str(op.symbol());
+                operand(rhs, out);
+            }
+            Node::Call { name, args, .. } => {
+                out.push_str(name);
+                out.push('(');
+                for (idx, arg) in args.iter().enumerate() {
+                    if idx > 0 {
+                        out.push_str(", ");
+                    }
+                    arg.write_grouped(out);
+                }
+                out.push(')');
+            }
+        }
+    }
+
+    /// Binding strength when printed; operands binding weaker get parentheses.
+    fn precedence(&self) -> u8 {
+        match self {
+            Node::Chain { rest, .. } => match rest.first() {
+                Some((BinaryOp::Add | BinaryOp::Sub, _)) => 1,
+                _ => 2,
+            },
+            Node::Negate(_) => 3,
+            Node::Binary(..) => 4,
+            Node::Number(_) | Node::Identifier(_) | Node::Call { .. } => 5,
+        }
+    }
+
+    fn fmt_operand(&self, f: &mut fmt::Formatter<'_>, min_precedence: u8) -> fmt::Result {
+        if self.precedence() < min_precedence {
+            write!(f, "({})", self)
+        } else {
+            write!(f, "{}", self)
+        }
+    }
+}
+
+/// Combines the number literals of a chain into one (dropping it when it is `0`
+/// in a sum or `1` in a product) and collects the signs of its operands in front.
+fn simplify_chain(first: Node, rest: Vec<(BinaryOp, Node)>) -> Node {
+    let additive = matches!(rest.first(), Some((BinaryOp::Add | BinaryOp::Sub, _)));
+    let (op, inverse_op, identity) = if additive {
+        (BinaryOp::Add, BinaryOp::Sub, 0.0)
+    } else {
+        (BinaryOp::Mul, BinaryOp::Div, 1.0)
+    };
+
+    // Operands as (inverse, node): subtracted terms and divisors are inverse.
+    let mut negative = false;
+    let mut terms: Vec<(bool, Node)> = Vec::new();
+    for (term_op, mut operand) in std::iter::once((op, first)).chain(rest) {
+        let mut inverse = term_op == inverse_op;
+        while let Node::Negate(inner) = operand {
+            if additive {
+                inverse = !inverse;
+            } else {
+                negative = !negative;
+            }
+            operand = *inner;
+        }
+        terms.push((inverse, operand));
+    }
+
+    let combined = terms
+        .iter()
+        .filter_map(|(inverse, operand)| Some((*inverse, operand.literal()?)))
+        .fold(identity, |acc, (inverse, value)| {
+            match (additive, inverse) {
+                (true, false) => acc + value,
+                (true, true) => acc - value,
+                (false, false) => acc * value,
+                (false, true) => acc / value,
+            }
+        });
+    if combined.is_finite() {
+        terms.retain(|(_, operand)| operand.literal().is_none());
+        if additive && combined != 0.0 {
+            terms.push((combined < 0.0, number(combined.abs())));
+        } else if !additive && (combined != 1.0 || terms.first().is_some_and(|(inv, _)| *inv)) {
+            negative ^= combined < 0.0;
+            terms.insert(0, (false, number(combined.abs())));
+        }
+    }
+
+    let mut terms = terms.into_iter();
+    let Some((first_inverse, first)) = terms.next() else {
+        return number(identity);
+    };
+    let first = if first_inverse {
+        Node::Negate(Box::new(first))
+    } else {
+        first
+    };
+    let rest: Vec<(BinaryOp, Node)> = terms
+        .map(|(inverse, operand)| (if inverse { inverse_op } else { op }, operand))
+        .collect();
+    let node = if rest.is_empty() {
+        first
+    } else {
+        Node::Chain {
+            first: Box::new(first),
+            rest,
+        }
+    };
+    if negative {
+        Node::Negate(Box::new(node))
+    } else {
+        node
+    }
+}
+
+impl BinaryOp {
+    /// The operator as printed, with spaces around `+` and `-`.
+    fn symbol(self) -> &'static str {
+        match self {
+            BinaryOp::Add => " + ",
+            BinaryOp::Sub => " - ",
+            BinaryOp::Mul => "*",
+            BinaryOp::Div => "/",
+            BinaryOp::Pow => "^",
+        }
+    }
+}
+
+impl fmt::Display for Node {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Node::Number(text) | Node::Identifier(text) => write!(f, "{}", text),
+            Node::Negate(operand) => {
+                write!(f, "-")?;
+                operand.fmt_operand(f, 3)
+            }
+            Node::Chain { first, rest } => {
+                let precedence = self.precedence();
+                first.fmt_operand(f, precedence)?;
+                for (op, operand) in rest {
+                    write!(f, "{}", op.symbol())?;
+                    operand.fmt_operand(f, precedence + 1)?;
+                }
+                Ok(())
+            }
+            Node::Binary(op, base, exponent) => {
+                base.fmt_operand(f, 5)?;
+                write!(f, "{}", op.symbol())?;
+                exponent.fmt_operand(f, 3)
+            }
+            Node::Call { name, args, .. } => {
+                let args: Vec<String> = args.iter().map(Node::to_string).collect();
+                write!(f, "{}({})", name, args.join(", "))
+            }
+        }
+    }
+}
@@ -0,0 +1,153 @@
+use crate::types::ValueFormat;
+
+/// Largest denominator tried when approximating a value as a fraction.
+const MAX_DENOMINATOR: i64 = 1_000_000;
+
+/// Significant digits kept by [`format_auto`]; the 16th and 17th of an `f64` are
+/// mostly rounding noise.
+const SIGNIFICANT_DIGITS: usize = 15;
+
+/// Magnitudes from which [`format_auto`] and [`format_value`] switch to scientific notation.
+const SCIENTIFIC_ABOVE: f64 = 1e15;
+const SCIENTIFIC_BELOW: f64 = 1e-6;
+
+/// Formats a result for display, with a fixed number of decimals when `precision` is set.
+pub fn format_value(value: f64, precision: Option<usize>) -> String {
+    match precision {
+        Some(decimals)
+            if value != 0.0
+                && value.is_finite()
+                && !(SCIENTIFIC_BELOW..SCIENTIFIC_ABOVE).contains(&value.abs()) =>
+        {
+            format!("{:.*e}", decimals, value)
+        }
+        Some(decimals) => format!("{:.*}", decimals, value),
+        None => format_auto(value),
+    }
+}
+
+/// The shortest form of `value` to 15 significant digits, so `0.1 + 0.2` shows as
+/// `0.3`: fixed notation in between, scientific such as `1e300` or `1.5e-9` for
+/// very large or tiny magnitudes.
+pub fn format_auto(value: f64) -> String {
+    if value == 0.0 {
+        // Also for -0
+        return "0".to_string();
+    }
+    if !value.is_finite() {
+        return value.to_string();
+    }
+    let rounded: f64 = format!("{:.*e}", SIGNIFICANT_DIGITS - 1, value)
+        .parse()
+        .unwrap_or(value);
+    if (SCIENTIFIC_BELOW..SCIENTIFIC_ABOVE).contains(&rounded.abs()) {
+        rounded.to_string()
+    } else {
+        format!("{:e}", rounded)
+    }
+}
+
+/// Like [`format_value`], but in the variable's display format when it has one.
+pub fn format_tagged(value: f64, format: Option<ValueFormat>, precision: Option<usize>) -> String {
+    match format {
+        None => format_value(value, precision),
+        Some(ValueFormat::Currency) => format_currency(value),
+        Some(ValueFormat::Percent) => format!("{}%", format_value(value * 100.0, precision)),
+        Some(ValueFormat::Scientific) => match precision {
+            Some(decimals) => format!("{:.*e}", decimals, value),
+            None => format_scientific(value),
+        },
+    }
+}
+
+/// Two decimals with `,` between groups of thousands.
+pub fn format_currency(value: f64) -> String {
+    let fixed = format!("{:.2}", value.abs());
+    let (int, cents) = fixed.split_once('.').unwrap_or((&fixed, "00"));
+    let mut grouped = String::new();
+    for (idx, digit) in int.chars().enumerate() {
+        if idx > 0 && (int.len() - idx) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    let sign = if value < 0.0 && fixed != "0.00" {
+        "-"
+    } else {
+        ""
+    };
+    format!("{}{}.{}", sign, grouped, cents)
+}
+
+pub fn format_full_precision(value: f64) -> String {
+    format!("{:.17}", value)
+        .trim_end_matches('0')
+        .trim_end_matches('.')
+        .to_string()
+}
+
+pub fn format_scientific(value: f64) -> String {
+    format!("{:e}", value)
+}
+
+/// Hexadecimal representation of integral values; `None` for fractional or
+/// out-of-range values.
+pub fn format_hex(value: f64) -> Option<String> {
+    if !value.is_finite() || value.fract() != 0.0 || value.abs() > i64::MAX as f64 {
+        return None;
+    }
+
+    let int = value as i64;
+    if int < 0 {
+        Some(format!("-0x{:X}", int.unsigned_abs()))
+    } else {
+        Some(format!("0x{:X}", int))
+    }
+}
+
+/// Best rational approximation `(numerator, denominator)` via continued fractions.
+pub fn approximate_fraction(value: f64) -> Option<(i64, i64)> {
+    if !value.is_finite() || value.abs() > i64::MAX as f64 {
+        return None;
+    }
+
+    let (mut prev_num, mut num) = (0_i64, 1_i64);
+    let (mut prev_den, mut den) = (1_i64, 0_i64);
+    let mut remainder = value.abs();
+
+    loop {
+        let whole = remainder.floor();
+        let next_num = (whole as i64)
+            .checked_mul(num)
+            .and_then(|n| n.checked_add(prev_num))?;
+        let next_den = (whole as i64)
+            .checked_mul(den)
+            .and_then(|d| d.checked_add(prev_den))?;
+        if next_den > MAX_DENOMINATOR {
+            break;
+        }
+
+        (prev_num, num) = (num, next_num);
+        (prev_den, den) = (den, next_den);
+
+        let fract = remainder - whole;
+        if (num as f64 / den as f64 - value.abs()).abs() < 1e-12 || fract < 1e-12 {
+            break;
+        }
+        remainder = 1.0 / fract;
+    }
+
+    if den == 0 {
+        return None;
+    }
+    Some((if value < 0.0 { -num } else { num }, den))
+}
+
+pub fn format_fraction(value: f64) -> Option<String> {
+    let (num, den) = approximate_fraction(value)?;
+    if den == 1 {
+        Some(num.to_string())
+    } else {
+        Some(format!("{}/{}", num, den))
+    }
+}
@@ -0,0 +1,105 @@
+use ratatui::crossterm::event::KeyEvent;
+
+use crate::{keymap::Keymap, prompt::Prompt};
+
+pub const SEARCH_PROMPT_LABEL: &str = "(reverse-i-search)`";
+
+/// Shell-style reverse incremental search over previously submitted expressions.
+///
+/// Matches are ordered newest first. Contiguous (substring) matches rank ahead of
+/// fuzzy matches where the query characters only appear in order.
+#[derive(Debug)]
+pub struct HistorySearch {
+    prompt: Prompt,
+    /// How many matches to skip, advanced by repeated `Ctrl+R`.
+    skip: usize,
+}
+
+impl Default for HistorySearch {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HistorySearch {
+    pub fn new() -> Self {
+        Self {
+            prompt: Prompt::new(SEARCH_PROMPT_LABEL),
+            skip: 0,
+        }
+    }
+
+    pub fn query(&self) -> &str {
+        self.prompt.text()
+    }
+
+    /// The prompt the query is typed into.
+    pub fn prompt(&self) -> &Prompt {
+        &self.prompt
+    }
+
+    pub fn push_char(&mut self, ch: char) {
+        self.prompt.insert_text(ch.encode_utf8(&mut [0; 4]));
+        self.skip = 0;
+    }
+
+    pub fn pop_char(&mut self) {
+        self.prompt.backspace();
+        self.skip = 0;
+    }
+
+    /// Edits the query with a key the search itself does not bind, such as a
+    /// typed character or a cursor motion.
+    pub fn edit(&mut self, key: KeyEvent, keymap: &Keymap) {
+        let before = self.query().to_string();
+        self.prompt.handle_key_event_with(key, keymap);
+        if self.query() != before {
+            self.skip = 0;
+        }
+    }
+
+    /// Moves on to the next older match, staying on the oldest one once reached.
+    pub fn next_match<'a>(&mut self, candidates: impl DoubleEndedIterator<Item = &'a str>) {
+        let count = search_matches(candidates, self.query()).len();
+        if self.skip + 1 < count {
+            self.skip += 1;
+        }
+    }
+
+    pub fn current_match<'a>(
+        &self,
+        candidates: impl DoubleEndedIterator<Item = &'a str>,
+    ) -> Option<&'a str> {
+        search_matches(candidates, self.query())
+            .get(self.skip)
+            .copied()
+    }
+}
+
+/// Returns the unique candidates matching `query`, newest (last) first.
+pub fn search_matches<'a>(
+    candidates: impl DoubleEndedIterator<Item = &'a str>,
+    query: &str,
+) -> Vec<&'a str> {
+    let mut contiguous: Vec<&str> = Vec::new();
+    let mut fuzzy: Vec<&str> = Vec::new();
+
+    for candidate in candidates.rev() {
+        if contiguous.contains(&candidate) || fuzzy.contains(&candidate) {
+            continue;
+        }
+        if candidate.contains(query) {
+            contiguous.push(candidate);
+        } else if is_subsequence(query, candidate) {
+            fuzzy.push(candidate);
+        }
+    }
+
+    contiguous.extend(fuzzy);
+    contiguous
+}
+
+fn is_subsequence(query: &str, candidate: &str) -> bool {
+    let mut remaining = candidate.chars();
+    query.chars().all(|q| remaining.any(|c| c == q))
+}
@@ -1,4 +1,7 @@
-use ratatui::crossterm::event::{KeyCode, KeyEvent};
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::keymap::{Action, KeyChord, KeyContext, Keymap, Resolution, default_keymap};
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum InputEditMode {
@@ -28,15 +31,21 @@ pub enum Motion {
 
 /// Reusable line editor with Vim-style insert/normal/visual modes and yank/paste support.
 ///
+/// Positions count grapheme clusters, so an emoji or a letter with combining
+/// accents is moved over and deleted as one character.
+///
 /// Cursor semantics:
 /// - `Insert`: cursor is between characters (`0..=len`)
 /// - `Normal`/`Visual`: cursor is on a character (`0..len-1`, or `0` when empty)
+#[derive(Debug)]
 pub struct InputEditor {
     input: String,
     cursor: usize,
     mode: InputEditMode,
     register: String,
     visual_anchor: Option<usize>,
+    /// Whether the editor stays in Insert mode, as in prompts
+    insert_only: bool,
 }
 
 impl Default for InputEditor {
@@ -53,17 +62,27 @@ impl InputEditor {
             mode: InputEditMode::Insert,
             register: String::new(),
             visual_anchor: None,
+            insert_only: false,
         }
     }
 
     pub fn with_input(input: String) -> Self {
-        let cursor = input.chars().count();
+        let cursor = input.graphemes(true).count();
         Self {
             input,
             cursor,
             mode: InputEditMode::Insert,
             register: String::new(),
             visual_anchor: None,
+            insert_only: false,
+        }
+    }
+
+    /// An editor that ignores switches to Normal and Visual mode, for prompts.
+    pub fn insert_only() -> Self {
+        Self {
+            insert_only: true,
+            ..Self::new()
         }
     }
 
@@ -83,6 +102,11 @@ impl InputEditor {
         &self.register
     }
 
+    /// Replaces the yank register, e.g. with text copied from another pane.
+    pub fn set_register(&mut self, text: String) {
+        self.register = text;
+    }
+
     pub fn visual_range(&self) -> Option<(usize, usize)> {
         let len = self.char_len();
         if len == 0 {
@@ -101,7 +125,7 @@ impl InputEditor {
 
     pub fn set_input(&mut self, input: String) {
         self.input = input;
-        self.cursor = self.input.chars().count();
+        self.cursor = self.char_len();
         self.mode = InputEditMode::Insert;
         self.visual_anchor = None;
     }
@@ -120,6 +144,9 @@ impl InputEditor {
     }
 
     pub fn switch_to_normal_mode(&mut self) {
+        if self.insert_only {
+            return;
+        }
         let previous_mode = self.mode;
         self.mode = InputEditMode::Normal;
         self.visual_anchor = None;
@@ -136,6 +163,9 @@ impl InputEditor {
     }
 
     pub fn switch_to_visual_mode(&mut self) {
+        if self.insert_only {
+            return;
+        }
         self.mode = InputEditMode::Visual;
         if self.char_len() == 0 {
             self.cursor = 0;
@@ -157,7 +187,10 @@ impl InputEditor {
     pub fn enter_char(&mut self, ch: char) {
         let byte_index = self.byte_index_from_char_index(self.cursor);
         self.input.insert(byte_index, ch);
-        self.cursor += 1;
+        // A combining mark joins the previous character instead of adding one.
+        self.cursor = self.input[..byte_index + ch.len_utf8()]
+            .graphemes(true)
+            .count();
     }
 
     pub fn backspace(&mut self) {
@@ -166,9 +199,7 @@ impl InputEditor {
         }
 
         let from_left = self.cursor - 1;
-        let before = self.input.chars().take(from_left);
-        let after = self.input.chars().skip(self.cursor);
-        self.input = before.chain(after).collect();
+        self.remove_char_range(from_left, self.cursor);
         self.cursor -= 1;
     }
 
@@ -178,9 +209,7 @@ impl InputEditor {
             return;
         }
 
-        let before = self.input.chars().take(self.cursor);
-        let after = self.input.chars().skip(self.cursor + 1);
-        self.input = before.chain(after).collect();
+        self.remove_char_range(self.cursor, self.cursor + 1);
 
         let new_len = self.char_len();
         if new_len == 0 {
@@ -209,9 +238,7 @@ impl InputEditor {
             return;
         };
 
-        let before = self.input.chars().take(from);
-        let after = self.input.chars().skip(to_inclusive + 1);
-        self.input = before.chain(after).collect();
+        self.remove_char_range(from, to_inclusive + 1);
 
         let new_len = self.char_len();
         if new_len == 0 {
@@ -222,7 +249,7 @@ impl InputEditor {
     }
 
     pub fn yank_with_motion(&mut self, motion: Motion) {
-        let chars: Vec<char> = self.input.chars().collect();
+        let chars: Vec<&str> = self.input.graphemes(true).collect();
         let len = chars.len();
         if len == 0 {
             self.register.clear();
@@ -282,7 +309,7 @@ impl InputEditor {
         }
 
         let register = self.register.clone();
-        let reg_len = register.chars().count();
+        let reg_len = register.graphemes(true).count();
         let len = self.char_len();
         let insert_at = if len == 0 {
             0
@@ -306,7 +333,7 @@ impl InputEditor {
         }
 
         let register = self.register.clone();
-        let reg_len = register.chars().count();
+        let reg_len = register.graphemes(true).count();
         let insert_at = self.cursor.min(self.char_len());
 
         self.insert_str_at_char_index(insert_at, &register);
@@ -318,6 +345,43 @@ impl InputEditor {
         self.clamp_cursor_for_mode();
     }
 
+    /// Replaces the characters from `start` up to the cursor with `text`, leaving
+    /// the cursor after it.
+    pub fn replace_before_cursor(&mut self, start: usize, text: &str) {
+        let start = start.min(self.cursor);
+        self.remove_char_range(start, self.cursor);
+        self.insert_str_at_char_index(start, text);
+        self.cursor = start + text.graphemes(true).count();
+    }
+
+    /// Inserts pasted `text` in one step, dropping line breaks. Insert mode puts it
+    /// at the cursor; the other modes put it after the cursor like `p`.
+    pub fn insert_text(&mut self, text: &str) {
+        let text: String = text
+            .chars()
+            .filter(|ch| !matches!(ch, '\n' | '\r'))
+            .collect();
+        let len = text.graphemes(true).count();
+        if len == 0 {
+            return;
+        }
+
+        if self.mode == InputEditMode::Insert {
+            let insert_at = self.cursor.min(self.char_len());
+            self.insert_str_at_char_index(insert_at, &text);
+            self.cursor = insert_at + len;
+        } else {
+            let insert_at = if self.char_len() == 0 {
+                0
+            } else {
+                (self.cursor + 1).min(self.char_len())
+            };
+            self.insert_str_at_char_index(insert_at, &text);
+            self.cursor = insert_at + len - 1;
+            self.clamp_cursor_for_mode();
+        }
+    }
+
     pub fn motion_from_key(code: KeyCode) -> Option<Motion> {
         match code {
             KeyCode::Left | KeyCode::Char('h') => Some(Motion::Left),
@@ -330,54 +394,41 @@ impl InputEditor {
         }
     }
 
-    /// Handles only input-editor concerns.
+    /// Handles only input-editor concerns using the default keymap.
     /// Caller can route `EditorCommand` to app-level actions.
     pub fn handle_key_event(&mut self, key: KeyEvent) -> EditorCommand {
-        match self.mode {
-            InputEditMode::Insert => self.handle_insert_key(key.code),
-            InputEditMode::Normal => self.handle_normal_key(key.code),
-            InputEditMode::Visual => self.handle_visual_key(key.code),
-        }
+        self.handle_key_event_with(key, default_keymap())
     }
 
-    fn handle_insert_key(&mut self, code: KeyCode) -> EditorCommand {
-        match code {
-            KeyCode::Esc => {
-                self.switch_to_normal_mode();
-                EditorCommand::None
-            }
-            KeyCode::Enter => EditorCommand::Submit,
-            KeyCode::Char(ch) => {
-                self.enter_char(ch);
-                EditorCommand::None
-            }
-            KeyCode::Backspace => {
-                self.backspace();
-                EditorCommand::None
-            }
-            KeyCode::Left => {
-                self.move_insert_left();
-                EditorCommand::None
-            }
-            KeyCode::Right => {
-                self.move_insert_right();
+    pub fn handle_key_event_with(&mut self, key: KeyEvent, keymap: &Keymap) -> EditorCommand {
+        match keymap.resolve(KeyContext::from(self.mode), &[KeyChord::from(key)]) {
+            Resolution::Action(action) => self.apply_action(action),
+            Resolution::Pending | Resolution::Unbound => {
+                self.insert_unbound_key(key);
                 EditorCommand::None
             }
-            _ => EditorCommand::None,
         }
     }
 
-    fn handle_normal_key(&mut self, code: KeyCode) -> EditorCommand {
-        match code {
-            KeyCode::Enter => EditorCommand::Submit,
-            KeyCode::Tab => EditorCommand::IncrementFocus,
-            KeyCode::BackTab => EditorCommand::DecrementFocus,
+    /// Types the character of an unbound key when in Insert mode; other keys are ignored.
+    pub fn insert_unbound_key(&mut self, key: KeyEvent) {
+        if let (InputEditMode::Insert, KeyCode::Char(ch)) = (self.mode, key.code)
+            && !key
+                .modifiers
+                .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT)
+        {
+            self.enter_char(ch);
+        }
+    }
 
-            KeyCode::Char('i') => {
-                self.switch_to_insert_mode();
-                EditorCommand::None
-            }
-            KeyCode::Char('a') => {
+    /// Applies an editor action. Actions that are not editor concerns are ignored.
+    pub fn apply_action(&mut self, action: Action) -> EditorCommand {
+        match action {
+            Action::Submit => return EditorCommand::Submit,
+            Action::FocusNext => return EditorCommand::IncrementFocus,
+            Action::FocusPrev => return EditorCommand::DecrementFocus,
+            Action::InsertMode => self.switch_to_insert_mode(),
+            Action::Append => {
                 let len = self.char_len();
                 if len == 0 {
                     self.cursor = 0;
@@ -385,78 +436,65 @@ impl InputEditor {
                     self.cursor = (self.cursor + 1).min(len);
                 }
                 self.switch_to_insert_mode();
-                EditorCommand::None
             }
-            KeyCode::Char('I') => {
+            Action::InsertLineStart => {
                 self.cursor = 0;
                 self.switch_to_insert_mode();
-                EditorCommand::None
             }
-            KeyCode::Char('A') => {
+            Action::AppendLineEnd => {
                 self.cursor = self.char_len();
                 self.switch_to_insert_mode();
-                EditorCommand::None
-            }
-
-            KeyCode::Char('x') => {
-                self.delete_under_cursor();
-                EditorCommand::None
-            }
-            KeyCode::Char('v') => {
-                self.switch_to_visual_mode();
-                EditorCommand::None
-            }
-            KeyCode::Char('p') => {
-                self.paste_after();
-                EditorCommand::None
-            }
-            KeyCode::Char('P') => {
-                self.paste_before();
-                EditorCommand::None
-            }
-
-            other => {
-                if let Some(motion) = Self::motion_from_key(other) {
-                    self.apply_motion(motion);
-                }
-                EditorCommand::None
-            }
-        }
-    }
-
-    fn handle_visual_key(&mut self, code: KeyCode) -> EditorCommand {
-        match code {
-            KeyCode::Esc | KeyCode::Char('v') => {
-                self.switch_to_normal_mode();
-                EditorCommand::None
             }
-            KeyCode::Enter => EditorCommand::Submit,
-            KeyCode::Char('y') => {
+            Action::NormalMode => self.switch_to_normal_mode(),
+            Action::VisualMode => self.switch_to_visual_mode(),
+            Action::MoveLeft => self.apply_mode_motion(Motion::Left),
+            Action::MoveRight => self.apply_mode_motion(Motion::Right),
+            Action::LineStart => self.apply_mode_motion(Motion::LineStart),
+            Action::LineEnd => self.apply_mode_motion(Motion::LineEnd),
+            Action::WordForward => self.apply_mode_motion(Motion::WordForward),
+            Action::WordBackward => self.apply_mode_motion(Motion::WordBackward),
+            Action::Backspace => self.backspace(),
+            Action::DeleteChar => self.delete_under_cursor(),
+            Action::PasteAfter => self.paste_after(),
+            Action::PasteBefore => self.paste_before(),
+            Action::YankSelection => {
                 let yanked_range = self.visual_range();
                 self.yank_visual_selection();
                 self.switch_to_normal_mode();
                 if let Some((start, end)) = yanked_range {
-                    EditorCommand::Yanked { start, end }
-                } else {
-                    EditorCommand::None
+                    return EditorCommand::Yanked { start, end };
                 }
             }
-            KeyCode::Char('d') | KeyCode::Char('x') => {
+            Action::DeleteSelection => {
                 self.delete_visual_selection();
                 self.switch_to_normal_mode();
-                EditorCommand::None
             }
-            other => {
-                if let Some(motion) = Self::motion_from_key(other) {
-                    self.apply_motion(motion);
-                }
-                EditorCommand::None
+            _ => {}
+        }
+        EditorCommand::None
+    }
+
+    /// Moves like `apply_motion`, but honors the between-characters cursor of Insert mode.
+    fn apply_mode_motion(&mut self, motion: Motion) {
+        if !matches!(self.mode, InputEditMode::Insert) {
+            self.apply_motion(motion);
+            return;
+        }
+
+        match motion {
+            Motion::Left => self.move_insert_left(),
+            Motion::Right => self.move_insert_right(),
+            Motion::LineStart => self.cursor = 0,
+            Motion::LineEnd => self.cursor = self.char_len(),
+            Motion::WordForward | Motion::WordBackward => {
+                self.apply_motion(motion);
+                self.clamp_cursor_for_mode();
             }
         }
     }
 
     fn motion_target(&self, motion: Motion) -> usize {
-        let chars: Vec<char> = self.input.chars().collect();
+        let chars: Vec<&str> = self.input.graphemes(true).collect();
         let len = chars.len();
         if len == 0 {
             return 0;
@@ -501,12 +539,15 @@ impl InputEditor {
         }
     }
 
-    fn is_word_char(c: char) -> bool {
-        c.is_alphanumeric() || c == '_'
+    fn is_word_char(grapheme: &str) -> bool {
+        grapheme
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_alphanumeric() || c == '_')
     }
 
     fn char_len(&self) -> usize {
-        self.input.chars().count()
+        self.input.graphemes(true).count()
     }
 
     fn clamp_cursor_for_mode(&mut self) {
@@ -525,7 +566,7 @@ impl InputEditor {
 
     fn byte_index_from_char_index(&self, char_index: usize) -> usize {
         self.input
-            .char_indices()
+            .grapheme_indices(true)
             .map(|(i, _)| i)
             .nth(char_index)
             .unwrap_or(self.input.len())
@@ -537,10 +578,14 @@ impl InputEditor {
     }
 
     fn slice_char_range(&self, start_inclusive: usize, end_exclusive: usize) -> String {
-        self.input
-            .chars()
-            .skip(start_inclusive)
-            .take(end_exclusive.saturating_sub(start_inclusive))
-            .collect()
+        let start = self.byte_index_from_char_index(start_inclusive);
+        let end = self.byte_index_from_char_index(end_exclusive.max(start_inclusive));
+        self.input[start..end].to_string()
+    }
+
+    fn remove_char_range(&mut self, start_inclusive: usize, end_exclusive: usize) {
+        let start = self.byte_index_from_char_index(start_inclusive);
+        let end = self.byte_index_from_char_index(end_exclusive);
+        self.input.replace_range(start..end, "");
     }
 }
@@ -1,10 +1,17 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 
-use crate::types::VariableEntry;
+use crate::{
+    builtins::{CONSTANTS, FUNCTIONS, is_builtin},
+    definitions::FunctionDefinition,
+    error::CalcError,
+    tokenize::{Lexer, Token, TokenKind, split_comment, tokenize},
+    types::{History, HistoryKind, VariableEntry, VariableSort},
+    variables::parse_variables,
+};
 
 const OPERATORS: &[&str] = &["+", "-", "*", "/", "^"];
 
-const PHRASE_LIMITERS: &[&str] = &["(", ")"];
+const PHRASE_LIMITERS: &[&str] = &["(", ")", ","];
 
 pub fn inspect_unknown_variables(
     tokens: &Vec<&str>,
@@ -19,7 +26,7 @@ pub fn inspect_unknown_variables(
         if OPERATORS.contains(t) | PHRASE_LIMITERS.contains(t) {
             continue;
         }
-        if variables.get(*t).is_some() {
+        if variables.get(*t).is_some() || is_builtin(t) {
             continue;
         }
         if !unknown_variables.contains(&t.to_string()) {
@@ -29,3 +36,321 @@ pub fn inspect_unknown_variables(
 
     unknown_variables
 }
+
+/// Every `)` without a matching `(` and every `(` that is never closed, as
+/// [`CalcError::UnmatchedParen`] and [`CalcError::UnclosedParen`] in input order.
+pub fn unmatched_parens(tokens: &[Token]) -> Vec<CalcError> {
+    let mut unmatched = Vec::new();
+    let mut open: Vec<usize> = Vec::new();
+    for token in tokens {
+        match token.kind {
+            TokenKind::LParen => open.push(token.span.start),
+            TokenKind::RParen if open.pop().is_none() => {
+                unmatched.push(CalcError::UnmatchedParen(token.span.start))
+            }
+            _ => {}
+        }
+    }
+    unmatched.extend(open.into_iter().map(CalcError::UnclosedParen));
+    unmatched.sort_by_key(|error| error.position());
+    unmatched
+}
+
+/// Operators without an operand on one side, as [`CalcError::MissingLeftOperand`]
+/// and [`CalcError::MissingRightOperand`] in input order. `+` and `-` need none
+/// before them, being signs there.
+pub fn missing_operands(tokens: &[Token]) -> Vec<CalcError> {
+    let mut missing = Vec::new();
+    for (idx, token) in tokens.iter().enumerate() {
+        if token.kind != TokenKind::Operator || token.is_implicit() {
+            continue;
+        }
+        let sign = matches!(token.text, "+" | "-");
+        let operand_before = idx.checked_sub(1).is_some_and(|before| {
+            matches!(
+                tokens[before].kind,
+                TokenKind::Number
+                    | TokenKind::HistoryRef
+                    | TokenKind::Identifier
+                    | TokenKind::RParen
+            )
+        });
+        if !sign && !operand_before {
+            missing.push(CalcError::MissingLeftOperand {
+                operator: token.text.to_string(),
+                position: token.span.start,
+            });
+        }
+        let operand_after = tokens.get(idx + 1).is_some_and(|after| match after.kind {
+            TokenKind::Operator => matches!(after.text, "+" | "-"),
+            TokenKind::Equals | TokenKind::RParen | TokenKind::Comma => false,
+            _ => true,
+        });
+        if !operand_after {
+            missing.push(CalcError::MissingRightOperand {
+                operator: token.text.to_string(),
+                position: token.span.start,
+            });
+        }
+    }
+    missing
+}
+
+/// Known names that words typed in `expression` were probably meant to be, e.g.
+/// `sqrt` for `sqr(4)`. Words are only looked at when they contain unknown
+/// variables, since the tokenizer splits `sqr` into the variables `s`, `q`, `r`.
+pub fn did_you_mean(expression: &str, variables: &HashMap<String, VariableEntry>) -> Vec<String> {
+    let (expression, _) = split_comment(expression);
+    let tokens = tokenize(expression);
+    let unknown = inspect_unknown_variables(&tokens, variables);
+    let known: Vec<&str> = variables
+        .keys()
+        .map(String::as_str)
+        .chain(FUNCTIONS.iter().copied())
+        .chain(CONSTANTS.iter().map(|(name, _)| *name))
+        .collect();
+
+    let mut suggestions: Vec<String> = Vec::new();
+    for word in expression.split(|c: char| !c.is_ascii_alphabetic()) {
+        if word.len() < 2
+            || !word.chars().any(|c| {
+                unknown
+                    .iter()
+                    .any(|name| name.len() == 1 && name.starts_with(c))
+            })
+        {
+            continue;
+        }
+        let closest = known
+            .iter()
+            .map(|name| (edit_distance(word, name), *name))
+            .filter(|(distance, _)| *distance > 0 && *distance <= (word.len() / 3).max(1))
+            .min();
+        if let Some((_, name)) = closest
+            && !suggestions.iter().any(|suggestion| suggestion == name)
+        {
+            suggestions.push(name.to_string());
+        }
+    }
+    suggestions
+}
+
+/// Levenshtein distance between `a` and `b`.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// Collects the current values of all known variables referenced by `tokens`.
+pub fn referenced_variables(
+    tokens: &[&str],
+    variables: &HashMap<String, VariableEntry>,
+) -> BTreeMap<String, f64> {
+    tokens
+        .iter()
+        .filter_map(|t| variables.get(*t).map(|entry| (t.to_string(), entry.value)))
+        .collect()
+}
+
+/// Who-references-whom between stored variables, parsed from their expressions.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct DependencyGraph {
+    /// Variables each variable's expression references
+    pub references: BTreeMap<String, BTreeSet<String>>,
+    /// Variables whose expressions reference each variable
+    pub dependents: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl DependencyGraph {
+    pub fn references_of(&self, name: &str) -> impl Iterator<Item = &str> {
+        self.references
+            .get(name)
+            .into_iter()
+            .flatten()
+            .map(String::as_str)
+    }
+
+    pub fn dependents_of(&self, name: &str) -> impl Iterator<Item = &str> {
+        self.dependents
+            .get(name)
+            .into_iter()
+            .flatten()
+            .map(String::as_str)
+    }
+
+    /// All variables with references first, so each comes after the ones its
+    /// expression uses. Fails with the variables of a cycle, e.g. after `a=b+1`
+    /// and then `b=a*2`, listed in reference order and starting and ending with
+    /// the same name.
+    pub fn topological_order(&self) -> Result<Vec<String>, Vec<String>> {
+        self.order(self.references.keys().map(String::as_str).collect())
+    }
+
+    /// The variables to recompute after `name` changes: everything depending on it
+    /// directly or indirectly, in topological order. Fails like
+    /// [`topological_order`](Self::topological_order) when `name` or one of them is
+    /// part of a cycle.
+    pub fn recompute_order(&self, name: &str) -> Result<Vec<String>, Vec<String>> {
+        let mut affected = BTreeSet::from([name]);
+        let mut pending = vec![name];
+        while let Some(current) = pending.pop() {
+            for dependent in self.dependents_of(current) {
+                if affected.insert(dependent) {
+                    pending.push(dependent);
+                }
+            }
+        }
+        let mut order = self.order(affected)?;
+        order.retain(|affected| affected != name);
+        Ok(order)
+    }
+
+    /// Whether `name` references itself, directly or through other variables.
+    pub fn is_cyclic(&self, name: &str) -> bool {
+        let mut seen = BTreeSet::new();
+        let mut pending: Vec<&str> = self.references_of(name).collect();
+        while let Some(current) = pending.pop() {
+            if current == name {
+                return true;
+            }
+            if seen.insert(current) {
+                pending.extend(self.references_of(current));
+            }
+        }
+        false
+    }
+
+    /// Depth-first topological sort of `names`, ignoring references to other variables.
+    fn order(&self, names: BTreeSet<&str>) -> Result<Vec<String>, Vec<String>> {
+        let mut done = BTreeSet::new();
+        let mut order = Vec::new();
+        for name in &names {
+            let mut path = Vec::new();
+            self.visit(name, &names, &mut path, &mut done, &mut order)?;
+        }
+        Ok(order)
+    }
+
+    fn visit<'a>(
+        &'a self,
+        name: &'a str,
+        names: &BTreeSet<&str>,
+        path: &mut Vec<&'a str>,
+        done: &mut BTreeSet<&'a str>,
+        order: &mut Vec<String>,
+    ) -> Result<(), Vec<String>> {
+        if done.contains(name) {
+            return Ok(());
+        }
+        if let Some(start) = path.iter().position(|visiting| *visiting == name) {
+            let mut cycle: Vec<String> = path[start..].iter().map(|n| n.to_string()).collect();
+            cycle.push(name.to_string());
+            return Err(cycle);
+        }
+        path.push(name);
+        for referenced in self.references_of(name) {
+            if names.contains(referenced) {
+                self.visit(referenced, names, path, done, order)?;
+            }
+        }
+        path.pop();
+        done.insert(name);
+        order.push(name.to_string());
+        Ok(())
+    }
+}
+
+pub fn dependency_graph(variables: &HashMap<String, VariableEntry>) -> DependencyGraph {
+    let mut graph = DependencyGraph::default();
+
+    for (name, entry) in variables {
+        let tokens = tokenize(&entry.expression);
+        let value_tokens = match parse_variables(tokens.clone()) {
+            Ok(parsed) => parsed.tokens,
+            Err(_) => tokens,
+        };
+
+        let references: BTreeSet<String> = value_tokens
+            .iter()
+            .filter(|t| variables.contains_key(**t))
+            .map(|t| t.to_string())
+            .collect();
+
+        for referenced in &references {
+            graph
+                .dependents
+                .entry(referenced.clone())
+                .or_default()
+                .insert(name.clone());
+        }
+        graph.references.insert(name.clone(), references);
+    }
+
+    graph
+}
+
+/// Which of `functions` each one's body calls, as a graph like the one of
+/// variables, so they can be defined in an order where each call resolves.
+pub fn function_graph(functions: &[FunctionDefinition]) -> DependencyGraph {
+    let names: Vec<&str> = functions.iter().map(|f| f.name.as_str()).collect();
+    let mut graph = DependencyGraph::default();
+
+    for function in functions {
+        let references: BTreeSet<String> = Lexer::new(&function.body)
+            .with_functions(names.iter().copied())
+            .filter(|token| token.kind == TokenKind::Function && names.contains(&token.text))
+            .map(|token| token.text.to_string())
+            .collect();
+
+        for referenced in &references {
+            graph
+                .dependents
+                .entry(referenced.clone())
+                .or_default()
+                .insert(function.name.clone());
+        }
+        graph.references.insert(function.name.clone(), references);
+    }
+
+    graph
+}
+
+/// Variable names in the order the Variables pane shows them for `sort`, using
+/// History to tell which were used or defined most recently. Variables History no
+/// longer mentions come last, alphabetically.
+pub fn sorted_variable_names(
+    variables: &HashMap<String, VariableEntry>,
+    history: &[History],
+    sort: VariableSort,
+) -> Vec<String> {
+    let mut names: Vec<String> = variables.keys().cloned().collect();
+    names.sort();
+    if sort == VariableSort::Alphabetical {
+        return names;
+    }
+
+    let mut last_seen: HashMap<&str, usize> = HashMap::new();
+    for (idx, entry) in history.iter().enumerate() {
+        if entry.kind == HistoryKind::Assignment
+            && let Some((name, _)) = entry.expression.split_once('=')
+        {
+            last_seen.insert(name.trim(), idx);
+        }
+        if sort == VariableSort::RecentlyUsed {
+            for name in entry.variables.keys() {
+                last_seen.insert(name, idx);
+            }
+        }
+    }
+    names.sort_by_key(|name| std::cmp::Reverse(last_seen.get(name.as_str()).copied()));
+    names
+}
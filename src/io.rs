@@ -1,39 +1,312 @@
-use std::{env, fs, io::Error, path::PathBuf};
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
 
-use crate::types::AppState;
+use serde::{Serialize, de::DeserializeOwned};
 
-pub fn create_rcalc_dir() -> Result<(), std::io::Error> {
+use crate::{
+    error::StateError,
+    types::{AppState, DEFAULT_SESSION, VariablePack},
+};
+
+pub fn create_rcalc_dir() -> Result<(), StateError> {
     fs::create_dir_all(get_config_dir()?)?;
     Ok(())
 }
 
-pub fn write_state_to_file(app: &AppState) -> Result<(), std::io::Error> {
+pub fn write_state_to_file(app: &AppState) -> Result<(), StateError> {
+    write_session_state(DEFAULT_SESSION, app)
+}
+
+pub fn get_state_from_file() -> Result<AppState, StateError> {
+    read_session_state(DEFAULT_SESSION)
+}
+
+/// How a state file is written, chosen by its extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StateFormat {
+    Json,
+    /// Easier to edit by hand, e.g. to adjust a variable
+    Toml,
+}
+
+impl StateFormat {
+    pub const ALL: [StateFormat; 2] = [StateFormat::Json, StateFormat::Toml];
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            StateFormat::Json => "json",
+            StateFormat::Toml => "toml",
+        }
+    }
+
+    pub fn from_path(path: &Path) -> Option<StateFormat> {
+        let extension = path.extension()?;
+        Self::ALL
+            .into_iter()
+            .find(|format| extension == format.extension())
+    }
+
+    /// The format new state files are created in: `RCALC_STATE_FORMAT=toml`, or JSON.
+    pub fn preferred() -> StateFormat {
+        match env::var("RCALC_STATE_FORMAT").as_deref() {
+            Ok("toml") => StateFormat::Toml,
+            _ => StateFormat::Json,
+        }
+    }
+
+    /// Serializes a state, or another saved structure such as a [`VariablePack`].
+    pub fn serialize<T: Serialize>(self, value: &T) -> Result<String, StateError> {
+        Ok(match self {
+            StateFormat::Json => serde_json::to_string(value)?,
+            StateFormat::Toml => toml::to_string_pretty(value)?,
+        })
+    }
+
+    pub fn deserialize<T: DeserializeOwned>(self, data: &str) -> Result<T, StateError> {
+        Ok(match self {
+            StateFormat::Json => serde_json::from_str(data)?,
+            StateFormat::Toml => toml::from_str(data)?,
+        })
+    }
+}
+
+/// Writes `app` to `path` in the format its extension names.
+pub fn write_state_file(path: &Path, app: &AppState) -> Result<(), StateError> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let format = StateFormat::from_path(path).unwrap_or(StateFormat::Json);
+    fs::write(path, format.serialize(app)?)?;
+    Ok(())
+}
+
+pub fn read_state_file(path: &Path) -> Result<AppState, StateError> {
+    let data = fs::read_to_string(path)?;
+    StateFormat::from_path(path)
+        .unwrap_or(StateFormat::Json)
+        .deserialize(&data)
+}
+
+/// Writes `pack` to `path`, in `format` or the one its extension names.
+pub fn write_variable_pack(
+    path: &Path,
+    pack: &VariablePack,
+    format: Option<StateFormat>,
+) -> Result<(), StateError> {
+    let format = format
+        .or_else(|| StateFormat::from_path(path))
+        .unwrap_or(StateFormat::Json);
+    fs::write(path, format.serialize(pack)?)?;
+    Ok(())
+}
+
+pub fn read_variable_pack(path: &Path) -> Result<VariablePack, StateError> {
+    let data = fs::read_to_string(path)?;
+    StateFormat::from_path(path)
+        .unwrap_or(StateFormat::Json)
+        .deserialize(&data)
+}
+
+/// `base` with the extension of the format it already exists in, trying the
+/// preferred format first, or of the preferred format for a new file.
+fn existing_or_preferred(base: PathBuf) -> PathBuf {
+    let preferred = StateFormat::preferred();
+    let mut formats = StateFormat::ALL.to_vec();
+    formats.sort_by_key(|format| *format != preferred);
+    formats
+        .into_iter()
+        .map(|format| base.with_extension(format.extension()))
+        .find(|path| path.exists())
+        .unwrap_or_else(|| base.with_extension(preferred.extension()))
+}
+
+pub fn write_session_state(session: &str, app: &AppState) -> Result<(), StateError> {
+    write_state_file(&session_state_path(session)?, app)
+}
+
+pub fn read_session_state(session: &str) -> Result<AppState, StateError> {
+    read_state_file(&session_state_path(session)?)
+}
+
+/// Saves `app` as `session`'s state while holding the session's lock file,
+/// merging in what other instances saved since `base` was read, so concurrent
+/// sessions don't clobber each other's variables. Returns the state written.
+pub fn save_session_state_merged(
+    session: &str,
+    mut app: AppState,
+    base: &AppState,
+) -> Result<AppState, StateError> {
+    let path = session_state_path(session)?;
+    let _lock = lock_state_file(&path)?;
+    match read_state_file(&path) {
+        Ok(theirs) => app.merge_saved(base, &theirs),
+        Err(err) if err.is_not_found() => {}
+        Err(err) => return Err(err),
+    }
+    write_state_file(&path, &app)?;
+    Ok(app)
+}
+
+/// Takes an exclusive advisory lock on the `.lock` file next to `path`,
+/// blocking until other instances release it. Dropping the file unlocks it.
+fn lock_state_file(path: &Path) -> Result<fs::File, StateError> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let file = fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(path.with_extension("lock"))?;
+    file.lock()?;
+    Ok(file)
+}
+
+/// State file of `session`: the default session keeps using `state.json` (or
+/// `state.toml`), named sessions live in `sessions/<name>.json`.
+pub fn session_state_path(session: &str) -> Result<PathBuf, StateError> {
+    if session == DEFAULT_SESSION {
+        return get_state_file_path();
+    }
+    Ok(existing_or_preferred(
+        get_config_dir()?.join("sessions").join(session),
+    ))
+}
+
+/// Names of all sessions with a state file, the default session first.
+pub fn list_sessions() -> Result<Vec<String>, StateError> {
+    let mut sessions: Vec<String> = match fs::read_dir(get_config_dir()?.join("sessions")) {
+        Ok(entries) => entries
+            .filter_map(|entry| {
+                let path = entry.ok()?.path();
+                StateFormat::from_path(&path)?;
+                path.file_stem()?.to_str().map(String::from)
+            })
+            .collect(),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+        Err(err) => return Err(err.into()),
+    };
+    sessions.sort();
+    sessions.dedup();
+    sessions.insert(0, DEFAULT_SESSION.to_string());
+    Ok(sessions)
+}
+
+/// Session and snapshot names are used as file names, so only letters, digits, `-`,
+/// and `_` are allowed.
+pub fn is_valid_state_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || ch == '-' || ch == '_')
+}
+
+/// Snapshots are saved with `:snapshot save <name>` in `snapshots/<name>.json`.
+pub fn snapshot_path(name: &str) -> Result<PathBuf, StateError> {
+    Ok(existing_or_preferred(
+        get_config_dir()?.join("snapshots").join(name),
+    ))
+}
+
+pub fn write_snapshot(name: &str, app: &AppState) -> Result<(), StateError> {
+    write_state_file(&snapshot_path(name)?, app)
+}
+
+pub fn read_snapshot(name: &str) -> Result<AppState, StateError> {
+    read_state_file(&snapshot_path(name)?)
+}
+
+/// Mapping lines (see `Keymap::apply_mapping`) applied at startup, one per line.
+pub fn keymap_path() -> Result<PathBuf, StateError> {
+    Ok(get_config_dir()?.join("keymap"))
+}
+
+pub fn read_keymap_lines() -> Result<Vec<String>, StateError> {
+    read_config_lines(&keymap_path()?)
+}
+
+pub fn append_keymap_lines(lines: &[String]) -> Result<(), StateError> {
+    append_lines(&keymap_path()?, lines)
+}
+
+/// Snippet definitions (see `Snippet::parse`) offered by `:snippet`, one per line.
+pub fn snippets_path() -> Result<PathBuf, StateError> {
+    Ok(get_config_dir()?.join("snippets"))
+}
+
+pub fn read_snippet_lines() -> Result<Vec<String>, StateError> {
+    read_config_lines(&snippets_path()?)
+}
+
+/// Non-empty lines of a config file that are not `#` comments; none if it is missing.
+fn read_config_lines(path: &Path) -> Result<Vec<String>, StateError> {
+    let data = match fs::read_to_string(path) {
+        Ok(data) => data,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err.into()),
+    };
+    Ok(data
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(String::from)
+        .collect())
+}
+
+fn append_lines(path: &Path, lines: &[String]) -> Result<(), StateError> {
+    use std::io::Write;
+
     create_rcalc_dir()?;
-    let json = serde_json::to_string(app).map_err(Error::other)?;
-    fs::write(get_state_file_path()?, json)?;
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    for line in lines {
+        writeln!(file, "{}", line)?;
+    }
     Ok(())
 }
 
-pub fn get_state_from_file() -> Result<AppState, std::io::Error> {
-    let data = fs::read_to_string(get_state_file_path()?)?;
-    let state = serde_json::from_str(&data).map_err(Error::other)?;
-    Ok(state)
+/// Every submitted input line, oldest first, shared by all sessions.
+pub fn input_history_path() -> Result<PathBuf, StateError> {
+    Ok(get_config_dir()?.join("input_history"))
+}
+
+/// Input lines loaded at startup; older ones stay in the file but are not recalled.
+pub const INPUT_HISTORY_LIMIT: usize = 1000;
+
+/// The last `limit` lines of the input history.
+pub fn read_input_history(limit: usize) -> Result<Vec<String>, StateError> {
+    let data = match fs::read_to_string(input_history_path()?) {
+        Ok(data) => data,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err.into()),
+    };
+    let lines: Vec<&str> = data.lines().filter(|line| !line.is_empty()).collect();
+    let skip = lines.len().saturating_sub(limit);
+    Ok(lines[skip..].iter().map(|line| line.to_string()).collect())
+}
+
+pub fn append_input_history(line: &str) -> Result<(), StateError> {
+    append_lines(&input_history_path()?, &[line.to_string()])
 }
 
-pub fn reset_file_state() -> Result<(), std::io::Error> {
-    let file_path = get_state_file_path()?;
-    fs::remove_file(file_path)?;
+/// Removes the state file of `session`.
+pub fn reset_session_state(session: &str) -> Result<(), StateError> {
+    fs::remove_file(session_state_path(session)?)?;
     create_rcalc_dir()?;
     Ok(())
 }
 
-fn get_config_dir() -> Result<PathBuf, std::io::Error> {
+fn get_config_dir() -> Result<PathBuf, StateError> {
     match env::var("HOME") {
         Ok(home) => Ok(PathBuf::from(home).join(".config").join("rcalc")),
-        Err(err) => Err(Error::other(err)),
+        Err(_) => Err(StateError::NoHome),
     }
 }
 
-fn get_state_file_path() -> Result<PathBuf, std::io::Error> {
-    Ok(get_config_dir()?.join("state.json"))
+fn get_state_file_path() -> Result<PathBuf, StateError> {
+    Ok(existing_or_preferred(get_config_dir()?.join("state")))
 }
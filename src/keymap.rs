@@ -0,0 +1,686 @@
+use std::{fmt, sync::OnceLock};
+
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use crate::input_editor::InputEditMode;
+
+/// Where a binding applies. `Global` bindings are consulted after the active context.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum KeyContext {
+    Global,
+    Insert,
+    Normal,
+    Visual,
+    History,
+    Variables,
+    Plot,
+    Search,
+}
+
+impl KeyContext {
+    pub const ALL: [KeyContext; 8] = [
+        KeyContext::Global,
+        KeyContext::Insert,
+        KeyContext::Normal,
+        KeyContext::Visual,
+        KeyContext::History,
+        KeyContext::Variables,
+        KeyContext::Plot,
+        KeyContext::Search,
+    ];
+
+    /// Name used by `:map` and the keymap file, e.g. `normal`.
+    pub fn name(self) -> &'static str {
+        match self {
+            KeyContext::Global => "global",
+            KeyContext::Insert => "insert",
+            KeyContext::Normal => "normal",
+            KeyContext::Visual => "visual",
+            KeyContext::History => "history",
+            KeyContext::Variables => "variables",
+            KeyContext::Plot => "plot",
+            KeyContext::Search => "search",
+        }
+    }
+
+    pub fn named(name: &str) -> Option<KeyContext> {
+        Self::ALL.into_iter().find(|context| context.name() == name)
+    }
+
+    pub fn title(self) -> &'static str {
+        match self {
+            KeyContext::Global => "Global",
+            KeyContext::Insert => "Input: Insert mode",
+            KeyContext::Normal => "Input: Normal mode",
+            KeyContext::Visual => "Input: Visual mode",
+            KeyContext::History => "History pane",
+            KeyContext::Variables => "Variables pane",
+            KeyContext::Plot => "Plot pane",
+            KeyContext::Search => "Reverse search (Ctrl+R)",
+        }
+    }
+}
+
+impl From<InputEditMode> for KeyContext {
+    fn from(mode: InputEditMode) -> Self {
+        match mode {
+            InputEditMode::Insert => KeyContext::Insert,
+            InputEditMode::Normal => KeyContext::Normal,
+            InputEditMode::Visual => KeyContext::Visual,
+        }
+    }
+}
+
+/// Everything a key can be bound to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Action {
+    Quit,
+    Help,
+    Submit,
+    InsertMode,
+    Append,
+    InsertLineStart,
+    AppendLineEnd,
+    NormalMode,
+    VisualMode,
+    MoveLeft,
+    MoveRight,
+    LineStart,
+    LineEnd,
+    WordForward,
+    WordBackward,
+    Backspace,
+    DeleteChar,
+    PasteAfter,
+    PasteBefore,
+    YankSelection,
+    DeleteSelection,
+    RecallOlder,
+    RecallNewer,
+    CompleteNext,
+    CompletePrev,
+    Search,
+    SearchOlder,
+    SearchAccept,
+    SearchCancel,
+    SearchBackspace,
+    FunctionMenu,
+    FocusNext,
+    FocusPrev,
+    FocusInput,
+    FocusHistory,
+    FocusVariables,
+    SelectUp,
+    SelectDown,
+    SelectFirst,
+    SelectLast,
+    HalfPageUp,
+    HalfPageDown,
+    Select,
+    ShowDetails,
+    Note,
+    YankExpression,
+    YankResult,
+    ToggleDependencies,
+    CycleVariableSort,
+    GrowHistory,
+    ShrinkHistory,
+    GrowInput,
+    ShrinkInput,
+    ToggleVariablesPane,
+    TogglePlotPane,
+    TogglePlotStyle,
+    PlotCursorLeft,
+    PlotCursorRight,
+    NextTab,
+    PrevTab,
+}
+
+impl Action {
+    pub const ALL: [Action; 60] = [
+        Action::Quit,
+        Action::Help,
+        Action::Submit,
+        Action::InsertMode,
+        Action::Append,
+        Action::InsertLineStart,
+        Action::AppendLineEnd,
+        Action::NormalMode,
+        Action::VisualMode,
+        Action::MoveLeft,
+        Action::MoveRight,
+        Action::LineStart,
+        Action::LineEnd,
+        Action::WordForward,
+        Action::WordBackward,
+        Action::Backspace,
+        Action::DeleteChar,
+        Action::PasteAfter,
+        Action::PasteBefore,
+        Action::YankSelection,
+        Action::DeleteSelection,
+        Action::RecallOlder,
+        Action::RecallNewer,
+        Action::CompleteNext,
+        Action::CompletePrev,
+        Action::Search,
+        Action::SearchOlder,
+        Action::SearchAccept,
+        Action::SearchCancel,
+        Action::SearchBackspace,
+        Action::FunctionMenu,
+        Action::FocusNext,
+        Action::FocusPrev,
+        Action::FocusInput,
+        Action::FocusHistory,
+        Action::FocusVariables,
+        Action::SelectUp,
+        Action::SelectDown,
+        Action::SelectFirst,
+        Action::SelectLast,
+        Action::HalfPageUp,
+        Action::HalfPageDown,
+        Action::Select,
+        Action::ShowDetails,
+        Action::Note,
+        Action::YankExpression,
+        Action::YankResult,
+        Action::ToggleDependencies,
+        Action::CycleVariableSort,
+        Action::GrowHistory,
+        Action::ShrinkHistory,
+        Action::GrowInput,
+        Action::ShrinkInput,
+        Action::ToggleVariablesPane,
+        Action::TogglePlotPane,
+        Action::TogglePlotStyle,
+        Action::PlotCursorLeft,
+        Action::PlotCursorRight,
+        Action::NextTab,
+        Action::PrevTab,
+    ];
+
+    /// Name used by `:map` and the keymap file, e.g. `line_start`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::Quit => "quit",
+            Action::Help => "help",
+            Action::Submit => "submit",
+            Action::InsertMode => "insert_mode",
+            Action::Append => "append",
+            Action::InsertLineStart => "insert_line_start",
+            Action::AppendLineEnd => "append_line_end",
+            Action::NormalMode => "normal_mode",
+            Action::VisualMode => "visual_mode",
+            Action::MoveLeft => "move_left",
+            Action::MoveRight => "move_right",
+            Action::LineStart => "line_start",
+            Action::LineEnd => "line_end",
+            Action::WordForward => "word_forward",
+            Action::WordBackward => "word_backward",
+            Action::Backspace => "backspace",
+            Action::DeleteChar => "delete_char",
+            Action::PasteAfter => "paste_after",
+            Action::PasteBefore => "paste_before",
+            Action::YankSelection => "yank_selection",
+            Action::DeleteSelection => "delete_selection",
+            Action::RecallOlder => "recall_older",
+            Action::RecallNewer => "recall_newer",
+            Action::CompleteNext => "complete_next",
+            Action::CompletePrev => "complete_prev",
+            Action::Search => "search",
+            Action::SearchOlder => "search_older",
+            Action::SearchAccept => "search_accept",
+            Action::SearchCancel => "search_cancel",
+            Action::SearchBackspace => "search_backspace",
+            Action::FunctionMenu => "function_menu",
+            Action::FocusNext => "focus_next",
+            Action::FocusPrev => "focus_prev",
+            Action::FocusInput => "focus_input",
+            Action::FocusHistory => "focus_history",
+            Action::FocusVariables => "focus_variables",
+            Action::SelectUp => "select_up",
+            Action::SelectDown => "select_down",
+            Action::SelectFirst => "select_first",
+            Action::SelectLast => "select_last",
+            Action::HalfPageUp => "half_page_up",
+            Action::HalfPageDown => "half_page_down",
+            Action::Select => "select",
+            Action::ShowDetails => "show_details",
+            Action::Note => "note",
+            Action::YankExpression => "yank_expression",
+            Action::YankResult => "yank_result",
+            Action::ToggleDependencies => "toggle_dependencies",
+            Action::CycleVariableSort => "cycle_variable_sort",
+            Action::GrowHistory => "grow_history",
+            Action::ShrinkHistory => "shrink_history",
+            Action::GrowInput => "grow_input",
+            Action::ShrinkInput => "shrink_input",
+            Action::ToggleVariablesPane => "toggle_variables_pane",
+            Action::TogglePlotPane => "toggle_plot_pane",
+            Action::TogglePlotStyle => "toggle_plot_style",
+            Action::PlotCursorLeft => "plot_cursor_left",
+            Action::PlotCursorRight => "plot_cursor_right",
+            Action::NextTab => "next_tab",
+            Action::PrevTab => "prev_tab",
+        }
+    }
+
+    pub fn named(name: &str) -> Option<Action> {
+        Self::ALL.into_iter().find(|action| action.name() == name)
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Action::Quit => "Save and quit",
+            Action::Help => "Show this help",
+            Action::Submit => "Submit expression",
+            Action::InsertMode => "Enter Insert mode",
+            Action::Append => "Append after cursor",
+            Action::InsertLineStart => "Insert at line start",
+            Action::AppendLineEnd => "Append at line end",
+            Action::NormalMode => "Switch to Normal mode",
+            Action::VisualMode => "Enter Visual mode",
+            Action::MoveLeft => "Move cursor left",
+            Action::MoveRight => "Move cursor right",
+            Action::LineStart => "Line start",
+            Action::LineEnd => "Line end",
+            Action::WordForward => "Next word",
+            Action::WordBackward => "Previous word",
+            Action::Backspace => "Delete previous character",
+            Action::DeleteChar => "Delete character under cursor",
+            Action::PasteAfter => "Paste after cursor",
+            Action::PasteBefore => "Paste before cursor",
+            Action::YankSelection => "Yank selection",
+            Action::DeleteSelection => "Delete selection",
+            Action::RecallOlder => "Recall older input",
+            Action::RecallNewer => "Recall newer input",
+            Action::CompleteNext => "Complete name",
+            Action::CompletePrev => "Previous completion",
+            Action::Search => "Reverse search history",
+            Action::SearchOlder => "Older match",
+            Action::SearchAccept => "Insert match into input",
+            Action::SearchCancel => "Cancel search",
+            Action::SearchBackspace => "Delete last query character",
+            Action::FunctionMenu => "Insert a function from a list",
+            Action::FocusNext => "Focus next pane",
+            Action::FocusPrev => "Focus previous pane",
+            Action::FocusInput => "Return to input Insert mode",
+            Action::FocusHistory => "Focus History",
+            Action::FocusVariables => "Focus Variables",
+            Action::SelectUp => "Move selection up",
+            Action::SelectDown => "Move selection down",
+            Action::SelectFirst => "Jump to first item",
+            Action::SelectLast => "Jump to last item",
+            Action::HalfPageUp => "Move up half a page",
+            Action::HalfPageDown => "Move down half a page",
+            Action::Select => "Load selected item into input",
+            Action::ShowDetails => "Open details",
+            Action::Note => "Label entry with a note",
+            Action::YankExpression => "Yank expression",
+            Action::YankResult => "Yank result",
+            Action::ToggleDependencies => "Toggle dependency display",
+            Action::CycleVariableSort => "Sort by name / recently used / recently defined",
+            Action::GrowHistory => "Widen History pane",
+            Action::ShrinkHistory => "Narrow History pane",
+            Action::GrowInput => "Taller input area",
+            Action::ShrinkInput => "Shorter input area",
+            Action::ToggleVariablesPane => "Show/hide Variables pane",
+            Action::TogglePlotPane => "Show/hide plot pane",
+            Action::TogglePlotStyle => "Switch between scatter and line plot",
+            Action::PlotCursorLeft => "Trace previous point",
+            Action::PlotCursorRight => "Trace next point",
+            Action::NextTab => "Show the next workspace tab",
+            Action::PrevTab => "Show the previous workspace tab",
+        }
+    }
+}
+
+/// A single key press, normalized so bindings match regardless of how the
+/// terminal reports Shift on characters.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct KeyChord {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl KeyChord {
+    pub fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        let modifiers = match code {
+            KeyCode::Char(_) | KeyCode::BackTab => modifiers - KeyModifiers::SHIFT,
+            _ => modifiers,
+        };
+        Self { code, modifiers }
+    }
+
+    pub fn plain(code: KeyCode) -> Self {
+        Self::new(code, KeyModifiers::NONE)
+    }
+
+    pub fn ctrl(ch: char) -> Self {
+        Self::new(KeyCode::Char(ch), KeyModifiers::CONTROL)
+    }
+}
+
+/// Parses a key sequence as written in `:map`, e.g. `H`, `gg`, `Ctrl+x`, or
+/// `Space` (the inverse of [`KeyChord`]'s `Display`).
+pub fn parse_keys(text: &str) -> Result<Vec<KeyChord>, String> {
+    if text.is_empty() {
+        return Err("No keys given".to_string());
+    }
+    let mut modifiers = KeyModifiers::NONE;
+    let mut rest = text;
+    loop {
+        if let Some(after) = rest.strip_prefix("Ctrl+") {
+            modifiers |= KeyModifiers::CONTROL;
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix("Alt+") {
+            modifiers |= KeyModifiers::ALT;
+            rest = after;
+        } else {
+            break;
+        }
+    }
+
+    let named = match rest {
+        "Enter" => Some(KeyCode::Enter),
+        "Esc" => Some(KeyCode::Esc),
+        "Tab" => Some(KeyCode::Tab),
+        "Shift+Tab" => Some(KeyCode::BackTab),
+        "Backspace" => Some(KeyCode::Backspace),
+        "Delete" => Some(KeyCode::Delete),
+        "Space" => Some(KeyCode::Char(' ')),
+        "Home" => Some(KeyCode::Home),
+        "End" => Some(KeyCode::End),
+        "Up" => Some(KeyCode::Up),
+        "Down" => Some(KeyCode::Down),
+        "Left" => Some(KeyCode::Left),
+        "Right" => Some(KeyCode::Right),
+        "PageUp" => Some(KeyCode::PageUp),
+        "PageDown" => Some(KeyCode::PageDown),
+        _ => rest
+            .strip_prefix('F')
+            .and_then(|n| n.parse().ok())
+            .map(KeyCode::F),
+    };
+    if let Some(code) = named {
+        return Ok(vec![KeyChord::new(code, modifiers)]);
+    }
+
+    let mut chars = rest.chars();
+    match (chars.next(), chars.next()) {
+        (Some(ch), None) => Ok(vec![KeyChord::new(KeyCode::Char(ch), modifiers)]),
+        (Some(_), Some(_)) if modifiers.is_empty() => Ok(rest
+            .chars()
+            .map(|ch| KeyChord::plain(KeyCode::Char(ch)))
+            .collect()),
+        _ => Err(format!("Unknown key: {}", text)),
+    }
+}
+
+impl From<KeyEvent> for KeyChord {
+    fn from(key: KeyEvent) -> Self {
+        Self::new(key.code, key.modifiers)
+    }
+}
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "Ctrl+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "Alt+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            write!(f, "Shift+")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "Space"),
+            KeyCode::Char(ch) => write!(f, "{}", ch),
+            KeyCode::BackTab => write!(f, "Shift+Tab"),
+            KeyCode::PageUp => write!(f, "PageUp"),
+            KeyCode::PageDown => write!(f, "PageDown"),
+            KeyCode::F(n) => write!(f, "F{}", n),
+            other => write!(f, "{:?}", other),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Binding {
+    pub context: KeyContext,
+    pub keys: Vec<KeyChord>,
+    pub action: Action,
+}
+
+/// Result of looking up a (possibly partial) key sequence.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Resolution {
+    Action(Action),
+    /// The keys so far are the prefix of a longer binding.
+    Pending,
+    Unbound,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Keymap {
+    bindings: Vec<Binding>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        use Action::*;
+        use KeyContext as C;
+
+        let plain = KeyChord::plain;
+        let ch = |c: char| KeyChord::plain(KeyCode::Char(c));
+        let motions = [
+            (plain(KeyCode::Left), MoveLeft),
+            (ch('h'), MoveLeft),
+            (plain(KeyCode::Right), MoveRight),
+            (ch('l'), MoveRight),
+            (ch('0'), LineStart),
+            (ch('$'), LineEnd),
+            (ch('w'), WordForward),
+            (ch('b'), WordBackward),
+        ];
+
+        let mut keymap = Keymap {
+            bindings: Vec::new(),
+        };
+        let mut bind = |context: KeyContext, key: KeyChord, action: Action| {
+            keymap.bind(context, vec![key], action);
+        };
+
+        bind(C::Global, KeyChord::ctrl('c'), Quit);
+        let ctrl = |code: KeyCode| KeyChord::new(code, KeyModifiers::CONTROL);
+        bind(C::Global, ctrl(KeyCode::Right), GrowHistory);
+        bind(C::Global, ctrl(KeyCode::Left), ShrinkHistory);
+        bind(C::Global, ctrl(KeyCode::Down), GrowInput);
+        bind(C::Global, ctrl(KeyCode::Up), ShrinkInput);
+        let alt = |c: char| KeyChord::new(KeyCode::Char(c), KeyModifiers::ALT);
+        bind(C::Global, alt('v'), ToggleVariablesPane);
+        bind(C::Global, alt('p'), TogglePlotPane);
+
+        bind(C::Insert, plain(KeyCode::Enter), Submit);
+        bind(C::Insert, plain(KeyCode::Esc), NormalMode);
+        bind(C::Insert, plain(KeyCode::Backspace), Backspace);
+        bind(C::Insert, plain(KeyCode::Left), MoveLeft);
+        bind(C::Insert, plain(KeyCode::Right), MoveRight);
+        bind(C::Insert, plain(KeyCode::Up), RecallOlder);
+        bind(C::Insert, plain(KeyCode::Down), RecallNewer);
+        bind(C::Insert, plain(KeyCode::Tab), CompleteNext);
+        bind(C::Insert, plain(KeyCode::BackTab), CompletePrev);
+        bind(C::Insert, KeyChord::ctrl('r'), Search);
+        bind(C::Insert, KeyChord::ctrl('f'), FunctionMenu);
+
+        bind(C::Normal, plain(KeyCode::Enter), Submit);
+        bind(C::Normal, plain(KeyCode::Tab), FocusNext);
+        bind(C::Normal, plain(KeyCode::BackTab), FocusPrev);
+        bind(C::Normal, ch('i'), InsertMode);
+        bind(C::Normal, ch('a'), Append);
+        bind(C::Normal, ch('I'), InsertLineStart);
+        bind(C::Normal, ch('A'), AppendLineEnd);
+        bind(C::Normal, ch('x'), DeleteChar);
+        bind(C::Normal, ch('v'), VisualMode);
+        bind(C::Normal, ch('p'), PasteAfter);
+        bind(C::Normal, ch('P'), PasteBefore);
+        bind(C::Normal, KeyChord::ctrl('r'), Search);
+        bind(C::Normal, KeyChord::ctrl('f'), FunctionMenu);
+        bind(C::Normal, ch('?'), Help);
+        for (key, action) in motions {
+            bind(C::Normal, key, action);
+        }
+
+        bind(C::Visual, plain(KeyCode::Esc), NormalMode);
+        bind(C::Visual, ch('v'), NormalMode);
+        bind(C::Visual, plain(KeyCode::Enter), Submit);
+        bind(C::Visual, ch('y'), YankSelection);
+        bind(C::Visual, ch('d'), DeleteSelection);
+        bind(C::Visual, ch('x'), DeleteSelection);
+        for (key, action) in motions {
+            bind(C::Visual, key, action);
+        }
+
+        for context in [C::History, C::Variables] {
+            bind(context, plain(KeyCode::Enter), Select);
+            bind(context, ch('i'), FocusInput);
+            bind(context, plain(KeyCode::Tab), FocusNext);
+            bind(context, plain(KeyCode::BackTab), FocusPrev);
+            bind(context, plain(KeyCode::Left), FocusHistory);
+            bind(context, plain(KeyCode::Right), FocusVariables);
+            bind(context, ch('h'), FocusHistory);
+            bind(context, ch('l'), FocusVariables);
+            bind(context, plain(KeyCode::Up), SelectUp);
+            bind(context, plain(KeyCode::Down), SelectDown);
+            bind(context, ch('k'), SelectUp);
+            bind(context, ch('j'), SelectDown);
+            bind(context, plain(KeyCode::Home), SelectFirst);
+            bind(context, plain(KeyCode::End), SelectLast);
+            bind(context, ch('G'), SelectLast);
+            bind(context, KeyChord::ctrl('u'), HalfPageUp);
+            bind(context, KeyChord::ctrl('d'), HalfPageDown);
+            bind(context, ch('?'), Help);
+            bind(context, ch('q'), Quit);
+        }
+        bind(C::History, ch('o'), ShowDetails);
+        bind(
+            C::History,
+            KeyChord::new(KeyCode::Enter, KeyModifiers::ALT),
+            ShowDetails,
+        );
+        bind(C::History, ch('n'), Note);
+        bind(C::Variables, ch('D'), ToggleDependencies);
+        bind(C::Variables, ch('s'), CycleVariableSort);
+
+        bind(C::Plot, ch('i'), FocusInput);
+        bind(C::Plot, plain(KeyCode::Tab), FocusNext);
+        bind(C::Plot, plain(KeyCode::BackTab), FocusPrev);
+        bind(C::Plot, plain(KeyCode::Up), FocusHistory);
+        bind(C::Plot, ch('s'), TogglePlotStyle);
+        bind(C::Plot, plain(KeyCode::Left), PlotCursorLeft);
+        bind(C::Plot, ch('h'), PlotCursorLeft);
+        bind(C::Plot, plain(KeyCode::Right), PlotCursorRight);
+        bind(C::Plot, ch('l'), PlotCursorRight);
+        bind(C::Plot, ch('?'), Help);
+        bind(C::Plot, ch('q'), Quit);
+
+        bind(C::Search, KeyChord::ctrl('r'), SearchOlder);
+        bind(C::Search, plain(KeyCode::Enter), SearchAccept);
+        bind(C::Search, plain(KeyCode::Esc), SearchCancel);
+        bind(C::Search, KeyChord::ctrl('g'), SearchCancel);
+        bind(C::Search, plain(KeyCode::Backspace), SearchBackspace);
+
+        for context in [C::History, C::Variables] {
+            keymap.bind(context, vec![ch('g'), ch('g')], SelectFirst);
+        }
+        for context in [C::Normal, C::History, C::Variables, C::Plot] {
+            keymap.bind(context, vec![ch('g'), ch('t')], NextTab);
+            keymap.bind(context, vec![ch('g'), ch('T')], PrevTab);
+        }
+        keymap.bind(C::History, vec![ch('y'), ch('e')], YankExpression);
+        keymap.bind(C::History, vec![ch('y'), ch('r')], YankResult);
+
+        keymap
+    }
+}
+
+impl Keymap {
+    /// Adds a binding, replacing any existing binding for the same keys in `context`.
+    pub fn bind(&mut self, context: KeyContext, keys: Vec<KeyChord>, action: Action) {
+        self.bindings
+            .retain(|b| !(b.context == context && b.keys == keys));
+        self.bindings.push(Binding {
+            context,
+            keys,
+            action,
+        });
+    }
+
+    /// Applies a mapping line such as `normal H line_start`, as used by `:map`
+    /// and the keymap file.
+    pub fn apply_mapping(&mut self, line: &str) -> Result<(), String> {
+        const USAGE: &str = "Usage: :map <context> <keys> <action>";
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        let [context, keys, action] = parts[..] else {
+            return Err(USAGE.to_string());
+        };
+        let context = KeyContext::named(context)
+            .ok_or_else(|| format!("Unknown key context: {}", context))?;
+        let keys = parse_keys(keys)?;
+        let action = Action::named(action).ok_or_else(|| format!("Unknown action: {}", action))?;
+        self.bind(context, keys, action);
+        Ok(())
+    }
+
+    pub fn bindings(&self, context: KeyContext) -> impl Iterator<Item = &Binding> {
+        self.bindings.iter().filter(move |b| b.context == context)
+    }
+
+    /// Looks up `keys` in `context`, falling back to `Global` bindings.
+    pub fn resolve(&self, context: KeyContext, keys: &[KeyChord]) -> Resolution {
+        for ctx in [context, KeyContext::Global] {
+            let mut pending = false;
+            for binding in self.bindings(ctx) {
+                if binding.keys == keys {
+                    return Resolution::Action(binding.action);
+                }
+                if binding.keys.len() > keys.len() && binding.keys.starts_with(keys) {
+                    pending = true;
+                }
+            }
+            if pending {
+                return Resolution::Pending;
+            }
+        }
+        Resolution::Unbound
+    }
+
+    /// Bindings of `context` grouped by action, in definition order, e.g.
+    /// `("h / Left", "Move cursor left")`.
+    pub fn help_entries(&self, context: KeyContext) -> Vec<(String, &'static str)> {
+        let mut entries: Vec<(Action, Vec<String>)> = Vec::new();
+        for binding in self.bindings(context) {
+            let keys: String = binding.keys.iter().map(KeyChord::to_string).collect();
+            match entries
+                .iter_mut()
+                .find(|(action, _)| *action == binding.action)
+            {
+                Some((_, all_keys)) => all_keys.push(keys),
+                None => entries.push((binding.action, vec![keys])),
+            }
+        }
+
+        entries
+            .into_iter()
+            .map(|(action, keys)| (keys.join(" / "), action.description()))
+            .collect()
+    }
+}
+
+/// Shared default keymap for callers that do not carry their own.
+pub fn default_keymap() -> &'static Keymap {
+    static DEFAULT: OnceLock<Keymap> = OnceLock::new();
+    DEFAULT.get_or_init(Keymap::default)
+}
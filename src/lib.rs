@@ -1,15 +1,73 @@
+pub mod builtins;
 pub mod calculate;
+pub mod commands;
+pub mod compiled;
+pub mod completion;
+pub mod csv_import;
+pub mod dates;
+pub mod definitions;
+pub mod doctor;
+pub mod error;
+pub mod evaluation;
+pub mod evaluator;
+pub mod events;
+pub mod export;
+pub mod expr;
+pub mod format;
+pub mod history_search;
 pub mod input_editor;
 pub mod inspect;
 pub mod widgets {
+    pub mod confirm_popup;
+    pub mod detail_popup;
+    pub mod function_menu;
     pub mod help_message;
+    pub mod help_overlay;
     pub mod history_block;
     pub mod input_area;
+    pub mod list_scrollbar;
     pub mod plot_block;
+    pub mod plot_picker;
+    pub mod prompt;
+    pub mod rpn_stack;
+    pub mod search_prompt;
+    pub mod session_picker;
+    pub mod snippet_picker;
+    pub mod stats_popup;
+    pub mod status_bar;
+    pub mod tab_bar;
+    pub mod toast;
     pub mod variable_block;
+    pub mod variable_chart;
 }
 pub mod io;
+pub mod keymap;
+pub mod msg;
+pub mod numeric;
+pub mod plot;
+pub mod prompt;
+pub mod references;
+pub mod rpn;
+pub mod snippets;
+pub mod stats;
+pub mod tape;
+pub mod theme;
 pub mod tokenize;
+pub mod trace;
 pub mod tui_app;
 pub mod types;
+pub mod validate;
 pub mod variables;
+
+/// The expression engine in one import: tokenizing, variable assignments, and
+/// evaluation, as used by the TUI.
+pub mod prelude {
+    pub use crate::{
+        calculate::{EvalLimits, calculate, calculate_with_angle_mode, calculate_with_limits},
+        compiled::CompiledExpr,
+        evaluator::Evaluator,
+        tokenize::tokenize,
+        types::{AngleMode, VariableEntry},
+        variables::parse_variables,
+    };
+}
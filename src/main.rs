@@ -1,13 +1,41 @@
-use std::{env, fs};
+use std::{
+    env, fs,
+    io::stdout,
+    path::{Path, PathBuf},
+};
 
 use color_eyre::Result;
-use color_eyre::eyre::eyre;
+use color_eyre::eyre::{Report, eyre};
+use ratatui::crossterm::{
+    event::{DisableBracketedPaste, EnableBracketedPaste},
+    execute,
+};
 use rustic_calc::{
-    io::{get_state_from_file, reset_file_state},
+    dates::local_offset,
+    definitions::parse_definition,
+    doctor::check_state,
+    error::CalcError,
+    evaluator::Evaluator,
+    export::export_plot,
+    format::format_value,
+    inspect::did_you_mean,
+    io::{
+        INPUT_HISTORY_LIMIT, StateFormat, get_state_from_file, is_valid_state_name,
+        read_input_history, read_keymap_lines, read_session_state, read_snippet_lines,
+        read_state_file, read_variable_pack, reset_session_state, save_session_state_merged,
+        session_state_path, write_state_file, write_variable_pack,
+    },
+    snippets::Snippet,
+    theme::{Background, Theme, no_color_requested},
+    tokenize::split_statements,
     tui_app::App,
+    types::{
+        AppState, COMPACT_HISTORY_LIMIT, ClearTarget, ConflictResolution, DEFAULT_SESSION,
+        VariablePack,
+    },
 };
 
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
 
 #[derive(Parser)]
 #[command(name = "rcalc")]
@@ -20,35 +48,439 @@ struct Cli {
 #[derive(Subcommand)]
 enum Commands {
     /// Run the application using cargo
-    Run {},
-    Clear {},
+    Run {
+        /// Terminal background to pick colors for; `auto` reads `COLORFGBG`
+        #[arg(long, value_enum, default_value_t = BackgroundArg::Auto)]
+        background: BackgroundArg,
+        /// Named session to open, each with its own history and variables
+        #[arg(long, default_value = DEFAULT_SESSION)]
+        session: String,
+        /// Screen-reader-friendly rendering without box-drawing characters
+        #[arg(long)]
+        plain: bool,
+    },
+    /// Remove the state file, or with flags only some of its data
+    Clear {
+        #[arg(long)]
+        history: bool,
+        #[arg(long)]
+        variables: bool,
+        /// The current plot and those saved with `:plot save`
+        #[arg(long)]
+        plots: bool,
+    },
+    /// Evaluate an expression with the saved variables and print the result
+    Eval {
+        expression: String,
+        /// Also print every operation on the way to the result
+        #[arg(long)]
+        trace: bool,
+        /// Also print the expression with every operation in parentheses
+        #[arg(long)]
+        grouping: bool,
+    },
+    /// Remove repeated and old History entries and redundant plot samples from the
+    /// state file
+    Compact {
+        /// Number of newest History entries to keep
+        #[arg(long, default_value_t = COMPACT_HISTORY_LIMIT)]
+        keep: usize,
+    },
+    /// Import the History and variables of another state file into the saved state
+    Merge {
+        path: PathBuf,
+        /// Whose definition to keep for variables defined differently in both
+        #[arg(long, value_enum, default_value_t = PreferArg::Ours)]
+        prefer: PreferArg,
+    },
+    /// Check the state file for invalid entries and variables that no longer evaluate
+    Doctor {
+        /// Repair what was found, keeping the original as `<file>.bak`
+        #[arg(long)]
+        fix: bool,
+        #[arg(long, default_value = DEFAULT_SESSION)]
+        session: String,
+    },
+    /// Write saved data to a file
+    Export {
+        #[command(subcommand)]
+        target: ExportTarget,
+    },
+    /// Read data written by `rcalc export` into the saved state
+    Import {
+        #[command(subcommand)]
+        target: ImportTarget,
+    },
+}
+
+#[derive(Subcommand)]
+enum ExportTarget {
+    /// The last plot, as `.csv`, `.svg`, or `.png` depending on the extension
+    Plot {
+        path: PathBuf,
+        /// Export the plot saved under this name with `:plot save` instead
+        #[arg(long)]
+        name: Option<String>,
+    },
+    /// The saved variables, without History, to share with `rcalc import vars`
+    Vars {
+        /// File to write; printed to stdout when omitted
+        path: Option<PathBuf>,
+        /// Defaults to the file's extension, or JSON
+        #[arg(long, value_enum)]
+        format: Option<FormatArg>,
+    },
+}
+
+#[derive(Subcommand)]
+enum ImportTarget {
+    /// Variables written by `rcalc export vars`, as `.json` or `.toml`
+    Vars {
+        path: PathBuf,
+        /// Whose definition to keep for variables that are already defined differently
+        #[arg(long, value_enum, default_value_t = PreferArg::Ours)]
+        prefer: PreferArg,
+    },
+}
+
+impl From<PreferArg> for ConflictResolution {
+    fn from(prefer: PreferArg) -> Self {
+        match prefer {
+            PreferArg::Ours => ConflictResolution::Ours,
+            PreferArg::Theirs => ConflictResolution::Theirs,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum FormatArg {
+    Json,
+    Toml,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum PreferArg {
+    /// The current state's
+    Ours,
+    /// The merged file's
+    Theirs,
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum BackgroundArg {
+    Auto,
+    Light,
+    Dark,
 }
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
 
     match cli.command {
-        Commands::Run {} => run(),
-        Commands::Clear {} => clear(),
+        Commands::Run {
+            background,
+            session,
+            plain,
+        } => run(background, session, plain),
+        Commands::Clear {
+            history,
+            variables,
+            plots,
+        } => {
+            let targets = [
+                (history, ClearTarget::History),
+                (variables, ClearTarget::Variables),
+                (plots, ClearTarget::Plots),
+            ];
+            clear(
+                &targets
+                    .into_iter()
+                    .filter_map(|(selected, target)| selected.then_some(target))
+                    .collect::<Vec<_>>(),
+            )
+        }
+        Commands::Eval {
+            expression,
+            trace,
+            grouping,
+        } => eval(&expression, trace, grouping),
+        Commands::Compact { keep } => compact(keep),
+        Commands::Merge { path, prefer } => merge(&path, prefer),
+        Commands::Doctor { fix, session } => doctor(fix, &session),
+        Commands::Export { target } => export(target),
+        Commands::Import { target } => import(target),
     }
 }
 
-fn run() -> Result<()> {
+fn run(background: BackgroundArg, session: String, plain: bool) -> Result<()> {
+    if !is_valid_state_name(&session) {
+        return Err(eyre!("Invalid session name: {session}"));
+    }
     let home = env::var("HOME").map_err(|_| eyre!("HOME is not set"))?;
     fs::create_dir_all(format!("{home}/.config/rcalc"))?;
 
+    let mut keymap_errors = Vec::new();
+    let keymap_lines = read_keymap_lines()?;
+    let mut snippet_errors = Vec::new();
+    let snippet_lines = read_snippet_lines()?;
+
+    // Read before any other threads start, which makes the offset unavailable.
+    let utc_offset = local_offset();
     color_eyre::install()?;
-    let terminal = ratatui::init();
-    let app_state = get_state_from_file();
-    let app_result = match app_state {
-        Ok(state) => App::from(&state).run(terminal),
-        Err(_) => App::new().run(terminal),
+    // Starting empty would overwrite an unreadable file on the first save.
+    let state = match read_session_state(&session) {
+        Ok(state) => state,
+        Err(err) if err.is_not_found() => AppState::default(),
+        Err(err) => {
+            return Err(eyre!(
+                "Could not read the state of session {session}: {err}\n\
+                 Run `rcalc doctor --session {session}` to check and repair it"
+            ));
+        }
     };
+    let terminal = ratatui::init();
+    let mut app = App::from(&state);
+    app.saved_state = Some(state);
+    for line in keymap_lines {
+        if let Err(err) = app.keymap.apply_mapping(&line) {
+            keymap_errors.push(format!("{}: {}", line, err));
+        }
+    }
+    for line in snippet_lines {
+        match Snippet::parse(&line) {
+            Ok(snippet) => app.snippets.push(snippet),
+            Err(err) => snippet_errors.push(format!("{}: {}", line, err)),
+        }
+    }
+    app.input_history = read_input_history(INPUT_HISTORY_LIMIT).unwrap_or_default();
+    app.session = session;
+    app.utc_offset = utc_offset;
+    // A theme saved with `:theme` wins over detection, but not over NO_COLOR or
+    // an explicit --background.
+    let no_color = no_color_requested(env::var("NO_COLOR").ok().as_deref());
+    let saved_theme = app.settings.theme.as_deref().and_then(Theme::named);
+    app.theme = match saved_theme {
+        Some(theme) if !no_color && background == BackgroundArg::Auto => theme,
+        _ => Theme::for_terminal(resolve_background(background), no_color),
+    }
+    .with_plain(plain);
+    // Pastes arrive as one event instead of a key press per character.
+    execute!(stdout(), EnableBracketedPaste)?;
+    let app_result = app.run(terminal);
+    let _ = execute!(stdout(), DisableBracketedPaste);
     ratatui::restore();
+    for err in keymap_errors {
+        eprintln!("Ignored keymap line {}", err);
+    }
+    for err in snippet_errors {
+        eprintln!("Ignored snippet line {}", err);
+    }
     app_result
 }
 
-fn clear() -> Result<()> {
-    let _ = reset_file_state();
+fn resolve_background(background: BackgroundArg) -> Background {
+    match background {
+        BackgroundArg::Auto => Background::from_colorfgbg(env::var("COLORFGBG").ok().as_deref()),
+        BackgroundArg::Light => Background::Light,
+        BackgroundArg::Dark => Background::Dark,
+    }
+}
+
+fn export(target: ExportTarget) -> Result<()> {
+    let state = get_state_from_file()?;
+    match target {
+        ExportTarget::Plot { path, name: None } => {
+            let plot_data = state
+                .plot_points()
+                .ok_or_else(|| eyre!("Nothing to export: no plot yet"))?;
+            let label = state.plot.map(|plot| plot.label()).unwrap_or_default();
+            export_plot(&path, &plot_data, &label, state.plot_style).map_err(|err| eyre!(err))?;
+            println!("Exported plot to {}", path.display());
+        }
+        ExportTarget::Plot {
+            path,
+            name: Some(name),
+        } => {
+            let plot = state
+                .plots
+                .iter()
+                .find(|plot| plot.name == name)
+                .ok_or_else(|| eyre!("No saved plot named {}", name))?;
+            let plot_data = plot.sample(&state.variables)?;
+            let label = plot.definition.label();
+            export_plot(&path, &plot_data, &label, state.plot_style).map_err(|err| eyre!(err))?;
+            println!("Exported plot to {}", path.display());
+        }
+        ExportTarget::Vars { path, format } => {
+            let pack = VariablePack::from_state(&state);
+            let format = format.map(|format| match format {
+                FormatArg::Json => StateFormat::Json,
+                FormatArg::Toml => StateFormat::Toml,
+            });
+            match path {
+                Some(path) => {
+                    write_variable_pack(&path, &pack, format)?;
+                    println!(
+                        "Exported {} variables to {}",
+                        pack.variables.len(),
+                        path.display()
+                    );
+                }
+                None => println!("{}", format.unwrap_or(StateFormat::Json).serialize(&pack)?),
+            }
+        }
+    }
+    Ok(())
+}
+
+fn import(target: ImportTarget) -> Result<()> {
+    match target {
+        ImportTarget::Vars { path, prefer } => {
+            let pack = read_variable_pack(&path)?;
+            let base = match read_session_state(DEFAULT_SESSION) {
+                Ok(state) => state,
+                Err(err) if err.is_not_found() => AppState::default(),
+                Err(err) => return Err(err.into()),
+            };
+            let mut state = base.clone();
+            let report = state.merge(&pack.to_state(), prefer.into());
+            save_session_state_merged(DEFAULT_SESSION, state, &base)?;
+            print!("Imported {} new variables", report.variables);
+            if !report.conflicts.is_empty() {
+                print!("; conflicting: {}", report.conflicts.join(", "));
+            }
+            println!();
+        }
+    }
+    Ok(())
+}
+
+fn eval(expression: &str, trace: bool, grouping: bool) -> Result<()> {
+    let state = get_state_from_file().unwrap_or_default();
+    let mut evaluator = Evaluator::new()
+        .with_power_rules(state.settings.power)
+        .with_variables(state.variables);
+    evaluator.define_functions(&state.functions)?;
+    for statement in split_statements(expression) {
+        if let Some(definition) = parse_definition(statement) {
+            let definition = definition?;
+            evaluator.define_function(&definition)?;
+            println!("{}", definition);
+            continue;
+        }
+        if grouping {
+            let parsed = evaluator.parse(statement)?;
+            println!("{}", parsed.grouped());
+        }
+        if trace {
+            let result = evaluator
+                .eval_trace(statement)
+                .map_err(|err| with_suggestions(err, statement, &evaluator))?;
+            for step in &result.steps {
+                println!("{}", step);
+            }
+        }
+        let value = evaluator
+            .eval(statement)
+            .map_err(|err| with_suggestions(err, statement, &evaluator))?;
+        println!("{}", format_value(value, None));
+    }
+    Ok(())
+}
+
+/// Adds "did you mean" suggestions to unknown-variable errors.
+fn with_suggestions(err: CalcError, statement: &str, evaluator: &Evaluator) -> Report {
+    let suggestions = did_you_mean(statement, evaluator.variables());
+    match err {
+        CalcError::UnknownVariable(_) if !suggestions.is_empty() => {
+            eyre!("{} — did you mean {}?", err, suggestions.join(" or "))
+        }
+        err => err.into(),
+    }
+}
+
+fn compact(keep: usize) -> Result<()> {
+    let path = session_state_path(DEFAULT_SESSION)?;
+    let size_before = fs::metadata(&path)?.len();
+    let mut state = read_state_file(&path)?;
+    let report = state.compact(keep);
+    write_state_file(&path, &state)?;
+    let size_after = fs::metadata(&path)?.len();
+    println!("{}; {} → {} bytes", report, size_before, size_after);
+    Ok(())
+}
+
+fn merge(other: &Path, prefer: PreferArg) -> Result<()> {
+    let theirs = read_state_file(other)?;
+    let base = match read_session_state(DEFAULT_SESSION) {
+        Ok(state) => state,
+        Err(err) if err.is_not_found() => AppState::default(),
+        Err(err) => return Err(err.into()),
+    };
+    let mut state = base.clone();
+    let report = state.merge(&theirs, prefer.into());
+    // A running instance may save in between; its changes are kept too.
+    save_session_state_merged(DEFAULT_SESSION, state, &base)?;
+    println!("{}", report);
+    Ok(())
+}
+
+fn doctor(fix: bool, session: &str) -> Result<()> {
+    if !is_valid_state_name(session) {
+        return Err(eyre!("Invalid session name: {session}"));
+    }
+    let path = session_state_path(session)?;
+    let data = match fs::read_to_string(&path) {
+        Ok(data) => data,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            println!("No state file at {}", path.display());
+            return Ok(());
+        }
+        Err(err) => return Err(err.into()),
+    };
+    let format = StateFormat::from_path(&path).unwrap_or(StateFormat::Json);
+    let checkup = check_state(&data, format)
+        .map_err(|err| eyre!("{}: {} (cannot be repaired)", path.display(), err))?;
+    if checkup.problems.is_empty() {
+        println!("{}: no problems found", path.display());
+        return Ok(());
+    }
+
+    println!("{}:", path.display());
+    for problem in &checkup.problems {
+        println!("  {}", problem);
+    }
+    if !fix {
+        println!("Run `rcalc doctor --fix` to repair");
+        return Ok(());
+    }
+    let count = checkup.problems.len();
+    let backup = path.with_extension(format!("{}.bak", format.extension()));
+    fs::copy(&path, &backup)?;
+    write_state_file(&path, &checkup.fix())?;
+    println!(
+        "Fixed {} problems; the original is in {}",
+        count,
+        backup.display()
+    );
+    Ok(())
+}
+
+/// Clears `targets` in the saved state, or removes the state file without any.
+fn clear(targets: &[ClearTarget]) -> Result<()> {
+    if targets.is_empty() {
+        let _ = reset_session_state(DEFAULT_SESSION);
+        return Ok(());
+    }
+    let base = match read_session_state(DEFAULT_SESSION) {
+        Ok(state) => state,
+        Err(err) if err.is_not_found() => return Ok(()),
+        Err(err) => return Err(err.into()),
+    };
+    let mut state = base.clone();
+    for target in targets {
+        state.clear(*target);
+    }
+    save_session_state_merged(DEFAULT_SESSION, state, &base)?;
     Ok(())
 }
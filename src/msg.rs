@@ -0,0 +1,29 @@
+use std::time::Instant;
+
+use ratatui::crossterm::event::KeyEvent;
+
+use crate::{commands::Command, keymap::Action};
+
+/// A change to the TUI's state, applied with
+/// [`App::update`](crate::tui_app::App::update). Events become messages, so a
+/// session can be replayed, or scripted, as a list of them.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Msg {
+    /// A key press, resolved through the keymap in the current context
+    Key(KeyEvent),
+    /// A bound action, as if its keys had been pressed
+    Action(Action),
+    /// Text pasted at the cursor
+    Paste(String),
+    /// The terminal now has this many columns and rows
+    Resize(u16, u16),
+    /// Replace the input with the text and submit it
+    Submit(String),
+    /// Run a command as if it had been entered with `:`
+    Command(Command),
+    /// Time has passed up to the given instant: toasts expire, autosave is due,
+    /// and a finished background evaluation is recorded
+    Tick(Instant),
+    /// Wait for the background evaluation and record its result
+    EvalFinished,
+}
@@ -0,0 +1,280 @@
+use std::f64::consts::PI;
+use std::fmt::Debug;
+
+use num_complex::Complex64;
+use num_rational::BigRational;
+use num_traits::{CheckedDiv, FromPrimitive, ToPrimitive, Zero};
+use rust_decimal::Decimal;
+
+use crate::{
+    builtins::{apply_function, constant},
+    types::AngleMode,
+};
+
+/// Largest integer exponent applied exactly by [`Decimal`] and [`BigRational`];
+/// larger ones go through `f64`.
+const MAX_EXACT_EXPONENT: i32 = 1024;
+
+/// Largest [`BigRational`] power, in numerator and denominator bits, computed
+/// exactly; larger ones go through `f64` as well.
+const MAX_EXACT_BITS: u64 = 1 << 16;
+
+/// A number type expressions can be evaluated in, see
+/// [`Evaluator::eval_as`](crate::evaluator::Evaluator::eval_as).
+///
+/// Operations return `None` when the result cannot be represented, such as a
+/// division by zero for exact types.
+pub trait Numeric: Clone + Debug + PartialEq + Send + Sync + 'static {
+    /// Parses a number literal such as `12`, `0.5`, or `.5`.
+    fn parse_literal(text: &str) -> Option<Self>;
+    fn from_f64(value: f64) -> Option<Self>;
+    /// The value as an `f64`, if it is real.
+    fn to_f64(&self) -> Option<f64>;
+
+    fn add(&self, rhs: &Self) -> Option<Self>;
+    fn sub(&self, rhs: &Self) -> Option<Self>;
+    fn mul(&self, rhs: &Self) -> Option<Self>;
+    fn div(&self, rhs: &Self) -> Option<Self>;
+    fn pow(&self, rhs: &Self) -> Option<Self>;
+    fn neg(&self) -> Self;
+
+    /// Applies the built-in function `name`; by default computed in `f64`.
+    fn apply_function(&self, name: &str, angle_mode: AngleMode) -> Option<Self> {
+        Self::from_f64(apply_function(name, self.to_f64()?, angle_mode)?)
+    }
+
+    /// The named constant `name`; by default the built-in ones.
+    fn constant(name: &str) -> Option<Self> {
+        Self::from_f64(constant(name)?)
+    }
+}
+
+impl Numeric for f64 {
+    fn parse_literal(text: &str) -> Option<Self> {
+        text.parse().ok()
+    }
+
+    fn from_f64(value: f64) -> Option<Self> {
+        Some(value)
+    }
+
+    fn to_f64(&self) -> Option<f64> {
+        Some(*self)
+    }
+
+    fn add(&self, rhs: &Self) -> Option<Self> {
+        Some(self + rhs)
+    }
+
+    fn sub(&self, rhs: &Self) -> Option<Self> {
+        Some(self - rhs)
+    }
+
+    fn mul(&self, rhs: &Self) -> Option<Self> {
+        Some(self * rhs)
+    }
+
+    fn div(&self, rhs: &Self) -> Option<Self> {
+        Some(self / rhs)
+    }
+
+    fn pow(&self, rhs: &Self) -> Option<Self> {
+        Some(self.powf(*rhs))
+    }
+
+    fn neg(&self) -> Self {
+        -self
+    }
+}
+
+/// `exponent` as a small integer, when [`MAX_EXACT_EXPONENT`] allows applying it exactly.
+fn exact_exponent(exponent: f64) -> Option<i32> {
+    (exponent.fract() == 0.0 && exponent.abs() <= MAX_EXACT_EXPONENT as f64)
+        .then_some(exponent as i32)
+}
+
+impl Numeric for Decimal {
+    fn parse_literal(text: &str) -> Option<Self> {
+        Decimal::from_str_exact(text)
+            .or_else(|_| Decimal::from_scientific(text))
+            .ok()
+    }
+
+    fn from_f64(value: f64) -> Option<Self> {
+        <Decimal as FromPrimitive>::from_f64(value)
+    }
+
+    fn to_f64(&self) -> Option<f64> {
+        ToPrimitive::to_f64(self)
+    }
+
+    fn add(&self, rhs: &Self) -> Option<Self> {
+        self.checked_add(*rhs)
+    }
+
+    fn sub(&self, rhs: &Self) -> Option<Self> {
+        self.checked_sub(*rhs)
+    }
+
+    fn mul(&self, rhs: &Self) -> Option<Self> {
+        self.checked_mul(*rhs)
+    }
+
+    fn div(&self, rhs: &Self) -> Option<Self> {
+        Decimal::checked_div(*self, *rhs)
+    }
+
+    fn pow(&self, rhs: &Self) -> Option<Self> {
+        let Some(exponent) = exact_exponent(Numeric::to_f64(rhs)?) else {
+            return Numeric::from_f64(Numeric::to_f64(self)?.powf(Numeric::to_f64(rhs)?));
+        };
+        let mut result = Decimal::ONE;
+        for _ in 0..exponent.unsigned_abs() {
+            result = result.checked_mul(*self)?;
+        }
+        if exponent < 0 {
+            Decimal::ONE.checked_div(result)
+        } else {
+            Some(result)
+        }
+    }
+
+    fn neg(&self) -> Self {
+        -self
+    }
+}
+
+impl Numeric for BigRational {
+    fn parse_literal(text: &str) -> Option<Self> {
+        let (text, exponent) = match text.split_once(['e', 'E']) {
+            Some((mantissa, exponent)) => (mantissa, exponent.parse::<i32>().ok()?),
+            None => (text, 0),
+        };
+        if exponent.abs() > MAX_EXACT_EXPONENT {
+            return None;
+        }
+        let (whole, fraction) = text.split_once('.').unwrap_or((text, ""));
+        let digits = format!("{}{}", whole, fraction);
+        let digits = if digits.is_empty() { "0" } else { &digits };
+        let mantissa: BigRational = format!("{}/1{}", digits, "0".repeat(fraction.len()))
+            .parse()
+            .ok()?;
+        Some(mantissa * BigRational::from_integer(10.into()).pow(exponent))
+    }
+
+    fn from_f64(value: f64) -> Option<Self> {
+        BigRational::from_float(value)
+    }
+
+    fn to_f64(&self) -> Option<f64> {
+        ToPrimitive::to_f64(self)
+    }
+
+    fn add(&self, rhs: &Self) -> Option<Self> {
+        Some(self + rhs)
+    }
+
+    fn sub(&self, rhs: &Self) -> Option<Self> {
+        Some(self - rhs)
+    }
+
+    fn mul(&self, rhs: &Self) -> Option<Self> {
+        Some(self * rhs)
+    }
+
+    fn div(&self, rhs: &Self) -> Option<Self> {
+        self.checked_div(rhs)
+    }
+
+    fn pow(&self, rhs: &Self) -> Option<Self> {
+        match rhs
+            .is_integer()
+            .then(|| exact_exponent(Numeric::to_f64(rhs)?))
+        {
+            Some(Some(exponent))
+                if !(exponent < 0 && self.is_zero())
+                    && (self.numer().bits() + self.denom().bits())
+                        .saturating_mul(exponent.unsigned_abs() as u64)
+                        <= MAX_EXACT_BITS =>
+            {
+                Some(BigRational::pow(self, exponent))
+            }
+            _ => Numeric::from_f64(Numeric::to_f64(self)?.powf(Numeric::to_f64(rhs)?)),
+        }
+    }
+
+    fn neg(&self) -> Self {
+        -self
+    }
+}
+
+/// Complex numbers add the imaginary unit `i` and complex versions of the functions,
+/// so `sqrt(-4)` is `2i` instead of NaN.
+impl Numeric for Complex64 {
+    fn parse_literal(text: &str) -> Option<Self> {
+        text.parse::<f64>().ok().map(Complex64::from)
+    }
+
+    fn from_f64(value: f64) -> Option<Self> {
+        Some(Complex64::from(value))
+    }
+
+    fn to_f64(&self) -> Option<f64> {
+        (self.im == 0.0).then_some(self.re)
+    }
+
+    fn add(&self, rhs: &Self) -> Option<Self> {
+        Some(self + rhs)
+    }
+
+    fn sub(&self, rhs: &Self) -> Option<Self> {
+        Some(self - rhs)
+    }
+
+    fn mul(&self, rhs: &Self) -> Option<Self> {
+        Some(self * rhs)
+    }
+
+    fn div(&self, rhs: &Self) -> Option<Self> {
+        Some(self / rhs)
+    }
+
+    fn pow(&self, rhs: &Self) -> Option<Self> {
+        Some(self.powc(*rhs))
+    }
+
+    /// Subtracts from zero instead of flipping signs, so `-4` has a `+0` imaginary
+    /// part and `sqrt(-4)` lands on `2i` rather than across the branch cut.
+    fn neg(&self) -> Self {
+        Complex64::new(0.0 - self.re, 0.0 - self.im)
+    }
+
+    fn apply_function(&self, name: &str, angle_mode: AngleMode) -> Option<Self> {
+        let per_radian = match angle_mode {
+            AngleMode::Radians => 1.0,
+            AngleMode::Degrees => 180.0 / PI,
+        };
+        let z = *self;
+        Some(match name {
+            "sin" => (z / per_radian).sin(),
+            "cos" => (z / per_radian).cos(),
+            "tan" => (z / per_radian).tan(),
+            "asin" => z.asin() * per_radian,
+            "acos" => z.acos() * per_radian,
+            "atan" => z.atan() * per_radian,
+            "sqrt" => z.sqrt(),
+            "abs" => Complex64::from(z.norm()),
+            "ln" => z.ln(),
+            "log" => z.log10(),
+            "exp" => z.exp(),
+            _ => return None,
+        })
+    }
+
+    fn constant(name: &str) -> Option<Self> {
+        match name {
+            "i" => Some(Complex64::i()),
+            _ => constant(name).map(Complex64::from),
+        }
+    }
+}
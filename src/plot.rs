@@ -0,0 +1,221 @@
+use std::{collections::HashMap, f64::consts::TAU};
+
+use crate::{
+    error::CalcError, evaluator::Evaluator, tokenize::tokenize, types::VariableEntry,
+    variables::parse_variables,
+};
+
+/// Values of the free variable at which a function plot is sampled by default.
+pub const FUNCTION_RANGE: PlotRange = PlotRange {
+    from: -10.0,
+    to: 10.0,
+    samples: 21,
+};
+
+/// Name of the parameter swept by polar and parametric plots.
+pub const PARAMETER: &str = "t";
+
+/// Number of parameter values sampled for polar and parametric plots.
+pub const PARAMETER_SAMPLES: usize = 200;
+
+/// The values a plot's free variable or parameter is swept over.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct PlotRange {
+    pub from: f64,
+    pub to: f64,
+    /// Number of evenly spaced values, including both ends
+    pub samples: usize,
+}
+
+impl PlotRange {
+    pub fn values(&self) -> impl Iterator<Item = f64> {
+        let step = (self.to - self.from) / self.samples.saturating_sub(1).max(1) as f64;
+        let from = self.from;
+        (0..self.samples).map(move |i| from + step * i as f64)
+    }
+}
+
+/// A plot saved under a name with `:plot save`, to switch back to later.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct PlotEntry {
+    pub name: String,
+    pub definition: PlotDefinition,
+    /// Range it was sampled over, when not the definition's default
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub range: Option<PlotRange>,
+}
+
+impl PlotEntry {
+    pub fn sample(
+        &self,
+        variables: &HashMap<String, VariableEntry>,
+    ) -> Result<Vec<(f64, f64)>, CalcError> {
+        let range = match self.range {
+            Some(range) => range,
+            None => self.definition.default_range(variables)?,
+        };
+        self.definition.sample_in(range, variables)
+    }
+}
+
+/// What is being plotted, kept so the plot can be labeled and re-sampled.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PlotDefinition {
+    /// `y = f(x)` for an expression with one unknown variable
+    Function {
+        expression: String,
+        variable: String,
+    },
+    /// `r = f(t)` for `t` in `0..2pi`
+    Polar { expression: String },
+    /// `(x(t), y(t))` for `t` in `from..to`, bounds being expressions themselves
+    Parametric {
+        x: String,
+        y: String,
+        from: String,
+        to: String,
+    },
+    /// The values of an imported list against their 1-based position
+    Points { name: String, values: Vec<f64> },
+}
+
+impl PlotDefinition {
+    pub fn label(&self) -> String {
+        match self {
+            PlotDefinition::Function { expression, .. } => expression.clone(),
+            PlotDefinition::Polar { expression } => format!("r = {}", expression),
+            PlotDefinition::Parametric { x, y, .. } => format!("({}, {})", x, y),
+            PlotDefinition::Points { name, .. } => name.clone(),
+        }
+    }
+
+    /// Polar and parametric curves are drawn with equal x and y scales.
+    pub fn is_square(&self) -> bool {
+        !matches!(self, PlotDefinition::Function { .. })
+    }
+
+    /// The range sampled unless another one is given: [`FUNCTION_RANGE`] for
+    /// functions, a full turn for polar curves, a parametric curve's own bounds, and
+    /// every position of a list.
+    pub fn default_range(
+        &self,
+        variables: &HashMap<String, VariableEntry>,
+    ) -> Result<PlotRange, CalcError> {
+        Ok(match self {
+            PlotDefinition::Function { .. } => FUNCTION_RANGE,
+            PlotDefinition::Polar { .. } => PlotRange {
+                from: 0.0,
+                to: TAU,
+                samples: PARAMETER_SAMPLES,
+            },
+            PlotDefinition::Parametric { from, to, .. } => {
+                let evaluator = Evaluator::new().with_variables(variables.clone());
+                PlotRange {
+                    from: evaluator.compile(from)?.eval()?,
+                    to: evaluator.compile(to)?.eval()?,
+                    samples: PARAMETER_SAMPLES,
+                }
+            }
+            PlotDefinition::Points { values, .. } => PlotRange {
+                from: 1.0,
+                to: values.len() as f64,
+                samples: values.len(),
+            },
+        })
+    }
+
+    /// Samples over [`default_range`](Self::default_range).
+    pub fn sample(
+        &self,
+        variables: &HashMap<String, VariableEntry>,
+    ) -> Result<Vec<(f64, f64)>, CalcError> {
+        self.sample_in(self.default_range(variables)?, variables)
+    }
+
+    pub fn sample_in(
+        &self,
+        range: PlotRange,
+        variables: &HashMap<String, VariableEntry>,
+    ) -> Result<Vec<(f64, f64)>, CalcError> {
+        let evaluator = Evaluator::new().with_variables(variables.clone());
+        match self {
+            PlotDefinition::Function {
+                expression,
+                variable,
+            } => Ok(sample_plot(
+                &expression_tokens(expression)?,
+                variable,
+                variables,
+                range,
+            )),
+            PlotDefinition::Polar { expression } => {
+                let r = evaluator.compile_tokens(&expression_tokens(expression)?)?;
+                Ok(sample_parameter(range, |t| {
+                    let r = r.eval_with(&[(PARAMETER, t)]).ok()?;
+                    Some((r * t.cos(), r * t.sin()))
+                }))
+            }
+            PlotDefinition::Parametric { x, y, .. } => {
+                let x = evaluator.compile_tokens(&expression_tokens(x)?)?;
+                let y = evaluator.compile_tokens(&expression_tokens(y)?)?;
+                Ok(sample_parameter(range, |t| {
+                    Some((
+                        x.eval_with(&[(PARAMETER, t)]).ok()?,
+                        y.eval_with(&[(PARAMETER, t)]).ok()?,
+                    ))
+                }))
+            }
+            PlotDefinition::Points { values, .. } => Ok((1..)
+                .map(|position| position as f64)
+                .zip(values.iter().copied())
+                .filter(|(x, y)| (range.from..=range.to).contains(x) && y.is_finite())
+                .collect()),
+        }
+    }
+}
+
+/// Tokens of `expression`, dropping an `r=`-style left-hand side.
+fn expression_tokens(expression: &str) -> Result<Vec<&str>, CalcError> {
+    let tokens = tokenize(expression);
+    if tokens.contains(&"=") {
+        return parse_variables(tokens).map(|parsed| parsed.tokens);
+    }
+    Ok(tokens)
+}
+
+/// Evaluates `tokens` over `range`, binding `variable` to each sample.
+///
+/// Points that fail to evaluate are plotted at zero.
+pub fn sample_plot(
+    tokens: &[&str],
+    variable: &str,
+    variables: &HashMap<String, VariableEntry>,
+    range: PlotRange,
+) -> Vec<(f64, f64)> {
+    let compiled = Evaluator::new()
+        .with_variables(variables.clone())
+        .compile_tokens(tokens);
+    range
+        .values()
+        .map(|x| {
+            let y = compiled
+                .as_ref()
+                .ok()
+                .and_then(|compiled| compiled.eval_with(&[(variable, x)]).ok())
+                .unwrap_or_default();
+            (x, y)
+        })
+        .collect()
+}
+
+/// Sweeps [`PARAMETER`] over `range`, skipping points that fail to evaluate.
+fn sample_parameter(
+    range: PlotRange,
+    point: impl Fn(f64) -> Option<(f64, f64)>,
+) -> Vec<(f64, f64)> {
+    range
+        .values()
+        .filter_map(|t| point(t).filter(|(x, y)| x.is_finite() && y.is_finite()))
+        .collect()
+}
@@ -0,0 +1,75 @@
+use ratatui::crossterm::event::KeyEvent;
+
+use crate::{
+    input_editor::{EditorCommand, InputEditor},
+    keymap::Keymap,
+};
+
+/// Checks the text of a [`Prompt`], returning why it cannot be submitted.
+pub type Validator = fn(&str) -> Result<(), String>;
+
+/// A one-line question in place of the input, such as the reverse search: a
+/// label and an Insert-only [`InputEditor`] for the answer, which `validate`
+/// checks before it is accepted.
+#[derive(Debug)]
+pub struct Prompt {
+    label: String,
+    editor: InputEditor,
+    validate: Option<Validator>,
+}
+
+impl Prompt {
+    pub fn new(label: impl Into<String>) -> Self {
+        Self {
+            label: label.into(),
+            editor: InputEditor::insert_only(),
+            validate: None,
+        }
+    }
+
+    pub fn with_validator(mut self, validate: Validator) -> Self {
+        self.validate = Some(validate);
+        self
+    }
+
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    pub fn text(&self) -> &str {
+        self.editor.input()
+    }
+
+    /// Position of the cursor in the text, in grapheme clusters.
+    pub fn cursor(&self) -> usize {
+        self.editor.cursor()
+    }
+
+    /// Why the current text would be rejected, if it would be.
+    pub fn error(&self) -> Option<String> {
+        self.validate
+            .and_then(|validate| validate(self.text()).err())
+    }
+
+    /// The text, once it passes validation.
+    pub fn submit(&self) -> Result<&str, String> {
+        match self.error() {
+            Some(err) => Err(err),
+            None => Ok(self.text()),
+        }
+    }
+
+    pub fn insert_text(&mut self, text: &str) {
+        self.editor.insert_text(text);
+    }
+
+    pub fn backspace(&mut self) {
+        self.editor.backspace();
+    }
+
+    /// Edits the text with the Insert mode bindings of `keymap`; typed characters
+    /// are inserted at the cursor.
+    pub fn handle_key_event_with(&mut self, key: KeyEvent, keymap: &Keymap) -> EditorCommand {
+        self.editor.handle_key_event_with(key, keymap)
+    }
+}
@@ -0,0 +1,41 @@
+use crate::types::History;
+
+/// Replaces each `#N` in `input` with the result of history entry `N` (1-based, as
+/// numbered in the History pane), parenthesized so it combines like a single value.
+/// `#` without a number starts a comment, which is left alone.
+pub fn resolve_history_references(input: &str, history: &[History]) -> Result<String, String> {
+    if !input.contains('#') {
+        return Ok(input.to_string());
+    }
+
+    let mut resolved = String::with_capacity(input.len());
+    let mut rest = input;
+    while let Some(idx) = rest.find('#') {
+        resolved.push_str(&rest[..idx]);
+        let after = &rest[idx + 1..];
+        let digits = after.len() - after.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+        if digits == 0 {
+            // A comment, kept as is up to the end of its line
+            let end = rest[idx..]
+                .find('\n')
+                .map_or(rest.len(), |newline| idx + newline);
+            resolved.push_str(&rest[idx..end]);
+            rest = &rest[end..];
+            continue;
+        }
+        let number: usize = after[..digits]
+            .parse()
+            .map_err(|_| "Expected an entry number after '#', e.g. #3".to_string())?;
+        let value = number
+            .checked_sub(1)
+            .and_then(|idx| history.get(idx))
+            .ok_or_else(|| format!("No history entry #{}", number))?
+            .result
+            .filter(|value| value.is_finite())
+            .ok_or_else(|| format!("History entry #{} has no result", number))?;
+        resolved.push_str(&format!("({})", value));
+        rest = &after[digits..];
+    }
+    resolved.push_str(rest);
+    Ok(resolved)
+}
@@ -0,0 +1,107 @@
+use std::collections::HashMap;
+
+use crate::{
+    builtins::{apply_function, constant, is_function},
+    calculate::BinaryOp,
+    error::CalcError,
+    types::{AngleMode, VariableEntry},
+};
+
+/// The value stack of Reverse Polish input, where `5 3 +` leaves `8`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RpnStack {
+    /// Bottom first, so the last value is the top of the stack
+    values: Vec<f64>,
+}
+
+impl RpnStack {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn values(&self) -> &[f64] {
+        &self.values
+    }
+
+    pub fn top(&self) -> Option<f64> {
+        self.values.last().copied()
+    }
+
+    /// Applies the whitespace-separated words of `input` in order. Numbers,
+    /// variables, and constants are pushed; operators replace the top two values
+    /// with their result and functions the top one; `dup`, `drop`, `swap`, and
+    /// `clear` rearrange the stack. When a word fails the stack is left unchanged.
+    pub fn apply(
+        &mut self,
+        input: &str,
+        variables: &HashMap<String, VariableEntry>,
+        angle_mode: AngleMode,
+    ) -> Result<(), CalcError> {
+        let mut values = self.values.clone();
+        for word in input.split_whitespace() {
+            apply_word(&mut values, word, variables, angle_mode)?;
+        }
+        self.values = values;
+        Ok(())
+    }
+}
+
+fn apply_word(
+    values: &mut Vec<f64>,
+    word: &str,
+    variables: &HashMap<String, VariableEntry>,
+    angle_mode: AngleMode,
+) -> Result<(), CalcError> {
+    let mut pop = |needed: usize| {
+        if values.len() < needed {
+            return Err(CalcError::StackUnderflow {
+                word: word.to_string(),
+                needed,
+            });
+        }
+        Ok(values.split_off(values.len() - needed))
+    };
+
+    let pushed = match word {
+        "+" | "-" | "*" | "/" | "^" | "**" => {
+            let op = match word {
+                "+" => BinaryOp::Add,
+                "-" => BinaryOp::Sub,
+                "*" => BinaryOp::Mul,
+                "/" => BinaryOp::Div,
+                _ => BinaryOp::Pow,
+            };
+            let operands = pop(2)?;
+            vec![op.apply(&operands[0], &operands[1])?]
+        }
+        "dup" => {
+            let top = pop(1)?;
+            vec![top[0], top[0]]
+        }
+        "drop" => {
+            pop(1)?;
+            Vec::new()
+        }
+        "swap" => {
+            let operands = pop(2)?;
+            vec![operands[1], operands[0]]
+        }
+        "clear" => {
+            values.clear();
+            Vec::new()
+        }
+        name if is_function(name) => {
+            let arg = pop(1)?[0];
+            vec![apply_function(name, arg, angle_mode).ok_or(CalcError::Undefined)?]
+        }
+        word => vec![
+            word.parse::<f64>()
+                .ok()
+                .or_else(|| variables.get(word).map(|entry| entry.value))
+                .or_else(|| constant(word))
+                .ok_or_else(|| CalcError::UnknownVariable(word.to_string()))?,
+        ],
+    };
+    values.extend(pushed);
+    Ok(())
+}
@@ -0,0 +1,63 @@
+use std::{fmt, ops::Range};
+
+use crate::variables::is_valid_name;
+
+/// A named expression with `{field}` placeholders, e.g. `vat = {amount} * 1.19`,
+/// read from the snippets file and inserted with `:snippet`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snippet {
+    pub name: String,
+    /// The expression after `=`, placeholders included
+    pub template: String,
+}
+
+impl Snippet {
+    /// Parses a `name = template` line.
+    pub fn parse(line: &str) -> Result<Snippet, String> {
+        const USAGE: &str = "Expected <name> = <expression>";
+        let (name, template) = line.split_once('=').ok_or(USAGE)?;
+        let (name, template) = (name.trim(), template.trim());
+        if !is_valid_name(name) {
+            return Err(format!("Invalid snippet name '{}'", name));
+        }
+        if template.is_empty() {
+            return Err(USAGE.to_string());
+        }
+        let mut rest = template;
+        while let Some(open) = rest.find(['{', '}']) {
+            let field = rest[open..]
+                .strip_prefix('{')
+                .and_then(|after| after.split_once('}'))
+                .map(|(field, _)| field)
+                .filter(|field| is_valid_name(field))
+                .ok_or_else(|| format!("Invalid placeholder in '{}'", template))?;
+            rest = &rest[open + field.len() + 2..];
+        }
+        Ok(Snippet {
+            name: name.to_string(),
+            template: template.to_string(),
+        })
+    }
+}
+
+/// `vat = {amount} * 1.19`
+impl fmt::Display for Snippet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} = {}", self.name, self.template)
+    }
+}
+
+/// Byte range of the first `{field}` placeholder left in `text`, and its name.
+pub fn next_placeholder(text: &str) -> Option<(Range<usize>, &str)> {
+    let mut offset = 0;
+    while let Some(open) = text[offset..].find('{') {
+        let start = offset + open;
+        if let Some((field, _)) = text[start + 1..].split_once('}')
+            && is_valid_name(field)
+        {
+            return Some((start..start + field.len() + 2, field));
+        }
+        offset = start + 1;
+    }
+    None
+}
@@ -0,0 +1,37 @@
+/// Summary statistics over a set of values, as shown by `:stats`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Summary {
+    pub count: usize,
+    pub sum: f64,
+    pub mean: f64,
+    pub min: f64,
+    pub max: f64,
+    /// Sample standard deviation; `None` for a single value
+    pub stddev: Option<f64>,
+}
+
+/// Summarizes the finite `values`, or returns `None` when there are none.
+pub fn summarize(values: impl IntoIterator<Item = f64>) -> Option<Summary> {
+    let values: Vec<f64> = values
+        .into_iter()
+        .filter(|value| value.is_finite())
+        .collect();
+    let count = values.len();
+    if count == 0 {
+        return None;
+    }
+    let sum: f64 = values.iter().sum();
+    let mean = sum / count as f64;
+    let stddev = (count > 1).then(|| {
+        let squares: f64 = values.iter().map(|value| (value - mean).powi(2)).sum();
+        (squares / (count - 1) as f64).sqrt()
+    });
+    Some(Summary {
+        count,
+        sum,
+        mean,
+        min: values.iter().copied().fold(f64::INFINITY, f64::min),
+        max: values.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+        stddev,
+    })
+}
@@ -0,0 +1,56 @@
+use std::fmt;
+
+use crate::error::CalcError;
+
+/// How a line of tape mode changes the running total.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TapeOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+impl TapeOp {
+    /// The new total after applying `value` to `total`; fails when it is not finite.
+    pub fn apply(self, total: f64, value: f64) -> Result<f64, CalcError> {
+        let total = match self {
+            TapeOp::Add => total + value,
+            TapeOp::Sub => total - value,
+            TapeOp::Mul => total * value,
+            TapeOp::Div => total / value,
+        };
+        if total.is_finite() {
+            Ok(total)
+        } else {
+            Err(CalcError::Undefined)
+        }
+    }
+}
+
+impl fmt::Display for TapeOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let symbol = match self {
+            TapeOp::Add => "+",
+            TapeOp::Sub => "-",
+            TapeOp::Mul => "*",
+            TapeOp::Div => "/",
+        };
+        write!(f, "{}", symbol)
+    }
+}
+
+/// Splits a line of tape input into its operation and the expression for its
+/// amount, as an adding machine reads it: `12.50` and `+12.50` add, `-3` subtracts,
+/// `*2` and `/4` scale the total.
+pub fn parse_tape_line(input: &str) -> (TapeOp, &str) {
+    let input = input.trim();
+    let op = match input.chars().next() {
+        Some('+') => TapeOp::Add,
+        Some('-') => TapeOp::Sub,
+        Some('*') => TapeOp::Mul,
+        Some('/') => TapeOp::Div,
+        _ => return (TapeOp::Add, input),
+    };
+    (op, input[1..].trim_start())
+}
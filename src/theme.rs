@@ -0,0 +1,339 @@
+use ratatui::{
+    style::{Color, Modifier},
+    widgets::{Block, BorderType},
+};
+
+/// Colors used by all widgets, switchable at runtime with `:theme`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Theme {
+    pub name: &'static str,
+    /// Borders and titles of the History pane and popups
+    pub accent: Color,
+    pub accent_focused: Color,
+    /// Borders and titles of the Variables pane
+    pub secondary: Color,
+    pub secondary_focused: Color,
+    pub plot_border: Color,
+    pub plot_points: Color,
+    pub axis: Color,
+    pub expression: Color,
+    pub assignment: Color,
+    pub result: Color,
+    pub error: Color,
+    /// Entries that have neither a result nor an error
+    pub pending: Color,
+    /// Background of the selected list item
+    pub selection: Color,
+    pub input_bg: Color,
+    pub visual_bg: Color,
+    pub yank_bg: Color,
+    /// Text drawn on top of highlighted backgrounds
+    pub on_highlight: Color,
+    pub status_bg: Color,
+    pub mode: Color,
+    pub muted: Color,
+    pub unsaved: Color,
+    /// Added to highlighted text, so themes without colors can still show it
+    pub highlight: Modifier,
+    /// Screen-reader-friendly rendering: no box-drawing characters, and textual
+    /// markers such as `[ERROR]` wherever color alone would carry meaning
+    pub plain: bool,
+}
+
+impl Theme {
+    pub const DEFAULT: Theme = Theme {
+        name: "default",
+        accent: Color::Cyan,
+        accent_focused: Color::LightCyan,
+        secondary: Color::Yellow,
+        secondary_focused: Color::LightYellow,
+        plot_border: Color::Magenta,
+        plot_points: Color::Yellow,
+        axis: Color::Gray,
+        expression: Color::Blue,
+        assignment: Color::Yellow,
+        result: Color::Green,
+        error: Color::Red,
+        pending: Color::Magenta,
+        selection: Color::DarkGray,
+        input_bg: Color::DarkGray,
+        visual_bg: Color::Cyan,
+        yank_bg: Color::Rgb(255, 165, 0),
+        on_highlight: Color::Black,
+        status_bg: Color::Black,
+        mode: Color::Blue,
+        muted: Color::DarkGray,
+        unsaved: Color::Yellow,
+        highlight: Modifier::empty(),
+        plain: false,
+    };
+
+    pub const SOLARIZED: Theme = Theme {
+        name: "solarized",
+        accent: Color::Rgb(38, 139, 210),
+        accent_focused: Color::Rgb(42, 161, 152),
+        secondary: Color::Rgb(181, 137, 0),
+        secondary_focused: Color::Rgb(203, 75, 22),
+        plot_border: Color::Rgb(108, 113, 196),
+        plot_points: Color::Rgb(181, 137, 0),
+        axis: Color::Rgb(147, 161, 161),
+        expression: Color::Rgb(38, 139, 210),
+        assignment: Color::Rgb(181, 137, 0),
+        result: Color::Rgb(133, 153, 0),
+        error: Color::Rgb(220, 50, 47),
+        pending: Color::Rgb(211, 54, 130),
+        selection: Color::Rgb(7, 54, 66),
+        input_bg: Color::Rgb(7, 54, 66),
+        visual_bg: Color::Rgb(42, 161, 152),
+        yank_bg: Color::Rgb(203, 75, 22),
+        on_highlight: Color::Rgb(0, 43, 54),
+        status_bg: Color::Rgb(0, 43, 54),
+        mode: Color::Rgb(38, 139, 210),
+        muted: Color::Rgb(88, 110, 117),
+        unsaved: Color::Rgb(181, 137, 0),
+        highlight: Modifier::empty(),
+        plain: false,
+    };
+
+    pub const GRUVBOX: Theme = Theme {
+        name: "gruvbox",
+        accent: Color::Rgb(131, 165, 152),
+        accent_focused: Color::Rgb(142, 192, 124),
+        secondary: Color::Rgb(250, 189, 47),
+        secondary_focused: Color::Rgb(254, 128, 25),
+        plot_border: Color::Rgb(211, 134, 155),
+        plot_points: Color::Rgb(250, 189, 47),
+        axis: Color::Rgb(168, 153, 132),
+        expression: Color::Rgb(131, 165, 152),
+        assignment: Color::Rgb(250, 189, 47),
+        result: Color::Rgb(184, 187, 38),
+        error: Color::Rgb(251, 73, 52),
+        pending: Color::Rgb(211, 134, 155),
+        selection: Color::Rgb(80, 73, 69),
+        input_bg: Color::Rgb(60, 56, 54),
+        visual_bg: Color::Rgb(142, 192, 124),
+        yank_bg: Color::Rgb(254, 128, 25),
+        on_highlight: Color::Rgb(40, 40, 40),
+        status_bg: Color::Rgb(40, 40, 40),
+        mode: Color::Rgb(131, 165, 152),
+        muted: Color::Rgb(102, 92, 84),
+        unsaved: Color::Rgb(250, 189, 47),
+        highlight: Modifier::empty(),
+        plain: false,
+    };
+
+    /// Dark text on light backgrounds.
+    pub const LIGHT: Theme = Theme {
+        name: "light",
+        accent: Color::Blue,
+        accent_focused: Color::Rgb(0, 95, 215),
+        secondary: Color::Rgb(175, 95, 0),
+        secondary_focused: Color::Rgb(215, 95, 0),
+        plot_border: Color::Magenta,
+        plot_points: Color::Rgb(175, 95, 0),
+        axis: Color::DarkGray,
+        expression: Color::Blue,
+        assignment: Color::Rgb(175, 95, 0),
+        result: Color::Rgb(0, 135, 0),
+        error: Color::Red,
+        pending: Color::Magenta,
+        selection: Color::Rgb(208, 208, 208),
+        input_bg: Color::Rgb(228, 228, 228),
+        visual_bg: Color::Rgb(135, 215, 255),
+        yank_bg: Color::Rgb(255, 175, 95),
+        on_highlight: Color::Black,
+        status_bg: Color::Rgb(208, 208, 208),
+        mode: Color::Blue,
+        muted: Color::Gray,
+        unsaved: Color::Rgb(175, 95, 0),
+        highlight: Modifier::empty(),
+        plain: false,
+    };
+
+    /// Blue and orange in place of green and red, from the Okabe–Ito palette, for
+    /// red-green color blindness with weak green (deuteranopia).
+    pub const DEUTERANOPIA: Theme = Theme {
+        name: "deuteranopia",
+        accent: Color::Rgb(86, 180, 233),
+        accent_focused: Color::Rgb(140, 205, 240),
+        secondary: Color::Rgb(240, 228, 66),
+        secondary_focused: Color::Rgb(250, 240, 130),
+        plot_border: Color::Rgb(204, 121, 167),
+        plot_points: Color::Rgb(240, 228, 66),
+        axis: Color::Gray,
+        expression: Color::Rgb(86, 180, 233),
+        assignment: Color::Rgb(240, 228, 66),
+        result: Color::Rgb(0, 114, 178),
+        error: Color::Rgb(230, 159, 0),
+        pending: Color::Rgb(204, 121, 167),
+        selection: Color::DarkGray,
+        input_bg: Color::DarkGray,
+        visual_bg: Color::Rgb(86, 180, 233),
+        yank_bg: Color::Rgb(240, 228, 66),
+        on_highlight: Color::Black,
+        status_bg: Color::Black,
+        mode: Color::Rgb(0, 114, 178),
+        muted: Color::DarkGray,
+        unsaved: Color::Rgb(230, 159, 0),
+        highlight: Modifier::empty(),
+        plain: false,
+    };
+
+    /// Like [`DEUTERANOPIA`](Self::DEUTERANOPIA), with a brighter error color
+    /// since reds look dark with weak red (protanopia).
+    pub const PROTANOPIA: Theme = Theme {
+        name: "protanopia",
+        accent: Color::Rgb(86, 180, 233),
+        accent_focused: Color::Rgb(140, 205, 240),
+        secondary: Color::Rgb(240, 228, 66),
+        secondary_focused: Color::Rgb(250, 240, 130),
+        plot_border: Color::Rgb(204, 121, 167),
+        plot_points: Color::Rgb(86, 180, 233),
+        axis: Color::Gray,
+        expression: Color::Rgb(86, 180, 233),
+        assignment: Color::Rgb(204, 121, 167),
+        result: Color::Rgb(86, 180, 233),
+        error: Color::Rgb(240, 228, 66),
+        pending: Color::Rgb(204, 121, 167),
+        selection: Color::DarkGray,
+        input_bg: Color::DarkGray,
+        visual_bg: Color::Rgb(86, 180, 233),
+        yank_bg: Color::Rgb(240, 228, 66),
+        on_highlight: Color::Black,
+        status_bg: Color::Black,
+        mode: Color::Rgb(0, 114, 178),
+        muted: Color::DarkGray,
+        unsaved: Color::Rgb(240, 228, 66),
+        highlight: Modifier::empty(),
+        plain: false,
+    };
+
+    /// Terminal default colors only, used when `NO_COLOR` is set.
+    pub const MONO: Theme = Theme {
+        name: "mono",
+        accent: Color::Reset,
+        accent_focused: Color::Reset,
+        secondary: Color::Reset,
+        secondary_focused: Color::Reset,
+        plot_border: Color::Reset,
+        plot_points: Color::Reset,
+        axis: Color::Reset,
+        expression: Color::Reset,
+        assignment: Color::Reset,
+        result: Color::Reset,
+        error: Color::Reset,
+        pending: Color::Reset,
+        selection: Color::Reset,
+        input_bg: Color::Reset,
+        visual_bg: Color::Reset,
+        yank_bg: Color::Reset,
+        on_highlight: Color::Reset,
+        status_bg: Color::Reset,
+        mode: Color::Reset,
+        muted: Color::Reset,
+        unsaved: Color::Reset,
+        highlight: Modifier::REVERSED,
+        plain: false,
+    };
+
+    pub const BUILTIN: [Theme; 7] = [
+        Theme::DEFAULT,
+        Theme::LIGHT,
+        Theme::SOLARIZED,
+        Theme::GRUVBOX,
+        Theme::MONO,
+        Theme::DEUTERANOPIA,
+        Theme::PROTANOPIA,
+    ];
+
+    pub fn named(name: &str) -> Option<Theme> {
+        Self::BUILTIN.into_iter().find(|theme| theme.name == name)
+    }
+
+    /// The built-in theme after this one, wrapping around.
+    pub fn next(&self) -> Theme {
+        let idx = Self::BUILTIN
+            .iter()
+            .position(|theme| theme.name == self.name)
+            .map_or(0, |idx| idx + 1);
+        Self::BUILTIN[idx % Self::BUILTIN.len()]
+    }
+
+    /// This theme with plain rendering switched on or off.
+    pub fn with_plain(self, plain: bool) -> Theme {
+        Theme { plain, ..self }
+    }
+
+    /// Block around a pane: thick borders when focused, rounded otherwise. Plain
+    /// themes draw no borders and mark the focused pane in its title instead.
+    pub fn pane_block<'a>(&self, focused: bool) -> Block<'a> {
+        match (self.plain, focused) {
+            (true, true) => Block::new().title("[FOCUSED]"),
+            (true, false) => Block::new(),
+            (false, true) => Block::bordered().border_type(BorderType::Thick),
+            (false, false) => Block::bordered().border_type(BorderType::Rounded),
+        }
+    }
+
+    /// Block around a popup drawn over the panes.
+    pub fn popup_block<'a>(&self) -> Block<'a> {
+        self.pane_block(false)
+    }
+
+    /// Marks the selected item of a list.
+    pub fn highlight_symbol(&self) -> &'static str {
+        if self.plain { "[SELECTED] " } else { "› " }
+    }
+
+    /// Put before a result, so success is not told apart from errors by color alone.
+    pub fn success_symbol(&self) -> &'static str {
+        if self.plain { "" } else { "✓ " }
+    }
+
+    /// Put before an error; plain themes spell it out.
+    pub fn error_symbol(&self) -> &'static str {
+        if self.plain { "[ERROR] " } else { "✗ " }
+    }
+
+    /// Picks the theme to start with: `NO_COLOR` wins, then the terminal background.
+    pub fn for_terminal(background: Background, no_color: bool) -> Theme {
+        match (no_color, background) {
+            (true, _) => Theme::MONO,
+            (false, Background::Dark) => Theme::DEFAULT,
+            (false, Background::Light) => Theme::LIGHT,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Background {
+    #[default]
+    Dark,
+    Light,
+}
+
+impl Background {
+    /// Reads the background from a `COLORFGBG` value such as `15;0`, as set by
+    /// rxvt, Konsole, and others. Defaults to dark when unknown.
+    pub fn from_colorfgbg(colorfgbg: Option<&str>) -> Background {
+        let background = colorfgbg
+            .and_then(|value| value.rsplit(';').next())
+            .and_then(|bg| bg.parse::<u8>().ok());
+        match background {
+            // ANSI 7 (white) and 9-15 (bright colors except 8, bright black)
+            Some(7) | Some(9..=15) => Background::Light,
+            _ => Background::Dark,
+        }
+    }
+}
+
+/// Whether the `NO_COLOR` convention asks for colorless output.
+pub fn no_color_requested(no_color: Option<&str>) -> bool {
+    no_color.is_some_and(|value| !value.is_empty())
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::DEFAULT
+    }
+}
@@ -1,117 +1,270 @@
-pub fn tokenize(phrase: &str) -> Vec<&str> {
-    let mut tokens: Vec<&str> = Vec::new();
-    let bytes = phrase.as_bytes();
-    let mut i = 0;
+use std::ops::Range;
 
-    while i < bytes.len() {
-        let b = bytes[i];
+use crate::builtins::{builtin_prefix_len, constant, is_function};
 
-        if b.is_ascii_whitespace() {
-            i += 1;
-            continue;
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TokenKind {
+    Number,
+    /// A single-letter variable or a named constant such as `pi`
+    Identifier,
+    Function,
+    /// `+`, `-`, `*`, `/`, or `^` (also written `**`)
+    Operator,
+    Equals,
+    LParen,
+    RParen,
+    /// Separates the arguments of a function call
+    Comma,
+    /// `#3`, the result of History entry 3
+    HistoryRef,
+}
+
+/// Operators written with two characters, and the operator they stand for.
+const TWO_CHAR_OPERATORS: &[(&str, &str)] = &[("**", "^")];
+
+/// A token with its position in the input.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Token<'a> {
+    pub kind: TokenKind,
+    /// The token as the parser reads it; operators written in two characters,
+    /// like `**`, are given in their one-character form
+    pub text: &'a str,
+    /// Byte range in the input; empty for the `*` of implicit multiplication
+    pub span: Range<usize>,
+}
+
+impl Token<'_> {
+    /// Whether this is a `*` inserted for implicit multiplication, as in `2x`.
+    pub fn is_implicit(&self) -> bool {
+        self.span.is_empty()
+    }
+}
+
+/// Splits an expression into [`Token`]s, inserting `*` where multiplication is
+/// implied (`2x`, `3(a+b)`, `(a)(b)`). Whitespace and unknown characters are skipped.
+pub struct Lexer<'a> {
+    phrase: &'a str,
+    pos: usize,
+    previous: Option<Token<'a>>,
+    /// Token held back while the implicit `*` before it is returned
+    pending: Option<Token<'a>>,
+    /// Function names recognized on top of the built-ins
+    functions: Vec<&'a str>,
+}
+
+impl<'a> Lexer<'a> {
+    pub fn new(phrase: &'a str) -> Self {
+        Self {
+            phrase,
+            pos: 0,
+            previous: None,
+            pending: None,
+            functions: Vec::new(),
         }
+    }
 
-        if b.is_ascii_digit() || b == b'.' {
-            let start = i;
-            let mut saw_dot = b == b'.';
-            i += 1;
+    /// Also recognizes `functions` as function names, e.g. ones added to an
+    /// [`Evaluator`](crate::evaluator::Evaluator).
+    pub fn with_functions(mut self, functions: impl IntoIterator<Item = &'a str>) -> Self {
+        self.functions.extend(functions);
+        self
+    }
 
-            while i < bytes.len() {
-                let c = bytes[i];
-                if c.is_ascii_digit() {
-                    i += 1;
-                    continue;
-                }
-                if c == b'.' && !saw_dot {
-                    saw_dot = true;
-                    i += 1;
-                    continue;
+    /// Reads the next token from the input, without implicit multiplication.
+    fn scan(&mut self) -> Option<Token<'a>> {
+        let bytes = self.phrase.as_bytes();
+        while self.pos < bytes.len() {
+            let start = self.pos;
+            let b = bytes[start];
+
+            // `# …` comments run to the end of the line; `#3` is a History reference.
+            if b == b'#' && !bytes.get(start + 1).is_some_and(u8::is_ascii_digit) {
+                self.pos = self.phrase[start..]
+                    .find('\n')
+                    .map_or(bytes.len(), |newline| start + newline);
+                continue;
+            }
+            if b == b'#' {
+                self.pos += 1;
+                while bytes.get(self.pos).is_some_and(u8::is_ascii_digit) {
+                    self.pos += 1;
                 }
-                break;
+                return Some(self.token(TokenKind::HistoryRef, start));
             }
 
-            if needs_implicit_mul_before_number(&tokens) {
-                tokens.push("*");
+            if b.is_ascii_digit() || b == b'.' {
+                let mut saw_dot = b == b'.';
+                self.pos += 1;
+                while let Some(&c) = bytes.get(self.pos) {
+                    if c.is_ascii_digit() || (c == b'.' && !saw_dot) {
+                        saw_dot |= c == b'.';
+                        self.pos += 1;
+                    } else {
+                        break;
+                    }
+                }
+                // An exponent such as the `e-9` of `1.5e-9`; a bare `2e` is 2 times e.
+                if matches!(bytes.get(self.pos), Some(b'e' | b'E')) {
+                    let sign = usize::from(matches!(bytes.get(self.pos + 1), Some(b'+' | b'-')));
+                    let digits = bytes[self.pos + 1 + sign..]
+                        .iter()
+                        .take_while(|c| c.is_ascii_digit())
+                        .count();
+                    if digits > 0 {
+                        self.pos += 1 + sign + digits;
+                    }
+                }
+                return Some(self.token(TokenKind::Number, start));
             }
 
-            tokens.push(&phrase[start..i]);
-            continue;
-        }
-
-        if b.is_ascii_alphabetic() {
-            // Split alphabetic runs into single-letter variables:
-            // "abc" -> ["a", "*", "b", "*", "c"]
-            if needs_implicit_mul_before_ident(&tokens) {
-                tokens.push("*");
+            if b.is_ascii_alphabetic() {
+                // Alphabetic runs split into built-in names and single-letter variables:
+                // "abc" -> a, b, c and "pix" -> pi, x
+                let rest = &self.phrase[start..];
+                let custom_len = self
+                    .functions
+                    .iter()
+                    .filter(|name| !name.is_empty() && rest.starts_with(*name))
+                    .map(|name| name.len())
+                    .max();
+                self.pos += builtin_prefix_len(rest).max(custom_len).unwrap_or(1);
+                let name = &self.phrase[start..self.pos];
+                let kind = if is_function(name) || self.functions.contains(&name) {
+                    TokenKind::Function
+                } else {
+                    TokenKind::Identifier
+                };
+                return Some(self.token(kind, start));
             }
 
-            let start = i;
-            i += 1;
-            tokens.push(&phrase[start..i]);
-
-            while i < bytes.len() && bytes[i].is_ascii_alphabetic() {
-                tokens.push("*");
-                let s = i;
-                i += 1;
-                tokens.push(&phrase[s..i]);
+            if let Some((written, operator)) = TWO_CHAR_OPERATORS
+                .iter()
+                .find(|(written, _)| bytes[start..].starts_with(written.as_bytes()))
+            {
+                self.pos += written.len();
+                return Some(Token {
+                    kind: TokenKind::Operator,
+                    text: operator,
+                    span: start..self.pos,
+                });
             }
 
-            continue;
+            self.pos += 1;
+            let kind = match b {
+                b'+' | b'-' | b'*' | b'/' | b'^' => TokenKind::Operator,
+                b'=' => TokenKind::Equals,
+                b'(' => TokenKind::LParen,
+                b')' => TokenKind::RParen,
+                b',' => TokenKind::Comma,
+                _ => continue,
+            };
+            return Some(self.token(kind, start));
+        }
+        None
+    }
+
+    fn token(&self, kind: TokenKind, start: usize) -> Token<'a> {
+        Token {
+            kind,
+            text: &self.phrase[start..self.pos],
+            span: start..self.pos,
         }
+    }
 
-        match b {
-            b'+' => tokens.push("+"),
-            b'-' => tokens.push("-"),
-            b'*' => tokens.push("*"),
-            b'/' => tokens.push("/"),
-            b'^' => tokens.push("^"),
-            b'=' => tokens.push("="),
-            b'(' => {
-                if needs_implicit_mul_before_lparen(&tokens) {
-                    tokens.push("*");
-                }
-                tokens.push("(");
+    /// Whether multiplication is implied between the previous token and `next`.
+    fn implies_multiplication(&self, next: &Token) -> bool {
+        let Some(previous) = &self.previous else {
+            return false;
+        };
+        // A one-letter function such as a defined `f` is not an operand.
+        let operand_before = matches!(previous.kind, TokenKind::RParen)
+            || (previous.kind != TokenKind::Function && is_identifier_token(previous.text));
+        match next.kind {
+            TokenKind::Number | TokenKind::HistoryRef => operand_before,
+            TokenKind::Identifier | TokenKind::Function | TokenKind::LParen => {
+                operand_before || is_number_token(previous.text)
             }
-            b')' => tokens.push(")"),
-            _ => {}
+            _ => false,
         }
-
-        i += 1;
     }
+}
+
+impl<'a> Iterator for Lexer<'a> {
+    type Item = Token<'a>;
 
-    tokens
+    fn next(&mut self) -> Option<Token<'a>> {
+        let token = match self.pending.take() {
+            Some(token) => token,
+            None => {
+                let token = self.scan()?;
+                if self.implies_multiplication(&token) {
+                    let at = token.span.start;
+                    self.pending = Some(token);
+                    Token {
+                        kind: TokenKind::Operator,
+                        text: "*",
+                        span: at..at,
+                    }
+                } else {
+                    token
+                }
+            }
+        };
+        self.previous = Some(token.clone());
+        Some(token)
+    }
 }
 
-fn needs_implicit_mul_before_ident(tokens: &[&str]) -> bool {
-    matches!(
-        tokens.last().copied(),
-        Some(tok) if is_number_token(tok) || is_identifier_token(tok) || tok == ")"
-    )
+/// The statements of `input` separated by `;` or line breaks, trimmed, skipping
+/// empty ones: `a=2; b=3; a*b` gives `a=2`, `b=3`, and `a*b`. A comment stays with
+/// the last statement of its line.
+pub fn split_statements(input: &str) -> Vec<&str> {
+    let mut statements = Vec::new();
+    for line in input.lines() {
+        let (code, _) = split_comment(line);
+        let mut start = 0;
+        let ends = code.match_indices(';').map(|(idx, _)| idx);
+        for end in ends.chain([line.len()]) {
+            let statement = line[start..end].trim();
+            if !split_comment(statement).0.trim().is_empty() {
+                statements.push(statement);
+            }
+            start = end + 1;
+        }
+    }
+    statements
 }
 
-fn needs_implicit_mul_before_number(tokens: &[&str]) -> bool {
-    matches!(
-        tokens.last().copied(),
-        Some(tok) if is_identifier_token(tok) || tok == ")"
-    )
+/// Splits `line` before its `# …` comment, if it has one.
+pub fn split_comment(line: &str) -> (&str, Option<&str>) {
+    let comment_start = line
+        .match_indices('#')
+        .map(|(idx, _)| idx)
+        .find(|idx| !line[idx + 1..].starts_with(|c: char| c.is_ascii_digit()));
+    match comment_start {
+        Some(idx) => (&line[..idx], Some(&line[idx..])),
+        None => (line, None),
+    }
 }
 
-fn needs_implicit_mul_before_lparen(tokens: &[&str]) -> bool {
-    matches!(
-        tokens.last().copied(),
-        Some(tok) if is_number_token(tok) || is_identifier_token(tok) || tok == ")"
-    )
+/// Token texts of `phrase`, for callers that do not need positions.
+pub fn tokenize(phrase: &str) -> Vec<&str> {
+    Lexer::new(phrase).map(|token| token.text).collect()
 }
 
 fn is_identifier_token(tok: &str) -> bool {
-    tok.len() == 1 && tok.as_bytes()[0].is_ascii_alphabetic()
+    (tok.len() == 1 && tok.as_bytes()[0].is_ascii_alphabetic()) || constant(tok).is_some()
 }
 
 fn is_number_token(tok: &str) -> bool {
     let mut saw_digit = false;
     let mut saw_dot = false;
 
-    for b in tok.bytes() {
+    // The lexer only lets a valid exponent into a number token.
+    let mantissa = tok
+        .split_once(['e', 'E'])
+        .map_or(tok, |(mantissa, _)| mantissa);
+    for b in mantissa.bytes() {
         if b.is_ascii_digit() {
             saw_digit = true;
             continue;
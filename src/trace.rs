@@ -0,0 +1,90 @@
+use std::fmt;
+
+use crate::{
+    calculate::{Node, apply_call},
+    error::CalcError,
+    types::AngleMode,
+};
+
+/// Result of [`Evaluator::eval_trace`](crate::evaluator::Evaluator::eval_trace).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trace {
+    pub value: f64,
+    /// Every operation in the order it was evaluated, innermost first
+    pub steps: Vec<TraceStep>,
+}
+
+/// One operation reduced to its value.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TraceStep {
+    /// The sub-expression, printed as it would be typed
+    pub expression: String,
+    /// Values of its operands, left to right
+    pub operands: Vec<f64>,
+    pub result: f64,
+}
+
+/// `2*(3 + 4): 2, 7 → 14`
+impl fmt::Display for TraceStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let operands: Vec<String> = self
+            .operands
+            .iter()
+            .map(|operand| operand.to_string())
+            .collect();
+        write!(
+            f,
+            "{}: {} → {}",
+            self.expression,
+            operands.join(", "),
+            self.result
+        )
+    }
+}
+
+impl Node {
+    /// Like `eval` in `f64`, recording a [`TraceStep`] for every operation.
+    pub(crate) fn eval_traced(
+        &self,
+        angle_mode: AngleMode,
+        lookup: &dyn Fn(&str) -> Option<f64>,
+        steps: &mut Vec<TraceStep>,
+    ) -> Result<f64, CalcError> {
+        let (operands, result) = match self {
+            Node::Number(_) | Node::Identifier(_) => return self.eval(angle_mode, lookup),
+            Node::Negate(operand) => {
+                let value = operand.eval_traced(angle_mode, lookup, steps)?;
+                (vec![value], -value)
+            }
+            Node::Chain { first, rest } => {
+                let mut result = first.eval_traced(angle_mode, lookup, steps)?;
+                let mut operands = vec![result];
+                for (op, operand) in rest {
+                    let value = operand.eval_traced(angle_mode, lookup, steps)?;
+                    result = op.apply(&result, &value)?;
+                    operands.push(value);
+                }
+                (operands, result)
+            }
+            Node::Binary(op, lhs, rhs) => {
+                let lhs = lhs.eval_traced(angle_mode, lookup, steps)?;
+                let rhs = rhs.eval_traced(angle_mode, lookup, steps)?;
+                (vec![lhs, rhs], op.apply(&lhs, &rhs)?)
+            }
+            Node::Call { name, custom, args } => {
+                let args = args
+                    .iter()
+                    .map(|arg| arg.eval_traced(angle_mode, lookup, steps))
+                    .collect::<Result<Vec<f64>, _>>()?;
+                let result = apply_call(name, custom.as_ref(), args.clone(), angle_mode)?;
+                (args, result)
+            }
+        };
+        steps.push(TraceStep {
+            expression: self.to_string(),
+            operands,
+            result,
+        });
+        Ok(result)
+    }
+}
@@ -1,37 +1,126 @@
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap, VecDeque},
+    path::Path,
     time::{Duration, Instant},
 };
 
 pub use crate::input_editor::InputEditMode;
 use crate::{
-    calculate::calculate,
-    inspect::inspect_unknown_variables,
-    io::{reset_file_state, write_state_to_file},
-    types::{AppState, Focus, History, YankFlash},
-    widgets::input_area::render_input,
+    builtins::{CONSTANTS, FUNCTIONS},
+    commands::{Command, TabAction, TapeAction, parse_command},
+    completion::Completion,
+    csv_import::read_csv_column,
+    dates,
+    definitions::{FunctionDefinition, parse_definition},
+    error::CalcError,
+    evaluation::{Evaluation, FOREGROUND_WAIT},
+    evaluator::Evaluator,
+    events::{AppEvent, EventSource, TerminalEvents},
+    export::export_plot,
+    expr::Expr,
+    format::format_value,
+    history_search::HistorySearch,
+    inspect::{
+        dependency_graph, did_you_mean, inspect_unknown_variables, missing_operands,
+        referenced_variables, sorted_variable_names, unmatched_parens,
+    },
+    io::{
+        append_input_history, append_keymap_lines, list_sessions, read_session_state,
+        read_snapshot, reset_session_state, save_session_state_merged, write_session_state,
+        write_snapshot,
+    },
+    msg::Msg,
+    plot::{FUNCTION_RANGE, PlotDefinition, PlotEntry, PlotRange, sample_plot},
+    references::resolve_history_references,
+    rpn::RpnStack,
+    snippets::{Snippet, next_placeholder},
+    stats::summarize,
+    tape::parse_tape_line,
+    theme::Theme,
+    trace::TraceStep,
+    types::{
+        AppState, COMPACT_HISTORY_LIMIT, ClearTarget, DEFAULT_SESSION, Focus, History, HistoryKind,
+        LayoutState, Overlay, PlotStyle, SLOW_EVALUATION, Settings, Toast, Workspace, YankFlash,
+    },
+    widgets::input_area::{cursor_column, render_input, render_input_preview, render_tape_total},
 };
 use crate::{
     input_editor::{EditorCommand, InputEditor, Motion},
-    widgets::plot_block::render_scatter,
+    keymap::{Action, KeyChord, KeyContext, Keymap, Resolution},
+    widgets::{
+        confirm_popup::render_confirm_popup,
+        detail_popup::render_detail_popup,
+        function_menu::render_function_menu,
+        help_overlay::render_help_overlay,
+        list_scrollbar::{list_scrollbar_state, render_list_scrollbar},
+        plot_block::{PlotOptions, render_plot},
+        plot_picker::render_plot_picker,
+        prompt::prompt_cursor_column,
+        rpn_stack::render_rpn_stack,
+        search_prompt::render_search_prompt,
+        session_picker::render_session_picker,
+        snippet_picker::render_snippet_picker,
+        stats_popup::render_stats_popup,
+        status_bar::render_status_bar,
+        tab_bar::render_tab_bar,
+        toast::render_toast,
+        variable_chart::render_variable_chart,
+    },
+};
+use crate::{
+    tokenize::{Lexer, Token, split_comment, split_statements, tokenize},
+    widgets::variable_block::render_variable_block,
+};
+use crate::{
+    types::VariableEntry,
+    validate::validate,
+    widgets::history_block::{history_item_height, render_history_block, visible_window},
 };
-use crate::{tokenize::tokenize, widgets::variable_block::render_variable_block};
-use crate::{types::VariableEntry, widgets::history_block::render_history_block};
 use crate::{variables::parse_variables, widgets::help_message::render_help_message};
 use color_eyre::Result;
 use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use ratatui::{
-    DefaultTerminal, Frame,
-    crossterm::event::{self, Event, KeyEventKind},
-    layout::{Constraint, Direction, Layout, Position},
-    widgets::ListState,
+    DefaultTerminal, Frame, Terminal,
+    backend::{Backend, TestBackend},
+    buffer::Buffer,
+    layout::{Constraint, Layout, Margin, Position, Rect},
+    widgets::{Clear, ListState},
 };
+use time::{OffsetDateTime, UtcOffset};
+use unicode_segmentation::UnicodeSegmentation;
+
+/// How long toast messages stay visible.
+const TOAST_DURATION: Duration = Duration::from_secs(2);
+/// How long after the last change the state is written, so bursts of edits save once.
+const AUTOSAVE_DELAY: Duration = Duration::from_secs(1);
+/// How many state changes `:undo` can step back through.
+const UNDO_LIMIT: usize = 20;
+
+/// A submitted expression whose result is computed on a worker thread.
+struct PendingEvaluation {
+    input: String,
+    expression: String,
+    var_name: Option<String>,
+    used_variables: BTreeMap<String, f64>,
+    evaluation: Evaluation,
+}
+
+/// Where the parts of the screen go in a terminal of a given size.
+struct Panes {
+    help: Rect,
+    input: Rect,
+    status: Rect,
+    messages: Rect,
+    history: Rect,
+    variables: Rect,
+    plot: Rect,
+}
 
 /// App holds the state of the application
 pub struct App {
     /// Current value of the input box
     pub input: String,
-    /// Position of cursor in the editor area.
+    /// Position of cursor in the editor area, in grapheme clusters.
     pub character_index: usize,
     /// History of recorded messages
     pub history: Vec<History>,
@@ -43,9 +132,99 @@ pub struct App {
     pub history_state: ListState,
     pub variables_state: ListState,
     pub plot_data: Option<Vec<(f64, f64)>>,
+    pub plot_style: PlotStyle,
+    /// Index of the traced point in `plot_data`, once moved in the plot pane
+    pub plot_cursor: Option<usize>,
+    /// What the current `plot_data` was sampled from
+    pub plot: Option<PlotDefinition>,
+    /// Range the current plot is sampled over, when not its default
+    pub plot_range: Option<PlotRange>,
+    /// Plots saved by name with `:plot save`
+    pub plots: Vec<PlotEntry>,
+    /// Whether the plot pane shows a bar chart of the variables instead of the plot
+    pub variable_chart: bool,
+    /// Pane proportions
+    pub layout: LayoutState,
+    /// Active `Ctrl+R` reverse search, if any.
+    pub search: Option<HistorySearch>,
+    /// Whether the Variables pane lists each variable's references and dependents.
+    pub show_dependencies: bool,
+    /// Popup currently drawn over the panes, if any.
+    pub overlay: Option<Overlay>,
+    /// Active key bindings.
+    pub keymap: Keymap,
+    /// Templates from the snippets file, offered by `:snippet`
+    pub snippets: Vec<Snippet>,
+    /// Display and evaluation preferences
+    pub settings: Settings,
+    /// Functions defined with `f(x) = …`, in the order they were defined
+    pub functions: Vec<FunctionDefinition>,
+    /// Values entered while `settings.rpn` is on; kept for the session only
+    pub rpn_stack: RpnStack,
+    /// Running total of tape mode; `None` while tape mode is off
+    pub tape_total: Option<f64>,
+    /// Columns of numbers imported with `:import csv`, by name
+    pub lists: BTreeMap<String, Vec<f64>>,
+    /// The list summarized by the open `:stats` popup; `None` for History
+    stats_list: Option<String>,
+    /// Colors used by all widgets
+    pub theme: Theme,
+    /// Name of the session whose state file is in use
+    pub session: String,
+    /// Whether there are changes not yet written to the state file
+    pub unsaved: bool,
+    /// The session's state file as last read or written; when set, saving merges
+    /// in what other instances saved since instead of overwriting it
+    pub saved_state: Option<AppState>,
+    /// When pending changes are written, pushed back by every further change
+    autosave_at: Option<Instant>,
+    /// Set by `:q`, ending the run loop after the current key
+    pub should_quit: bool,
+    /// Offset used to group history entries by local day
+    pub utc_offset: UtcOffset,
+    /// Current-value preview for an input recalled from Variables, with that input
+    input_preview: Option<(String, String)>,
+    /// Entry number typed in the History pane, selecting that entry
+    history_number: String,
+    /// Rows the History and Variables panes show, for half-page jumps
+    history_page_len: usize,
+    variables_page_len: usize,
     editor: InputEditor,
+    /// Keys typed so far of a multi-key binding such as `ye`.
+    pending_keys: Vec<KeyChord>,
     editor_needs_sync: bool,
     yank_flash: Option<YankFlash>,
+    /// Transient message shown in the corner of the panes
+    pub toast: Option<Toast>,
+    /// Sessions listed by the session picker, read when it opens
+    session_choices: Vec<String>,
+    /// Lines given to `:map` since the last `:map save`
+    unsaved_mappings: Vec<String>,
+    /// Expression being evaluated in the background; Esc cancels it
+    evaluating: Option<PendingEvaluation>,
+    /// Statements of a `;`-separated submission still to be evaluated
+    queued_statements: VecDeque<String>,
+    /// Size of the terminal, as of the last [`Msg::Resize`]; empty before one
+    pub area: Rect,
+    /// Whether the terminal is too small for side-by-side panes
+    pub compact: bool,
+    /// Every submitted input line, oldest first, recalled with Up/Down and Ctrl+R
+    pub input_history: Vec<String>,
+    /// Entry of `input_history` shown while browsing it, with the line typed before
+    recalled_input: Option<(usize, String)>,
+    /// Tab completion in progress in the input
+    completion: Option<Completion>,
+    /// Whether the result detail popup lists the evaluation steps, toggled with `s`
+    pub detail_steps: bool,
+    /// State before each recent clear, overwrite, or restore, newest last, with
+    /// what it was; kept for the session only
+    undo_stack: VecDeque<(String, AppState)>,
+    /// The workspace tabs not shown, in order
+    pub workspaces: Vec<Workspace>,
+    /// Position of the shown workspace among all tabs
+    pub workspace: usize,
+    /// [`Workspace::id`] of the shown workspace
+    pub workspace_id: u64,
 }
 
 impl App {
@@ -62,9 +241,54 @@ impl App {
             history_state: ListState::default(),
             variables_state: ListState::default(),
             plot_data: None,
+            plot_range: None,
+            plots: Vec::new(),
+            plot_style: PlotStyle::default(),
+            plot_cursor: None,
+            plot: None,
+            variable_chart: false,
+            layout: LayoutState::default(),
+            search: None,
+            show_dependencies: false,
+            overlay: None,
+            keymap: Keymap::default(),
+            snippets: Vec::new(),
+            settings: Settings::default(),
+            functions: Vec::new(),
+            rpn_stack: RpnStack::new(),
+            tape_total: None,
+            lists: BTreeMap::new(),
+            stats_list: None,
+            theme: Theme::default(),
+            session: DEFAULT_SESSION.to_string(),
+            unsaved: false,
+            saved_state: None,
             editor,
+            pending_keys: Vec::new(),
             editor_needs_sync: false,
             yank_flash: None,
+            toast: None,
+            autosave_at: None,
+            should_quit: false,
+            utc_offset: UtcOffset::UTC,
+            input_preview: None,
+            history_number: String::new(),
+            history_page_len: 10,
+            variables_page_len: 10,
+            session_choices: Vec::new(),
+            unsaved_mappings: Vec::new(),
+            evaluating: None,
+            queued_statements: VecDeque::new(),
+            area: Rect::default(),
+            compact: false,
+            input_history: Vec::new(),
+            recalled_input: None,
+            completion: None,
+            detail_steps: false,
+            undo_stack: VecDeque::new(),
+            workspaces: Vec::new(),
+            workspace: 0,
+            workspace_id: 0,
         }
     }
 
@@ -80,10 +304,55 @@ impl App {
             input_edit_mode: editor.mode(),
             history_state: ListState::default(),
             variables_state: ListState::default(),
-            plot_data: state.plot_data.clone(),
+            plot_data: state.plot_points(),
+            plot: state.plot.clone(),
+            plot_range: state.plot_range,
+            plots: state.plots.clone(),
+            plot_style: state.plot_style,
+            plot_cursor: None,
+            variable_chart: false,
+            layout: state.layout,
+            search: None,
+            show_dependencies: false,
+            overlay: None,
+            keymap: Keymap::default(),
+            snippets: Vec::new(),
+            settings: state.settings.clone(),
+            functions: state.functions.clone(),
+            rpn_stack: RpnStack::new(),
+            tape_total: state.tape_total,
+            lists: state.lists.clone(),
+            stats_list: None,
+            theme: Theme::default(),
+            session: DEFAULT_SESSION.to_string(),
+            unsaved: false,
+            saved_state: None,
             editor,
+            pending_keys: Vec::new(),
             editor_needs_sync: false,
             yank_flash: None,
+            toast: None,
+            autosave_at: None,
+            should_quit: false,
+            utc_offset: UtcOffset::UTC,
+            input_preview: None,
+            history_number: String::new(),
+            history_page_len: 10,
+            variables_page_len: 10,
+            session_choices: Vec::new(),
+            unsaved_mappings: Vec::new(),
+            evaluating: None,
+            queued_statements: VecDeque::new(),
+            area: Rect::default(),
+            compact: false,
+            input_history: Vec::new(),
+            recalled_input: None,
+            completion: None,
+            detail_steps: false,
+            undo_stack: VecDeque::new(),
+            workspaces: state.workspaces.clone(),
+            workspace: state.workspace.min(state.workspaces.len()),
+            workspace_id: state.workspace_id,
         }
     }
 
@@ -108,7 +377,7 @@ impl App {
             return;
         }
 
-        let public_len = self.input.chars().count();
+        let public_len = self.input.graphemes(true).count();
         let target_cursor = match self.input_edit_mode {
             InputEditMode::Insert => self.character_index.min(public_len),
             InputEditMode::Normal | InputEditMode::Visual => {
@@ -182,11 +451,67 @@ impl App {
         self.sync_public_from_editor();
     }
 
+    /// Writes the current state to the state file, clearing the unsaved indicator on success.
+    /// With a [`saved_state`](Self::saved_state), variables and history other
+    /// instances saved in the meantime are merged in.
+    fn save_state(&mut self) {
+        let saved = match self.saved_state.take() {
+            Some(base) => match save_session_state_merged(&self.session, self.to_state(), &base) {
+                Ok(merged) => {
+                    self.variables = merged.variables.clone();
+                    self.history = merged.history.clone();
+                    self.workspaces = merged.workspaces.clone();
+                    self.saved_state = Some(merged);
+                    Ok(())
+                }
+                Err(err) => {
+                    self.saved_state = Some(base);
+                    Err(err)
+                }
+            },
+            None => write_session_state(&self.session, &self.to_state()),
+        };
+        match saved {
+            Ok(_) => {
+                self.unsaved = false;
+                self.autosave_at = None;
+            }
+            Err(err) => {
+                eprintln!("Failed to write state to file: {}", err);
+            }
+        }
+    }
+
+    /// Flags a change to be saved once no further changes follow for [`AUTOSAVE_DELAY`].
+    fn mark_unsaved(&mut self) {
+        self.unsaved = true;
+        self.autosave_at = Some(Instant::now() + AUTOSAVE_DELAY);
+    }
+
+    /// Saves pending changes whose debounce delay has passed by `now`.
+    pub fn autosave_if_due(&mut self, now: Instant) {
+        if self.autosave_at.is_some_and(|due| now >= due) {
+            self.save_state();
+        }
+    }
+
     pub fn to_state(&self) -> AppState {
         AppState {
             history: self.history.clone(),
             variables: self.variables.clone(),
             plot_data: self.plot_data.clone(),
+            plot: self.plot.clone(),
+            plot_range: self.plot_range,
+            plots: self.plots.clone(),
+            settings: self.settings.clone(),
+            layout: self.layout,
+            plot_style: self.plot_style,
+            functions: self.functions.clone(),
+            tape_total: self.tape_total,
+            lists: self.lists.clone(),
+            workspaces: self.workspaces.clone(),
+            workspace: self.workspace,
+            workspace_id: self.workspace_id,
         }
     }
 
@@ -198,9 +523,174 @@ impl App {
             Focus::Input => {}
             Focus::History => self.select_first_history_if_available(),
             Focus::Variables => self.select_first_variable_if_available(),
+            Focus::Plot => {}
+        }
+    }
+
+    /// Whether the plot pane is drawn: enabled in the layout and with data to show.
+    fn plot_shown(&self) -> bool {
+        let has_data = if self.variable_chart {
+            !self.variables.is_empty()
+        } else {
+            self.plot_data
+                .as_ref()
+                .is_some_and(|plot_data| !plot_data.is_empty())
+        };
+        !self.compact && self.layout.plot_visible() && has_data
+    }
+
+    fn pane_visible(&self, focus: Focus) -> bool {
+        match focus {
+            Focus::Variables => self.layout.variables_visible(),
+            Focus::Plot => self.plot_shown(),
+            Focus::Input | Focus::History => true,
+        }
+    }
+
+    /// Moves the traced point by `delta` samples, starting from the middle of the plot.
+    fn move_plot_cursor(&mut self, delta: isize) {
+        let len = self.plot_data.as_ref().map_or(0, Vec::len);
+        if len == 0 {
+            return;
+        }
+        self.plot_cursor = Some(match self.plot_cursor {
+            Some(idx) => idx.saturating_add_signed(delta).min(len - 1),
+            None => len / 2,
+        });
+    }
+
+    /// Appends `entry` to History. With `dedup_history` set, earlier entries with
+    /// the same input are removed first, passing their note on.
+    fn push_history(&mut self, mut entry: History) {
+        if self.settings.dedup_history {
+            while let Some(idx) = self
+                .history
+                .iter()
+                .rposition(|old| old.expression == entry.expression)
+            {
+                let old = self.history.remove(idx);
+                entry.note = entry.note.or(old.note);
+            }
+        }
+        self.history.push(entry);
+    }
+
+    /// Shows a new plot and logs it in History.
+    fn set_plot(&mut self, definition: PlotDefinition, plot_data: Vec<(f64, f64)>) {
+        self.plot = Some(definition);
+        self.plot_range = None;
+        self.plot_data = Some(plot_data);
+        self.variable_chart = false;
+        self.plot_cursor = None;
+        self.push_history(History {
+            expression: self.input.clone(),
+            result: None,
+            error: None,
+            note: None,
+            variables: BTreeMap::new(),
+            kind: HistoryKind::Expression,
+            timestamp: Some(dates::now()),
+            duration_ms: None,
+        });
+    }
+
+    /// Re-evaluates variables from their stored expressions in dependency order:
+    /// all of them, or `name` and the ones defined in terms of it. Returns how many
+    /// values changed.
+    fn recompute(&mut self, name: Option<&str>) -> Result<usize, String> {
+        let graph = dependency_graph(&self.variables);
+        let order = match name {
+            Some(name) if !self.variables.contains_key(name) => {
+                return Err(format!("Unknown variable: {}", name));
+            }
+            Some(name) => graph
+                .recompute_order(name)
+                .map(|dependents| [vec![name.to_string()], dependents].concat()),
+            None => graph.topological_order(),
+        }
+        .map_err(|cycle| format!("Circular definition: {}", cycle.join(" → ")))?;
+
+        let mut evaluator = self.evaluator().map_err(|err| err.to_string())?;
+        let mut changed = 0;
+        for name in order {
+            let Some(entry) = self.variables.get_mut(&name) else {
+                continue;
+            };
+            let value = evaluator
+                .eval(&entry.expression)
+                .map_err(|err| format!("{}: {}", name, err))?;
+            if value != entry.value {
+                entry.value = value;
+                changed += 1;
+            }
+        }
+        Ok(changed)
+    }
+
+    /// Displays the plot saved as `name`, sampled with the current variables.
+    fn show_plot(&mut self, name: &str) -> Result<(), String> {
+        let entry = self
+            .plots
+            .iter()
+            .find(|plot| plot.name == name)
+            .ok_or_else(|| format!("No saved plot named {}", name))?;
+        let plot_data = entry
+            .sample(&self.variables)
+            .map_err(|err| err.to_string())?;
+        self.plot = Some(entry.definition.clone());
+        self.plot_range = entry.range;
+        self.plot_data = Some(plot_data);
+        self.plot_cursor = None;
+        self.variable_chart = false;
+        self.mark_unsaved();
+        Ok(())
+    }
+
+    /// Re-samples the current plot so it reflects updated variables.
+    fn resample_plot(&mut self) {
+        let Some(plot) = &self.plot else {
+            return;
+        };
+        let range = match self.plot_range {
+            Some(range) => Ok(range),
+            None => plot.default_range(&self.variables),
+        };
+        if let Ok(plot_data) = range.and_then(|range| plot.sample_in(range, &self.variables)) {
+            let last = plot_data.len().saturating_sub(1);
+            self.plot_cursor = self.plot_cursor.map(|idx| idx.min(last));
+            self.plot_data = Some(plot_data);
+        }
+    }
+
+    /// The expression behind the current plot, falling back to the latest plotted
+    /// History entry for plots restored from older state files.
+    fn plot_title(&self) -> String {
+        if let Some(plot) = &self.plot {
+            return plot.label();
+        }
+        self.history
+            .iter()
+            .rev()
+            .find(|entry| entry.result.is_none() && entry.error.is_none())
+            .map(|entry| entry.expression.clone())
+            .unwrap_or_default()
+    }
+
+    fn leave_hidden_pane(&mut self) {
+        if !self.pane_visible(self.focus) {
+            self.set_focus(Focus::History);
         }
     }
 
+    /// Moves focus with `step`, skipping panes that are hidden.
+    fn cycle_focus(&mut self, step: fn(Focus) -> Focus) {
+        let mut focus = step(self.focus);
+        while !self.pane_visible(focus) {
+            focus = step(focus);
+        }
+        self.set_focus(focus);
+    }
+
     fn set_input_edit_mode(&mut self, mode: InputEditMode) {
         self.input_edit_mode = mode;
         self.editor_needs_sync = true;
@@ -209,7 +699,7 @@ impl App {
 
     fn set_input_text(&mut self, text: String) {
         self.input = text;
-        self.character_index = self.input.chars().count();
+        self.character_index = self.input.graphemes(true).count();
         self.input_edit_mode = InputEditMode::Insert;
         self.editor_needs_sync = true;
         self.ensure_editor_synced_from_public();
@@ -238,66 +728,36 @@ impl App {
         }
     }
 
-    fn move_history_selection_up(&mut self) {
-        let len = self.history.len();
-        if len == 0 {
-            self.history_state.select(None);
-            return;
-        }
-
-        let next = match self.history_state.selected() {
-            Some(i) => i.saturating_sub(1),
-            None => 0,
+    /// Moves the selection of the focused list to `target(selected, len)`, clamped to
+    /// the list; with nothing selected yet, the first item is selected.
+    fn move_list_selection(&mut self, target: impl FnOnce(usize, usize) -> usize) {
+        let (state, len) = match self.focus {
+            Focus::History => (&mut self.history_state, self.history.len()),
+            Focus::Variables => (&mut self.variables_state, self.variables.len()),
+            Focus::Input | Focus::Plot => return,
         };
-        self.history_state.select(Some(next));
-    }
-
-    fn move_history_selection_down(&mut self) {
-        let len = self.history.len();
         if len == 0 {
-            self.history_state.select(None);
+            state.select(None);
             return;
         }
 
-        let next = match self.history_state.selected() {
-            Some(i) => (i + 1).min(len - 1),
+        let next = match state.selected() {
+            Some(i) => target(i, len).min(len - 1),
             None => 0,
         };
-        self.history_state.select(Some(next));
-    }
-
-    fn sorted_variable_keys(&self) -> Vec<String> {
-        let mut keys: Vec<String> = self.variables.keys().cloned().collect();
-        keys.sort();
-        keys
+        state.select(Some(next));
     }
 
-    fn move_variables_selection_up(&mut self) {
-        let len = self.sorted_variable_keys().len();
-        if len == 0 {
-            self.variables_state.select(None);
-            return;
+    /// Rows visible in the focused list pane, as fitted to the terminal size.
+    fn list_page_len(&self) -> usize {
+        match self.focus {
+            Focus::Variables => self.variables_page_len,
+            _ => self.history_page_len,
         }
-
-        let next = match self.variables_state.selected() {
-            Some(i) => i.saturating_sub(1),
-            None => 0,
-        };
-        self.variables_state.select(Some(next));
     }
 
-    fn move_variables_selection_down(&mut self) {
-        let len = self.sorted_variable_keys().len();
-        if len == 0 {
-            self.variables_state.select(None);
-            return;
-        }
-
-        let next = match self.variables_state.selected() {
-            Some(i) => (i + 1).min(len - 1),
-            None => 0,
-        };
-        self.variables_state.select(Some(next));
+    fn sorted_variable_keys(&self) -> Vec<String> {
+        sorted_variable_names(&self.variables, &self.history, self.settings.variable_sort)
     }
 
     fn populate_input_from_history(&mut self) {
@@ -321,26 +781,293 @@ impl App {
             && let Some(key) = keys.get(selected_idx)
             && let Some(entry) = self.variables.get(key)
         {
-            self.set_input_text(entry.expression.clone());
+            let expression = entry.expression.clone();
+            let stored = entry.value;
+            let preview = match self.evaluate_preview(&expression) {
+                Ok(value) if value == stored => {
+                    format!("= {}", format_value(value, self.settings.precision))
+                }
+                Ok(value) => format!(
+                    "= {} (stored {})",
+                    format_value(value, self.settings.precision),
+                    format_value(stored, self.settings.precision)
+                ),
+                Err(err) => err.to_string(),
+            };
+            self.input_preview = Some((expression.clone(), preview));
+            self.set_input_text(expression);
             self.set_focus(Focus::Input);
         }
     }
 
+    /// Evaluates `expression` (an assignment's right-hand side, for assignments) with
+    /// the current variables, without recording anything.
+    fn evaluate_preview(&self, expression: &str) -> Result<f64, CalcError> {
+        if let Some(diagnostic) = validate(expression, &self.variables).into_iter().next() {
+            return Err(diagnostic.error);
+        }
+        let mut tokens = tokenize(expression);
+        if tokens.contains(&"=") {
+            tokens = parse_variables(tokens)?.tokens;
+        }
+        self.evaluator()?.eval_tokens(&tokens)
+    }
+
+    /// An evaluator with the current settings, variables, and functions.
+    fn evaluator(&self) -> Result<Evaluator, CalcError> {
+        self.evaluator_with(&self.functions)
+    }
+
+    /// Like [`evaluator`](Self::evaluator), with `functions` in place of the
+    /// current ones.
+    fn evaluator_with(&self, functions: &[FunctionDefinition]) -> Result<Evaluator, CalcError> {
+        let mut evaluator = Evaluator::new()
+            .with_angle_mode(self.settings.angle_mode)
+            .with_power_rules(self.settings.power)
+            .with_variables(self.variables.clone());
+        evaluator.define_functions(functions)?;
+        Ok(evaluator)
+    }
+
+    /// Adds a user function, or replaces one in its place, recording it in History.
+    /// Fails when the functions would no longer all define, e.g. calling each other
+    /// in a loop.
+    fn define_function(&mut self, definition: Result<FunctionDefinition, CalcError>) {
+        let result = definition.and_then(|definition| {
+            let mut functions = self.functions.clone();
+            match functions.iter_mut().find(|f| f.name == definition.name) {
+                Some(existing) => *existing = definition.clone(),
+                None => functions.push(definition.clone()),
+            }
+            self.evaluator_with(&functions)?;
+            Ok((definition, functions))
+        });
+        let (kind, error) = match result {
+            Ok((definition, functions)) => {
+                self.notify(format!("Defined {}", definition));
+                self.functions = functions;
+                (HistoryKind::Definition, None)
+            }
+            Err(err) => (HistoryKind::Expression, Some(err.to_string())),
+        };
+        self.push_history(History {
+            expression: self.input.clone(),
+            result: None,
+            error,
+            note: None,
+            variables: BTreeMap::new(),
+            kind,
+            timestamp: Some(dates::now()),
+            duration_ms: None,
+        });
+        self.input.clear();
+        self.reset_cursor();
+        self.save_state();
+    }
+
+    /// The preview text, as long as the input still holds what it was computed for.
+    pub fn input_preview(&self) -> Option<&str> {
+        self.input_preview
+            .as_ref()
+            .filter(|(input, _)| *input == self.input)
+            .map(|(_, preview)| preview.as_str())
+    }
+
     pub fn submit_message(&mut self) {
         if self.input.is_empty() {
             return;
         }
+        self.record_input();
+
+        // `a=2; b=3; a*b` runs as three entries; commands keep their `;`.
+        if !self.input.trim_start().starts_with(':') && self.input.contains(';') {
+            self.queued_statements = split_statements(&self.input)
+                .into_iter()
+                .map(String::from)
+                .collect();
+            self.submit_queued_statements();
+            return;
+        }
+        self.submit_statement();
+    }
+
+    /// Applies the input to the Reverse Polish stack, recording the new top of the
+    /// stack in History.
+    fn submit_rpn(&mut self) {
+        let result = self.rpn_stack.apply(
+            split_comment(&self.input).0,
+            &self.variables,
+            self.settings.angle_mode,
+        );
+        let (result, error) = match result {
+            Ok(()) => (self.rpn_stack.top(), None),
+            Err(err) => (None, Some(err.to_string())),
+        };
+        if result.is_some() || error.is_some() {
+            self.push_history(History {
+                expression: self.input.clone(),
+                result,
+                error,
+                note: None,
+                variables: BTreeMap::new(),
+                kind: HistoryKind::Expression,
+                timestamp: Some(dates::now()),
+                duration_ms: None,
+            });
+        }
+        self.input.clear();
+        self.reset_cursor();
+        self.save_state();
+    }
+
+    /// Applies a line of tape input to the running total, recording it in History
+    /// with the new total as its result.
+    fn submit_tape(&mut self, total: f64) {
+        let (op, amount) = parse_tape_line(split_comment(&self.input).0);
+        let result = self
+            .evaluator()
+            .and_then(|mut evaluator| evaluator.eval(amount))
+            .and_then(|value| op.apply(total, value));
+        let (kind, expression, result, error) = match result {
+            Ok(total) => {
+                self.tape_total = Some(total);
+                (
+                    HistoryKind::Tape,
+                    format!("{} {}", op, amount),
+                    Some(total),
+                    None,
+                )
+            }
+            Err(err) => (
+                HistoryKind::Expression,
+                self.input.clone(),
+                None,
+                Some(err.to_string()),
+            ),
+        };
+        self.push_history(History {
+            expression,
+            result,
+            error,
+            note: None,
+            variables: BTreeMap::new(),
+            kind,
+            timestamp: Some(dates::now()),
+            duration_ms: None,
+        });
+        self.input.clear();
+        self.reset_cursor();
+        self.save_state();
+    }
+
+    /// Submits queued statements one after another until one has to wait for a
+    /// background evaluation; [`poll_evaluation`](Self::poll_evaluation) resumes.
+    fn submit_queued_statements(&mut self) {
+        while self.evaluating.is_none()
+            && let Some(statement) = self.queued_statements.pop_front()
+        {
+            self.input = statement;
+            self.submit_statement();
+        }
+    }
+
+    fn submit_statement(&mut self) {
+        // Nothing to evaluate in a line that is only a comment
+        if split_comment(&self.input).0.trim().is_empty() {
+            self.input.clear();
+            self.reset_cursor();
+            return;
+        }
+
+        if let Some(number) = self.input.trim().strip_prefix('#') {
+            match number.parse::<usize>() {
+                Ok(number) if (1..=self.history.len()).contains(&number) => {
+                    self.set_input_text(self.history[number - 1].expression.clone());
+                    return;
+                }
+                Ok(number) => {
+                    self.notify(format!("No history entry {}", number));
+                    return;
+                }
+                Err(_) => {}
+            }
+        }
+
+        if let Some(command) = parse_command(&self.input) {
+            let input = self.input.clone();
+            let outcome = command.and_then(|command| self.run_command(command));
+            if let Err(err) = outcome {
+                self.push_history(History {
+                    expression: input.clone(),
+                    result: None,
+                    error: Some(err),
+                    note: None,
+                    variables: BTreeMap::new(),
+                    kind: HistoryKind::Expression,
+                    timestamp: Some(dates::now()),
+                    duration_ms: None,
+                });
+            }
+            // Commands like `:simplify` put new text into the input; keep it.
+            if self.input == input {
+                self.input.clear();
+                self.reset_cursor();
+            }
+            self.save_state();
+            return;
+        }
 
         if self.input == "/clear" {
+            self.remember("clearing everything");
             self.variables.clear();
             self.history.clear();
             self.set_input_text(String::new());
             self.set_focus(Focus::Input);
-            let _ = reset_file_state();
+            let _ = reset_session_state(&self.session);
+            return;
+        }
+
+        if self.settings.rpn {
+            self.submit_rpn();
+            return;
+        }
+        if let Some(total) = self.tape_total {
+            self.submit_tape(total);
             return;
         }
 
-        let mut tokenized = tokenize(&self.input);
+        let expression = match resolve_history_references(&self.input, &self.history) {
+            Ok(expression) => expression,
+            Err(err) => {
+                self.push_history(History {
+                    expression: self.input.clone(),
+                    result: None,
+                    error: Some(err),
+                    note: None,
+                    variables: BTreeMap::new(),
+                    kind: HistoryKind::Expression,
+                    timestamp: Some(dates::now()),
+                    duration_ms: None,
+                });
+                self.input.clear();
+                self.reset_cursor();
+                self.save_state();
+                return;
+            }
+        };
+        if let Some(definition) = parse_definition(&expression) {
+            self.define_function(definition);
+            return;
+        }
+        let function_names: Vec<String> = self
+            .functions
+            .iter()
+            .map(|function| function.name.clone())
+            .collect();
+        let mut tokenized: Vec<&str> = Lexer::new(&expression)
+            .with_functions(function_names.iter().map(String::as_str))
+            .map(|token| token.text)
+            .collect();
         let mut var_name: Option<String> = None;
         if tokenized.contains(&"=") {
             let parsed_variables = parse_variables(tokenized);
@@ -350,91 +1077,280 @@ impl App {
                     var_name = Some(result.var_name);
                 }
                 Err(err) => {
-                    self.history.push(History {
+                    self.push_history(History {
                         expression: self.input.clone(),
                         result: None,
-                        error: Some(err),
+                        error: Some(err.to_string()),
+                        note: None,
+                        variables: BTreeMap::new(),
+                        kind: HistoryKind::Expression,
+                        timestamp: Some(dates::now()),
+                        duration_ms: None,
                     });
+                    self.mark_unsaved();
                     return;
                 }
             }
         }
 
-        let unknown_variables = inspect_unknown_variables(&tokenized, &self.variables);
+        let lexed: Vec<Token> = Lexer::new(&expression).collect();
+        if let Some(error) = unmatched_parens(&lexed)
+            .into_iter()
+            .chain(missing_operands(&lexed))
+            .next()
+        {
+            self.push_history(History {
+                expression: self.input.clone(),
+                result: None,
+                error: Some(error.to_string()),
+                note: None,
+                variables: BTreeMap::new(),
+                kind: HistoryKind::Expression,
+                timestamp: Some(dates::now()),
+                duration_ms: None,
+            });
+            self.input.clear();
+            self.reset_cursor();
+            self.save_state();
+            return;
+        }
+
+        let mut unknown_variables = inspect_unknown_variables(&tokenized, &self.variables);
+        unknown_variables.retain(|name| !function_names.contains(name));
+        let calls_function = tokenized
+            .iter()
+            .any(|token| function_names.iter().any(|name| name == token));
         if !unknown_variables.is_empty() {
-            if unknown_variables.len() == 1 {
-                let mut plot_data: Vec<(f64, f64)> = Vec::new();
-                let mut cloned_variables = self.variables.clone();
-                for i in -10..11 {
-                    cloned_variables.insert(
-                        unknown_variables[0].to_string(),
-                        VariableEntry {
-                            expression: "".to_string(),
-                            value: i as f64,
-                        },
-                    );
-                    let value = calculate(tokenized.clone(), &cloned_variables).unwrap_or_default();
-                    plot_data.push((i as f64, value));
-                }
-                self.plot_data = Some(plot_data);
-                self.history.push(History {
+            if unknown_variables.len() == 1 && calls_function {
+                self.push_history(History {
                     expression: self.input.clone(),
                     result: None,
-                    error: None,
+                    error: Some("Plots of defined functions are not supported".to_string()),
+                    note: None,
+                    variables: BTreeMap::new(),
+                    kind: HistoryKind::Expression,
+                    timestamp: Some(dates::now()),
+                    duration_ms: None,
                 });
                 self.input.clear();
                 self.reset_cursor();
-                match write_state_to_file(&self.to_state()) {
-                    Ok(_) => {}
-                    Err(err) => {
-                        eprintln!("Failed to write state to file: {}", err);
-                    }
-                }
+                self.save_state();
+                return;
+            }
+            if unknown_variables.len() == 1 {
+                let plot_data = sample_plot(
+                    &tokenized,
+                    &unknown_variables[0],
+                    &self.variables,
+                    FUNCTION_RANGE,
+                );
+                self.set_plot(
+                    PlotDefinition::Function {
+                        expression: expression.clone(),
+                        variable: unknown_variables[0].clone(),
+                    },
+                    plot_data,
+                );
+                self.input.clear();
+                self.reset_cursor();
+                self.save_state();
                 return;
             }
 
-            self.history.push(History {
+            let mut error = format!("Unknown variables: {}", unknown_variables.join(", "));
+            let suggestions = did_you_mean(&expression, &self.variables);
+            if !suggestions.is_empty() {
+                error.push_str(&format!(" — did you mean {}?", suggestions.join(" or ")));
+            }
+            self.push_history(History {
                 expression: self.input.clone(),
                 result: None,
-                error: Some(format!(
-                    "Unknown variables: {}",
-                    unknown_variables.join(", ")
-                )),
+                error: Some(error),
+                note: None,
+                variables: BTreeMap::new(),
+                kind: HistoryKind::Expression,
+                timestamp: Some(dates::now()),
+                duration_ms: None,
             });
             self.input.clear();
             self.reset_cursor();
-            match write_state_to_file(&self.to_state()) {
-                Ok(_) => {}
-                Err(err) => {
-                    eprintln!("Failed to write state to file: {}", err);
+            self.save_state();
+            return;
+        }
+        let evaluator = match self.evaluator() {
+            Ok(evaluator) => evaluator,
+            Err(err) => {
+                self.push_history(History {
+                    expression: self.input.clone(),
+                    result: None,
+                    error: Some(err.to_string()),
+                    note: None,
+                    variables: BTreeMap::new(),
+                    kind: HistoryKind::Expression,
+                    timestamp: Some(dates::now()),
+                    duration_ms: None,
+                });
+                self.input.clear();
+                self.reset_cursor();
+                self.save_state();
+                return;
+            }
+        };
+        let used_variables = referenced_variables(&tokenized, &self.variables);
+        let evaluation = Evaluation::spawn(evaluator, &tokenized, self.settings.eval_timeout());
+        let pending = PendingEvaluation {
+            input: self.input.clone(),
+            expression,
+            var_name,
+            used_variables,
+            evaluation,
+        };
+        match pending.evaluation.wait(FOREGROUND_WAIT) {
+            Some(result) => self.finish_evaluation(pending, result),
+            None => self.evaluating = Some(pending),
+        }
+    }
+
+    /// Adds the submitted line to the input history, in memory and on disk.
+    fn record_input(&mut self) {
+        self.recalled_input = None;
+        if self.input_history.last() == Some(&self.input) {
+            return;
+        }
+        self.input_history.push(self.input.clone());
+        if let Err(err) = append_input_history(&self.input) {
+            eprintln!("Failed to write input history: {}", err);
+        }
+    }
+
+    /// Steps through the input history: `older` goes back, otherwise forward until
+    /// the line typed before browsing is restored.
+    fn recall_input(&mut self, older: bool) {
+        let len = self.input_history.len();
+        let (idx, draft) = match (self.recalled_input.take(), older) {
+            (None, true) if len > 0 => (len - 1, self.input.clone()),
+            (None, _) => return,
+            (Some((idx, draft)), true) => (idx.saturating_sub(1), draft),
+            (Some((idx, draft)), false) if idx + 1 < len => (idx + 1, draft),
+            (Some((_, draft)), false) => {
+                self.set_input_text(draft);
+                return;
+            }
+        };
+        self.set_input_text(self.input_history[idx].clone());
+        self.recalled_input = Some((idx, draft));
+    }
+
+    /// Completes the variable, function, or constant name before the cursor, or
+    /// cycles to the next (`forward`) or previous match if Tab was just pressed.
+    fn complete(&mut self, forward: bool) {
+        let input = self.editor.input().to_string();
+        let cursor = self.editor.cursor();
+        let mut completion = match self.completion.take() {
+            Some(completion) if completion.is_current(&input, cursor) => completion,
+            _ => {
+                let names = self
+                    .variables
+                    .keys()
+                    .map(String::as_str)
+                    .chain(FUNCTIONS.iter().copied())
+                    .chain(CONSTANTS.iter().map(|(name, _)| *name));
+                match Completion::new(&input, cursor, names) {
+                    Some(completion) => completion,
+                    None => return,
                 }
             }
+        };
+
+        let candidate = completion.cycle(forward).to_string();
+        self.editor
+            .replace_before_cursor(completion.start(), &candidate);
+        self.sync_public_from_editor();
+        completion.set_expected(self.editor.input().to_string(), self.editor.cursor());
+        self.completion = Some(completion);
+    }
+
+    /// Whether an expression is still being evaluated in the background.
+    pub fn is_evaluating(&self) -> bool {
+        self.evaluating.is_some()
+    }
+
+    /// Applies the result of a background evaluation once it has arrived.
+    pub fn poll_evaluation(&mut self) {
+        let Some(result) = self
+            .evaluating
+            .as_ref()
+            .and_then(|pending| pending.evaluation.try_result())
+        else {
             return;
+        };
+        if let Some(pending) = self.evaluating.take() {
+            self.finish_evaluation(pending, result);
+            self.submit_queued_statements();
+        }
+    }
+
+    /// Abandons the background evaluation, keeping its input for editing. Statements
+    /// queued after it are dropped.
+    fn cancel_evaluation(&mut self) {
+        self.queued_statements.clear();
+        if let Some(pending) = self.evaluating.take() {
+            pending.evaluation.cancel();
+            self.notify("Evaluation cancelled");
         }
-        let res = calculate(tokenized, &self.variables);
-        match res {
+    }
+
+    fn finish_evaluation(&mut self, pending: PendingEvaluation, result: Result<f64, CalcError>) {
+        match result {
             Ok(result) => {
-                if let Some(var_name) = var_name {
-                    self.variables.insert(
-                        var_name.to_string(),
-                        VariableEntry {
-                            expression: self.input.clone(),
-                            value: result,
-                        },
-                    );
-                } else {
-                    self.history.push(History {
-                        expression: self.input.clone(),
-                        result: Some(result),
-                        error: None,
-                    });
-                }
+                let kind = match pending.var_name {
+                    Some(var_name) => {
+                        if self.variables.contains_key(&var_name) {
+                            self.remember(format!("overwriting {}", var_name));
+                        }
+                        // Reassigning keeps the display format and unit
+                        let (format, unit) = self
+                            .variables
+                            .get(&var_name)
+                            .map(|entry| (entry.format, entry.unit.clone()))
+                            .unwrap_or_default();
+                        self.variables.insert(
+                            var_name.to_string(),
+                            VariableEntry {
+                                expression: pending.expression.clone(),
+                                value: result,
+                                format,
+                                unit,
+                            },
+                        );
+                        self.resample_plot();
+                        HistoryKind::Assignment
+                    }
+                    None => HistoryKind::Expression,
+                };
+                let duration = pending.evaluation.started_at.elapsed();
+                self.push_history(History {
+                    expression: pending.input.clone(),
+                    result: Some(result),
+                    error: None,
+                    note: None,
+                    variables: pending.used_variables,
+                    kind,
+                    timestamp: Some(dates::now()),
+                    duration_ms: (duration > SLOW_EVALUATION)
+                        .then(|| duration.as_millis().try_into().unwrap_or(u64::MAX)),
+                });
             }
             Err(err) => {
-                self.history.push(History {
-                    expression: self.input.clone(),
+                self.push_history(History {
+                    expression: pending.input.clone(),
                     result: None,
-                    error: Some(err),
+                    error: Some(err.to_string()),
+                    note: None,
+                    variables: BTreeMap::new(),
+                    kind: HistoryKind::Expression,
+                    timestamp: Some(dates::now()),
+                    duration_ms: None,
                 });
             }
         }
@@ -444,41 +1360,678 @@ impl App {
         self.set_focus(Focus::Input);
         self.set_input_edit_mode(InputEditMode::Insert);
         self.yank_flash = None;
-        match write_state_to_file(&self.to_state()) {
-            Ok(_) => {}
-            Err(err) => {
-                eprintln!("Failed to write state to file: {}", err);
-            }
-        }
+        self.save_state();
     }
 
-    fn handle_input_key_event(&mut self, key: KeyEvent) -> bool {
-        if key.code == KeyCode::Up && matches!(self.input_edit_mode, InputEditMode::Insert) {
-            if let Some(last) = self.history.last() {
-                self.set_input_text(last.expression.clone());
+    fn run_command(&mut self, command: Command) -> Result<(), String> {
+        match command {
+            Command::Note { entry, text } => {
+                let history_entry = entry
+                    .checked_sub(1)
+                    .and_then(|idx| self.history.get_mut(idx))
+                    .ok_or_else(|| format!("No history entry {}", entry))?;
+                let removed = text.is_none();
+                history_entry.note = text;
+                self.mark_unsaved();
+                if removed {
+                    self.notify(format!("Removed note from #{}", entry));
+                } else {
+                    self.notify(format!("Labeled #{}", entry));
+                }
             }
-            return false;
-        }
-
-        self.mark_editor_dirty_if_public_changed();
-        self.ensure_editor_synced_from_public();
-        match self.editor.handle_key_event(key) {
-            EditorCommand::None => {
+            Command::Recompute(variable) => {
+                let changed = self.recompute(variable.as_deref())?;
+                if changed > 0 {
+                    self.mark_unsaved();
+                    self.resample_plot();
+                }
+                self.notify(format!("Recomputed, {} changed", changed));
+            }
+            Command::Simplify(expression) => {
+                // Keep an `x =` in front, simplifying only the value.
+                let (target, value) = match expression.split_once('=') {
+                    Some((target, value)) => (format!("{} = ", target.trim()), value),
+                    None => (String::new(), expression.as_str()),
+                };
+                let simplified = Expr::parse(value)
+                    .map_err(|err| err.to_string())?
+                    .simplify();
+                self.set_input_text(format!("{}{}", target, simplified));
+            }
+            Command::Parse(expression) => {
+                let parsed = self
+                    .evaluator()
+                    .and_then(|evaluator| evaluator.parse(&expression))
+                    .map_err(|err| err.to_string())?;
+                self.notify(format!("Parsed as {}", parsed.grouped()));
+            }
+            Command::Angle(angle_mode) => {
+                self.settings.angle_mode = angle_mode;
+                self.notify(format!("Angle mode: {}", angle_mode));
+            }
+            Command::Tape(action) => {
+                self.tape_total = match action {
+                    TapeAction::Start => Some(self.tape_total.unwrap_or(0.0)),
+                    TapeAction::Reset => Some(0.0),
+                    TapeAction::Stop => None,
+                };
+                self.mark_unsaved();
+                self.notify(match action {
+                    TapeAction::Start => "Tape mode: amounts add up, +-*/ in front change that",
+                    TapeAction::Reset => "Tape total reset to 0",
+                    TapeAction::Stop => "Tape mode off",
+                });
+            }
+            Command::Tab(action) => self.run_tab_action(action)?,
+            Command::Rpn(rpn) => {
+                self.settings.rpn = rpn;
+                self.notify(if rpn {
+                    "RPN mode: words like 5 3 + act on the stack"
+                } else {
+                    "RPN mode off"
+                });
+            }
+            Command::DedupHistory(dedup) => {
+                self.settings.dedup_history = dedup;
+                self.notify(if dedup {
+                    "Repeated inputs replace their History entry"
+                } else {
+                    "Repeated inputs are added to History again"
+                });
+            }
+            Command::Negation(negation) => {
+                self.settings.power.negation = negation;
+                self.notify(format!("Negation: {}", negation));
+            }
+            Command::Associativity(associativity) => {
+                self.settings.power.associativity = associativity;
+                self.notify(format!("Associativity: {}", associativity));
+            }
+            Command::Timeout(timeout_ms) => {
+                self.settings.eval_timeout_ms = timeout_ms;
+                self.notify(format!(
+                    "Evaluation timeout: {} s",
+                    self.settings.eval_timeout().as_secs_f64()
+                ));
+            }
+            Command::Precision(precision) => {
+                self.settings.precision = precision;
+                self.notify(match precision {
+                    Some(decimals) => format!("Precision: {} decimals", decimals),
+                    None => "Precision: auto".to_string(),
+                });
+            }
+            Command::Theme(theme) => {
+                self.theme = theme
+                    .unwrap_or_else(|| self.theme.next())
+                    .with_plain(self.theme.plain);
+                self.settings.theme = Some(self.theme.name.to_string());
+                self.notify(format!("Theme: {}", self.theme.name));
+            }
+            Command::Layout(preset) => {
+                self.resize_layout(|layout| layout.preset = preset);
+                self.leave_hidden_pane();
+                self.notify(format!("Layout: {}", preset.name()));
+            }
+            Command::Session(Some(name)) => self.switch_session(name)?,
+            Command::Session(None) => {
+                self.session_choices = list_sessions().map_err(|err| err.to_string())?;
+                let selected = self
+                    .session_choices
+                    .iter()
+                    .position(|session| *session == self.session)
+                    .unwrap_or(0);
+                self.overlay = Some(Overlay::SessionPicker { selected });
+            }
+            Command::Map(mapping) => {
+                self.keymap.apply_mapping(&mapping)?;
+                self.notify(format!("Mapped {} (:map save to keep it)", mapping));
+                self.unsaved_mappings.push(mapping);
+            }
+            Command::MapSave => {
+                if self.unsaved_mappings.is_empty() {
+                    return Err("No new mappings to save".to_string());
+                }
+                append_keymap_lines(&self.unsaved_mappings)
+                    .map_err(|err| format!("Could not save mappings: {}", err))?;
+                self.notify(format!("Saved {} mapping(s)", self.unsaved_mappings.len()));
+                self.unsaved_mappings.clear();
+            }
+            Command::SnapshotSave(name) => {
+                write_snapshot(&name, &self.to_state())
+                    .map_err(|err| format!("Could not save snapshot {}: {}", name, err))?;
+                self.notify(format!("Saved snapshot {}", name));
+            }
+            Command::SnapshotRestore(name) => {
+                let state = read_snapshot(&name).map_err(|err| {
+                    if err.is_not_found() {
+                        format!("No snapshot named {}", name)
+                    } else {
+                        format!("Could not restore snapshot {}: {}", name, err)
+                    }
+                })?;
+                self.remember(format!("restoring snapshot {}", name));
+                self.load_state(state);
+                self.mark_unsaved();
+                self.notify(format!("Restored snapshot {}", name));
+            }
+            Command::Format { variable, format } => {
+                let entry = self
+                    .variables
+                    .get_mut(&variable)
+                    .ok_or_else(|| format!("Unknown variable: {}", variable))?;
+                entry.format = format;
+                self.mark_unsaved();
+            }
+            Command::Unit { variable, unit } => {
+                let entry = self
+                    .variables
+                    .get_mut(&variable)
+                    .ok_or_else(|| format!("Unknown variable: {}", variable))?;
+                entry.unit = unit;
+                self.mark_unsaved();
+            }
+            Command::Quit => self.should_quit = true,
+            Command::Undo => self.undo()?,
+            Command::Compact => {
+                let mut state = self.to_state();
+                let report = state.compact(COMPACT_HISTORY_LIMIT);
+                self.load_state(state);
+                self.notify(report.to_string());
+            }
+            Command::Clear(target) => self.overlay = Some(Overlay::ConfirmClear(target)),
+            Command::Stats(list) => {
+                if let Some(name) = &list
+                    && !self.lists.contains_key(name)
+                {
+                    return Err(format!("No list named {}", name));
+                }
+                self.stats_list = list;
+                self.overlay = Some(Overlay::Stats);
+            }
+            Command::ImportCsv { path, column, name } => {
+                let data = std::fs::read_to_string(&path)
+                    .map_err(|err| format!("Could not read {}: {}", path, err))?;
+                let values = read_csv_column(&data, column)?;
+                self.notify(format!("Imported {} values into {}", values.len(), name));
+                self.lists.insert(name, values);
+                self.mark_unsaved();
+            }
+            Command::PlotList(name) => {
+                let values = self
+                    .lists
+                    .get(&name)
+                    .cloned()
+                    .ok_or_else(|| format!("No list named {}", name))?;
+                let definition = PlotDefinition::Points { name, values };
+                let plot_data = definition
+                    .sample(&self.variables)
+                    .map_err(|err| err.to_string())?;
+                self.set_plot(definition, plot_data);
+            }
+            Command::VariableChart(shown) => {
+                self.variable_chart = shown;
+                self.leave_hidden_pane();
+            }
+            Command::ExportPlot(path) => {
+                let plot_data = self
+                    .plot_data
+                    .as_ref()
+                    .ok_or_else(|| "Nothing to export: no plot yet".to_string())?;
+                export_plot(
+                    Path::new(&path),
+                    plot_data,
+                    &self.plot_title(),
+                    self.plot_style,
+                )?;
+                self.notify(format!("Exported plot to {}", path));
+            }
+            Command::SavePlot(name) => {
+                let definition = self
+                    .plot
+                    .clone()
+                    .ok_or_else(|| "No plot to save".to_string())?;
+                let entry = PlotEntry {
+                    name: name.clone(),
+                    definition,
+                    range: self.plot_range,
+                };
+                match self.plots.iter_mut().find(|plot| plot.name == name) {
+                    Some(existing) => *existing = entry,
+                    None => self.plots.push(entry),
+                }
+                self.notify(format!("Saved plot {}", name));
+            }
+            Command::Snippet(Some(name)) => {
+                let template = self
+                    .snippets
+                    .iter()
+                    .find(|snippet| snippet.name == name)
+                    .map(|snippet| snippet.template.clone())
+                    .ok_or_else(|| format!("No snippet named {}", name))?;
+                // Replaces the command in the input.
+                self.input.clear();
+                self.reset_cursor();
+                self.insert_snippet(&template);
+            }
+            Command::Snippet(None) => {
+                if self.snippets.is_empty() {
+                    return Err("No snippets; define them in ~/.config/rcalc/snippets".to_string());
+                }
+                self.overlay = Some(Overlay::SnippetPicker { selected: 0 });
+            }
+            Command::ShowPlot(Some(name)) => self.show_plot(&name)?,
+            Command::ShowPlot(None) => {
+                if self.plots.is_empty() {
+                    return Err("No saved plots; save one with :plot save <name>".to_string());
+                }
+                self.overlay = Some(Overlay::PlotPicker { selected: 0 });
+            }
+            Command::Plot(definition) => {
+                let plot_data = definition
+                    .sample(&self.variables)
+                    .map_err(|err| err.to_string())?;
+                self.set_plot(definition, plot_data);
+            }
+        }
+        Ok(())
+    }
+
+    /// Saves the current session, then replaces the app state with `name`'s, starting
+    /// empty when it has no state file yet.
+    fn switch_session(&mut self, name: String) -> Result<(), String> {
+        if name == self.session {
+            return Ok(());
+        }
+        let state = match read_session_state(&name) {
+            Ok(state) => state,
+            Err(err) if err.is_not_found() => AppState::default(),
+            Err(err) => return Err(format!("Could not load session {}: {}", name, err)),
+        };
+        self.save_state();
+
+        if self.saved_state.is_some() {
+            self.saved_state = Some(state.clone());
+        }
+        self.load_state(state);
+        self.notify(format!("Switched to session {}", name));
+        self.session = name;
+        self.undo_stack.clear();
+        self.unsaved = false;
+        Ok(())
+    }
+
+    /// Replaces history, variables, plot, and layout with `state`, resetting selections.
+    fn load_state(&mut self, state: AppState) {
+        self.plot_data = state.plot_points();
+        self.history = state.history;
+        self.variables = state.variables;
+        self.plot = state.plot;
+        self.plot_range = state.plot_range;
+        self.plots = state.plots;
+        self.settings = state.settings;
+        self.functions = state.functions;
+        self.tape_total = state.tape_total;
+        self.lists = state.lists;
+        self.workspaces = state.workspaces;
+        self.workspace = state.workspace.min(self.workspaces.len());
+        self.workspace_id = state.workspace_id;
+        self.plot_style = state.plot_style;
+        self.layout = state.layout;
+        self.plot_cursor = None;
+        self.variable_chart = false;
+        self.history_state.select(None);
+        self.variables_state.select(None);
+        self.search = None;
+        self.leave_hidden_pane();
+    }
+
+    /// Moves the shown history, variables, and plot out of the app.
+    fn take_workspace(&mut self) -> Workspace {
+        Workspace {
+            id: self.workspace_id,
+            history: std::mem::take(&mut self.history),
+            variables: std::mem::take(&mut self.variables),
+            plot_data: self.plot_data.take(),
+            plot: self.plot.take(),
+            plot_range: self.plot_range.take(),
+        }
+    }
+
+    fn show_workspace(&mut self, workspace: Workspace) {
+        self.workspace_id = workspace.id;
+        self.history = workspace.history;
+        self.variables = workspace.variables;
+        self.plot_data = workspace.plot_data;
+        self.plot = workspace.plot;
+        self.plot_range = workspace.plot_range;
+        self.resample_plot();
+        self.plot_cursor = None;
+        self.variable_chart = false;
+        self.history_state.select(None);
+        self.variables_state.select(None);
+        self.search = None;
+        self.leave_hidden_pane();
+    }
+
+    /// Shows the tab at 0-based `index`, keeping the current one in its place.
+    fn switch_workspace(&mut self, index: usize) -> Result<(), String> {
+        if index > self.workspaces.len() {
+            return Err(format!("No tab {}", index + 1));
+        }
+        if index != self.workspace {
+            let current = self.take_workspace();
+            self.workspaces.insert(self.workspace, current);
+            let next = self.workspaces.remove(index);
+            self.workspace = index;
+            self.show_workspace(next);
+            self.mark_unsaved();
+        }
+        self.notify(format!(
+            "Tab {} of {}",
+            index + 1,
+            self.workspaces.len() + 1
+        ));
+        Ok(())
+    }
+
+    /// An id for a new tab, unlike those of this app's tabs and, being taken from
+    /// the clock, unlikely to match one another instance opens meanwhile.
+    fn new_workspace_id(&self) -> u64 {
+        let highest = self
+            .workspaces
+            .iter()
+            .map(|workspace| workspace.id)
+            .chain([self.workspace_id])
+            .max()
+            .unwrap_or(0);
+        let now = OffsetDateTime::now_utc().unix_timestamp_nanos() as u64;
+        now.max(highest + 1)
+    }
+
+    /// Switches to the next tab, or with a negative `step` a previous one, wrapping
+    /// around at either end.
+    fn cycle_workspace(&mut self, step: isize) {
+        let count = self.workspaces.len() + 1;
+        let index = (self.workspace as isize + step).rem_euclid(count as isize) as usize;
+        let _ = self.switch_workspace(index);
+    }
+
+    fn run_tab_action(&mut self, action: TabAction) -> Result<(), String> {
+        match action {
+            TabAction::New => {
+                let id = self.new_workspace_id();
+                let current = self.take_workspace();
+                self.workspaces.insert(self.workspace, current);
+                self.workspace += 1;
+                self.show_workspace(Workspace {
+                    id,
+                    ..Workspace::default()
+                });
+                self.mark_unsaved();
+                self.notify(format!(
+                    "Opened tab {} of {}",
+                    self.workspace + 1,
+                    self.workspaces.len() + 1
+                ));
+            }
+            TabAction::Close => {
+                if self.workspaces.is_empty() {
+                    return Err("The only tab cannot be closed".to_string());
+                }
+                self.remember(format!("closing tab {}", self.workspace + 1));
+                let closed = self.workspace + 1;
+                self.workspace = self.workspace.min(self.workspaces.len() - 1);
+                let next = self.workspaces.remove(self.workspace);
+                self.show_workspace(next);
+                self.mark_unsaved();
+                self.notify(format!("Closed tab {}", closed));
+            }
+            TabAction::Switch(number) => self.switch_workspace(number - 1)?,
+        }
+        Ok(())
+    }
+
+    /// Keeps the current state so `:undo` can return to it before `change`.
+    fn remember(&mut self, change: impl Into<String>) {
+        if self.undo_stack.len() == UNDO_LIMIT {
+            self.undo_stack.pop_front();
+        }
+        self.undo_stack.push_back((change.into(), self.to_state()));
+    }
+
+    fn undo(&mut self) -> Result<(), String> {
+        let (change, state) = self
+            .undo_stack
+            .pop_back()
+            .ok_or_else(|| "Nothing to undo".to_string())?;
+        self.load_state(state);
+        self.mark_unsaved();
+        self.notify(format!("Undid {}", change));
+        Ok(())
+    }
+
+    fn clear(&mut self, target: ClearTarget) {
+        self.remember(format!("clearing {}", target.name()));
+        let mut state = self.to_state();
+        state.clear(target);
+        self.load_state(state);
+        self.mark_unsaved();
+        self.notify(format!("Cleared {}", target.name()));
+    }
+
+    fn notify(&mut self, message: impl Into<String>) {
+        self.toast = Some(Toast {
+            message: message.into(),
+            expires_at: Instant::now() + TOAST_DURATION,
+        });
+    }
+
+    fn selected_history_index(&self) -> Option<usize> {
+        let len = self.history.len();
+        let selected_visual_idx = self.history_state.selected()?;
+        (selected_visual_idx < len).then(|| len - 1 - selected_visual_idx)
+    }
+
+    fn open_result_detail(&mut self) {
+        if let Some(history_idx) = self.selected_history_index() {
+            self.overlay = Some(Overlay::ResultDetail(history_idx));
+            self.detail_steps = false;
+        }
+    }
+
+    /// Evaluation steps of a successful history entry, with the variable values it
+    /// was computed with.
+    fn history_steps(&self, entry: &History) -> Option<Vec<TraceStep>> {
+        entry.result?;
+        let expression = resolve_history_references(&entry.expression, &self.history).ok()?;
+        let mut evaluator = Evaluator::new()
+            .with_angle_mode(self.settings.angle_mode)
+            .with_power_rules(self.settings.power);
+        for (name, value) in &entry.variables {
+            evaluator.set_variable(name, *value);
+        }
+        Some(evaluator.eval_trace(&expression).ok()?.steps)
+    }
+
+    fn handle_overlay_key_event(&mut self, key: KeyEvent) -> bool {
+        match (self.overlay, key.code) {
+            (Some(Overlay::ConfirmClear(target)), KeyCode::Char('y') | KeyCode::Enter) => {
+                self.overlay = None;
+                self.clear(target);
+            }
+            (Some(Overlay::ConfirmClear(_)), KeyCode::Char('n')) => self.overlay = None,
+            (Some(Overlay::ResultDetail(_)), KeyCode::Char('s')) => {
+                self.detail_steps = !self.detail_steps;
+            }
+            (Some(Overlay::SessionPicker { selected }), KeyCode::Down | KeyCode::Char('j')) => {
+                let last = self.session_choices.len().saturating_sub(1);
+                self.overlay = Some(Overlay::SessionPicker {
+                    selected: (selected + 1).min(last),
+                });
+            }
+            (Some(Overlay::SessionPicker { selected }), KeyCode::Up | KeyCode::Char('k')) => {
+                self.overlay = Some(Overlay::SessionPicker {
+                    selected: selected.saturating_sub(1),
+                });
+            }
+            (Some(Overlay::SessionPicker { selected }), KeyCode::Enter) => {
+                self.overlay = None;
+                if let Some(name) = self.session_choices.get(selected).cloned()
+                    && let Err(err) = self.switch_session(name)
+                {
+                    self.notify(err);
+                }
+            }
+            (Some(Overlay::PlotPicker { selected }), KeyCode::Down | KeyCode::Char('j')) => {
+                let last = self.plots.len().saturating_sub(1);
+                self.overlay = Some(Overlay::PlotPicker {
+                    selected: (selected + 1).min(last),
+                });
+            }
+            (Some(Overlay::PlotPicker { selected }), KeyCode::Up | KeyCode::Char('k')) => {
+                self.overlay = Some(Overlay::PlotPicker {
+                    selected: selected.saturating_sub(1),
+                });
+            }
+            (Some(Overlay::PlotPicker { selected }), KeyCode::Enter) => {
+                self.overlay = None;
+                if let Some(name) = self.plots.get(selected).map(|plot| plot.name.clone())
+                    && let Err(err) = self.show_plot(&name)
+                {
+                    self.notify(err);
+                }
+            }
+            (Some(Overlay::FunctionMenu { selected }), KeyCode::Down | KeyCode::Char('j')) => {
+                let last = (FUNCTIONS.len() + self.functions.len()).saturating_sub(1);
+                self.overlay = Some(Overlay::FunctionMenu {
+                    selected: (selected + 1).min(last),
+                });
+            }
+            (Some(Overlay::FunctionMenu { selected }), KeyCode::Up | KeyCode::Char('k')) => {
+                self.overlay = Some(Overlay::FunctionMenu {
+                    selected: selected.saturating_sub(1),
+                });
+            }
+            (Some(Overlay::FunctionMenu { selected }), KeyCode::Enter) => {
+                self.overlay = None;
+                let name = FUNCTIONS
+                    .iter()
+                    .map(|name| name.to_string())
+                    .chain(self.functions.iter().map(|f| f.name.clone()))
+                    .nth(selected);
+                if let Some(name) = name {
+                    self.insert_function_call(&name);
+                }
+            }
+            (Some(Overlay::SnippetPicker { selected }), KeyCode::Down | KeyCode::Char('j')) => {
+                let last = self.snippets.len().saturating_sub(1);
+                self.overlay = Some(Overlay::SnippetPicker {
+                    selected: (selected + 1).min(last),
+                });
+            }
+            (Some(Overlay::SnippetPicker { selected }), KeyCode::Up | KeyCode::Char('k')) => {
+                self.overlay = Some(Overlay::SnippetPicker {
+                    selected: selected.saturating_sub(1),
+                });
+            }
+            (Some(Overlay::SnippetPicker { selected }), KeyCode::Enter) => {
+                self.overlay = None;
+                if let Some(template) = self.snippets.get(selected).map(|s| s.template.clone()) {
+                    self.insert_snippet(&template);
+                }
+            }
+            (Some(Overlay::Help { scroll }), KeyCode::Down | KeyCode::Char('j')) => {
+                self.overlay = Some(Overlay::Help {
+                    scroll: scroll.saturating_add(1),
+                });
+            }
+            (Some(Overlay::Help { scroll }), KeyCode::Up | KeyCode::Char('k')) => {
+                self.overlay = Some(Overlay::Help {
+                    scroll: scroll.saturating_sub(1),
+                });
+            }
+            (Some(Overlay::Help { scroll }), KeyCode::PageDown) => {
+                self.overlay = Some(Overlay::Help {
+                    scroll: scroll.saturating_add(10),
+                });
+            }
+            (Some(Overlay::Help { scroll }), KeyCode::PageUp) => {
+                self.overlay = Some(Overlay::Help {
+                    scroll: scroll.saturating_sub(10),
+                });
+            }
+            (
+                _,
+                KeyCode::Esc
+                | KeyCode::Enter
+                | KeyCode::Char('q')
+                | KeyCode::Char('o')
+                | KeyCode::Char('?'),
+            ) => self.overlay = None,
+            _ => {}
+        }
+        false
+    }
+
+    /// Copies the selected history entry's expression or result into the editor register.
+    fn yank_from_selected_history(&mut self, action: Action) {
+        let Some(entry) = self
+            .selected_history_index()
+            .and_then(|idx| self.history.get(idx))
+        else {
+            return;
+        };
+
+        let text = match action {
+            Action::YankExpression => Some(entry.expression.clone()),
+            Action::YankResult => entry.result.map(|result| result.to_string()),
+            _ => None,
+        };
+        if let Some(text) = text {
+            self.notify(format!("Yanked {}", text));
+            self.editor.set_register(text);
+        }
+    }
+
+    fn start_note_for_selected_history(&mut self) {
+        let len = self.history.len();
+        if let Some(selected_visual_idx) = self.history_state.selected()
+            && selected_visual_idx < len
+        {
+            let entry = len - selected_visual_idx;
+            self.set_input_text(format!(":note {} ", entry));
+            self.set_focus(Focus::Input);
+        }
+    }
+
+    pub fn current_search_match(&self) -> Option<&str> {
+        self.search
+            .as_ref()?
+            .current_match(self.input_history.iter().map(String::as_str))
+    }
+
+    /// Context used to look up key bindings in the current app state.
+    fn key_context(&self) -> KeyContext {
+        if self.search.is_some() {
+            return KeyContext::Search;
+        }
+        match self.focus {
+            Focus::Input => KeyContext::from(self.input_edit_mode),
+            Focus::History => KeyContext::History,
+            Focus::Variables => KeyContext::Variables,
+            Focus::Plot => KeyContext::Plot,
+        }
+    }
+
+    fn handle_editor_action(&mut self, action: Action) {
+        match self.editor.apply_action(action) {
+            EditorCommand::None | EditorCommand::IncrementFocus | EditorCommand::DecrementFocus => {
                 self.sync_public_from_editor();
-                false
             }
             EditorCommand::Submit => {
                 self.sync_public_from_editor();
                 self.submit_message();
-                false
-            }
-            EditorCommand::IncrementFocus => {
-                self.set_focus(self.focus.next());
-                false
-            }
-            EditorCommand::DecrementFocus => {
-                self.set_focus(self.focus.prev());
-                false
             }
             EditorCommand::Yanked { start, end } => {
                 self.sync_public_from_editor();
@@ -487,155 +2040,765 @@ impl App {
                     end,
                     expires_at: Instant::now() + Duration::from_millis(250),
                 });
-                false
+                self.notify(format!("Yanked {} chars", end + 1 - start));
             }
         }
     }
 
-    fn handle_list_key_event(&mut self, code: KeyCode) -> bool {
-        match code {
-            KeyCode::Enter => {
-                match self.focus {
-                    Focus::History => self.populate_input_from_history(),
-                    Focus::Variables => self.populate_input_from_variable(),
-                    Focus::Input => {}
+    /// Performs `action`; returns `true` when the app should quit.
+    fn handle_action(&mut self, action: Action) -> bool {
+        match action {
+            Action::Quit => return true,
+            Action::Help => self.overlay = Some(Overlay::Help { scroll: 0 }),
+            Action::RecallOlder => self.recall_input(true),
+            Action::RecallNewer => self.recall_input(false),
+            Action::CompleteNext => {
+                if !self.jump_to_placeholder() {
+                    self.complete(true);
+                }
+            }
+            Action::CompletePrev => self.complete(false),
+            Action::Search => self.search = Some(HistorySearch::new()),
+            Action::SearchOlder => {
+                if let Some(search) = self.search.as_mut() {
+                    search.next_match(self.input_history.iter().map(String::as_str));
+                }
+            }
+            Action::SearchAccept => {
+                if let Some(found) = self.current_search_match().map(str::to_string) {
+                    self.set_input_text(found);
+                }
+                self.search = None;
+            }
+            Action::SearchCancel => self.search = None,
+            Action::FunctionMenu => self.overlay = Some(Overlay::FunctionMenu { selected: 0 }),
+            Action::SearchBackspace => {
+                if let Some(search) = self.search.as_mut() {
+                    search.pop_char();
                 }
-                false
             }
-            KeyCode::Char('i') => {
+            Action::FocusNext => self.cycle_focus(Focus::next),
+            Action::FocusPrev => self.cycle_focus(Focus::prev),
+            Action::FocusInput => {
                 self.set_focus(Focus::Input);
                 self.set_input_edit_mode(InputEditMode::Insert);
-                false
             }
-            KeyCode::Tab => {
-                self.set_focus(self.focus.next());
-                false
+            Action::FocusHistory => self.set_focus(Focus::History),
+            Action::FocusVariables => {
+                if self.pane_visible(Focus::Variables) {
+                    self.set_focus(Focus::Variables);
+                }
             }
-            KeyCode::BackTab => {
-                self.set_focus(self.focus.prev());
-                false
+            Action::SelectUp => self.move_list_selection(|i, _| i.saturating_sub(1)),
+            Action::SelectDown => self.move_list_selection(|i, _| i + 1),
+            Action::SelectFirst => self.move_list_selection(|_, _| 0),
+            Action::SelectLast => self.move_list_selection(|_, len| len - 1),
+            Action::HalfPageUp => {
+                let half = (self.list_page_len() / 2).max(1);
+                self.move_list_selection(|i, _| i.saturating_sub(half));
             }
-            KeyCode::Left => {
-                self.set_focus(Focus::History);
-                false
+            Action::HalfPageDown => {
+                let half = (self.list_page_len() / 2).max(1);
+                self.move_list_selection(|i, _| i + half);
             }
-            KeyCode::Right => {
-                self.set_focus(Focus::Variables);
-                false
+            Action::Select => match self.focus {
+                Focus::History => self.populate_input_from_history(),
+                Focus::Variables => self.populate_input_from_variable(),
+                Focus::Input | Focus::Plot => {}
+            },
+            Action::ShowDetails => self.open_result_detail(),
+            Action::Note => self.start_note_for_selected_history(),
+            Action::YankExpression | Action::YankResult => self.yank_from_selected_history(action),
+            Action::ToggleDependencies => self.show_dependencies = !self.show_dependencies,
+            Action::GrowHistory => self.resize_layout(|layout| layout.resize_history(5)),
+            Action::ShrinkHistory => self.resize_layout(|layout| layout.resize_history(-5)),
+            Action::GrowInput => self.resize_layout(|layout| layout.resize_input(1)),
+            Action::ShrinkInput => self.resize_layout(|layout| layout.resize_input(-1)),
+            Action::ToggleVariablesPane => {
+                self.resize_layout(|layout| layout.show_variables = !layout.show_variables);
+                self.leave_hidden_pane();
             }
-            KeyCode::Up => {
-                match self.focus {
-                    Focus::History => self.move_history_selection_up(),
-                    Focus::Variables => self.move_variables_selection_up(),
-                    Focus::Input => {}
-                }
-                false
+            Action::PlotCursorLeft => self.move_plot_cursor(-1),
+            Action::PlotCursorRight => self.move_plot_cursor(1),
+            Action::NextTab => self.cycle_workspace(1),
+            Action::PrevTab => self.cycle_workspace(-1),
+            Action::CycleVariableSort => {
+                self.settings.variable_sort = self.settings.variable_sort.next();
+                self.variables_state
+                    .select(Some(0).filter(|_| !self.variables.is_empty()));
+            }
+            Action::TogglePlotStyle => {
+                self.plot_style = self.plot_style.toggle();
+                self.mark_unsaved();
+            }
+            Action::TogglePlotPane => {
+                self.resize_layout(|layout| layout.show_plot = !layout.show_plot);
+                self.leave_hidden_pane();
             }
-            KeyCode::Down => {
-                match self.focus {
-                    Focus::History => self.move_history_selection_down(),
-                    Focus::Variables => self.move_variables_selection_down(),
-                    Focus::Input => {}
+            editor_action => {
+                if matches!(self.focus, Focus::Input) {
+                    self.handle_editor_action(editor_action);
                 }
-                false
             }
-            _ => false,
+        }
+        false
+    }
+
+    fn resize_layout(&mut self, resize: impl FnOnce(&mut LayoutState)) {
+        let before = self.layout;
+        resize(&mut self.layout);
+        if self.layout != before {
+            self.mark_unsaved();
+        }
+    }
+
+    /// Handles keys without a binding: typing into the search query or the editor.
+    fn handle_unbound_key(&mut self, key: KeyEvent) {
+        let typed = key
+            .modifiers
+            .intersection(KeyModifiers::CONTROL | KeyModifiers::ALT);
+        if let KeyCode::Char(digit @ '1'..='9') = key.code
+            && typed == KeyModifiers::ALT
+        {
+            let index = digit as usize - '1' as usize;
+            if let Err(err) = self.switch_workspace(index) {
+                self.notify(err);
+            }
+            return;
+        }
+        if let Some(search) = self.search.as_mut() {
+            search.edit(key, &self.keymap);
+            return;
+        }
+
+        if matches!(self.focus, Focus::Input) {
+            self.editor.insert_unbound_key(key);
+            self.sync_public_from_editor();
+        }
+
+        if let (Focus::History, KeyCode::Char(digit @ '0'..='9')) = (self.focus, key.code) {
+            self.history_number.push(digit);
+            self.select_history_number();
+        }
+    }
+
+    /// Handles a bracketed paste as a single insert, so its characters never
+    /// trigger key bindings.
+    pub fn handle_paste(&mut self, text: &str) {
+        if self.overlay.is_some() || self.evaluating.is_some() {
+            return;
+        }
+        if let Some(search) = self.search.as_mut() {
+            text.chars()
+                .filter(|ch| !ch.is_control())
+                .for_each(|ch| search.push_char(ch));
+            return;
+        }
+        if matches!(self.focus, Focus::Input) {
+            self.mark_editor_dirty_if_public_changed();
+            self.ensure_editor_synced_from_public();
+            self.editor.insert_text(text);
+            self.sync_public_from_editor();
+        }
+    }
+
+    /// Inserts `name()` at the cursor and leaves the cursor between the parentheses.
+    fn insert_function_call(&mut self, name: &str) {
+        self.mark_editor_dirty_if_public_changed();
+        self.set_input_edit_mode(InputEditMode::Insert);
+        self.editor.insert_text(&format!("{}()", name));
+        self.editor.move_insert_left();
+        self.sync_public_from_editor();
+    }
+
+    /// Inserts a snippet template at the cursor and moves to its first placeholder.
+    fn insert_snippet(&mut self, template: &str) {
+        self.mark_editor_dirty_if_public_changed();
+        self.set_input_edit_mode(InputEditMode::Insert);
+        self.editor.insert_text(template);
+        self.sync_public_from_editor();
+        self.jump_to_placeholder();
+    }
+
+    /// Removes the first `{field}` placeholder left in the input and puts the
+    /// cursor in its place, so Tab moves through the fields of a snippet.
+    fn jump_to_placeholder(&mut self) -> bool {
+        let Some((range, field)) = next_placeholder(&self.input) else {
+            return false;
+        };
+        let message = format!("Fill in {}", field);
+        self.character_index = self.input[..range.start].graphemes(true).count();
+        self.input.replace_range(range, "");
+        self.input_edit_mode = InputEditMode::Insert;
+        self.editor_needs_sync = true;
+        self.ensure_editor_synced_from_public();
+        self.notify(message);
+        true
+    }
+
+    /// Selects the entry whose number has been typed so far in the History pane.
+    fn select_history_number(&mut self) {
+        let len = self.history.len();
+        if let Ok(number) = self.history_number.parse::<usize>()
+            && (1..=len).contains(&number)
+        {
+            self.history_state.select(Some(len - number));
         }
     }
 
     pub fn handle_key_event(&mut self, key: KeyEvent) -> bool {
-        if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
-            return true;
+        if !matches!(key.code, KeyCode::Char('0'..='9')) {
+            self.history_number.clear();
+        }
+        if matches!(self.focus, Focus::Input) {
+            self.mark_editor_dirty_if_public_changed();
+            self.ensure_editor_synced_from_public();
         }
 
-        match self.focus {
-            Focus::Input => self.handle_input_key_event(key),
-            Focus::History | Focus::Variables => self.handle_list_key_event(key.code),
+        if self.evaluating.is_some() {
+            // Only quitting and cancelling make sense until the result arrives.
+            let quit = self
+                .keymap
+                .resolve(KeyContext::Global, &[KeyChord::from(key)])
+                == Resolution::Action(Action::Quit);
+            if key.code == KeyCode::Esc {
+                self.cancel_evaluation();
+            }
+            return quit;
+        }
+
+        if self.overlay.is_some() {
+            if self
+                .keymap
+                .resolve(KeyContext::Global, &[KeyChord::from(key)])
+                == Resolution::Action(Action::Quit)
+            {
+                return true;
+            }
+            return self.handle_overlay_key_event(key);
+        }
+
+        self.pending_keys.push(KeyChord::from(key));
+        match self.keymap.resolve(self.key_context(), &self.pending_keys) {
+            Resolution::Pending => false,
+            Resolution::Action(action) => {
+                self.pending_keys.clear();
+                self.handle_action(action) || self.should_quit
+            }
+            Resolution::Unbound => {
+                if self.pending_keys.len() == 1 {
+                    self.handle_unbound_key(key);
+                }
+                self.pending_keys.clear();
+                false
+            }
         }
     }
 
     pub fn run(mut self, mut terminal: DefaultTerminal) -> Result<()> {
+        self.run_with(&mut terminal, &mut TerminalEvents::default())
+    }
+
+    /// Draws to `terminal` and handles the events of `events` until the app quits
+    /// or the events run out, then saves unsaved changes.
+    pub fn run_with<B>(
+        &mut self,
+        terminal: &mut Terminal<B>,
+        events: &mut impl EventSource,
+    ) -> Result<()>
+    where
+        B: Backend,
+        B::Error: Send + Sync + 'static,
+    {
+        let size = terminal.size()?;
+        self.update(Msg::Resize(size.width, size.height));
         loop {
-            if let Some(flash) = &self.yank_flash
-                && Instant::now() >= flash.expires_at
-            {
-                self.yank_flash = None;
+            terminal.draw(|frame| self.view(frame))?;
+            let quit = match events.next_event()? {
+                Some(event) => self.handle_event(event),
+                None => true,
+            };
+            if quit {
+                if self.unsaved {
+                    self.save_state();
+                }
+                return Ok(());
             }
+        }
+    }
 
-            terminal.draw(|frame| self.draw(frame))?;
+    /// Reacts to one event through [`update`](Self::update); returns whether the
+    /// app should quit.
+    pub fn handle_event(&mut self, event: AppEvent) -> bool {
+        let msg = match event {
+            AppEvent::Key(key) => Msg::Key(key),
+            AppEvent::Paste(text) => Msg::Paste(text),
+            AppEvent::Resize(columns, rows) => Msg::Resize(columns, rows),
+            AppEvent::Tick => Msg::Tick(Instant::now()),
+            AppEvent::EvalFinished => Msg::EvalFinished,
+        };
+        self.update(msg)
+    }
 
-            if event::poll(Duration::from_millis(16))?
-                && let Event::Key(key) = event::read()?
-                && key.kind == KeyEventKind::Press
-                && self.handle_key_event(key)
-            {
-                return Ok(());
+    /// Applies one message to the state; returns whether the app should quit.
+    pub fn update(&mut self, msg: Msg) -> bool {
+        let quit = self.apply(msg);
+        self.fit_panes();
+        quit
+    }
+
+    fn apply(&mut self, msg: Msg) -> bool {
+        match msg {
+            Msg::Key(key) => return self.handle_key_event(key),
+            Msg::Action(action) => return self.handle_action(action) || self.should_quit,
+            Msg::Resize(columns, rows) => {
+                self.area = Rect::new(0, 0, columns, rows);
+                self.compact = LayoutState::is_compact(columns, rows);
+            }
+            Msg::Paste(text) => self.handle_paste(&text),
+            Msg::Submit(text) => {
+                self.set_input_text(text);
+                self.submit_message();
+            }
+            Msg::Command(command) => {
+                if let Err(err) = self.run_command(command) {
+                    self.notify(err);
+                }
+            }
+            Msg::Tick(now) => {
+                if self
+                    .yank_flash
+                    .as_ref()
+                    .is_some_and(|f| now >= f.expires_at)
+                {
+                    self.yank_flash = None;
+                }
+                if self.toast.as_ref().is_some_and(|t| now >= t.expires_at) {
+                    self.toast = None;
+                }
+                self.autosave_if_due(now);
+                self.poll_evaluation();
             }
+            Msg::EvalFinished => {
+                if let Some(pending) = self.evaluating.take() {
+                    let result = pending.evaluation.result();
+                    self.finish_evaluation(pending, result);
+                    self.submit_queued_statements();
+                }
+            }
+        }
+        self.should_quit
+    }
+
+    /// Fits the state to the terminal size: focus leaves a pane compact mode
+    /// hides, and the History and Variables panes get their page sizes and scroll
+    /// so their selection is in view.
+    fn fit_panes(&mut self) {
+        if self.compact && !self.pane_visible(self.focus) {
+            self.set_focus(Focus::History);
         }
+        if self.area.is_empty() {
+            return;
+        }
+        let panes = self.panes(self.area);
+        self.history_page_len = panes.history.height.saturating_sub(2) as usize;
+        let window = visible_window(
+            self.history.len(),
+            self.history_state.selected(),
+            self.history_state.offset(),
+            self.history_page_len,
+            |row| self.history_row_height(row),
+        );
+        *self.history_state.offset_mut() = window.start;
+        self.variables_page_len = panes.variables.height.saturating_sub(2) as usize;
+        let window = visible_window(
+            self.variables.len(),
+            self.variables_state.selected(),
+            self.variables_state.offset(),
+            self.variables_page_len,
+            |_| 1,
+        );
+        *self.variables_state.offset_mut() = window.start;
     }
 
-    fn draw(&mut self, frame: &mut Frame) {
+    /// Lays out the screen for a terminal the size of `area`.
+    fn panes(&self, area: Rect) -> Panes {
         let vertical = Layout::vertical([
-            Constraint::Length(1),
-            Constraint::Length(3),
+            Constraint::Length(if self.compact { 0 } else { 1 }),
+            Constraint::Length(self.layout.input_height),
             Constraint::Min(1),
+            Constraint::Length(1),
         ]);
-        let [help_area, input_area, messages_area] = vertical.areas(frame.area());
+        let [help, input, messages, status] = vertical.areas(area);
 
-        let help_message = render_help_message(self.focus, self.input_edit_mode);
-        frame.render_widget(help_message, help_area);
+        // In compact mode a single list fills the column: Variables while it is
+        // focused, History otherwise.
+        let variables_width = match (self.compact, self.focus) {
+            (true, Focus::Variables) => 100,
+            (true, _) => 0,
+            (false, _) if self.layout.variables_visible() => 100 - self.layout.history_width,
+            (false, _) => 0,
+        };
+        let [history_column, variables] = Layout::horizontal([
+            Constraint::Percentage(100 - variables_width),
+            Constraint::Percentage(variables_width),
+        ])
+        .areas(messages);
+
+        let plot_height = if self.plot_shown() {
+            self.layout.plot_height()
+        } else {
+            0
+        };
+        let [history, plot] = Layout::vertical([
+            Constraint::Percentage(100 - plot_height),
+            Constraint::Percentage(plot_height),
+        ])
+        .areas(history_column);
+
+        Panes {
+            help,
+            input,
+            status,
+            messages,
+            history,
+            variables,
+            plot,
+        }
+    }
+
+    /// Shows where the visible rows of a bordered list pane sit within the whole list.
+    fn draw_list_scrollbar(&self, frame: &mut Frame, pane: Rect, len: usize, offset: usize) {
+        if self.theme.plain {
+            return;
+        }
+        let track = pane.inner(Margin::new(0, 1));
+        if let Some(mut state) = list_scrollbar_state(len, offset, track.height as usize) {
+            frame.render_stateful_widget(render_list_scrollbar(&self.theme), track, &mut state);
+        }
+    }
+
+    fn draw_history(&self, frame: &mut Frame, history_pane: Rect) {
+        // Only the rows that fit are built; the List sees them with the window's
+        // start as its scroll offset.
+        let window = visible_window(
+            self.history.len(),
+            self.history_state.selected(),
+            self.history_state.offset(),
+            history_pane.height.saturating_sub(2) as usize,
+            |row| self.history_row_height(row),
+        );
+        let mut window_state = ListState::default().with_selected(
+            self.history_state
+                .selected()
+                .and_then(|selected| selected.checked_sub(window.start)),
+        );
+        let history_block = render_history_block(
+            &self.history,
+            &self.variables,
+            window.clone(),
+            self.focus,
+            self.settings.precision,
+            self.utc_offset,
+            &self.theme,
+        );
+        frame.render_stateful_widget(history_block, history_pane, &mut window_state);
+        self.draw_list_scrollbar(frame, history_pane, self.history.len(), window.start);
+    }
+
+    /// Rows taken by the `row`th entry of the newest-first History list.
+    fn history_row_height(&self, row: usize) -> usize {
+        history_item_height(&self.history, self.history.len() - 1 - row, self.utc_offset)
+    }
 
-        let get_visual_range = || self.editor.visual_range();
+    /// Draws the state into `frame`, laid out for the size of the last
+    /// [`Msg::Resize`].
+    pub fn view(&self, frame: &mut Frame) {
+        let Panes {
+            help: help_area,
+            input: input_area,
+            status: status_area,
+            messages: messages_area,
+            history: history_pane,
+            variables: variables_pane,
+            plot: plot_pane,
+        } = self.panes(frame.area());
 
-        let input = render_input(
+        let status_bar = render_status_bar(
             self.focus,
             self.input_edit_mode,
-            &self.input,
-            self.yank_flash.as_ref(),
-            get_visual_range,
+            &self.settings,
+            &self.session,
+            self.unsaved,
+            self.evaluating.is_some(),
+            &self.theme,
         );
-        frame.render_widget(input, input_area);
+        frame.render_widget(status_bar, status_area);
+        if !self.workspaces.is_empty() {
+            let tabs = render_tab_bar(self.workspace, self.workspaces.len() + 1, &self.theme);
+            frame.render_widget(tabs, status_area);
+        }
 
-        if matches!(self.focus, Focus::Input) {
+        let help_message = render_help_message(self.focus, self.input_edit_mode, &self.theme);
+        frame.render_widget(help_message, help_area);
+
+        if let Some(search) = &self.search {
+            let prompt =
+                render_search_prompt(search.prompt(), self.current_search_match(), &self.theme);
+            frame.render_widget(prompt, input_area);
             frame.set_cursor_position(Position::new(
-                input_area.x + self.character_index as u16 + 2,
+                input_area.x + prompt_cursor_column(search.prompt()),
                 input_area.y + 1,
             ));
+        } else {
+            let get_visual_range = || self.editor.visual_range();
+
+            let lexed: Vec<Token> = Lexer::new(&self.input).collect();
+            let unmatched: Vec<usize> = unmatched_parens(&lexed)
+                .iter()
+                .filter_map(CalcError::position)
+                .collect();
+            let input = render_input(
+                self.focus,
+                self.input_edit_mode,
+                &self.input,
+                self.yank_flash.as_ref(),
+                get_visual_range,
+                &unmatched,
+                &self.theme,
+            );
+            frame.render_widget(input, input_area);
+            if let Some(total) = self.tape_total {
+                let total_area = Rect {
+                    y: input_area.y + 1,
+                    height: 1.min(input_area.height.saturating_sub(1)),
+                    ..input_area
+                };
+                frame.render_widget(
+                    render_tape_total(total, self.settings.precision, &self.theme),
+                    total_area,
+                );
+            } else if self.settings.rpn {
+                let stack_area = Rect {
+                    y: input_area.y + 1,
+                    height: 1.min(input_area.height.saturating_sub(1)),
+                    ..input_area
+                };
+                frame.render_widget(
+                    render_rpn_stack(
+                        self.rpn_stack.values(),
+                        self.settings.precision,
+                        &self.theme,
+                    ),
+                    stack_area,
+                );
+            } else if let Some(preview) = self.input_preview() {
+                let preview_area = Rect {
+                    y: input_area.y + 1,
+                    height: 1.min(input_area.height.saturating_sub(1)),
+                    ..input_area
+                };
+                frame.render_widget(render_input_preview(preview, &self.theme), preview_area);
+            }
         }
 
-        let layout = Layout::default()
-            .direction(Direction::Horizontal)
-            .constraints(vec![Constraint::Percentage(50), Constraint::Percentage(50)])
-            .split(messages_area);
-        let right_pane = layout[0];
-        let left_pane = layout[1];
-        let mut right_layout_constraints = vec![Constraint::Percentage(100)];
-        if let Some(plot_data) = self.plot_data.as_ref()
-            && !plot_data.is_empty()
-        {
-            right_layout_constraints = vec![Constraint::Percentage(50), Constraint::Percentage(50)];
+        if matches!(self.focus, Focus::Input) && self.search.is_none() {
+            frame.set_cursor_position(Position::new(
+                input_area.x + cursor_column(&self.input, self.character_index) + 2,
+                input_area.y + 1,
+            ));
         }
-        let right_layout = Layout::default()
-            .direction(Direction::Vertical)
-            .constraints(right_layout_constraints)
-            .split(right_pane);
 
-        let history_block = render_history_block(&self.history, self.focus);
-        frame.render_stateful_widget(history_block, right_layout[0], &mut self.history_state);
+        let show_plot = self.plot_shown();
+        if !history_pane.is_empty() {
+            self.draw_history(frame, history_pane);
+        }
 
-        let variable_list = render_variable_block(&self.variables, self.focus);
-        frame.render_stateful_widget(variable_list, left_pane, &mut self.variables_state);
+        if !variables_pane.is_empty() {
+            let dependencies = self
+                .show_dependencies
+                .then(|| dependency_graph(&self.variables));
+            let names = self.sorted_variable_keys();
+            let variable_list = render_variable_block(
+                &self.variables,
+                &names,
+                self.settings.variable_sort,
+                self.focus,
+                dependencies.as_ref(),
+                self.settings.precision,
+                &self.theme,
+            );
+            let mut variables_state = self.variables_state;
+            frame.render_stateful_widget(variable_list, variables_pane, &mut variables_state);
+            let offset = variables_state.offset();
+            self.draw_list_scrollbar(frame, variables_pane, self.variables.len(), offset);
+        }
 
-        if let Some(plot_data) = &self.plot_data
-            && let Some(pane) = right_layout.get(1)
-            && let Some(last) = self.history.last()
-        {
-            let chart = render_scatter(plot_data, last.expression.clone());
-            frame.render_widget(chart, *pane);
+        if show_plot && self.variable_chart {
+            let chart = render_variable_chart(
+                &self.variables,
+                self.focus,
+                self.settings.precision,
+                &self.theme,
+            );
+            frame.render_widget(chart, plot_pane);
+        } else if show_plot && let Some(plot_data) = &self.plot_data {
+            let square = self.plot.as_ref().is_some_and(PlotDefinition::is_square);
+            let options = PlotOptions {
+                style: self.plot_style,
+                cursor: self.plot_cursor,
+                // Terminal cells are about twice as tall as they are wide
+                aspect: square.then(|| plot_pane.width as f64 / (2.0 * plot_pane.height as f64)),
+            };
+            let chart = render_plot(
+                plot_data,
+                self.plot_title(),
+                options,
+                self.focus,
+                self.settings.precision,
+                &self.theme,
+            );
+            frame.render_widget(chart, plot_pane);
+        }
+
+        if let Some(toast) = &self.toast {
+            let width = (toast.message.chars().count() as u16 + 4).min(messages_area.width);
+            let toast_area = Rect::new(
+                messages_area.right().saturating_sub(width),
+                messages_area.bottom().saturating_sub(3),
+                width,
+                3.min(messages_area.height),
+            );
+            frame.render_widget(Clear, toast_area);
+            frame.render_widget(render_toast(&toast.message, &self.theme), toast_area);
+        }
+
+        let popup_area = frame
+            .area()
+            .centered(Constraint::Percentage(60), Constraint::Percentage(60));
+        match self.overlay {
+            Some(Overlay::ResultDetail(history_idx)) => {
+                if let Some(entry) = self.history.get(history_idx) {
+                    let steps = self
+                        .detail_steps
+                        .then(|| self.history_steps(entry))
+                        .flatten();
+                    frame.render_widget(Clear, popup_area);
+                    frame.render_widget(
+                        render_detail_popup(
+                            entry,
+                            history_idx + 1,
+                            &self.variables,
+                            steps.as_deref(),
+                            &self.theme,
+                        ),
+                        popup_area,
+                    );
+                }
+            }
+            Some(Overlay::Help { scroll }) => {
+                let area = frame
+                    .area()
+                    .centered(Constraint::Percentage(70), Constraint::Percentage(80));
+                frame.render_widget(Clear, area);
+                frame.render_widget(render_help_overlay(&self.keymap, scroll, &self.theme), area);
+            }
+            Some(Overlay::ConfirmClear(target)) => {
+                let area = frame
+                    .area()
+                    .centered(Constraint::Length(44), Constraint::Length(6));
+                let question = format!("Clear all {}? This cannot be undone.", target.name());
+                frame.render_widget(Clear, area);
+                frame.render_widget(render_confirm_popup(&question, &self.theme), area);
+            }
+            Some(Overlay::Stats) => {
+                let area = frame
+                    .area()
+                    .centered(Constraint::Length(36), Constraint::Length(8));
+                let list = self.stats_list.as_deref();
+                let summary = match list.and_then(|name| self.lists.get(name)) {
+                    Some(values) => summarize(values.iter().copied()),
+                    None => summarize(self.history.iter().filter_map(|entry| entry.result)),
+                };
+                frame.render_widget(Clear, area);
+                frame.render_widget(
+                    render_stats_popup(
+                        summary.as_ref(),
+                        list,
+                        self.settings.precision,
+                        &self.theme,
+                    ),
+                    area,
+                );
+            }
+            Some(Overlay::SessionPicker { selected }) => {
+                let area = frame
+                    .area()
+                    .centered(Constraint::Percentage(40), Constraint::Percentage(50));
+                let picker =
+                    render_session_picker(&self.session_choices, &self.session, &self.theme);
+                frame.render_widget(Clear, area);
+                frame.render_stateful_widget(
+                    picker,
+                    area,
+                    &mut ListState::default().with_selected(Some(selected)),
+                );
+            }
+            Some(Overlay::FunctionMenu { selected }) => {
+                let area = frame
+                    .area()
+                    .centered(Constraint::Percentage(60), Constraint::Percentage(60));
+                let menu = render_function_menu(&self.functions, &self.theme);
+                frame.render_widget(Clear, area);
+                frame.render_stateful_widget(
+                    menu,
+                    area,
+                    &mut ListState::default().with_selected(Some(selected)),
+                );
+            }
+            Some(Overlay::SnippetPicker { selected }) => {
+                let area = frame
+                    .area()
+                    .centered(Constraint::Percentage(60), Constraint::Percentage(50));
+                let picker = render_snippet_picker(&self.snippets, &self.theme);
+                frame.render_widget(Clear, area);
+                frame.render_stateful_widget(
+                    picker,
+                    area,
+                    &mut ListState::default().with_selected(Some(selected)),
+                );
+            }
+            Some(Overlay::PlotPicker { selected }) => {
+                let area = frame
+                    .area()
+                    .centered(Constraint::Percentage(50), Constraint::Percentage(50));
+                let picker = render_plot_picker(&self.plots, self.plot.as_ref(), &self.theme);
+                frame.render_widget(Clear, area);
+                frame.render_stateful_widget(
+                    picker,
+                    area,
+                    &mut ListState::default().with_selected(Some(selected)),
+                );
+            }
+            None => {}
         }
     }
 }
 
+/// Draws `app` as it would appear in a terminal the size of `area`, without a
+/// terminal, e.g. to assert on rendered frames in tests.
+///
+/// The panes are laid out as [`App::update`] last fitted them, so send a
+/// [`Msg::Resize`] of the same size first.
+pub fn render_app(app: &App, area: Rect) -> Buffer {
+    // The test backend never fails.
+    let Ok(mut terminal) = Terminal::new(TestBackend::new(area.width, area.height));
+    let Ok(_) = terminal.draw(|frame| app.view(frame));
+    let mut buffer = terminal.backend().buffer().clone();
+    buffer.area = area;
+    buffer
+}
+
 impl Default for App {
     fn default() -> Self {
         Self::new()
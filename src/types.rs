@@ -1,9 +1,46 @@
-use std::{collections::HashMap, time::Instant};
+use std::{
+    collections::{BTreeMap, HashMap},
+    time::{Duration, Instant},
+};
 
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+use crate::{
+    definitions::FunctionDefinition,
+    plot::{PlotDefinition, PlotEntry, PlotRange},
+};
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct VariableEntry {
     pub expression: String,
     pub value: f64,
+    /// How the value is displayed, set with `:format`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<ValueFormat>,
+    /// Unit label shown after the value, set with `:unit`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unit: Option<String>,
+}
+
+/// Display formats a variable can be tagged with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ValueFormat {
+    /// Thousands separators and two decimals, e.g. `1,234.50`
+    Currency,
+    /// Multiplied by 100 with a `%` sign, e.g. `12.5%`
+    Percent,
+    /// Mantissa and exponent, e.g. `1.5e3`
+    Scientific,
+}
+
+impl ValueFormat {
+    pub fn named(name: &str) -> Option<ValueFormat> {
+        match name {
+            "currency" => Some(ValueFormat::Currency),
+            "percent" | "%" => Some(ValueFormat::Percent),
+            "sci" | "scientific" => Some(ValueFormat::Scientific),
+            _ => None,
+        }
+    }
 }
 
 pub struct YankFlash {
@@ -12,18 +49,57 @@ pub struct YankFlash {
     pub expires_at: Instant,
 }
 
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+/// Short-lived feedback message, e.g. after a command succeeds.
+#[derive(Debug, Clone)]
+pub struct Toast {
+    pub message: String,
+    pub expires_at: Instant,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HistoryKind {
+    /// A plain expression, plot, or failed input
+    #[default]
+    Expression,
+    /// A variable assignment such as `x=2`
+    Assignment,
+    /// A function definition such as `f(x) = x^2+1`
+    Definition,
+    /// A line of tape mode such as `+ 12.50`, with the running total as its result
+    Tape,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct History {
     pub expression: String,
     pub result: Option<f64>,
     pub error: Option<String>,
+    /// Optional user label shown next to the entry
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    /// Values of the variables the expression referenced when it was evaluated
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub variables: BTreeMap<String, f64>,
+    #[serde(default)]
+    pub kind: HistoryKind,
+    /// Unix time in seconds when the entry was added; missing in older state files
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<i64>,
+    /// How long the evaluation took, kept only when it exceeded [`SLOW_EVALUATION`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u64>,
 }
 
+/// Evaluations taking longer than this show their duration in History.
+pub const SLOW_EVALUATION: Duration = Duration::from_millis(500);
+
 impl std::fmt::Display for History {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match (self.result, self.error.clone()) {
             (Some(result), _) => write!(f, "{} = {}", self.expression, result),
             (_, Some(error)) => write!(f, "'{}' resulted in error: {}", self.expression, error),
+            _ if self.kind == HistoryKind::Definition => write!(f, "{}", self.expression),
             (_, _) => write!(f, "{} 📈", self.expression),
         }
     }
@@ -34,6 +110,7 @@ pub enum Focus {
     Input,
     History,
     Variables,
+    Plot,
 }
 
 impl Focus {
@@ -41,24 +118,631 @@ impl Focus {
         match self {
             Focus::Input => Focus::History,
             Focus::History => Focus::Variables,
-            Focus::Variables => Focus::Input, // wrap
+            Focus::Variables => Focus::Plot,
+            Focus::Plot => Focus::Input, // wrap
         }
     }
 
     pub fn prev(self) -> Self {
         match self {
-            Focus::Input => Focus::Variables, // wrap
+            Focus::Input => Focus::Plot, // wrap
             Focus::History => Focus::Input,
             Focus::Variables => Focus::History,
+            Focus::Plot => Focus::Variables,
+        }
+    }
+}
+
+/// Name of the session backed by the default state file.
+pub const DEFAULT_SESSION: &str = "default";
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AngleMode {
+    #[default]
+    Radians,
+    Degrees,
+}
+
+impl std::fmt::Display for AngleMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AngleMode::Radians => write!(f, "RAD"),
+            AngleMode::Degrees => write!(f, "DEG"),
         }
     }
 }
 
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+/// How `^` combines with signs and with itself, which calculators disagree on.
+///
+/// The default follows mathematical convention: `-2^2` is `-4` and `2^3^2` is `2^9`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct PowerRules {
+    pub negation: Negation,
+    pub associativity: Associativity,
+}
+
+/// `-2^2=-4 ^ right`, as shown in the status bar
+impl std::fmt::Display for PowerRules {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ^ {}", self.negation, self.associativity)
+    }
+}
+
+/// What a leading minus applies to in `-2^2`, set with `:negation`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Negation {
+    /// `-2^2` is `-(2^2)`, i.e. `-4`
+    #[default]
+    Power,
+    /// `-2^2` is `(-2)^2`, i.e. `4`
+    Base,
+}
+
+impl std::fmt::Display for Negation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Negation::Power => write!(f, "-2^2=-4"),
+            Negation::Base => write!(f, "-2^2=4"),
+        }
+    }
+}
+
+/// How `2^3^2` is grouped, set with `:associativity`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Associativity {
+    /// `2^3^2` is `2^(3^2)`
+    #[default]
+    Right,
+    /// `2^3^2` is `(2^3)^2`
+    Left,
+}
+
+impl std::fmt::Display for Associativity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Associativity::Right => write!(f, "right"),
+            Associativity::Left => write!(f, "left"),
+        }
+    }
+}
+
+/// User preferences for evaluating and displaying results, saved with the state.
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub angle_mode: AngleMode,
+    pub power: PowerRules,
+    /// Fixed number of decimals for results; `None` prints the shortest exact form
+    pub precision: Option<usize>,
+    pub variable_sort: VariableSort,
+    /// Input is Reverse Polish, applied to a stack of values (`5 3 +`)
+    pub rpn: bool,
+    /// Re-submitting an input moves its History entry to the top instead of adding another
+    pub dedup_history: bool,
+    /// Longest an evaluation may run before it fails; `None` uses [`DEFAULT_EVAL_TIMEOUT`]
+    pub eval_timeout_ms: Option<u64>,
+    /// Name of the theme picked with `:theme`; `None` follows the terminal background
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub theme: Option<String>,
+}
+
+pub const DEFAULT_EVAL_TIMEOUT: Duration = Duration::from_secs(10);
+
+impl Settings {
+    pub fn eval_timeout(&self) -> Duration {
+        self.eval_timeout_ms
+            .map_or(DEFAULT_EVAL_TIMEOUT, Duration::from_millis)
+    }
+}
+
+/// Order of the Variables pane, cycled with `s` while it is focused.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VariableSort {
+    #[default]
+    Alphabetical,
+    /// Last referenced or assigned in History first
+    RecentlyUsed,
+    /// Last assigned first
+    RecentlyDefined,
+}
+
+impl VariableSort {
+    pub fn next(self) -> Self {
+        match self {
+            VariableSort::Alphabetical => VariableSort::RecentlyUsed,
+            VariableSort::RecentlyUsed => VariableSort::RecentlyDefined,
+            VariableSort::RecentlyDefined => VariableSort::Alphabetical,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            VariableSort::Alphabetical => "a-z",
+            VariableSort::RecentlyUsed => "recently used",
+            VariableSort::RecentlyDefined => "recently defined",
+        }
+    }
+}
+
+/// Arrangement of the panes, selected with `:layout <name>`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LayoutPreset {
+    /// History with the plot below it, Variables on the side
+    #[default]
+    Classic,
+    /// Like classic, but the plot takes most of the History column
+    PlotFocused,
+    /// Input and History only
+    Minimal,
+}
+
+impl LayoutPreset {
+    pub const ALL: [LayoutPreset; 3] = [
+        LayoutPreset::Classic,
+        LayoutPreset::PlotFocused,
+        LayoutPreset::Minimal,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            LayoutPreset::Classic => "classic",
+            LayoutPreset::PlotFocused => "plot",
+            LayoutPreset::Minimal => "minimal",
+        }
+    }
+}
+
+/// Pane proportions, adjustable at runtime and persisted with the state.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct LayoutState {
+    /// Share of the width given to the History column, in percent
+    pub history_width: u16,
+    /// Height of the input area in rows, including padding
+    pub input_height: u16,
+    pub show_variables: bool,
+    pub show_plot: bool,
+    pub preset: LayoutPreset,
+}
+
+impl LayoutState {
+    pub const MIN_HISTORY_WIDTH: u16 = 20;
+    pub const MAX_HISTORY_WIDTH: u16 = 80;
+    pub const MIN_INPUT_HEIGHT: u16 = 3;
+    pub const MAX_INPUT_HEIGHT: u16 = 8;
+    /// Below this terminal size the panes collapse into a single column
+    pub const COMPACT_WIDTH: u16 = 60;
+    pub const COMPACT_HEIGHT: u16 = 16;
+
+    pub fn is_compact(width: u16, height: u16) -> bool {
+        width < Self::COMPACT_WIDTH || height < Self::COMPACT_HEIGHT
+    }
+
+    pub fn resize_history(&mut self, delta: i16) {
+        self.history_width = self
+            .history_width
+            .saturating_add_signed(delta)
+            .clamp(Self::MIN_HISTORY_WIDTH, Self::MAX_HISTORY_WIDTH);
+    }
+
+    pub fn resize_input(&mut self, delta: i16) {
+        self.input_height = self
+            .input_height
+            .saturating_add_signed(delta)
+            .clamp(Self::MIN_INPUT_HEIGHT, Self::MAX_INPUT_HEIGHT);
+    }
+
+    pub fn variables_visible(&self) -> bool {
+        self.show_variables && self.preset != LayoutPreset::Minimal
+    }
+
+    pub fn plot_visible(&self) -> bool {
+        self.show_plot && self.preset != LayoutPreset::Minimal
+    }
+
+    /// Share of the History column's height given to the plot, in percent
+    pub fn plot_height(&self) -> u16 {
+        match self.preset {
+            LayoutPreset::PlotFocused => 75,
+            _ => 50,
+        }
+    }
+}
+
+impl Default for LayoutState {
+    fn default() -> Self {
+        Self {
+            history_width: 50,
+            input_height: 3,
+            show_variables: true,
+            show_plot: true,
+            preset: LayoutPreset::Classic,
+        }
+    }
+}
+
+/// How plotted points are drawn.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PlotStyle {
+    #[default]
+    Scatter,
+    /// Points joined by lines, better suited to continuous functions
+    Line,
+}
+
+impl PlotStyle {
+    pub fn toggle(self) -> Self {
+        match self {
+            PlotStyle::Scatter => PlotStyle::Line,
+            PlotStyle::Line => PlotStyle::Scatter,
+        }
+    }
+}
+
+/// Popups drawn on top of the regular panes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Overlay {
+    /// Detail view of the history entry at the given index
+    ResultDetail(usize),
+    /// Scrollable list of all key bindings
+    Help { scroll: u16 },
+    /// Saved sessions to switch to, with the highlighted row
+    SessionPicker { selected: usize },
+    /// Plots saved with `:plot save` to display, with the highlighted row
+    PlotPicker { selected: usize },
+    /// Asks before wiping a collection with `:clear`
+    ConfirmClear(ClearTarget),
+    /// Statistics over the results in History, from `:stats`
+    Stats,
+    /// Functions to insert into the input, with the highlighted row
+    FunctionMenu { selected: usize },
+    /// Snippets to insert into the input, with the highlighted row
+    SnippetPicker { selected: usize },
+}
+
+/// What `:clear` and `rcalc clear` wipe.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ClearTarget {
+    History,
+    Variables,
+    /// The current plot and the saved ones
+    Plots,
+}
+
+impl ClearTarget {
+    pub fn name(self) -> &'static str {
+        match self {
+            ClearTarget::History => "history",
+            ClearTarget::Variables => "variables",
+            ClearTarget::Plots => "plots",
+        }
+    }
+}
+
+/// The calculations of one tab: its own history, variables, and plot.
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Workspace {
+    /// Identifies the tab across saves, whatever its position; 0 for the first tab
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub id: u64,
+    #[serde(default)]
+    pub history: Vec<History>,
+    #[serde(default)]
+    pub variables: HashMap<String, VariableEntry>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plot_data: Option<Vec<(f64, f64)>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plot: Option<PlotDefinition>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plot_range: Option<PlotRange>,
+}
+
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
 pub struct AppState {
     /// History of recorded messages
     pub history: Vec<History>,
     /// Variables stored in the calculator
     pub variables: HashMap<String, VariableEntry>,
     pub plot_data: Option<Vec<(f64, f64)>>,
+    #[serde(default)]
+    pub layout: LayoutState,
+    #[serde(default)]
+    pub plot_style: PlotStyle,
+    /// What `plot_data` was sampled from, so it can be re-sampled after loading
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plot: Option<PlotDefinition>,
+    /// Range `plot_data` was sampled over, when it is not the plot's default
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plot_range: Option<PlotRange>,
+    /// Plots saved by name, besides the one shown
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub plots: Vec<PlotEntry>,
+    #[serde(default)]
+    pub settings: Settings,
+    /// Functions defined with `f(x) = …`, in the order they were defined
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub functions: Vec<FunctionDefinition>,
+    /// Running total of tape mode; `None` while tape mode is off
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tape_total: Option<f64>,
+    /// Columns of numbers imported with `:import csv`, by name
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub lists: BTreeMap<String, Vec<f64>>,
+    /// The tabs not shown, in order; the shown one is the rest of the state
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub workspaces: Vec<Workspace>,
+    /// Position of the shown tab among all of them
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub workspace: usize,
+    /// [`Workspace::id`] of the shown tab
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub workspace_id: u64,
+}
+
+fn is_zero<T: Default + PartialEq>(value: &T) -> bool {
+    *value == T::default()
+}
+
+impl AppState {
+    /// Points of the saved plot, re-sampled from its definition with the current
+    /// variables. Plots saved without a definition keep their saved points.
+    pub fn plot_points(&self) -> Option<Vec<(f64, f64)>> {
+        let resampled = self.plot.as_ref().and_then(|plot| {
+            let range = match self.plot_range {
+                Some(range) => range,
+                None => plot.default_range(&self.variables).ok()?,
+            };
+            plot.sample_in(range, &self.variables).ok()
+        });
+        resampled.or_else(|| self.plot_data.clone())
+    }
+
+    /// Wipes one aspect of the state, keeping the rest.
+    pub fn clear(&mut self, target: ClearTarget) {
+        match target {
+            ClearTarget::History => self.history.clear(),
+            ClearTarget::Variables => {
+                self.variables.clear();
+                self.lists.clear();
+            }
+            ClearTarget::Plots => {
+                self.plot = None;
+                self.plot_data = None;
+                self.plot_range = None;
+                self.plots.clear();
+            }
+        }
+    }
+
+    /// Shrinks the state: drops History entries repeating the one before them,
+    /// keeps only the newest `keep_history` entries, and drops sampled points of
+    /// plots that are re-sampled on load anyway. Entries with a note are kept.
+    pub fn compact(&mut self, keep_history: usize) -> CompactReport {
+        let history_before = self.history.len();
+        let mut previous: Option<(String, Option<f64>, Option<String>)> = None;
+        self.history.retain(|entry| {
+            let key = (entry.expression.clone(), entry.result, entry.error.clone());
+            let repeated = previous.as_ref() == Some(&key) && entry.note.is_none();
+            previous = Some(key);
+            !repeated
+        });
+        let mut excess = self.history.len().saturating_sub(keep_history);
+        self.history.retain(|entry| {
+            let drop = excess > 0 && entry.note.is_none();
+            excess -= usize::from(drop);
+            !drop
+        });
+
+        let plot_points = match &self.plot {
+            Some(_) => self.plot_data.take().map_or(0, |points| points.len()),
+            None => 0,
+        };
+        CompactReport {
+            history_entries: history_before - self.history.len(),
+            plot_points,
+        }
+    }
+
+    /// Brings in what another instance saved as `theirs` since both started from
+    /// `base`, tab by tab as matched by [`Workspace::id`]: variables it added,
+    /// changed, or removed that this state left as in `base`, its new history
+    /// entries, ordered by time, and tabs it opened. Everything else, such as the
+    /// plot, settings, and tabs this state closed, stays as in this state.
+    pub fn merge_saved(&mut self, base: &AppState, theirs: &AppState) {
+        for their_tab in theirs.tabs() {
+            let base_tab = base.tabs().into_iter().find(|tab| tab.id == their_tab.id);
+            let Some((history, variables)) = self.tab_mut(their_tab.id) else {
+                if base_tab.is_none() {
+                    self.workspaces.push(their_tab);
+                }
+                continue;
+            };
+            let base_tab = base_tab.unwrap_or_default();
+
+            for (name, entry) in &their_tab.variables {
+                let before = base_tab.variables.get(name);
+                if before != Some(entry) && variables.get(name) == before {
+                    variables.insert(name.clone(), entry.clone());
+                }
+            }
+            for (name, entry) in &base_tab.variables {
+                if !their_tab.variables.contains_key(name) && variables.get(name) == Some(entry) {
+                    variables.remove(name);
+                }
+            }
+
+            let added: Vec<&History> = their_tab
+                .history
+                .iter()
+                .filter(|entry| !base_tab.history.contains(entry))
+                .collect();
+            add_history(history, added);
+        }
+    }
+
+    /// All tabs in order, the shown one included.
+    fn tabs(&self) -> Vec<Workspace> {
+        let shown = Workspace {
+            id: self.workspace_id,
+            history: self.history.clone(),
+            variables: self.variables.clone(),
+            plot_data: self.plot_data.clone(),
+            plot: self.plot.clone(),
+            plot_range: self.plot_range,
+        };
+        let mut tabs = self.workspaces.clone();
+        tabs.insert(self.workspace.min(tabs.len()), shown);
+        tabs
+    }
+
+    /// History and variables of the tab with `id`, shown or not.
+    fn tab_mut(
+        &mut self,
+        id: u64,
+    ) -> Option<(&mut Vec<History>, &mut HashMap<String, VariableEntry>)> {
+        if id == self.workspace_id {
+            return Some((&mut self.history, &mut self.variables));
+        }
+        self.workspaces
+            .iter_mut()
+            .find(|tab| tab.id == id)
+            .map(|tab| (&mut tab.history, &mut tab.variables))
+    }
+
+    /// Imports the history and variables of `other`, e.g. a session from another
+    /// machine. Variables defined differently in both are resolved by `resolution`.
+    pub fn merge(&mut self, other: &AppState, resolution: ConflictResolution) -> MergeReport {
+        let mut report = MergeReport {
+            history_entries: add_history(&mut self.history, other.history.iter()),
+            ..MergeReport::default()
+        };
+        let mut names: Vec<&String> = other.variables.keys().collect();
+        names.sort();
+        for name in names {
+            let entry = &other.variables[name];
+            match self.variables.get(name) {
+                None => report.variables += 1,
+                Some(current) if current == entry => continue,
+                Some(_) => {
+                    report.conflicts.push(name.clone());
+                    if resolution == ConflictResolution::Ours {
+                        continue;
+                    }
+                }
+            }
+            self.variables.insert(name.clone(), entry.clone());
+        }
+        report
+    }
+}
+
+/// Adds the entries not in `history` yet, ordered by time, and returns how many.
+fn add_history<'a>(
+    history: &mut Vec<History>,
+    entries: impl IntoIterator<Item = &'a History>,
+) -> usize {
+    let added: Vec<History> = entries
+        .into_iter()
+        .filter(|entry| !history.contains(entry))
+        .cloned()
+        .collect();
+    let count = added.len();
+    if count > 0 {
+        history.extend(added);
+        history.sort_by_key(|entry| entry.timestamp);
+    }
+    count
+}
+
+/// Variables shared on their own, without History, e.g. a set of material
+/// constants exported with `rcalc export vars`.
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct VariablePack {
+    pub variables: BTreeMap<String, VariableEntry>,
+}
+
+impl VariablePack {
+    pub fn from_state(state: &AppState) -> Self {
+        Self {
+            variables: state
+                .variables
+                .iter()
+                .map(|(name, entry)| (name.clone(), entry.clone()))
+                .collect(),
+        }
+    }
+
+    /// A state holding only the pack's variables, to [`merge`](AppState::merge) in.
+    pub fn to_state(&self) -> AppState {
+        AppState {
+            variables: self
+                .variables
+                .iter()
+                .map(|(name, entry)| (name.clone(), entry.clone()))
+                .collect(),
+            ..AppState::default()
+        }
+    }
+}
+
+/// Which definition [`AppState::merge`] keeps for a variable defined differently
+/// in both states.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ConflictResolution {
+    /// The current state's
+    #[default]
+    Ours,
+    /// The merged-in state's
+    Theirs,
+}
+
+/// What [`AppState::merge`] imported.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MergeReport {
+    pub history_entries: usize,
+    /// Variables that were not defined before
+    pub variables: usize,
+    /// Variables defined differently in both states, sorted
+    pub conflicts: Vec<String>,
+}
+
+impl std::fmt::Display for MergeReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Imported {} history entries and {} new variables",
+            self.history_entries, self.variables
+        )?;
+        if !self.conflicts.is_empty() {
+            write!(f, "; conflicting: {}", self.conflicts.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// History entries kept by `rcalc compact` and `:compact` unless told otherwise.
+pub const COMPACT_HISTORY_LIMIT: usize = 1000;
+
+/// What [`AppState::compact`] removed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CompactReport {
+    pub history_entries: usize,
+    pub plot_points: usize,
+}
+
+impl std::fmt::Display for CompactReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Removed {} history entries and {} plot points",
+            self.history_entries, self.plot_points
+        )
+    }
 }
@@ -0,0 +1,82 @@
+use std::{collections::HashMap, ops::Range};
+
+use crate::{
+    calculate::{EvalLimits, parse},
+    error::CalcError,
+    inspect::{inspect_unknown_variables, missing_operands, unmatched_parens},
+    tokenize::{Lexer, Token, TokenKind},
+    types::{PowerRules, VariableEntry},
+    variables::parse_variables,
+};
+
+/// A problem found in an expression without evaluating it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub error: CalcError,
+    /// Byte range of the input the problem is at, if it is at one place
+    pub span: Option<Range<usize>>,
+}
+
+/// Checks `expr` for unbalanced parentheses, unknown identifiers, and syntax
+/// errors, without evaluating it. For an assignment only the value is checked.
+///
+/// Diagnostics are ordered by position, those without a span last.
+pub fn validate(expr: &str, variables: &HashMap<String, VariableEntry>) -> Vec<Diagnostic> {
+    let mut tokens: Vec<Token> = Lexer::new(expr).collect();
+    if let Some(equals) = tokens.iter().position(|t| t.kind == TokenKind::Equals) {
+        let texts: Vec<&str> = tokens.iter().map(|token| token.text).collect();
+        if let Err(error) = parse_variables(texts) {
+            // The left side, or the `=` when there is none.
+            let start = tokens[0].span.start;
+            let end = tokens[equals.saturating_sub(1)].span.end;
+            return vec![Diagnostic {
+                error,
+                span: Some(start..end),
+            }];
+        }
+        tokens.drain(..=equals);
+    }
+
+    let mut diagnostics: Vec<Diagnostic> = unmatched_parens(&tokens)
+        .into_iter()
+        .chain(missing_operands(&tokens))
+        .map(|error| Diagnostic {
+            span: error.position().map(|position| position..position + 1),
+            error,
+        })
+        .collect();
+
+    let texts: Vec<&str> = tokens.iter().map(|token| token.text).collect();
+    for name in inspect_unknown_variables(&texts, variables) {
+        let span = tokens
+            .iter()
+            .find(|token| token.text == name)
+            .map(|token| token.span.clone());
+        diagnostics.push(Diagnostic {
+            error: CalcError::UnknownVariable(name),
+            span,
+        });
+    }
+
+    // Parenthesis and operand problems are already reported with their position.
+    if diagnostics
+        .iter()
+        .all(|diagnostic| matches!(diagnostic.error, CalcError::UnknownVariable(_)))
+        && let Err(error) = parse(
+            &texts,
+            PowerRules::default(),
+            &EvalLimits::default(),
+            &HashMap::new(),
+        )
+    {
+        diagnostics.push(Diagnostic { error, span: None });
+    }
+
+    diagnostics.sort_by_key(|diagnostic| {
+        diagnostic
+            .span
+            .as_ref()
+            .map_or(usize::MAX, |span| span.start)
+    });
+    diagnostics
+}
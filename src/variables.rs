@@ -1,24 +1,31 @@
+use crate::{builtins::is_builtin, definitions::definition_shape, error::CalcError};
+
 #[derive(Debug)]
 pub struct VariableParseReturn<'a> {
     pub var_name: String,
     pub tokens: Vec<&'a str>,
 }
 
-pub fn parse_variables<'a>(tokens: Vec<&'a str>) -> Result<VariableParseReturn<'a>, String> {
-    if !tokens.contains(&"=") {
-        return Err("No assignment found".to_string());
-    }
-
+/// Splits an assignment such as `x = 2+3` into the variable name and the tokens
+/// of its value. The left side must be a single name; `2=5` and `a*b=3` fail.
+pub fn parse_variables<'a>(tokens: Vec<&'a str>) -> Result<VariableParseReturn<'a>, CalcError> {
     let assignment_index = tokens
         .iter()
         .position(|&t| t == "=")
-        .ok_or_else(|| "No assignment found".to_string())?;
-
-    if assignment_index == 0 {
-        return Err("Missing variable name before '='".to_string());
-    }
+        .ok_or(CalcError::NoAssignment)?;
 
-    let var_name = tokens[assignment_index - 1].to_string();
+    let var_name = match &tokens[..assignment_index] {
+        [] => return Err(CalcError::MissingVariableName),
+        [name] if is_builtin(name) => return Err(CalcError::ReservedName(name.to_string())),
+        [name] if is_valid_name(name) => name.to_string(),
+        [name] => return Err(CalcError::InvalidVariableName(name.to_string())),
+        target => {
+            if let Some((name, _)) = definition_shape(target) {
+                return Err(CalcError::FunctionDefinition(name.to_string()));
+            }
+            return Err(CalcError::InvalidAssignmentTarget(target.concat()));
+        }
+    };
     let value_tokens = tokens.into_iter().skip(assignment_index + 1).collect();
 
     Ok(VariableParseReturn {
@@ -26,3 +33,9 @@ pub fn parse_variables<'a>(tokens: Vec<&'a str>) -> Result<VariableParseReturn<'
         tokens: value_tokens,
     })
 }
+
+/// Whether `name` can be assigned to: a letter followed by letters or digits.
+pub fn is_valid_name(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_alphabetic())
+        && name.chars().all(|c| c.is_ascii_alphanumeric())
+}
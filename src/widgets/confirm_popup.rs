@@ -0,0 +1,24 @@
+use ratatui::{
+    layout::Alignment,
+    style::Style,
+    text::Line,
+    widgets::{Padding, Paragraph, Wrap},
+};
+
+use crate::theme::Theme;
+
+/// Yes/no question asked before destructive commands.
+pub fn render_confirm_popup<'a>(question: &str, theme: &Theme) -> Paragraph<'a> {
+    let block = theme
+        .popup_block()
+        .border_style(Style::default().fg(theme.error))
+        .padding(Padding::new(1, 1, 1, 0))
+        .title_style(Style::default().fg(theme.error).bold())
+        .title("Confirm")
+        .title_bottom(Line::from(" y: yes • n/Esc: no ").right_aligned());
+
+    Paragraph::new(question.to_string())
+        .alignment(Alignment::Center)
+        .wrap(Wrap { trim: true })
+        .block(block)
+}
@@ -0,0 +1,146 @@
+use ratatui::{
+    style::Style,
+    text::{Line, Span},
+    widgets::{Padding, Paragraph, Wrap},
+};
+
+use std::collections::HashMap;
+
+use crate::{
+    format::{format_fraction, format_full_precision, format_hex, format_scientific},
+    inspect::inspect_unknown_variables,
+    theme::Theme,
+    tokenize::tokenize,
+    trace::TraceStep,
+    types::{History, VariableEntry},
+    variables::parse_variables,
+};
+
+/// Details of a history entry; for failed entries, the error along with how the
+/// input was tokenized and which variables are unknown. `steps` are listed when
+/// the user expanded them.
+pub fn render_detail_popup<'a>(
+    entry: &History,
+    number: usize,
+    variables: &HashMap<String, VariableEntry>,
+    steps: Option<&[TraceStep]>,
+    theme: &Theme,
+) -> Paragraph<'a> {
+    let label = |text: &str| Span::styled(format!("{:<12}", text), Style::default().dim());
+
+    let mut lines = vec![Line::from(vec![
+        label("Expression"),
+        Span::styled(
+            entry.expression.clone(),
+            Style::default().fg(theme.expression),
+        ),
+    ])];
+
+    if let Some(note) = &entry.note {
+        lines.push(Line::from(vec![
+            label("Note"),
+            Span::styled(note.clone(), Style::default().italic()),
+        ]));
+    }
+
+    match entry.result {
+        Some(value) => {
+            let unavailable = || "—".to_string();
+            lines.push(Line::from(vec![
+                label("Value"),
+                Span::styled(
+                    format_full_precision(value),
+                    Style::default().bold().fg(theme.result),
+                ),
+            ]));
+            lines.push(Line::from(vec![
+                label("Scientific"),
+                Span::raw(format_scientific(value)),
+            ]));
+            lines.push(Line::from(vec![
+                label("Hex"),
+                Span::raw(format_hex(value).unwrap_or_else(unavailable)),
+            ]));
+            lines.push(Line::from(vec![
+                label("Fraction"),
+                Span::raw(format_fraction(value).unwrap_or_else(unavailable)),
+            ]));
+        }
+        None => lines.push(Line::from(vec![label("Value"), Span::raw("—")])),
+    }
+
+    if let Some(error) = &entry.error {
+        lines.push(Line::from(vec![
+            label("Error"),
+            Span::styled(error.clone(), Style::default().fg(theme.error)),
+        ]));
+
+        let tokens = tokenize(&entry.expression);
+        let token_spans = tokens.iter().enumerate().flat_map(|(i, token)| {
+            let separator = Span::styled(if i == 0 { "" } else { " · " }, Style::default().dim());
+            [separator, Span::raw(token.to_string())]
+        });
+        lines.push(Line::from(
+            std::iter::once(label("Tokens"))
+                .chain(token_spans)
+                .collect::<Vec<_>>(),
+        ));
+
+        let operand = match parse_variables(tokens.clone()) {
+            Ok(assignment) if tokens.contains(&"=") => assignment.tokens,
+            _ => tokens,
+        };
+        let unknown = inspect_unknown_variables(&operand, variables);
+        if !unknown.is_empty() {
+            lines.push(Line::from(vec![
+                label("Unknown"),
+                Span::styled(unknown.join(", "), Style::default().fg(theme.error)),
+            ]));
+        }
+    }
+
+    if !entry.variables.is_empty() {
+        lines.push(Line::raw(""));
+        lines.push(Line::styled("Variables used", Style::default().bold()));
+        for (name, value) in &entry.variables {
+            lines.push(Line::from(vec![
+                Span::styled(format!("  {} = ", name), Style::default().bold()),
+                Span::styled(value.to_string(), Style::default().fg(theme.result)),
+            ]));
+        }
+    }
+
+    if let Some(steps) = steps {
+        lines.push(Line::raw(""));
+        lines.push(Line::styled("Steps", Style::default().bold()));
+        for (i, step) in steps.iter().enumerate() {
+            lines.push(Line::from(vec![
+                Span::styled(format!("  {}. ", i + 1), Style::default().dim()),
+                Span::raw(step.to_string()),
+            ]));
+        }
+    }
+
+    let block = theme
+        .popup_block()
+        .border_style(Style::default().fg(theme.accent_focused))
+        .padding(Padding::new(1, 1, 0, 0))
+        .title_style(Style::default().fg(theme.accent).bold())
+        .title(if entry.error.is_some() {
+            format!("Error #{}", number)
+        } else {
+            format!("Result #{}", number)
+        })
+        .title_bottom(
+            Line::from(if entry.result.is_some() {
+                " s: steps · Esc: close "
+            } else {
+                " Esc: close "
+            })
+            .right_aligned(),
+        );
+
+    Paragraph::new(lines)
+        .wrap(Wrap { trim: false })
+        .block(block)
+}
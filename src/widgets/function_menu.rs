@@ -0,0 +1,55 @@
+use ratatui::{
+    style::Style,
+    text::{Line, Span},
+    widgets::{List, ListItem, Padding},
+};
+
+use crate::{
+    builtins::{FUNCTIONS, function_doc},
+    definitions::FunctionDefinition,
+    theme::Theme,
+};
+
+/// Built-in functions followed by those defined with `f(x) = …`, each with its
+/// signature and a short description.
+pub fn render_function_menu<'a>(functions: &[FunctionDefinition], theme: &Theme) -> List<'a> {
+    let builtins = FUNCTIONS.iter().map(|name| {
+        (
+            format!("{}(x)", name),
+            function_doc(name).unwrap_or_default().to_string(),
+        )
+    });
+    let defined = functions.iter().map(|definition| {
+        (
+            format!("{}({})", definition.name, definition.params.join(", ")),
+            format!("= {}", definition.body),
+        )
+    });
+    let items: Vec<ListItem> = builtins
+        .chain(defined)
+        .map(|(signature, doc)| {
+            ListItem::new(Line::from(vec![
+                Span::styled(format!("{:<10}", signature), Style::default().bold()),
+                Span::styled(format!("  {}", doc), Style::default().fg(theme.muted)),
+            ]))
+        })
+        .collect();
+
+    let block = theme
+        .popup_block()
+        .border_style(Style::default().fg(theme.accent_focused))
+        .padding(Padding::new(1, 1, 0, 0))
+        .title_style(Style::default().fg(theme.accent).bold())
+        .title("Functions")
+        .title_bottom(Line::from(" Enter: insert • Esc: close ").right_aligned());
+
+    List::new(items)
+        .highlight_style(
+            Style::default()
+                .bg(theme.selection)
+                .add_modifier(theme.highlight)
+                .bold(),
+        )
+        .highlight_symbol(theme.highlight_symbol())
+        .block(block)
+}
@@ -4,10 +4,10 @@ use ratatui::{
     widgets::Paragraph,
 };
 
-use crate::{input_editor::InputEditMode, types::Focus};
+use crate::{input_editor::InputEditMode, theme::Theme, types::Focus};
 
-pub fn render_help_message<'a>(focus: Focus, input_edit_mode: InputEditMode) -> Paragraph<'a> {
-    let mode_label = match focus {
+pub fn mode_label(focus: Focus, input_edit_mode: InputEditMode) -> &'static str {
+    match focus {
         Focus::Input => match input_edit_mode {
             InputEditMode::Insert => "INSERT",
             InputEditMode::Normal => "NORMAL",
@@ -15,22 +15,38 @@ pub fn render_help_message<'a>(focus: Focus, input_edit_mode: InputEditMode) ->
         },
         Focus::History => "HISTORY",
         Focus::Variables => "VARIABLES",
-    };
+        Focus::Plot => "PLOT",
+    }
+}
+
+pub fn render_help_message<'a>(
+    focus: Focus,
+    input_edit_mode: InputEditMode,
+    theme: &Theme,
+) -> Paragraph<'a> {
+    let mode_label = mode_label(focus, input_edit_mode);
 
     let help_line = Line::from(vec![
         Span::styled(
             format!("[{}] ", mode_label),
             match input_edit_mode {
                 InputEditMode::Insert => Style::default().bold(),
-                InputEditMode::Normal | InputEditMode::Visual => Style::default().bold().blue(),
+                InputEditMode::Normal | InputEditMode::Visual => {
+                    Style::default().bold().fg(theme.mode)
+                }
             },
         ),
         Span::raw(match focus {
             Focus::Input => {
-                "Enter: submit/select • Esc: mode/focus • i: input • v: visual • y: yank • d/x: delete • p/P: paste"
+                "?: all keys • Enter: submit/select • Esc: mode/focus • i: input • v: visual • y: yank • d/x: delete • p/P: paste • Ctrl+R: search"
+            }
+            Focus::History => {
+                "?: all keys • Enter: select • Esc: mode/focus • n: note • o: details • ye/yr: yank expr/result"
+            }
+            Focus::Variables => "?: all keys • Enter: select • Esc: mode/focus • D: dependencies",
+            Focus::Plot => {
+                "?: all keys • Tab: next pane • i: input • ←/→: trace • s: scatter/line • Alt+p: hide plot"
             }
-            Focus::History => "Enter: select • Esc: mode/focus • d/x: delete",
-            Focus::Variables => "Enter: select • Esc: mode/focus • d/x: delete",
         }),
     ]);
 
@@ -0,0 +1,46 @@
+use ratatui::{
+    style::Style,
+    text::{Line, Span},
+    widgets::{Padding, Paragraph},
+};
+
+use crate::{
+    keymap::{KeyContext, Keymap},
+    theme::Theme,
+};
+
+/// Every binding of `keymap`, grouped by context.
+pub fn render_help_overlay<'a>(keymap: &Keymap, scroll: u16, theme: &Theme) -> Paragraph<'a> {
+    let mut lines: Vec<Line> = Vec::new();
+
+    for context in KeyContext::ALL {
+        let entries = keymap.help_entries(context);
+        if entries.is_empty() {
+            continue;
+        }
+
+        if !lines.is_empty() {
+            lines.push(Line::raw(""));
+        }
+        lines.push(Line::styled(
+            context.title(),
+            Style::default().fg(theme.accent).bold(),
+        ));
+        for (keys, description) in entries {
+            lines.push(Line::from(vec![
+                Span::styled(format!("  {:<18}", keys), Style::default().bold()),
+                Span::raw(description),
+            ]));
+        }
+    }
+
+    let block = theme
+        .popup_block()
+        .border_style(Style::default().fg(theme.accent_focused))
+        .padding(Padding::new(1, 1, 0, 0))
+        .title_style(Style::default().fg(theme.accent).bold())
+        .title("Key bindings")
+        .title_bottom(Line::from(" j/k: scroll • Esc: close ").right_aligned());
+
+    Paragraph::new(lines).scroll((scroll, 0)).block(block)
+}
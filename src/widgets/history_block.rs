@@ -1,60 +1,193 @@
 use ratatui::{
-    style::{Color, Style},
-    text::{Line, Span},
-    widgets::{Block, BorderType, List, ListItem, Padding},
+    style::Style,
+    text::{Line, Span, Text},
+    widgets::{List, ListItem, Padding},
 };
 
-use crate::types::{Focus, History};
+use std::{collections::HashMap, ops::Range};
 
-pub fn render_history_block<'a>(history: &[History], focus: Focus) -> List<'a> {
+use time::{Date, UtcOffset};
+
+use crate::{
+    dates::{self, day_label, local_date},
+    format::format_tagged,
+    theme::Theme,
+    tokenize::split_comment,
+    types::{Focus, History, HistoryKind, ValueFormat, VariableEntry},
+};
+
+/// Items of a list (History newest first) to build for a pane `height` rows tall,
+/// starting at `offset` and scrolled just enough to keep `selected` visible.
+/// Item `i` takes `item_height(i)` rows.
+///
+/// Only these items are turned into list items, so drawing stays cheap however
+/// long the history grows.
+pub fn visible_window(
+    len: usize,
+    selected: Option<usize>,
+    offset: usize,
+    height: usize,
+    item_height: impl Fn(usize) -> usize,
+) -> Range<usize> {
+    // The first item of the window ending at `last` that fills `height` rows.
+    let first_fitting = |last: usize| {
+        let mut start = last;
+        let mut rows = item_height(last);
+        while start > 0 && rows + item_height(start - 1) <= height {
+            start -= 1;
+            rows += item_height(start);
+        }
+        start
+    };
+    let mut start = offset.min(len.checked_sub(1).map_or(0, first_fitting));
+    if let Some(selected) = selected.filter(|&selected| selected < len) {
+        start = start.clamp(first_fitting(selected), selected);
+    }
+    let mut end = start;
+    let mut rows = 0;
+    while end < len && rows < height.max(1) {
+        rows += item_height(end);
+        end += 1;
+    }
+    start..end
+}
+
+/// Rows the entry `history[i]` takes in the History list: two when a day header
+/// goes above it.
+pub fn history_item_height(history: &[History], i: usize, utc_offset: UtcOffset) -> usize {
+    1 + usize::from(new_day(history, i, utc_offset).is_some())
+}
+
+/// The day of `history[i]`, when it is the latest entry of that day. Newest entries
+/// come first, so that is where the day's header goes.
+fn new_day(history: &[History], i: usize, utc_offset: UtcOffset) -> Option<Date> {
+    let day_of = |entry: &History| local_date(entry.timestamp?, utc_offset);
+    let day = day_of(&history[i])?;
+    (history.get(i + 1).and_then(day_of) != Some(day)).then_some(day)
+}
+
+/// Display format of the variable an assignment entry sets, if it has one.
+fn assigned_format(
+    entry: &History,
+    variables: &HashMap<String, VariableEntry>,
+) -> Option<ValueFormat> {
+    if entry.kind != HistoryKind::Assignment {
+        return None;
+    }
+    let (name, _) = entry.expression.split_once('=')?;
+    variables.get(name.trim())?.format
+}
+
+/// Renders the rows in `window` (see [`visible_window`]) of the newest-first History list.
+pub fn render_history_block<'a>(
+    history: &[History],
+    variables: &HashMap<String, VariableEntry>,
+    window: Range<usize>,
+    focus: Focus,
+    precision: Option<usize>,
+    utc_offset: UtcOffset,
+    theme: &Theme,
+) -> List<'a> {
+    let today = local_date(dates::now(), utc_offset);
     let results: Vec<ListItem> = history
         .iter()
         .enumerate()
         .rev()
-        .map(|(i, m)| match (m.result, &m.error) {
-            (Some(result), _) => {
-                let content = Line::from(vec![
+        .skip(window.start)
+        .take(window.len())
+        .map(|(i, m)| {
+            let (expression, comment) = split_comment(&m.expression);
+            let mut content = match (m.result, &m.error) {
+                (Some(result), _) => Line::from(vec![
                     Span::styled(format!("{} ", i + 1), Style::default().dim()),
-                    Span::styled(m.expression.clone(), Style::default().blue()),
-                    Span::raw(" = "),
-                    Span::styled(result.to_string(), Style::default().bold().green()),
-                ]);
-                ListItem::new(content)
-            }
-            (_, Some(_)) => {
-                let content = Line::from(vec![
+                    Span::styled(theme.success_symbol(), Style::default().fg(theme.result)),
+                    Span::styled(
+                        expression.trim_end().to_string(),
+                        match m.kind {
+                            HistoryKind::Expression | HistoryKind::Tape => {
+                                Style::default().fg(theme.expression)
+                            }
+                            HistoryKind::Assignment | HistoryKind::Definition => {
+                                Style::default().fg(theme.assignment)
+                            }
+                        },
+                    ),
+                    Span::raw(match m.kind {
+                        HistoryKind::Tape if theme.plain => "  total ",
+                        HistoryKind::Tape => "  Σ ",
+                        _ => " = ",
+                    }),
+                    Span::styled(
+                        format_tagged(result, assigned_format(m, variables), precision),
+                        Style::default().bold().fg(theme.result),
+                    ),
+                    Span::styled(
+                        m.duration_ms.map_or(String::new(), |ms| {
+                            format!("  in {:.1} s", ms as f64 / 1000.0)
+                        }),
+                        Style::default().fg(theme.muted),
+                    ),
+                    Span::styled(
+                        comment.map_or(String::new(), |comment| format!("  {}", comment)),
+                        Style::default().fg(theme.muted),
+                    ),
+                ]),
+                (_, Some(_)) => Line::from(vec![
                     Span::styled(format!("{} ", i + 1), Style::default().dim()),
-                    Span::styled(format!("{m}"), Style::default().red().bold()),
-                ]);
-                ListItem::new(content)
-            }
-            (_, _) => {
-                let content = Line::from(vec![
+                    Span::styled(theme.error_symbol(), Style::default().fg(theme.error)),
+                    Span::styled(format!("{m}"), Style::default().fg(theme.error).bold()),
+                ]),
+                (_, _) if m.kind == HistoryKind::Definition => Line::from(vec![
+                    Span::styled(format!("{} ", i + 1), Style::default().dim()),
+                    Span::styled(m.expression.clone(), Style::default().fg(theme.assignment)),
+                ]),
+                (_, _) => Line::from(vec![
                     Span::styled(format!("{} ", i + 1), Style::default().dim()),
-                    Span::styled(format!("{m}"), Style::default().magenta().bold()),
-                ]);
-                ListItem::new(content)
+                    Span::styled(format!("{m}"), Style::default().fg(theme.pending).bold()),
+                ]),
+            };
+            if let Some(note) = &m.note {
+                content.push_span(Span::styled(
+                    format!("  {}", note),
+                    Style::default().dim().italic(),
+                ));
+            }
+
+            match (new_day(history, i, utc_offset), today) {
+                (Some(day), Some(today)) => {
+                    let header = Line::styled(
+                        if theme.plain {
+                            format!("-- {} --", day_label(day, today))
+                        } else {
+                            format!("── {} ──", day_label(day, today))
+                        },
+                        Style::default().fg(theme.muted),
+                    );
+                    ListItem::new(Text::from(vec![header, content]))
+                }
+                _ => ListItem::new(content),
             }
         })
         .collect();
 
     let history_focused = matches!(focus, Focus::History);
-    let block = Block::bordered()
-        .border_type(if history_focused {
-            BorderType::Thick
-        } else {
-            BorderType::Rounded
-        })
+    let block = theme
+        .pane_block(history_focused)
         .border_style(Style::default().fg(if history_focused {
-            Color::LightCyan
+            theme.accent_focused
         } else {
-            Color::Cyan
+            theme.accent
         }))
         .padding(Padding::new(1, 1, 0, 0))
-        .title_style(Style::default().fg(Color::Cyan).bold())
+        .title_style(Style::default().fg(theme.accent).bold())
         .title("History");
     List::new(results)
-        .highlight_style(Style::default().bg(Color::DarkGray).bold())
-        .highlight_symbol("› ")
+        .highlight_style(
+            Style::default()
+                .bg(theme.selection)
+                .add_modifier(theme.highlight)
+                .bold(),
+        )
+        .highlight_symbol(theme.highlight_symbol())
         .block(block)
 }
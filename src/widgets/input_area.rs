@@ -1,22 +1,38 @@
 use std::time::Instant;
 
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
 use ratatui::{
-    style::{Color, Style},
+    style::Style,
     text::{Line, Span},
     widgets::{Block, Padding, Paragraph},
 };
 
 use crate::{
+    format::format_value,
+    theme::Theme,
     tui_app::InputEditMode,
     types::{Focus, YankFlash},
 };
 
+/// Terminal columns taken by the first `char_index` grapheme clusters of `text`, so
+/// the cursor lands after wide characters such as CJK or emoji.
+pub fn cursor_column(text: &str, char_index: usize) -> u16 {
+    text.graphemes(true)
+        .take(char_index)
+        .map(UnicodeWidthStr::width)
+        .sum::<usize>() as u16
+}
+
 pub fn render_input<'a>(
     focus: Focus,
     input_edit_mode: InputEditMode,
     input: &str,
     yank_flash: Option<&YankFlash>,
     visual_selection_range: impl Fn() -> Option<(usize, usize)>,
+    unmatched_parens: &[usize],
+    theme: &Theme,
 ) -> Paragraph<'a> {
     let caret = if matches!(focus, Focus::Input) {
         match input_edit_mode {
@@ -44,7 +60,7 @@ pub fn render_input<'a>(
     });
 
     let mut spans = vec![Span::raw(format!("{} ", caret))];
-    for (idx, ch) in input.chars().enumerate() {
+    for (idx, (offset, ch)) in input.grapheme_indices(true).enumerate() {
         let ch_text = ch.to_string();
         if let Some((start, end)) = flash_range
             && idx >= start
@@ -53,8 +69,9 @@ pub fn render_input<'a>(
             spans.push(Span::styled(
                 ch_text,
                 Style::default()
-                    .bg(Color::Rgb(255, 165, 0))
-                    .fg(Color::Black)
+                    .bg(theme.yank_bg)
+                    .fg(theme.on_highlight)
+                    .add_modifier(theme.highlight)
                     .bold(),
             ));
             continue;
@@ -66,7 +83,22 @@ pub fn render_input<'a>(
         {
             spans.push(Span::styled(
                 ch_text,
-                Style::default().bg(Color::Cyan).fg(Color::Black),
+                Style::default()
+                    .bg(theme.visual_bg)
+                    .fg(theme.on_highlight)
+                    .add_modifier(theme.highlight),
+            ));
+            continue;
+        }
+
+        if unmatched_parens.contains(&offset) {
+            spans.push(Span::styled(
+                ch_text,
+                Style::default()
+                    .fg(theme.error)
+                    .add_modifier(theme.highlight)
+                    .bold()
+                    .underlined(),
             ));
             continue;
         }
@@ -75,6 +107,27 @@ pub fn render_input<'a>(
     }
 
     Paragraph::new(Line::from(spans))
-        .style(Style::new().bg(Color::DarkGray))
+        .style(Style::new().bg(theme.input_bg))
         .block(Block::new().padding(Padding::vertical(1)))
 }
+
+/// Value the input would evaluate to, shown at the right edge of the input line.
+pub fn render_input_preview<'a>(preview: &str, theme: &Theme) -> Paragraph<'a> {
+    Paragraph::new(Line::styled(
+        format!("{} ", preview),
+        Style::default().fg(theme.muted).italic(),
+    ))
+    .right_aligned()
+}
+
+/// Running total of tape mode, shown prominently at the right edge of the input line.
+pub fn render_tape_total<'a>(total: f64, precision: Option<usize>, theme: &Theme) -> Paragraph<'a> {
+    Paragraph::new(Line::from(vec![
+        Span::styled("Total ", Style::default().fg(theme.muted)),
+        Span::styled(
+            format!("{} ", format_value(total, precision)),
+            Style::default().bold().fg(theme.result),
+        ),
+    ]))
+    .right_aligned()
+}
@@ -0,0 +1,25 @@
+use ratatui::{
+    style::Style,
+    widgets::{Scrollbar, ScrollbarOrientation, ScrollbarState},
+};
+
+use crate::theme::Theme;
+
+/// Thin scrollbar drawn over the right border of a list pane.
+pub fn render_list_scrollbar<'a>(theme: &Theme) -> Scrollbar<'a> {
+    Scrollbar::new(ScrollbarOrientation::VerticalRight)
+        .begin_symbol(None)
+        .end_symbol(None)
+        .track_symbol(None)
+        .thumb_style(Style::default().fg(theme.muted))
+}
+
+/// Scroll state for a list of `len` items showing `visible` of them from `offset`, or
+/// `None` when everything fits.
+pub fn list_scrollbar_state(len: usize, offset: usize, visible: usize) -> Option<ScrollbarState> {
+    (len > visible).then(|| {
+        ScrollbarState::new(len - visible + 1)
+            .position(offset)
+            .viewport_content_length(visible)
+    })
+}
@@ -1,44 +1,111 @@
 use ratatui::{
     layout::Constraint,
-    style::{Color, Style},
+    style::Style,
     symbols::Marker,
-    widgets::{Axis, Block, BorderType, Chart, Dataset, GraphType, LegendPosition, Padding},
+    text::{Line, Span},
+    widgets::{Axis, Chart, Dataset, GraphType, LegendPosition, Padding},
 };
 
-pub fn render_scatter<'a>(data: &'a [(f64, f64)], name: String) -> Chart<'a> {
-    let datasets = vec![
+use crate::{
+    format::{format_auto, format_value},
+    theme::Theme,
+    types::{Focus, PlotStyle},
+};
+
+pub struct PlotOptions {
+    pub style: PlotStyle,
+    /// Index of a traced point in the data to highlight and read out
+    pub cursor: Option<usize>,
+    /// Width / height of the chart area in equal units; when set, both axes use
+    /// the same scale so circles stay round
+    pub aspect: Option<f64>,
+}
+
+/// A chart of `data`, titled with the plot style and, when tracing, the traced
+/// point. Values that cannot be drawn are marked with the error symbol.
+pub fn render_plot<'a>(
+    data: &'a [(f64, f64)],
+    name: String,
+    options: PlotOptions,
+    focus: Focus,
+    precision: Option<usize>,
+    theme: &Theme,
+) -> Chart<'a> {
+    let mut datasets = vec![
         Dataset::default()
             .name(name)
-            .marker(Marker::Dot)
-            .graph_type(GraphType::Scatter)
-            .style(Style::new().yellow())
+            .marker(match options.style {
+                PlotStyle::Scatter => Marker::Dot,
+                PlotStyle::Line => Marker::Braille,
+            })
+            .graph_type(match options.style {
+                PlotStyle::Scatter => GraphType::Scatter,
+                PlotStyle::Line => GraphType::Line,
+            })
+            .style(Style::new().fg(theme.plot_points))
             .data(data),
     ];
 
-    let (x_min, x_max, y_min, y_max) = min_max_xy(data).unwrap_or((0., 10., 0., 100.));
-    let x_labels = generate_labels(x_min, x_max);
-    let y_labels = generate_labels(y_min, y_max);
+    let error = |message: String| {
+        Span::styled(
+            format!("{}{}", theme.error_symbol(), message),
+            Style::default().fg(theme.error),
+        )
+    };
+    let mut title = Line::raw(match options.style {
+        PlotStyle::Scatter => "Scatter Chart",
+        PlotStyle::Line => "Line Chart",
+    });
+    if let Some(idx) = options.cursor.filter(|&idx| idx < data.len()) {
+        let (x, y) = data[idx];
+        title.push_span(format!(" · x = {}, f(x) = ", format_value(x, precision)));
+        if y.is_finite() {
+            title.push_span(format_value(y, precision));
+        } else {
+            title.push_span(error(format_value(y, precision)));
+        }
+        datasets.push(
+            Dataset::default()
+                .marker(Marker::Block)
+                .graph_type(GraphType::Scatter)
+                .style(Style::new().fg(theme.accent_focused))
+                .data(&data[idx..=idx]),
+        );
+    }
+
+    let finite_bounds = min_max_xy(data);
+    if finite_bounds.is_none() {
+        title.push_span(" · ");
+        title.push_span(error("no finite points".to_string()));
+    }
+    let mut bounds = finite_bounds.unwrap_or((0., 10., 0., 100.));
+    if let Some(aspect) = options.aspect {
+        bounds = square_bounds(bounds, aspect);
+    }
+    let (x_min, x_max, y_min, y_max) = bounds;
+    let (x_bounds, x_labels) = axis_ticks(x_min, x_max);
+    let (y_bounds, y_labels) = axis_ticks(y_min, y_max);
 
     Chart::new(datasets)
         .block(
-            Block::bordered()
-                .title("Scatter Chart")
-                .border_type(BorderType::Rounded)
-                .border_style(Style::default().fg(Color::Magenta))
+            theme
+                .pane_block(matches!(focus, Focus::Plot))
+                .title(title)
+                .border_style(Style::default().fg(theme.plot_border))
                 .padding(Padding::uniform(1)),
         )
         .x_axis(
             Axis::default()
                 .title("x")
-                .bounds([x_min, x_max])
-                .style(Style::default().fg(Color::Gray))
+                .bounds(x_bounds)
+                .style(Style::default().fg(theme.axis))
                 .labels(x_labels),
         )
         .y_axis(
             Axis::default()
                 .title("y")
-                .bounds([y_min, y_max])
-                .style(Style::default().fg(Color::Gray))
+                .bounds(y_bounds)
+                .style(Style::default().fg(theme.axis))
                 .labels(y_labels),
         )
         .legend_position(Some(LegendPosition::Bottom))
@@ -46,24 +113,119 @@ pub fn render_scatter<'a>(data: &'a [(f64, f64)], name: String) -> Chart<'a> {
 }
 
 fn min_max_xy(data: &[(f64, f64)]) -> Option<(f64, f64, f64, f64)> {
-    let &(x0, y0) = data.first()?;
+    let mut finite = data
+        .iter()
+        .copied()
+        .filter(|(x, y)| x.is_finite() && y.is_finite());
+    let (x0, y0) = finite.next()?;
 
     Some(
-        data.iter()
-            .copied()
-            .fold((x0, x0, y0, y0), |(min_x, max_x, min_y, max_y), (x, y)| {
-                (min_x.min(x), max_x.max(x), min_y.min(y), max_y.max(y))
-            }),
+        finite.fold((x0, x0, y0, y0), |(min_x, max_x, min_y, max_y), (x, y)| {
+            (min_x.min(x), max_x.max(x), min_y.min(y), max_y.max(y))
+        }),
+    )
+}
+
+/// Widens the narrower axis of `bounds` so one unit spans the same distance on both.
+fn square_bounds(
+    (x_min, x_max, y_min, y_max): (f64, f64, f64, f64),
+    aspect: f64,
+) -> (f64, f64, f64, f64) {
+    let x_span = (x_max - x_min).max(f64::EPSILON);
+    let y_span = (y_max - y_min).max(f64::EPSILON);
+    let (x_span, y_span) = if x_span / y_span < aspect {
+        (y_span * aspect, y_span)
+    } else {
+        (x_span, x_span / aspect)
+    };
+    let (x_mid, y_mid) = ((x_min + x_max) / 2., (y_min + y_max) / 2.);
+    (
+        x_mid - x_span / 2.,
+        x_mid + x_span / 2.,
+        y_mid - y_span / 2.,
+        y_mid + y_span / 2.,
     )
 }
 
-fn generate_labels(min: f64, max: f64) -> Vec<String> {
-    let delta = max - min;
-    let step = delta / 10.;
-    let mut labels = Vec::new();
-    for i in 0..11 {
-        let x = min + step * i as f64;
-        labels.push(format!("{:.1}", x));
+/// Number of ticks aimed for on each axis.
+const TARGET_TICKS: usize = 6;
+
+/// Expands `min..max` to "nice" round bounds and returns them with evenly spaced
+/// tick labels, e.g. `-9.3..41` becomes `-20..60` labeled every `20`.
+pub fn axis_ticks(min: f64, max: f64) -> ([f64; 2], Vec<String>) {
+    let (min, max) = if !min.is_finite() || !max.is_finite() {
+        (0., 1.)
+    } else if min == max {
+        let pad = if min == 0. { 1. } else { min.abs() / 10. };
+        (min - pad, max + pad)
+    } else {
+        (min, max)
+    };
+
+    let range = nice_number(max - min, false);
+    let step = nice_number(range / (TARGET_TICKS - 1) as f64, true);
+    let lo = (min / step).floor() * step;
+    let hi = (max / step).ceil() * step;
+    let count = ((hi - lo) / step).round() as usize + 1;
+
+    let labels = (0..count)
+        .map(|i| format_tick(lo + step * i as f64, step, lo.abs().max(hi.abs())))
+        .collect();
+    ([lo, hi], labels)
+}
+
+/// Rounds `x` to 1, 2, 5, or 10 times a power of ten.
+fn nice_number(x: f64, round: bool) -> f64 {
+    let exponent = x.log10().floor();
+    let magnitude = 10f64.powf(exponent);
+    let fraction = x / magnitude;
+    let nice = match (round, fraction) {
+        (true, f) if f < 1.5 => 1.,
+        (true, f) if f < 3. => 2.,
+        (true, f) if f < 7. => 5.,
+        (false, f) if f <= 1. => 1.,
+        (false, f) if f <= 2. => 2.,
+        (false, f) if f <= 5. => 5.,
+        _ => 10.,
+    };
+    nice * magnitude
+}
+
+/// Formats a tick with just enough decimals for `step`, using SI prefixes when the
+/// axis spans very large or very small values.
+pub fn format_tick(value: f64, step: f64, magnitude: f64) -> String {
+    const PREFIXES: [(i32, &str); 8] = [
+        (12, "T"),
+        (9, "G"),
+        (6, "M"),
+        (3, "k"),
+        (-3, "m"),
+        (-6, "µ"),
+        (-9, "n"),
+        (-12, "p"),
+    ];
+
+    let decimals = |step: f64| (-step.log10().floor()).clamp(0., 10.) as usize;
+    // Snap values like 0.30000000000000004 and -0 produced by the tick arithmetic
+    let value = if (value / step).abs() < 1e-9 {
+        0.
+    } else {
+        value
+    };
+
+    if (1e-2..1e4).contains(&magnitude) {
+        return format!("{:.*}", decimals(step), value);
+    }
+
+    let exponent = ((magnitude.log10() / 3.).floor() * 3.) as i32;
+    match PREFIXES
+        .iter()
+        .find(|(prefix_exponent, _)| *prefix_exponent == exponent)
+    {
+        Some((prefix_exponent, prefix)) => {
+            let scale = 10f64.powi(*prefix_exponent);
+            format!("{:.*}{}", decimals(step / scale), value / scale, prefix)
+        }
+        None => format_auto(value),
     }
-    labels
 }
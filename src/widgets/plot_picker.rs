@@ -0,0 +1,56 @@
+use ratatui::{
+    style::Style,
+    text::{Line, Span},
+    widgets::{List, ListItem, Padding},
+};
+
+use crate::{
+    plot::{PlotDefinition, PlotEntry},
+    theme::Theme,
+};
+
+/// List of plots saved with `:plot save`, marking the one displayed.
+pub fn render_plot_picker<'a>(
+    plots: &[PlotEntry],
+    current: Option<&PlotDefinition>,
+    theme: &Theme,
+) -> List<'a> {
+    let items: Vec<ListItem> = plots
+        .iter()
+        .map(|plot| {
+            let label = Span::styled(
+                format!("  {}", plot.definition.label()),
+                Style::default().fg(theme.muted),
+            );
+            if Some(&plot.definition) == current {
+                ListItem::new(Line::from(vec![
+                    Span::styled(
+                        format!("{} (shown)", plot.name),
+                        Style::default().fg(theme.accent).bold(),
+                    ),
+                    label,
+                ]))
+            } else {
+                ListItem::new(Line::from(vec![Span::raw(plot.name.clone()), label]))
+            }
+        })
+        .collect();
+
+    let block = theme
+        .popup_block()
+        .border_style(Style::default().fg(theme.accent_focused))
+        .padding(Padding::new(1, 1, 0, 0))
+        .title_style(Style::default().fg(theme.accent).bold())
+        .title("Plots")
+        .title_bottom(Line::from(" Enter: show • Esc: close ").right_aligned());
+
+    List::new(items)
+        .highlight_style(
+            Style::default()
+                .bg(theme.selection)
+                .add_modifier(theme.highlight)
+                .bold(),
+        )
+        .highlight_symbol(theme.highlight_symbol())
+        .block(block)
+}
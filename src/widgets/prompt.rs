@@ -0,0 +1,34 @@
+use ratatui::{
+    style::Style,
+    text::{Line, Span},
+    widgets::{Block, Padding, Paragraph},
+};
+
+use crate::{prompt::Prompt, theme::Theme, widgets::input_area::cursor_column};
+
+/// A prompt drawn in place of the input: its label, the text typed so far, and
+/// `trailer`, or the validation error while the text is rejected.
+pub fn render_prompt<'a>(prompt: &Prompt, trailer: Vec<Span<'a>>, theme: &Theme) -> Paragraph<'a> {
+    let mut spans = vec![
+        Span::styled(prompt.label().to_string(), Style::default().dim()),
+        Span::raw(prompt.text().to_string()),
+    ];
+    match prompt.error() {
+        Some(err) if !prompt.text().is_empty() => {
+            spans.push(Span::styled(
+                format!("  {}", err),
+                Style::default().fg(theme.error),
+            ));
+        }
+        _ => spans.extend(trailer),
+    }
+
+    Paragraph::new(Line::from(spans))
+        .style(Style::new().bg(theme.input_bg))
+        .block(Block::new().padding(Padding::vertical(1)))
+}
+
+/// Column of the cursor in a prompt drawn with [`render_prompt`], from its left edge.
+pub fn prompt_cursor_column(prompt: &Prompt) -> u16 {
+    cursor_column(prompt.label(), usize::MAX) + cursor_column(prompt.text(), prompt.cursor())
+}
@@ -0,0 +1,47 @@
+use ratatui::{
+    style::Style,
+    text::{Line, Span},
+    widgets::Paragraph,
+};
+
+use crate::{format::format_value, theme::Theme};
+
+/// Stack levels shown; deeper values are summarized as `…`.
+const SHOWN_LEVELS: usize = 4;
+
+/// The Reverse Polish stack, shown in place of the input preview with the top of
+/// the stack, level 1, at the right: `3: 2  2: 5  1: 8`.
+pub fn render_rpn_stack<'a>(
+    values: &[f64],
+    precision: Option<usize>,
+    theme: &Theme,
+) -> Paragraph<'a> {
+    if values.is_empty() {
+        return Paragraph::new(Line::styled(
+            "stack empty ",
+            Style::default().fg(theme.muted).italic(),
+        ))
+        .right_aligned();
+    }
+
+    let mut spans = Vec::new();
+    if values.len() > SHOWN_LEVELS {
+        spans.push(Span::styled("…  ", Style::default().fg(theme.muted)));
+    }
+    let shown = &values[values.len().saturating_sub(SHOWN_LEVELS)..];
+    for (idx, value) in shown.iter().enumerate() {
+        let level = shown.len() - idx;
+        spans.push(Span::styled(
+            format!("{}: ", level),
+            Style::default().fg(theme.muted),
+        ));
+        let style = if level == 1 {
+            Style::default().bold().fg(theme.result)
+        } else {
+            Style::default()
+        };
+        spans.push(Span::styled(format_value(*value, precision), style));
+        spans.push(Span::raw(if level == 1 { " " } else { "  " }));
+    }
+    Paragraph::new(Line::from(spans)).right_aligned()
+}
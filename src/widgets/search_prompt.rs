@@ -0,0 +1,24 @@
+use ratatui::{style::Style, text::Span, widgets::Paragraph};
+
+use crate::{prompt::Prompt, theme::Theme, widgets::prompt::render_prompt};
+
+/// The reverse search prompt with the newest matching input after the query.
+pub fn render_search_prompt<'a>(
+    prompt: &Prompt,
+    current_match: Option<&str>,
+    theme: &Theme,
+) -> Paragraph<'a> {
+    let match_span = match current_match {
+        Some(found) => Span::styled(found.to_string(), Style::default().bold().fg(theme.result)),
+        None if prompt.text().is_empty() => Span::raw(""),
+        None => Span::styled(
+            format!("{}no match", theme.error_symbol()),
+            Style::default().fg(theme.error),
+        ),
+    };
+    render_prompt(
+        prompt,
+        vec![Span::styled("': ", Style::default().dim()), match_span],
+        theme,
+    )
+}
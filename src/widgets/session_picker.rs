@@ -0,0 +1,42 @@
+use ratatui::{
+    style::Style,
+    text::Line,
+    widgets::{List, ListItem, Padding},
+};
+
+use crate::theme::Theme;
+
+/// List of saved sessions, marking the one in use.
+pub fn render_session_picker<'a>(sessions: &[String], current: &str, theme: &Theme) -> List<'a> {
+    let items: Vec<ListItem> = sessions
+        .iter()
+        .map(|session| {
+            if session == current {
+                ListItem::new(Line::styled(
+                    format!("{} (current)", session),
+                    Style::default().fg(theme.accent).bold(),
+                ))
+            } else {
+                ListItem::new(session.clone())
+            }
+        })
+        .collect();
+
+    let block = theme
+        .popup_block()
+        .border_style(Style::default().fg(theme.accent_focused))
+        .padding(Padding::new(1, 1, 0, 0))
+        .title_style(Style::default().fg(theme.accent).bold())
+        .title("Sessions")
+        .title_bottom(Line::from(" Enter: switch • Esc: close ").right_aligned());
+
+    List::new(items)
+        .highlight_style(
+            Style::default()
+                .bg(theme.selection)
+                .add_modifier(theme.highlight)
+                .bold(),
+        )
+        .highlight_symbol(theme.highlight_symbol())
+        .block(block)
+}
@@ -0,0 +1,41 @@
+use ratatui::{
+    style::Style,
+    text::{Line, Span},
+    widgets::{List, ListItem, Padding},
+};
+
+use crate::{snippets::Snippet, theme::Theme};
+
+/// List of the snippets in the snippets file, each with its template.
+pub fn render_snippet_picker<'a>(snippets: &[Snippet], theme: &Theme) -> List<'a> {
+    let items: Vec<ListItem> = snippets
+        .iter()
+        .map(|snippet| {
+            ListItem::new(Line::from(vec![
+                Span::raw(snippet.name.clone()),
+                Span::styled(
+                    format!("  {}", snippet.template),
+                    Style::default().fg(theme.muted),
+                ),
+            ]))
+        })
+        .collect();
+
+    let block = theme
+        .popup_block()
+        .border_style(Style::default().fg(theme.accent_focused))
+        .padding(Padding::new(1, 1, 0, 0))
+        .title_style(Style::default().fg(theme.accent).bold())
+        .title("Snippets")
+        .title_bottom(Line::from(" Enter: insert • Esc: close ").right_aligned());
+
+    List::new(items)
+        .highlight_style(
+            Style::default()
+                .bg(theme.selection)
+                .add_modifier(theme.highlight)
+                .bold(),
+        )
+        .highlight_symbol(theme.highlight_symbol())
+        .block(block)
+}
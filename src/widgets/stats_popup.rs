@@ -0,0 +1,53 @@
+use ratatui::{
+    style::Style,
+    text::{Line, Span},
+    widgets::{Padding, Paragraph},
+};
+
+use crate::{format::format_value, stats::Summary, theme::Theme};
+
+/// Statistics over the results in History, opened with `:stats`, or over the
+/// imported list named `list`.
+pub fn render_stats_popup<'a>(
+    summary: Option<&Summary>,
+    list: Option<&str>,
+    precision: Option<usize>,
+    theme: &Theme,
+) -> Paragraph<'a> {
+    let label = |text: &str| Span::styled(format!("{:<10}", text), Style::default().dim());
+    let value = |value: f64| Span::styled(format_value(value, precision), Style::default().bold());
+
+    let lines = match summary {
+        Some(summary) => vec![
+            Line::from(vec![label("Count"), Span::raw(summary.count.to_string())]),
+            Line::from(vec![label("Sum"), value(summary.sum)]),
+            Line::from(vec![label("Mean"), value(summary.mean)]),
+            Line::from(vec![label("Min"), value(summary.min)]),
+            Line::from(vec![label("Max"), value(summary.max)]),
+            Line::from(vec![
+                label("Std dev"),
+                summary.stddev.map_or(Span::raw("—"), value),
+            ]),
+        ],
+        None => vec![Line::styled(
+            match list {
+                Some(name) => format!("No values in {}", name),
+                None => "No results in History yet".to_string(),
+            },
+            Style::default().fg(theme.muted),
+        )],
+    };
+
+    let block = theme
+        .popup_block()
+        .border_style(Style::default().fg(theme.accent_focused))
+        .padding(Padding::new(1, 1, 0, 0))
+        .title_style(Style::default().fg(theme.accent).bold())
+        .title(match list {
+            Some(name) => format!("Statistics of {}", name),
+            None => "Statistics".to_string(),
+        })
+        .title_bottom(Line::from(" Esc: close ").right_aligned());
+
+    Paragraph::new(lines).block(block)
+}
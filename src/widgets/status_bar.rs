@@ -0,0 +1,65 @@
+use ratatui::{
+    style::Style,
+    text::{Line, Span},
+    widgets::Paragraph,
+};
+
+use crate::{
+    input_editor::InputEditMode,
+    theme::Theme,
+    types::{Focus, Settings},
+    widgets::help_message::mode_label,
+};
+
+pub fn render_status_bar<'a>(
+    focus: Focus,
+    input_edit_mode: InputEditMode,
+    settings: &Settings,
+    session: &str,
+    unsaved: bool,
+    computing: bool,
+    theme: &Theme,
+) -> Paragraph<'a> {
+    let separator = || {
+        let bar = if theme.plain { " | " } else { " │ " };
+        Span::styled(bar, Style::default().fg(theme.muted))
+    };
+    let precision = match settings.precision {
+        Some(decimals) => format!("{} dp", decimals),
+        None => "auto".to_string(),
+    };
+
+    let mut spans = vec![
+        Span::styled(
+            format!(" {} ", mode_label(focus, input_edit_mode)),
+            Style::default()
+                .bg(theme.mode)
+                .fg(theme.on_highlight)
+                .add_modifier(theme.highlight)
+                .bold(),
+        ),
+        separator(),
+        Span::raw(settings.angle_mode.to_string()),
+        separator(),
+        Span::raw(settings.power.to_string()),
+        separator(),
+        Span::raw(precision),
+        separator(),
+        Span::styled(session.to_string(), Style::default().fg(theme.accent)),
+    ];
+    if computing {
+        spans.push(separator());
+        spans.push(Span::styled(
+            "computing… (Esc to cancel)",
+            Style::default().fg(theme.pending),
+        ));
+    }
+    if unsaved {
+        spans.push(Span::styled(
+            if theme.plain { " [UNSAVED]" } else { " ●" },
+            Style::default().fg(theme.unsaved).bold(),
+        ));
+    }
+
+    Paragraph::new(Line::from(spans)).style(Style::default().bg(theme.status_bg))
+}
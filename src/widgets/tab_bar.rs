@@ -0,0 +1,34 @@
+use ratatui::{
+    style::Style,
+    text::{Line, Span},
+    widgets::Paragraph,
+};
+
+use crate::theme::Theme;
+
+/// The workspace tabs, numbered from 1 with the shown one highlighted, drawn at
+/// the right of the status bar: ` 1  2  3 `.
+pub fn render_tab_bar<'a>(active: usize, count: usize, theme: &Theme) -> Paragraph<'a> {
+    let spans: Vec<Span> = (0..count)
+        .map(|idx| {
+            if idx == active {
+                let label = if theme.plain {
+                    format!("[{}]", idx + 1)
+                } else {
+                    format!(" {} ", idx + 1)
+                };
+                Span::styled(
+                    label,
+                    Style::default()
+                        .bg(theme.accent)
+                        .fg(theme.on_highlight)
+                        .add_modifier(theme.highlight)
+                        .bold(),
+                )
+            } else {
+                Span::styled(format!(" {} ", idx + 1), Style::default().fg(theme.muted))
+            }
+        })
+        .collect();
+    Paragraph::new(Line::from(spans)).right_aligned()
+}
@@ -0,0 +1,11 @@
+use ratatui::{style::Style, widgets::Paragraph};
+
+use crate::theme::Theme;
+
+pub fn render_toast<'a>(message: &str, theme: &Theme) -> Paragraph<'a> {
+    Paragraph::new(message.to_string()).block(
+        theme
+            .popup_block()
+            .border_style(Style::default().fg(theme.accent_focused)),
+    )
+}
@@ -1,48 +1,89 @@
 use std::collections::HashMap;
 
 use ratatui::{
-    style::{Color, Style},
+    style::Style,
     text::{Line, Span},
-    widgets::{Block, BorderType, List, ListItem, Padding},
+    widgets::{List, ListItem, Padding},
 };
 
-use crate::types::{Focus, VariableEntry};
+use crate::{
+    format::format_tagged,
+    inspect::DependencyGraph,
+    theme::Theme,
+    types::{Focus, VariableEntry, VariableSort},
+};
 
+/// Lists `variables` in the order of `names`.
 pub fn render_variable_block<'a>(
     variables: &HashMap<String, VariableEntry>,
+    names: &[String],
+    sort: VariableSort,
     focus: Focus,
+    dependencies: Option<&DependencyGraph>,
+    precision: Option<usize>,
+    theme: &Theme,
 ) -> List<'a> {
-    let mut sorted_variables: Vec<(&String, &VariableEntry)> = variables.iter().collect();
-    sorted_variables.sort_by(|(ka, _), (kb, _)| ka.cmp(kb));
-
-    let variable_items: Vec<ListItem> = sorted_variables
-        .into_iter()
+    let variable_items: Vec<ListItem> = names
+        .iter()
+        .filter_map(|name| variables.get_key_value(name))
         .map(|(k, v)| {
-            let content = Line::from(vec![
+            let mut content = Line::from(vec![
                 Span::styled(format!("{} = ", k), Style::default().bold()),
-                Span::styled(v.value.to_string(), Style::default().bold().green()),
+                Span::styled(
+                    format_tagged(v.value, v.format, precision),
+                    Style::default().bold().fg(theme.result),
+                ),
             ]);
+            if let Some(unit) = &v.unit {
+                content.push_span(Span::styled(
+                    format!(" {}", unit),
+                    Style::default().fg(theme.result),
+                ));
+            }
+            if let Some(graph) = dependencies {
+                let uses: Vec<&str> = graph.references_of(k).collect();
+                let used_by: Vec<&str> = graph.dependents_of(k).collect();
+                if !uses.is_empty() {
+                    content.push_span(Span::styled(
+                        format!("  uses {}", uses.join(", ")),
+                        Style::default().dim(),
+                    ));
+                }
+                if !used_by.is_empty() {
+                    content.push_span(Span::styled(
+                        format!("  used by {}", used_by.join(", ")),
+                        Style::default().dim().italic(),
+                    ));
+                }
+                if graph.is_cyclic(k) {
+                    content.push_span(Span::styled("  circular", Style::default().fg(theme.error)));
+                }
+            }
             ListItem::new(content)
         })
         .collect();
 
     let variables_focused = matches!(focus, Focus::Variables);
-    let block = Block::bordered()
-        .border_type(if variables_focused {
-            BorderType::Thick
-        } else {
-            BorderType::Rounded
-        })
+    let block = theme
+        .pane_block(variables_focused)
         .border_style(Style::default().fg(if variables_focused {
-            Color::LightYellow
+            theme.secondary_focused
         } else {
-            Color::Yellow
+            theme.secondary
         }))
         .padding(Padding::new(1, 1, 0, 0))
-        .title_style(Style::default().fg(Color::Yellow).bold())
-        .title("Variables");
+        .title_style(Style::default().fg(theme.secondary).bold())
+        .title(match sort {
+            VariableSort::Alphabetical => "Variables".to_string(),
+            sort => format!("Variables ({})", sort.name()),
+        });
     List::new(variable_items)
-        .highlight_style(Style::default().bg(Color::DarkGray).bold())
-        .highlight_symbol("› ")
+        .highlight_style(
+            Style::default()
+                .bg(theme.selection)
+                .add_modifier(theme.highlight)
+                .bold(),
+        )
+        .highlight_symbol(theme.highlight_symbol())
         .block(block)
 }
@@ -0,0 +1,81 @@
+use std::collections::HashMap;
+
+use ratatui::{
+    style::Style,
+    widgets::{Bar, BarChart, Block, BorderType, Padding},
+};
+
+use crate::{
+    format::format_value,
+    theme::Theme,
+    types::{Focus, VariableEntry},
+};
+
+/// Resolution of the bars; values are scaled so the largest magnitude fills the pane.
+const BAR_SCALE: f64 = 1000.0;
+
+/// Horizontal bars of the variables' values, sorted by name. Negative values are
+/// drawn by magnitude in the error color, and values that are not finite are
+/// marked with the error symbol.
+pub fn render_variable_chart<'a>(
+    variables: &HashMap<String, VariableEntry>,
+    focus: Focus,
+    precision: Option<usize>,
+    theme: &Theme,
+) -> BarChart<'a> {
+    let mut sorted_variables: Vec<(&String, &VariableEntry)> = variables.iter().collect();
+    sorted_variables.sort_by_key(|(k, _)| *k);
+
+    let largest = sorted_variables
+        .iter()
+        .map(|(_, v)| v.value.abs())
+        .filter(|value| value.is_finite())
+        .fold(0.0, f64::max);
+
+    let bars: Vec<Bar> = sorted_variables
+        .into_iter()
+        .map(|(k, v)| {
+            let scaled = if largest > 0.0 && v.value.is_finite() {
+                (v.value.abs() / largest * BAR_SCALE).round() as u64
+            } else {
+                0
+            };
+            Bar::default()
+                .label(k.clone())
+                .value(scaled)
+                .text_value(if v.value.is_finite() {
+                    format_value(v.value, precision)
+                } else {
+                    format!(
+                        "{}{}",
+                        theme.error_symbol(),
+                        format_value(v.value, precision)
+                    )
+                })
+                .style(
+                    Style::default().fg(if v.value < 0.0 || !v.value.is_finite() {
+                        theme.error
+                    } else {
+                        theme.plot_points
+                    }),
+                )
+        })
+        .collect();
+
+    BarChart::horizontal(bars)
+        .max(BAR_SCALE as u64)
+        .bar_width(1)
+        .bar_gap(0)
+        .value_style(Style::default().fg(theme.on_highlight).bold())
+        .block(
+            Block::bordered()
+                .title("Variables Chart")
+                .border_type(if matches!(focus, Focus::Plot) {
+                    BorderType::Thick
+                } else {
+                    BorderType::Rounded
+                })
+                .border_style(Style::default().fg(theme.plot_border))
+                .padding(Padding::uniform(1)),
+        )
+}
@@ -0,0 +1,44 @@
+use std::{
+    env,
+    ffi::OsString,
+    path::Path,
+    sync::{Mutex, MutexGuard, PoisonError},
+};
+
+/// Points HOME at a directory until dropped. Tests holding one take turns, so
+/// they never see each other's HOME.
+pub struct HomeGuard {
+    previous_home: Option<OsString>,
+    _lock: MutexGuard<'static, ()>,
+}
+
+impl HomeGuard {
+    pub fn set(home: &Path) -> Self {
+        static ENV_LOCK: Mutex<()> = Mutex::new(());
+        // A failed test only poisons the lock; HOME is restored all the same.
+        let lock = ENV_LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+        let previous_home = env::var_os("HOME");
+
+        unsafe {
+            env::set_var("HOME", home);
+        }
+
+        Self {
+            previous_home,
+            _lock: lock,
+        }
+    }
+}
+
+impl Drop for HomeGuard {
+    fn drop(&mut self) {
+        match self.previous_home.take() {
+            Some(old) => unsafe {
+                env::set_var("HOME", old);
+            },
+            None => unsafe {
+                env::remove_var("HOME");
+            },
+        }
+    }
+}
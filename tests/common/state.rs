@@ -1,6 +1,6 @@
 use std::collections::HashMap;
 
-use rustic_calc::types::{AppState, History, VariableEntry};
+use rustic_calc::types::{AppState, History, HistoryKind, VariableEntry};
 
 pub fn sample_state() -> AppState {
     let mut variables = HashMap::new();
@@ -9,6 +9,8 @@ pub fn sample_state() -> AppState {
         VariableEntry {
             expression: "2+3".to_string(),
             value: 5.0,
+            format: None,
+            unit: None,
         },
     );
 
@@ -17,8 +19,25 @@ pub fn sample_state() -> AppState {
             expression: "1+1".to_string(),
             result: Some(2.0),
             error: None,
+            note: None,
+            variables: Default::default(),
+            kind: HistoryKind::Expression,
+            timestamp: None,
+            duration_ms: None,
         }],
         variables,
         plot_data: Some(vec![(0.0, 1.0), (1.0, 2.0)]),
+        layout: Default::default(),
+        plot_style: Default::default(),
+        plot: None,
+        plot_range: None,
+        plots: Vec::new(),
+        settings: Default::default(),
+        functions: Vec::new(),
+        tape_total: None,
+        lists: Default::default(),
+        workspaces: Vec::new(),
+        workspace: 0,
+        workspace_id: 0,
     }
 }
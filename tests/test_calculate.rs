@@ -1,9 +1,12 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, time::Instant};
 
 use approx::assert_relative_eq;
-use rustic_calc::calculate::calculate;
+use rustic_calc::calculate::{
+    EvalLimits, MAX_EVAL_DEPTH, calculate, calculate_with_angle_mode, calculate_with_limits,
+};
+use rustic_calc::error::CalcError;
 use rustic_calc::tokenize::tokenize;
-use rustic_calc::types::VariableEntry;
+use rustic_calc::types::{AngleMode, VariableEntry};
 
 #[test]
 fn test_multiply() {
@@ -88,6 +91,8 @@ fn test_parenthesized_expression_with_variable() {
             VariableEntry {
                 expression: "a=5".to_string(),
                 value: 10.0,
+                format: None,
+                unit: None,
             },
         )]),
     )
@@ -120,7 +125,101 @@ fn test_error_handling() {
     match res {
         Ok(_) => panic!("no way"),
         Err(err) => {
-            assert_eq!(err, "Unknown variable: a")
+            assert_eq!(err, CalcError::UnknownVariable("a".to_string()));
+            assert_eq!(err.to_string(), "Unknown variable: a");
         }
     }
 }
+
+#[test]
+fn history_references_are_an_error_without_history() {
+    assert_eq!(
+        calculate(tokenize("#99"), &HashMap::new()),
+        Err(CalcError::HistoryUnavailable("#99".to_string()))
+    );
+    assert_eq!(
+        calculate(tokenize("#1+1"), &HashMap::new()),
+        Err(CalcError::HistoryUnavailable("#1".to_string()))
+    );
+}
+
+#[test]
+fn functions_and_constants() {
+    let res = calculate(tokenize("2sin(pi/2)"), &HashMap::new()).unwrap();
+    assert_relative_eq!(res, 2.0);
+
+    let res = calculate(tokenize("sqrt 16 + exp(0)"), &HashMap::new()).unwrap();
+    assert_relative_eq!(res, 5.0);
+
+    let res = calculate_with_angle_mode(tokenize("cos(180)"), &HashMap::new(), AngleMode::Degrees)
+        .unwrap();
+    assert_relative_eq!(res, -1.0);
+}
+
+#[test]
+fn evaluation_stops_at_limits() {
+    let steps = EvalLimits {
+        max_steps: 3,
+        ..EvalLimits::default()
+    };
+    let res = calculate_with_limits(
+        tokenize("1+2+3+4"),
+        &HashMap::new(),
+        AngleMode::Radians,
+        &steps,
+    );
+    assert_eq!(res, Err(CalcError::StepLimit(3)));
+
+    let expired = EvalLimits {
+        deadline: Some(Instant::now()),
+        ..EvalLimits::default()
+    };
+    let res = calculate_with_limits(
+        tokenize("1+2"),
+        &HashMap::new(),
+        AngleMode::Radians,
+        &expired,
+    );
+    assert_eq!(res, Err(CalcError::TimedOut));
+}
+
+#[test]
+fn deep_nesting_fails_instead_of_overflowing() {
+    let nested = |depth: usize| format!("{}1{}", "(".repeat(depth), ")".repeat(depth));
+    let res = calculate(tokenize(&nested(100)), &HashMap::new());
+    assert_eq!(res, Ok(1.0));
+
+    let res = calculate(tokenize(&nested(100_000)), &HashMap::new());
+    assert_eq!(res, Err(CalcError::TooDeep(MAX_EVAL_DEPTH)));
+    let res = calculate(tokenize(&"-".repeat(100_000)), &HashMap::new());
+    assert_eq!(res, Err(CalcError::TooDeep(MAX_EVAL_DEPTH)));
+}
+
+#[test]
+fn prelude_exposes_the_engine() {
+    use rustic_calc::prelude;
+
+    let parsed = prelude::parse_variables(prelude::tokenize("y=3*2")).unwrap();
+    assert_eq!(parsed.var_name, "y");
+    let res = prelude::calculate(parsed.tokens, &HashMap::new()).unwrap();
+    assert_relative_eq!(res, 6.0);
+}
+
+#[test]
+fn implicit_multiplication_after_closing_parenthesis() {
+    let variables = HashMap::from([(
+        "x".to_string(),
+        VariableEntry {
+            expression: "3".to_string(),
+            value: 3.0,
+            format: None,
+            unit: None,
+        },
+    )]);
+    assert_relative_eq!(calculate(tokenize("(2+3)4"), &variables).unwrap(), 20.0);
+    assert_relative_eq!(calculate(tokenize("(2)(3)"), &variables).unwrap(), 6.0);
+    assert_relative_eq!(
+        calculate(tokenize("2(x+1)(x-1)"), &variables).unwrap(),
+        16.0
+    );
+}
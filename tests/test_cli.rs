@@ -1,6 +1,16 @@
+use std::fs;
+
 use rustic_calc::{
-    io::{get_state_from_file, write_state_to_file},
+    io::{
+        append_input_history, get_state_from_file, read_input_history, save_session_state_merged,
+        write_state_file, write_state_to_file,
+    },
+    plot::{PlotDefinition, PlotRange},
     tui_app::App,
+    types::{
+        AngleMode, AppState, ConflictResolution, DEFAULT_SESSION, History, HistoryKind,
+        LayoutState, Settings, VariableEntry, Workspace,
+    },
 };
 
 #[path = "common/state.rs"]
@@ -38,6 +48,136 @@ fn write_and_read_state_round_trip() {
     });
 }
 
+#[test]
+fn compact_drops_repeats_old_entries_and_resampled_points() {
+    let mut state = sample_state();
+    let entry = state.history[0].clone();
+    state.history = vec![entry.clone(), entry.clone(), entry.clone()];
+    state.history[2].expression = "2+2".to_string();
+    state.history.insert(
+        0,
+        History {
+            note: Some("keep me".to_string()),
+            ..entry
+        },
+    );
+
+    let report = state.compact(2);
+    assert_eq!(report.history_entries, 2);
+    assert_eq!(
+        report.plot_points, 0,
+        "points of plots without a definition stay"
+    );
+    let expressions: Vec<&str> = state
+        .history
+        .iter()
+        .map(|h| h.expression.as_str())
+        .collect();
+    assert_eq!(expressions, ["1+1", "2+2"]);
+    assert!(state.history[0].note.is_some());
+
+    state.plot = Some(PlotDefinition::Polar {
+        expression: "1".to_string(),
+    });
+    assert_eq!(state.compact(2).plot_points, 2);
+    assert!(state.plot_data.is_none());
+}
+
+#[test]
+fn existing_toml_state_is_read_and_written_as_toml() {
+    let home = temp_home_dir("toml");
+
+    with_home(&home, || {
+        let state_dir = home.join(".config").join("rcalc");
+        fs::create_dir_all(&state_dir).expect("state directory should be creatable");
+        let toml_file = state_dir.join("state.toml");
+        write_state_file(&toml_file, &sample_state()).expect("TOML state should be writable");
+        let written = fs::read_to_string(&toml_file).expect("TOML state should exist");
+        assert!(
+            written.contains("[variables.x]"),
+            "unexpected TOML: {written}"
+        );
+
+        let loaded = get_state_from_file().expect("state.toml should be picked up");
+        assert_eq!(loaded.variables["x"].value, 5.0);
+        assert_eq!(loaded.history[0].result, Some(2.0));
+        assert_eq!(loaded.plot_data, Some(vec![(0.0, 1.0), (1.0, 2.0)]));
+
+        write_state_to_file(&loaded).expect("state should be writable");
+        assert!(!state_dir.join("state.json").exists());
+    });
+}
+
+#[test]
+fn history_notes_round_trip() {
+    let home = temp_home_dir("notes-roundtrip");
+
+    with_home(&home, || {
+        let mut state = sample_state();
+        state.history[0].note = Some("monthly budget".to_string());
+
+        write_state_to_file(&state).expect("write_state_to_file should succeed");
+        let loaded = get_state_from_file().expect("get_state_from_file should succeed");
+
+        assert_eq!(loaded.history[0].note.as_deref(), Some("monthly budget"));
+    });
+}
+
+#[test]
+fn history_entries_without_kind_load_as_expressions() {
+    let state: AppState = serde_json::from_str(
+        r#"{"history":[{"expression":"1+1","result":2.0,"error":null}],"variables":{},"plot_data":null}"#,
+    )
+    .expect("legacy state should deserialize");
+
+    assert_eq!(state.history[0].kind, HistoryKind::Expression);
+    assert!(state.history[0].note.is_none());
+    assert_eq!(state.layout, LayoutState::default());
+    assert_eq!(state.settings, Settings::default());
+}
+
+#[test]
+fn settings_survive_a_save_and_load() {
+    let home = temp_home_dir("settings");
+
+    with_home(&home, || {
+        let mut app = App::new();
+        for command in [":angle deg", ":precision 2", ":theme gruvbox"] {
+            app.input = command.to_string();
+            app.submit_message();
+        }
+
+        let state = get_state_from_file().expect("commands should save the state");
+        let restored = App::from(&state);
+
+        assert_eq!(restored.settings.angle_mode, AngleMode::Degrees);
+        assert_eq!(restored.settings.precision, Some(2));
+        assert_eq!(restored.settings.theme.as_deref(), Some("gruvbox"));
+    });
+}
+
+#[test]
+fn saved_plots_are_resampled_from_their_definition() {
+    let mut state: AppState = serde_json::from_str(
+        r#"{"history":[],"variables":{},"plot_data":[[0.0,0.0]],
+            "plot":{"function":{"expression":"x^2","variable":"x"}}}"#,
+    )
+    .expect("state with a plot definition should deserialize");
+    assert_eq!(state.plot_points().map(|points| points.len()), Some(21));
+
+    state.plot_range = Some(PlotRange {
+        from: 0.0,
+        to: 2.0,
+        samples: 3,
+    });
+    let json = serde_json::to_string(&state).expect("state should serialize");
+    let state: AppState = serde_json::from_str(&json).expect("state should round-trip");
+    assert_eq!(
+        state.plot_points(),
+        Some(vec![(0.0, 0.0), (1.0, 1.0), (2.0, 4.0)])
+    );
+}
+
 #[test]
 fn app_starts_from_saved_state_via_app_from() {
     let home = temp_home_dir("start-from-file");
@@ -74,3 +214,157 @@ fn app_submit_message_saves_state_to_file() {
         assert!(loaded.history[0].error.is_none());
     });
 }
+
+#[test]
+fn input_history_keeps_the_latest_lines() {
+    let home = temp_home_dir("input-history");
+
+    with_home(&home, || {
+        for line in ["1+1", ":angle deg", "x=2"] {
+            append_input_history(line).expect("append_input_history should succeed");
+        }
+
+        let lines = read_input_history(2).expect("read_input_history should succeed");
+        assert_eq!(lines, vec![":angle deg", "x=2"]);
+    });
+}
+
+fn variable(expression: &str, value: f64) -> VariableEntry {
+    VariableEntry {
+        expression: expression.to_string(),
+        value,
+        format: None,
+        unit: None,
+    }
+}
+
+#[test]
+fn concurrent_saves_keep_each_others_variables() {
+    let home = temp_home_dir("concurrent-save");
+
+    with_home(&home, || {
+        let base = sample_state();
+        write_state_to_file(&base).expect("initial state should be writable");
+
+        // Another instance started from the same file adds `y` and changes `x`.
+        let mut theirs = base.clone();
+        theirs.variables.insert("y".to_string(), variable("7", 7.0));
+        theirs.variables.insert("x".to_string(), variable("1", 1.0));
+        save_session_state_merged(DEFAULT_SESSION, theirs, &base)
+            .expect("first save should succeed");
+
+        let mut ours = base.clone();
+        ours.variables.insert("z".to_string(), variable("3", 3.0));
+        ours.history[0].timestamp = Some(10);
+        let merged = save_session_state_merged(DEFAULT_SESSION, ours, &base)
+            .expect("second save should succeed");
+
+        let loaded = get_state_from_file().expect("merged state should be readable");
+        let mut names: Vec<&String> = loaded.variables.keys().collect();
+        names.sort();
+        assert_eq!(names, ["x", "y", "z"]);
+        assert_eq!(loaded.variables["x"].value, 1.0);
+        assert_eq!(loaded.history.len(), 1);
+        assert_eq!(merged.variables.len(), 3);
+    });
+}
+
+#[test]
+fn merged_save_keeps_own_changes_and_removals() {
+    let mut base = sample_state();
+    base.variables.insert("y".to_string(), variable("7", 7.0));
+
+    let mut theirs = base.clone();
+    theirs.variables.insert("x".to_string(), variable("1", 1.0));
+    theirs.history.push(History {
+        expression: "2*2".to_string(),
+        result: Some(4.0),
+        error: None,
+        note: None,
+        variables: Default::default(),
+        kind: HistoryKind::Expression,
+        timestamp: Some(5),
+        duration_ms: None,
+    });
+
+    let mut ours = base.clone();
+    ours.variables.insert("x".to_string(), variable("9", 9.0));
+    ours.variables.remove("y");
+    ours.merge_saved(&base, &theirs);
+
+    assert_eq!(ours.variables["x"].value, 9.0);
+    assert!(!ours.variables.contains_key("y"));
+    assert_eq!(ours.history.len(), 2);
+    assert_eq!(ours.history[1].expression, "2*2");
+}
+
+#[test]
+fn merged_save_matches_tabs_by_id_not_position() {
+    let mut base = sample_state();
+    base.workspaces.push(Workspace {
+        id: 7,
+        ..Workspace::default()
+    });
+
+    // The other instance switched to the second tab, changed `x` in the first
+    // one before, and defined `y` in the second.
+    let mut theirs = base.clone();
+    theirs.variables.insert("x".to_string(), variable("1", 1.0));
+    let first = Workspace {
+        id: 0,
+        history: theirs.history.clone(),
+        variables: theirs.variables.clone(),
+        ..Workspace::default()
+    };
+    let mut second = theirs.workspaces.remove(0);
+    second.variables.insert("y".to_string(), variable("7", 7.0));
+    theirs.history = second.history;
+    theirs.variables = second.variables;
+    theirs.workspaces = vec![first];
+    theirs.workspace = 1;
+    theirs.workspace_id = 7;
+
+    let mut ours = base.clone();
+    ours.variables.insert("z".to_string(), variable("3", 3.0));
+    ours.merge_saved(&base, &theirs);
+
+    assert_eq!(ours.workspace, 0);
+    assert_eq!(ours.variables["x"].value, 1.0);
+    assert!(ours.variables.contains_key("z") && !ours.variables.contains_key("y"));
+    assert_eq!(ours.workspaces.len(), 1);
+    assert_eq!(ours.workspaces[0].id, 7);
+    assert_eq!(ours.workspaces[0].variables["y"].value, 7.0);
+}
+
+#[test]
+fn merged_save_keeps_tabs_the_other_instance_opened() {
+    let base = sample_state();
+    let mut theirs = base.clone();
+    let mut opened = Workspace {
+        id: 42,
+        ..Workspace::default()
+    };
+    opened.variables.insert("w".to_string(), variable("2", 2.0));
+    theirs.workspaces.push(opened.clone());
+
+    let mut ours = base.clone();
+    ours.merge_saved(&base, &theirs);
+
+    assert_eq!(ours.workspaces, [opened]);
+}
+
+#[test]
+fn merge_keeps_own_variables_on_conflict_by_default() {
+    let mut state = sample_state();
+    let mut other = sample_state();
+    other.variables.insert("x".to_string(), variable("4", 4.0));
+    other.variables.insert("y".to_string(), variable("7", 7.0));
+
+    let report = state.merge(&other, ConflictResolution::default());
+
+    assert_eq!(report.history_entries, 0);
+    assert_eq!(report.variables, 1);
+    assert_eq!(report.conflicts, ["x"]);
+    assert_eq!(state.variables["x"].value, 5.0);
+    assert_eq!(state.variables["y"].value, 7.0);
+}
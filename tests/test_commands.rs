@@ -0,0 +1,129 @@
+use rustic_calc::{
+    commands::{Command, parse_command},
+    plot::PlotDefinition,
+    types::{AngleMode, Associativity, Negation},
+};
+
+#[test]
+fn non_command_input_is_not_parsed() {
+    assert!(parse_command("1+2").is_none());
+}
+
+#[test]
+fn parse_note_command() {
+    let command = parse_command(":note 3 monthly budget").unwrap().unwrap();
+    assert_eq!(
+        command,
+        Command::Note {
+            entry: 3,
+            text: Some("monthly budget".to_string()),
+        }
+    );
+
+    let command = parse_command(":note #2").unwrap().unwrap();
+    assert_eq!(
+        command,
+        Command::Note {
+            entry: 2,
+            text: None
+        }
+    );
+}
+
+#[test]
+fn invalid_commands_report_errors() {
+    assert_eq!(
+        parse_command(":note budget").unwrap(),
+        Err("Usage: :note <entry> <label>".to_string())
+    );
+    assert_eq!(
+        parse_command(":nope").unwrap(),
+        Err("Unknown command: nope".to_string())
+    );
+}
+
+#[test]
+fn parse_settings_commands() {
+    assert_eq!(
+        parse_command(":angle deg"),
+        Some(Ok(Command::Angle(AngleMode::Degrees)))
+    );
+    assert_eq!(
+        parse_command(":precision 2"),
+        Some(Ok(Command::Precision(Some(2))))
+    );
+    assert_eq!(
+        parse_command(":precision auto"),
+        Some(Ok(Command::Precision(None)))
+    );
+    assert_eq!(
+        parse_command(":timeout 2.5"),
+        Some(Ok(Command::Timeout(Some(2500))))
+    );
+    assert_eq!(
+        parse_command(":timeout default"),
+        Some(Ok(Command::Timeout(None)))
+    );
+    assert!(matches!(parse_command(":timeout 0"), Some(Err(_))));
+    assert!(matches!(parse_command(":angle grad"), Some(Err(_))));
+    assert_eq!(
+        parse_command(":negation base"),
+        Some(Ok(Command::Negation(Negation::Base)))
+    );
+    assert_eq!(
+        parse_command(":associativity left"),
+        Some(Ok(Command::Associativity(Associativity::Left)))
+    );
+}
+
+#[test]
+fn parse_plot_commands() {
+    assert_eq!(
+        parse_command("plot polar r=1+cos(t)"),
+        Some(Ok(Command::Plot(PlotDefinition::Polar {
+            expression: "r=1+cos(t)".to_string()
+        })))
+    );
+    assert_eq!(
+        parse_command(":plot param (cos(t), sin(2t)) for t in 0..pi"),
+        Some(Ok(Command::Plot(PlotDefinition::Parametric {
+            x: "cos(t)".to_string(),
+            y: "sin(2t)".to_string(),
+            from: "0".to_string(),
+            to: "pi".to_string(),
+        })))
+    );
+    assert!(matches!(parse_command(":plot param cos(t)"), Some(Err(_))));
+}
+
+#[test]
+fn parse_import_csv() {
+    assert_eq!(
+        parse_command(":import csv data/prices.csv column=2 as prices"),
+        Some(Ok(Command::ImportCsv {
+            path: "data/prices.csv".to_string(),
+            column: 2,
+            name: "prices".to_string(),
+        }))
+    );
+    assert_eq!(
+        parse_command(":import csv data.csv as xs"),
+        Some(Ok(Command::ImportCsv {
+            path: "data.csv".to_string(),
+            column: 1,
+            name: "xs".to_string(),
+        }))
+    );
+    assert!(matches!(
+        parse_command(":import csv data.csv column=0 as xs"),
+        Some(Err(_))
+    ));
+    assert!(matches!(
+        parse_command(":import csv data.csv"),
+        Some(Err(_))
+    ));
+    assert_eq!(
+        parse_command(":stats xs"),
+        Some(Ok(Command::Stats(Some("xs".to_string()))))
+    );
+}
@@ -0,0 +1,32 @@
+use rustic_calc::csv_import::read_csv_column;
+
+#[test]
+fn reads_a_column_skipping_the_header_and_empty_cells() {
+    let data = "item,price\napple, 1.5\npear,\n\"plum\",\"2\"\n\nfig,0.25\n";
+    assert_eq!(read_csv_column(data, 2), Ok(vec![1.5, 2.0, 0.25]));
+}
+
+#[test]
+fn quoted_cells_may_hold_commas_quotes_and_line_breaks() {
+    let data =
+        "name,price,stock\n\"Smith, J.\",3.5,4\n\"a \"\"big\"\"\nbox\",2,1\nlid,\"7\",\"8\"\n";
+    assert_eq!(read_csv_column(data, 2), Ok(vec![3.5, 2.0, 7.0]));
+    assert_eq!(read_csv_column(data, 3), Ok(vec![4.0, 1.0, 8.0]));
+    assert_eq!(
+        read_csv_column("x\n\"1,5\"\n", 1),
+        Err("Line 2: '1,5' is not a number".to_string())
+    );
+}
+
+#[test]
+fn reports_cells_that_are_not_numbers() {
+    assert_eq!(
+        read_csv_column("1\n2\nthree\n", 1),
+        Err("Line 3: 'three' is not a number".to_string())
+    );
+    assert_eq!(
+        read_csv_column("1,2\n3\n", 2),
+        Err("Line 2 has no column 2".to_string())
+    );
+    assert!(read_csv_column("1\n", 0).is_err());
+}
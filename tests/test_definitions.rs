@@ -0,0 +1,45 @@
+use rustic_calc::{
+    definitions::{FunctionDefinition, parse_definition},
+    error::CalcError,
+};
+
+#[test]
+fn parse_definition_reads_name_params_and_body() {
+    let definition = parse_definition("f(x) = x^2+1  # parabola")
+        .expect("should be a definition")
+        .expect("should be valid");
+    assert_eq!(
+        definition,
+        FunctionDefinition {
+            name: "f".to_string(),
+            params: vec!["x".to_string()],
+            body: "x^2+1".to_string(),
+        }
+    );
+    assert_eq!(definition.to_string(), "f(x) = x^2+1");
+
+    let definition = parse_definition("g(x, y) = x*y").unwrap().unwrap();
+    assert_eq!(definition.params, ["x", "y"]);
+}
+
+#[test]
+fn parse_definition_leaves_assignments_and_rejects_bad_params() {
+    assert!(parse_definition("x = 2").is_none());
+    assert!(parse_definition("2(x) = 1").is_none());
+    assert_eq!(
+        parse_definition("f(x, x) = x").unwrap(),
+        Err(CalcError::DuplicateParameter("x".to_string()))
+    );
+}
+
+#[test]
+fn parse_definition_rejects_builtin_names() {
+    assert_eq!(
+        parse_definition("sin(x) = x").unwrap(),
+        Err(CalcError::ReservedName("sin".to_string()))
+    );
+    assert_eq!(
+        parse_definition("f(e) = e").unwrap(),
+        Err(CalcError::ReservedName("e".to_string()))
+    );
+}
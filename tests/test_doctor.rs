@@ -0,0 +1,93 @@
+use rustic_calc::{
+    doctor::{Problem, check_state},
+    io::StateFormat,
+};
+
+const DAMAGED: &str = r#"{
+    "history": [{"expression": "1+1", "result": 2.0, "error": null}, {"result": 3.0}],
+    "variables": {
+        "a": {"expression": "2", "value": 2.0},
+        "b": {"expression": "a*0/0", "value": null},
+        "c": {"expression": "a+d", "value": 5.0},
+        "e": {"expression": "(a", "value": 1.0},
+        "f": {"value": 1.0}
+    },
+    "plot_data": null,
+    "version": 9
+}"#;
+
+#[test]
+fn check_state_reads_around_invalid_entries() {
+    let checkup = check_state(DAMAGED, StateFormat::Json).expect("file should be readable");
+
+    assert_eq!(checkup.state.history.len(), 1);
+    assert_eq!(checkup.state.variables.len(), 4);
+    let problems: Vec<String> = checkup.problems.iter().map(ToString::to_string).collect();
+    assert_eq!(problems[0], "unknown field 'version'");
+    assert!(problems[1].starts_with("History entry 2 is invalid: missing field `expression`"));
+    assert!(problems[2].starts_with("variable f is invalid"));
+    assert_eq!(
+        checkup.problems[3..],
+        [
+            Problem::NonFiniteValue("b".to_string()),
+            Problem::DanglingReference {
+                variable: "c".to_string(),
+                missing: vec!["d".to_string()],
+            },
+            Problem::NotReevaluable {
+                variable: "e".to_string(),
+                error: "'(' at position 1 is never closed".to_string(),
+            },
+        ]
+    );
+}
+
+#[test]
+fn fix_freezes_broken_expressions_and_drops_unrecoverable_values() {
+    let checkup = check_state(DAMAGED, StateFormat::Json).expect("file should be readable");
+    let state = checkup.fix();
+
+    assert!(!state.variables.contains_key("b"));
+    assert_eq!(state.variables["c"].expression, "5");
+    assert_eq!(state.variables["e"].expression, "1");
+    assert_eq!(state.variables["a"].expression, "2");
+
+    let recheck = check_state(
+        &StateFormat::Json
+            .serialize(&state)
+            .expect("state should serialize"),
+        StateFormat::Json,
+    )
+    .expect("fixed state should be readable");
+    assert!(recheck.problems.is_empty(), "{:?}", recheck.problems);
+}
+
+/// Variables as the TUI saves them: the whole assignment, with user functions.
+const FROM_TUI: &str = r#"{
+    "history": [],
+    "variables": {
+        "a": {"expression": "a=2", "value": 2.0},
+        "b": {"expression": "b=a*3", "value": 6.0},
+        "y": {"expression": "y=f(2)", "value": 5.0},
+        "z": {"expression": "z=a+d", "value": 4.0}
+    },
+    "plot_data": null,
+    "functions": [{"name": "f", "params": ["x"], "body": "x^2+1"}]
+}"#;
+
+#[test]
+fn assignments_and_user_functions_are_checked_like_the_tui_evaluates_them() {
+    let checkup = check_state(FROM_TUI, StateFormat::Json).expect("file should be readable");
+    assert_eq!(
+        checkup.problems,
+        [Problem::DanglingReference {
+            variable: "z".to_string(),
+            missing: vec!["d".to_string()],
+        }]
+    );
+
+    let state = checkup.fix();
+    assert_eq!(state.variables["b"].expression, "b=a*3");
+    assert_eq!(state.variables["y"].expression, "y=f(2)");
+    assert_eq!(state.variables["z"].expression, "z=4");
+}
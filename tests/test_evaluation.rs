@@ -0,0 +1,27 @@
+use std::time::Duration;
+
+use rustic_calc::{error::CalcError, evaluation::Evaluation, evaluator::Evaluator};
+
+#[test]
+fn evaluates_on_a_worker_thread() {
+    let evaluation = Evaluation::spawn(
+        Evaluator::new(),
+        &["2", "*", "(", "3", "+", "4", ")"],
+        Duration::from_secs(5),
+    );
+    assert_eq!(evaluation.wait(Duration::from_secs(5)), Some(Ok(14.0)));
+}
+
+#[test]
+fn cancelled_worker_stops_early() {
+    // Long enough that the worker is still at it when cancelled.
+    let mut tokens = vec!["1"];
+    tokens.extend(["+", "1"].repeat(200_000));
+
+    let evaluation = Evaluation::spawn(Evaluator::new(), &tokens, Duration::from_secs(60));
+    evaluation.cancel();
+    assert_eq!(
+        evaluation.wait(Duration::from_secs(60)),
+        Some(Err(CalcError::Cancelled))
+    );
+}
@@ -0,0 +1,191 @@
+use approx::assert_relative_eq;
+use num_complex::Complex64;
+use num_rational::BigRational;
+use rust_decimal::Decimal;
+use rustic_calc::{
+    definitions::parse_definition,
+    error::CalcError,
+    evaluator::Evaluator,
+    types::{AngleMode, Associativity, Negation, PowerRules},
+};
+
+#[test]
+fn evaluator_keeps_its_own_variables_and_functions() {
+    let mut evaluator = Evaluator::new().with_function("double", |x| 2.0 * x);
+    assert_relative_eq!(evaluator.eval("r = 3").unwrap(), 3.0);
+    assert_relative_eq!(evaluator.eval("double(r) + 1").unwrap(), 7.0);
+    assert_relative_eq!(evaluator.eval("double r").unwrap(), 6.0);
+    assert_eq!(evaluator.variables()["r"].expression, "3");
+    assert_eq!(
+        evaluator.eval("q"),
+        Err(CalcError::UnknownVariable("q".to_string()))
+    );
+}
+
+#[test]
+fn assignment_names_are_checked_before_operands() {
+    let mut evaluator = Evaluator::new();
+    assert_eq!(
+        evaluator.eval("+=3"),
+        Err(CalcError::InvalidVariableName("+".to_string()))
+    );
+    assert_eq!(
+        evaluator.eval("x*=3"),
+        Err(CalcError::InvalidAssignmentTarget("x*".to_string()))
+    );
+    assert_eq!(
+        evaluator.eval("x=3+"),
+        Err(CalcError::MissingRightOperand {
+            operator: "+".to_string(),
+            position: 3,
+        })
+    );
+}
+
+#[test]
+fn registered_functions_take_several_arguments() {
+    let mut evaluator = Evaluator::new();
+    evaluator.register_fn("roi", 2, |args| (args[1] - args[0]) / args[0] * 100.0);
+    assert_relative_eq!(evaluator.eval("roi(200, 250)").unwrap(), 25.0);
+    assert_relative_eq!(evaluator.eval("2 roi(100, 1+2*50)").unwrap(), 2.0);
+    assert_eq!(
+        evaluator.parse("roi(1, 2+3)").unwrap().grouped(),
+        "roi(1, 2 + 3)"
+    );
+    assert_eq!(
+        evaluator.eval("roi(1)"),
+        Err(CalcError::ArgumentCount {
+            name: "roi".to_string(),
+            expected: 2,
+            found: 1
+        })
+    );
+    assert_eq!(
+        evaluator.eval("sqrt(4, 9)"),
+        Err(CalcError::ArgumentCount {
+            name: "sqrt".to_string(),
+            expected: 1,
+            found: 2
+        })
+    );
+    assert_eq!(
+        evaluator.eval("1, 2"),
+        Err(CalcError::UnexpectedToken(",".to_string()))
+    );
+}
+
+#[test]
+fn evaluator_applies_angle_mode_and_precision() {
+    let mut evaluator = Evaluator::new()
+        .with_angle_mode(AngleMode::Degrees)
+        .with_precision(Some(2));
+    assert_relative_eq!(evaluator.eval("sin(30)").unwrap(), 0.5);
+    assert_relative_eq!(evaluator.eval("2/3").unwrap(), 0.67);
+}
+
+#[test]
+fn evaluator_follows_power_rules() {
+    let mut evaluator = Evaluator::new();
+    assert_relative_eq!(evaluator.eval("-2^2").unwrap(), -4.0);
+    assert_relative_eq!(evaluator.eval("2^3^2").unwrap(), 512.0);
+
+    let mut evaluator = Evaluator::new().with_power_rules(PowerRules {
+        negation: Negation::Base,
+        associativity: Associativity::Left,
+    });
+    assert_relative_eq!(evaluator.eval("-2^2").unwrap(), 4.0);
+    assert_relative_eq!(evaluator.eval("2^3^2").unwrap(), 64.0);
+    assert_relative_eq!(evaluator.eval("1 - 2^2").unwrap(), -3.0);
+    assert_relative_eq!(evaluator.eval("2^-1^2").unwrap(), 0.25);
+}
+
+#[test]
+fn compiled_expression_evaluates_with_bindings() {
+    let mut evaluator = Evaluator::new();
+    evaluator.set_variable("a", 2.0);
+    let compiled = evaluator.compile("a x^2 + 1").unwrap();
+    assert_relative_eq!(compiled.eval_with(&[("x", 3.0)]).unwrap(), 19.0);
+    assert_relative_eq!(compiled.eval_with(&[("x", -1.0), ("a", 0.0)]).unwrap(), 1.0);
+    assert_eq!(
+        compiled.eval(),
+        Err(CalcError::UnknownVariable("x".to_string()))
+    );
+}
+
+#[test]
+fn evaluator_computes_in_other_number_types() {
+    let evaluator = Evaluator::new();
+    let decimal: Decimal = evaluator.eval_as("0.1 + 0.2").unwrap();
+    assert_eq!(decimal, Decimal::from_str_exact("0.3").unwrap());
+
+    let rational: BigRational = evaluator.eval_as("1/3 + 1/6").unwrap();
+    assert_eq!(rational, BigRational::new(1.into(), 2.into()));
+    assert_eq!(
+        evaluator.eval_as::<BigRational>("1/0"),
+        Err(CalcError::Undefined)
+    );
+    // Results too large to hold exactly are undefined, however they are written.
+    for input in ["1e3000000", "((10^1000)^1000)^1000"] {
+        assert_eq!(
+            evaluator.eval_as::<BigRational>(input),
+            Err(CalcError::Undefined)
+        );
+    }
+
+    let complex: Complex64 = evaluator.eval_as("sqrt(-4) + 2i*i").unwrap();
+    assert_relative_eq!(complex.re, -2.0);
+    assert_relative_eq!(complex.im, 2.0);
+}
+
+#[test]
+fn eval_trace_lists_reductions_innermost_first() {
+    let trace = Evaluator::new().eval_trace("x = 2(3+4)").unwrap();
+    assert_relative_eq!(trace.value, 14.0);
+    let expressions: Vec<&str> = trace
+        .steps
+        .iter()
+        .map(|step| step.expression.as_str())
+        .collect();
+    assert_eq!(expressions, ["3 + 4", "2*(3 + 4)"]);
+    assert_eq!(trace.steps[1].operands, [2.0, 7.0]);
+}
+
+#[test]
+fn defined_functions_can_be_called() {
+    let mut evaluator = Evaluator::new();
+    evaluator.eval("a = 2").unwrap();
+    let square = parse_definition("f(x) = x^2 + a").unwrap().unwrap();
+    evaluator.define_function(&square).unwrap();
+    assert_relative_eq!(evaluator.eval("f(3)").unwrap(), 11.0);
+    assert_relative_eq!(evaluator.eval("2f(1) + 1").unwrap(), 7.0);
+
+    let unknown = parse_definition("g(x) = x + q").unwrap().unwrap();
+    assert_eq!(
+        evaluator.define_function(&unknown),
+        Err(CalcError::UnknownVariable("q".to_string()))
+    );
+    let pair = parse_definition("h(x, y) = x + y").unwrap().unwrap();
+    assert_eq!(
+        evaluator.define_function(&pair),
+        Err(CalcError::TooManyParameters {
+            name: "h".to_string(),
+            count: 2
+        })
+    );
+}
+
+#[test]
+fn unary_minus_binds_looser_than_power() {
+    let mut evaluator = Evaluator::new();
+    evaluator.set_variable("x", 3.0);
+    assert_relative_eq!(evaluator.eval("-x^2").unwrap(), -9.0);
+    assert_relative_eq!(evaluator.eval("2^-3").unwrap(), 0.125);
+    assert!(evaluator.eval("(-2)^0.5").unwrap().is_nan());
+    assert_relative_eq!(evaluator.eval("-2^0.5").unwrap(), -(2f64.sqrt()));
+
+    let evaluator = evaluator.with_power_rules(PowerRules {
+        negation: Negation::Base,
+        ..PowerRules::default()
+    });
+    assert_eq!(evaluator.parse("-x^2").unwrap().grouped(), "(-x)^2");
+}
@@ -0,0 +1,44 @@
+use std::{env, fs, path::Path};
+
+use rustic_calc::{
+    export::{ExportFormat, export_plot, plot_to_csv, plot_to_svg},
+    types::PlotStyle,
+};
+
+const DATA: [(f64, f64); 3] = [(-1.0, 1.0), (0.0, 0.0), (1.0, 1.0)];
+
+#[test]
+fn format_follows_extension() {
+    assert_eq!(
+        ExportFormat::from_path(Path::new("out.CSV")),
+        Ok(ExportFormat::Csv)
+    );
+    assert_eq!(
+        ExportFormat::from_path(Path::new("plot.png")),
+        Ok(ExportFormat::Png)
+    );
+    assert!(ExportFormat::from_path(Path::new("plot.pdf")).is_err());
+}
+
+#[test]
+fn csv_and_svg_contain_points() {
+    assert_eq!(plot_to_csv(&DATA), "x,y\n-1,1\n0,0\n1,1\n");
+
+    let svg = plot_to_svg(&DATA, "x^2 & more", PlotStyle::Line);
+    assert!(svg.starts_with("<svg"));
+    assert!(svg.contains("<polyline"));
+    assert!(svg.contains("x^2 &amp; more"));
+}
+
+#[test]
+fn png_export_writes_valid_header() {
+    let path = env::temp_dir().join(format!("rcalc-export-{}.png", std::process::id()));
+    export_plot(&path, &DATA, "x^2", PlotStyle::Scatter).unwrap();
+
+    let bytes = fs::read(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(&bytes[..8], b"\x89PNG\r\n\x1a\n");
+    assert_eq!(&bytes[12..16], b"IHDR");
+    assert!(bytes.ends_with(&[0xae, 0x42, 0x60, 0x82])); // IEND checksum
+    assert!(bytes.len() < 20_000, "{} bytes", bytes.len());
+}
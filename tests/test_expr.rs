@@ -0,0 +1,38 @@
+use rustic_calc::expr::Expr;
+
+fn simplified(input: &str) -> String {
+    Expr::parse(input).unwrap().simplify().to_string()
+}
+
+#[test]
+fn simplify_folds_constants_and_drops_identities() {
+    assert_eq!(simplified("2*3 + 4"), "10");
+    assert_eq!(simplified("x*1 + 0"), "x");
+    assert_eq!(simplified("1*x/1"), "x");
+    assert_eq!(simplified("x^1 + y^0"), "x + 1");
+    assert_eq!(simplified("x + 1 + 2"), "x + 3");
+    assert_eq!(simplified("1/x"), "1/x");
+    assert_eq!(simplified("sin(0+x) * 2"), "2*sin(x)");
+}
+
+#[test]
+fn simplify_normalizes_signs() {
+    assert_eq!(simplified("a - -b"), "a + b");
+    assert_eq!(simplified("--x"), "x");
+    assert_eq!(simplified("-a*-b"), "a*b");
+    assert_eq!(simplified("x * -1"), "-x");
+    assert_eq!(simplified("-a + b"), "-a + b");
+    assert_eq!(simplified("0 - (a + b)"), "-(a + b)");
+    assert_eq!(simplified("(-2)^x"), "(-2)^x");
+}
+
+#[test]
+fn grouped_shows_how_signs_and_powers_bind() {
+    let grouped = |input: &str| Expr::parse(input).unwrap().grouped();
+    assert_eq!(grouped("-x^2"), "-(x^2)");
+    assert_eq!(grouped("2^-3"), "2^(-3)");
+    assert_eq!(grouped("(-2)^0.5"), "(-2)^0.5");
+    assert_eq!(grouped("2^3^2"), "2^(3^2)");
+    assert_eq!(grouped("1 + 2*3"), "1 + (2*3)");
+    assert_eq!(grouped("-sin(x)^2"), "-(sin(x)^2)");
+}
@@ -0,0 +1,78 @@
+use rustic_calc::{
+    evaluator::Evaluator,
+    format::{
+        approximate_fraction, format_auto, format_currency, format_fraction, format_full_precision,
+        format_hex, format_scientific, format_tagged, format_value,
+    },
+    types::ValueFormat,
+};
+
+#[test]
+fn full_precision_and_scientific_formats() {
+    assert_eq!(format_full_precision(0.1 + 0.2), "0.30000000000000004");
+    assert_eq!(format_full_precision(42.0), "42");
+    assert_eq!(format_scientific(1500.0), "1.5e3");
+}
+
+#[test]
+fn automatic_format_trims_noise_and_switches_to_scientific() {
+    assert_eq!(format_auto(0.1 + 0.2), "0.3");
+    assert_eq!(format_auto(1e300), "1e300");
+    assert_eq!(format_auto(0.000000001), "1e-9");
+    assert_eq!(format_auto(-1234.5), "-1234.5");
+    assert_eq!(format_auto(-0.0), "0");
+    assert_eq!(format_auto(f64::INFINITY), "inf");
+    assert_eq!(format_value(0.1 + 0.2, Some(3)), "0.300");
+}
+
+#[test]
+fn fixed_decimals_switch_to_scientific_at_the_same_magnitudes() {
+    assert_eq!(format_value(1e300, Some(2)), "1.00e300");
+    assert_eq!(format_value(-1.5e-9, Some(1)), "-1.5e-9");
+    assert_eq!(format_value(1234.5, Some(2)), "1234.50");
+    assert_eq!(format_value(0.0, Some(2)), "0.00");
+}
+
+#[test]
+fn automatic_format_reads_back_as_the_same_value() {
+    let mut evaluator = Evaluator::new();
+    for value in [1e20, 1e301, 1.5e-9, -2.5e-7, 12345.678, 0.3] {
+        let shown = format_auto(value);
+        assert_eq!(evaluator.eval(&shown), Ok(value), "{}", shown);
+    }
+    assert_eq!(evaluator.eval("2e"), Ok(2.0 * std::f64::consts::E));
+}
+
+#[test]
+fn hex_only_for_integral_values() {
+    assert_eq!(format_hex(255.0).as_deref(), Some("0xFF"));
+    assert_eq!(format_hex(-16.0).as_deref(), Some("-0x10"));
+    assert_eq!(format_hex(1.5), None);
+}
+
+#[test]
+fn fraction_approximation() {
+    assert_eq!(approximate_fraction(0.75), Some((3, 4)));
+    assert_eq!(approximate_fraction(-1.0 / 3.0), Some((-1, 3)));
+    assert_eq!(
+        format_fraction(std::f64::consts::PI).as_deref(),
+        Some("1146408/364913")
+    );
+    assert_eq!(format_fraction(4.0).as_deref(), Some("4"));
+}
+
+#[test]
+fn tagged_formats() {
+    assert_eq!(format_currency(1234567.891), "1,234,567.89");
+    assert_eq!(format_currency(-42.5), "-42.50");
+    assert_eq!(format_currency(999.999), "1,000.00");
+    assert_eq!(
+        format_tagged(0.125, Some(ValueFormat::Percent), None),
+        "12.5%"
+    );
+    assert_eq!(
+        format_tagged(1500.0, Some(ValueFormat::Scientific), Some(1)),
+        "1.5e3"
+    );
+    assert_eq!(format_tagged(2.5, None, None), "2.5");
+}
@@ -0,0 +1,28 @@
+use std::collections::HashMap;
+
+use proptest::prelude::*;
+use rustic_calc::{calculate::calculate, evaluator::fuzz_eval, tokenize::tokenize};
+
+proptest! {
+    #[test]
+    fn arbitrary_input_never_panics(data in proptest::collection::vec(any::<u8>(), 0..64)) {
+        fuzz_eval(&data);
+    }
+
+    #[test]
+    fn operator_soup_never_panics(input in "[0-9a-z+*/^()=. -]{0,64}") {
+        fuzz_eval(input.as_bytes());
+    }
+
+    #[test]
+    fn formatted_numbers_evaluate_to_themselves(value in -1e12f64..1e12) {
+        let res = calculate(tokenize(&value.to_string()), &HashMap::new()).unwrap();
+        prop_assert_eq!(res, value);
+    }
+}
+
+#[test]
+fn long_sums_do_not_overflow_the_stack() {
+    let input = format!("{}1", "1+".repeat(100_000));
+    assert_eq!(calculate(tokenize(&input), &HashMap::new()), Ok(100_001.0));
+}
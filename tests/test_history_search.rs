@@ -0,0 +1,54 @@
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use rustic_calc::{
+    history_search::{HistorySearch, search_matches},
+    keymap::default_keymap,
+};
+
+#[test]
+fn matches_are_newest_first_and_deduplicated() {
+    let history = ["1+2", "x*2", "1+2", "12/4"];
+    let matches = search_matches(history.iter().copied(), "1");
+    assert_eq!(matches, vec!["12/4", "1+2"]);
+}
+
+#[test]
+fn contiguous_matches_rank_before_fuzzy_matches() {
+    let history = ["12+3", "1+23"];
+    let matches = search_matches(history.iter().copied(), "12");
+    assert_eq!(matches, vec!["12+3", "1+23"]);
+}
+
+#[test]
+fn next_match_walks_older_entries_and_stops_at_oldest() {
+    let history = ["2*3", "2+2", "5-1"];
+    let mut search = HistorySearch::new();
+    search.push_char('2');
+    assert_eq!(search.current_match(history.iter().copied()), Some("2+2"));
+
+    search.next_match(history.iter().copied());
+    assert_eq!(search.current_match(history.iter().copied()), Some("2*3"));
+
+    search.next_match(history.iter().copied());
+    assert_eq!(search.current_match(history.iter().copied()), Some("2*3"));
+
+    search.push_char('+');
+    assert_eq!(search.current_match(history.iter().copied()), Some("2+2"));
+}
+
+#[test]
+fn query_is_edited_at_the_cursor() {
+    let history = ["12+3", "1+23"];
+    let mut search = HistorySearch::new();
+    search.push_char('1');
+    search.push_char('+');
+    search.edit(
+        KeyEvent::new(KeyCode::Left, KeyModifiers::NONE),
+        default_keymap(),
+    );
+    search.edit(
+        KeyEvent::new(KeyCode::Char('2'), KeyModifiers::NONE),
+        default_keymap(),
+    );
+    assert_eq!(search.query(), "12+");
+    assert_eq!(search.current_match(history.iter().copied()), Some("12+3"));
+}
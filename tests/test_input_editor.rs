@@ -200,3 +200,45 @@ fn normal_mode_word_motions_work() {
     app.handle_key_event(key(KeyCode::Char('b')));
     assert_eq!(app.character_index, 0);
 }
+
+#[test]
+fn editing_treats_grapheme_clusters_as_one_character() {
+    let mut ed = InputEditor::with_input("a👍🏽e\u{301}".to_string());
+    assert_eq!(ed.cursor(), 3);
+
+    ed.handle_key_event(key(KeyCode::Backspace));
+    assert_eq!(ed.input(), "a👍🏽");
+    ed.handle_key_event(key(KeyCode::Left));
+    ed.handle_key_event(key(KeyCode::Backspace));
+    assert_eq!(ed.input(), "👍🏽");
+
+    ed.handle_key_event(key(KeyCode::Right));
+    ed.handle_key_event(key(KeyCode::Char('e')));
+    ed.handle_key_event(key(KeyCode::Char('\u{301}')));
+    assert_eq!(ed.input(), "👍🏽e\u{301}");
+    assert_eq!(ed.cursor(), 2);
+}
+
+#[test]
+fn pasted_text_is_inserted_at_once_without_line_breaks() {
+    let mut ed = InputEditor::with_input("1+".to_string());
+    ed.insert_text("2*3\r\n");
+    assert_eq!(ed.input(), "1+2*3");
+    assert_eq!(ed.cursor(), 5);
+
+    ed.handle_key_event(key(KeyCode::Esc)); // -> Normal, cursor on '3'
+    ed.handle_key_event(key(KeyCode::Char('0')));
+    ed.insert_text("dd");
+    assert_eq!(ed.input(), "1dd+2*3");
+    assert_eq!(ed.cursor(), 2);
+}
+
+#[test]
+fn app_paste_in_normal_mode_does_not_run_bindings() {
+    let mut app = App::new();
+    app.handle_key_event(key(KeyCode::Esc));
+    app.handle_paste("x=1\n");
+    assert_eq!(app.input, "x=1");
+    assert_eq!(app.input_edit_mode, InputEditMode::Normal);
+    assert_eq!(app.focus, Focus::Input);
+}
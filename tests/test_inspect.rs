@@ -1,6 +1,29 @@
 use std::collections::HashMap;
 
-use rustic_calc::{inspect::inspect_unknown_variables, types::VariableEntry};
+#[path = "common/home_guard.rs"]
+mod home_guard;
+#[path = "common/temp_home.rs"]
+mod temp_home;
+
+use rustic_calc::{
+    error::CalcError,
+    inspect::{
+        dependency_graph, did_you_mean, inspect_unknown_variables, missing_operands,
+        sorted_variable_names, unmatched_parens,
+    },
+    tokenize::{Lexer, Token},
+    tui_app::App,
+    types::{VariableEntry, VariableSort},
+};
+
+use home_guard::HomeGuard;
+use temp_home::temp_home_dir;
+
+/// Points HOME at a fresh directory for the rest of the test, so saving state
+/// and input history never touches the real config directory.
+fn isolated_home() -> HomeGuard {
+    HomeGuard::set(&temp_home_dir("inspect"))
+}
 
 #[test]
 fn inspect_zero_unknown_variables() {
@@ -14,6 +37,8 @@ fn inspect_zero_unknown_variables() {
             VariableEntry {
                 expression: "a=1".to_string(),
                 value: 1.0,
+                format: None,
+                unit: None,
             },
         )]),
     );
@@ -41,3 +66,145 @@ fn inspect_phrase() {
         inspect_unknown_variables(&vec!["3", "*", "(", "2", "-", "5", ")"], &HashMap::new());
     assert!(unknown_variables.is_empty());
 }
+
+#[test]
+fn unmatched_parens_are_reported_with_positions() {
+    let _home = isolated_home();
+    let tokens: Vec<Token> = Lexer::new("(1 + 2)) * (3").collect();
+    assert_eq!(
+        unmatched_parens(&tokens),
+        vec![CalcError::UnmatchedParen(7), CalcError::UnclosedParen(11)]
+    );
+
+    let mut app = App::new();
+    app.input = "2*(3+4".to_string();
+    app.submit_message();
+    assert_eq!(
+        app.history[0].error.as_deref(),
+        Some("'(' at position 3 is never closed")
+    );
+}
+
+#[test]
+fn misspelled_names_get_suggestions() {
+    let _home = isolated_home();
+    let variables = HashMap::new();
+    assert_eq!(did_you_mean("sqr(4) + 2pj", &variables), ["sqrt", "pi"]);
+    assert!(did_you_mean("sqrt(4) + x", &variables).is_empty());
+
+    let mut app = App::new();
+    app.input = "sqr(4)".to_string();
+    app.submit_message();
+    assert_eq!(
+        app.history[0].error.as_deref(),
+        Some("Unknown variables: s, q, r — did you mean sqrt?")
+    );
+}
+
+#[test]
+fn dependency_graph_tracks_references_and_dependents() {
+    let variable = |expression: &str, value: f64| VariableEntry {
+        expression: expression.to_string(),
+        value,
+        format: None,
+        unit: None,
+    };
+    let variables = HashMap::from([
+        ("b".to_string(), variable("b=2", 2.0)),
+        ("a".to_string(), variable("a=b+5", 7.0)),
+        ("c".to_string(), variable("c=2a+b", 16.0)),
+    ]);
+
+    let graph = dependency_graph(&variables);
+
+    assert_eq!(graph.references_of("c").collect::<Vec<_>>(), vec!["a", "b"]);
+    assert_eq!(graph.references_of("b").count(), 0);
+    assert_eq!(graph.dependents_of("b").collect::<Vec<_>>(), vec!["a", "c"]);
+    assert_eq!(graph.dependents_of("c").count(), 0);
+    assert_eq!(graph.topological_order().unwrap(), ["b", "a", "c"]);
+    assert_eq!(graph.recompute_order("b").unwrap(), ["a", "c"]);
+}
+
+#[test]
+fn dependency_graph_detects_cycles() {
+    let variable = |expression: &str| VariableEntry {
+        expression: expression.to_string(),
+        value: 0.0,
+        format: None,
+        unit: None,
+    };
+    let variables = HashMap::from([
+        ("a".to_string(), variable("a=b+1")),
+        ("b".to_string(), variable("b=2a")),
+        ("c".to_string(), variable("c=a")),
+    ]);
+
+    let graph = dependency_graph(&variables);
+
+    assert_eq!(
+        graph.topological_order(),
+        Err(vec!["a".to_string(), "b".to_string(), "a".to_string()])
+    );
+    assert!(graph.recompute_order("c").unwrap().is_empty());
+    assert!(graph.recompute_order("a").is_err());
+    assert!(graph.is_cyclic("b"));
+    assert!(!graph.is_cyclic("c"));
+}
+
+#[test]
+fn recompute_reevaluates_dependents_in_order() {
+    let _home = isolated_home();
+    let mut app = App::new();
+    for input in ["a=2", "b=a+1", "c=2b", "a=10"] {
+        app.input = input.to_string();
+        app.submit_message();
+    }
+    assert_eq!(app.variables["c"].value, 6.0);
+
+    app.input = ":recompute a".to_string();
+    app.submit_message();
+    assert_eq!(app.variables["b"].value, 11.0);
+    assert_eq!(app.variables["c"].value, 22.0);
+}
+
+#[test]
+fn variables_sort_by_recent_use_or_definition() {
+    let _home = isolated_home();
+    let mut app = App::new();
+    for input in ["b=1", "a=2", "c=3", "b+1"] {
+        app.input = input.to_string();
+        app.submit_message();
+    }
+
+    let sorted = |sort| sorted_variable_names(&app.variables, &app.history, sort);
+    assert_eq!(sorted(VariableSort::Alphabetical), ["a", "b", "c"]);
+    assert_eq!(sorted(VariableSort::RecentlyUsed), ["b", "c", "a"]);
+    assert_eq!(sorted(VariableSort::RecentlyDefined), ["c", "a", "b"]);
+}
+
+#[test]
+fn missing_operands_are_reported_with_positions() {
+    let _home = isolated_home();
+    let tokens: Vec<Token> = Lexer::new("*3 + (2 -) * -1").collect();
+    assert_eq!(
+        missing_operands(&tokens),
+        vec![
+            CalcError::MissingLeftOperand {
+                operator: "*".to_string(),
+                position: 0,
+            },
+            CalcError::MissingRightOperand {
+                operator: "-".to_string(),
+                position: 8,
+            },
+        ]
+    );
+
+    let mut app = App::new();
+    app.input = "2+".to_string();
+    app.submit_message();
+    assert_eq!(
+        app.history[0].error.as_deref(),
+        Some("'+' at position 2 is missing its right operand")
+    );
+}
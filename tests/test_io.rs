@@ -68,3 +68,181 @@ fn rcalc_clear_command_is_available() {
         "help output should contain the clear command"
     );
 }
+
+#[test]
+fn rcalc_export_plot_by_name() {
+    let home = temp_home_dir("export-named");
+    let state_dir = home.join(".config").join("rcalc");
+    fs::create_dir_all(&state_dir).expect("state directory should be creatable");
+    fs::write(
+        state_dir.join("state.json"),
+        r#"{"history":[],"variables":{},"plot_data":null,
+            "plots":[{"name":"square","definition":{"function":{"expression":"x^2","variable":"x"}},
+                      "range":{"from":0.0,"to":2.0,"samples":3}}]}"#,
+    )
+    .expect("state file should be writable");
+    let out = home.join("square.csv");
+
+    let status = Command::new(env!("CARGO_BIN_EXE_rcalc"))
+        .args(["export", "plot"])
+        .arg(&out)
+        .args(["--name", "square"])
+        .env("HOME", &home)
+        .status()
+        .expect("should execute rcalc export plot");
+
+    assert!(status.success());
+    let csv = fs::read_to_string(&out).expect("export should write the file");
+    assert!(csv.contains("2,4"), "unexpected CSV: {csv}");
+}
+
+#[test]
+fn rcalc_merge_imports_history_and_variables() {
+    let home = temp_home_dir("merge");
+    let state_dir = home.join(".config").join("rcalc");
+    fs::create_dir_all(&state_dir).expect("state directory should be creatable");
+    fs::write(
+        state_dir.join("state.json"),
+        r#"{"history":[],"variables":{"x":{"expression":"1","value":1.0}},"plot_data":null}"#,
+    )
+    .expect("state file should be writable");
+    let other = home.join("laptop.json");
+    fs::write(
+        &other,
+        r#"{"history":[{"expression":"2*3","result":6.0,"error":null}],
+            "variables":{"x":{"expression":"2","value":2.0},"y":{"expression":"3","value":3.0}},
+            "plot_data":null}"#,
+    )
+    .expect("other state file should be writable");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_rcalc"))
+        .arg("merge")
+        .arg(&other)
+        .args(["--prefer", "theirs"])
+        .env("HOME", &home)
+        .output()
+        .expect("should execute rcalc merge");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("Imported 1 history entries and 1 new variables; conflicting: x"),
+        "unexpected output: {stdout}"
+    );
+    let state = fs::read_to_string(state_dir.join("state.json")).expect("state should exist");
+    assert!(
+        state.contains(r#""expression":"2*3""#),
+        "unexpected state: {state}"
+    );
+    assert!(
+        state.contains(r#""x":{"expression":"2""#),
+        "unexpected state: {state}"
+    );
+}
+
+#[test]
+fn rcalc_export_and_import_vars_as_toml() {
+    let home = temp_home_dir("export-vars");
+    let state_dir = home.join(".config").join("rcalc");
+    fs::create_dir_all(&state_dir).expect("state directory should be creatable");
+    fs::write(
+        state_dir.join("state.json"),
+        r#"{"history":[{"expression":"1+1","result":2.0,"error":null}],
+            "variables":{"k":{"expression":"205","value":205.0,"unit":"GPa"}},"plot_data":null}"#,
+    )
+    .expect("state file should be writable");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_rcalc"))
+        .args(["export", "vars", "--format", "toml"])
+        .env("HOME", &home)
+        .output()
+        .expect("should execute rcalc export vars");
+    assert!(output.status.success());
+    let pack = String::from_utf8_lossy(&output.stdout);
+    assert!(pack.contains("[variables.k]"), "unexpected pack: {pack}");
+    assert!(
+        !pack.contains("1+1"),
+        "history should not be exported: {pack}"
+    );
+
+    let teammate = temp_home_dir("import-vars");
+    let pack_path = teammate.join("steel.toml");
+    fs::write(&pack_path, pack.as_bytes()).expect("pack should be writable");
+    let output = Command::new(env!("CARGO_BIN_EXE_rcalc"))
+        .args(["import", "vars"])
+        .arg(&pack_path)
+        .env("HOME", &teammate)
+        .output()
+        .expect("should execute rcalc import vars");
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout).trim(),
+        "Imported 1 new variables"
+    );
+    let state = fs::read_to_string(teammate.join(".config").join("rcalc").join("state.json"))
+        .expect("import should write the state file");
+    assert!(
+        state.contains(r#""unit":"GPa""#),
+        "unexpected state: {state}"
+    );
+}
+
+#[test]
+fn rcalc_clear_history_keeps_variables() {
+    let home = temp_home_dir("clear-history");
+    let state_dir = home.join(".config").join("rcalc");
+    let state_file = state_dir.join("state.json");
+    fs::create_dir_all(&state_dir).expect("state directory should be creatable");
+    fs::write(
+        &state_file,
+        r#"{"history":[{"expression":"1+1","result":2.0,"error":null}],
+            "variables":{"x":{"expression":"1","value":1.0}},"plot_data":null}"#,
+    )
+    .expect("state file should be writable");
+
+    let status = Command::new(env!("CARGO_BIN_EXE_rcalc"))
+        .args(["clear", "--history"])
+        .env("HOME", &home)
+        .status()
+        .expect("should execute rcalc clear --history");
+
+    assert!(status.success());
+    let state = fs::read_to_string(&state_file).expect("state file should be kept");
+    assert!(
+        state.contains(r#""history":[]"#),
+        "unexpected state: {state}"
+    );
+    assert!(state.contains(r#""x":"#), "unexpected state: {state}");
+}
+
+#[test]
+fn rcalc_doctor_fix_repairs_and_keeps_a_backup() {
+    let home = temp_home_dir("doctor");
+    let state_dir = home.join(".config").join("rcalc");
+    let state_file = state_dir.join("state.json");
+    fs::create_dir_all(&state_dir).expect("state directory should be creatable");
+    fs::write(
+        &state_file,
+        r#"{"history":[],"variables":{"x":{"expression":"2+3","value":null}},"plot_data":null}"#,
+    )
+    .expect("state file should be writable");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_rcalc"))
+        .args(["doctor", "--fix"])
+        .env("HOME", &home)
+        .output()
+        .expect("should execute rcalc doctor");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("variable x has no finite value"),
+        "unexpected output: {stdout}"
+    );
+    assert!(state_dir.join("state.json.bak").exists());
+    let state = fs::read_to_string(&state_file).expect("state file should exist");
+    assert!(
+        state.contains(r#""value":5.0"#),
+        "unexpected state: {state}"
+    );
+}
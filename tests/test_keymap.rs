@@ -0,0 +1,83 @@
+use ratatui::crossterm::event::{KeyCode, KeyModifiers};
+use rustic_calc::keymap::{Action, KeyChord, KeyContext, Keymap, Resolution, parse_keys};
+
+fn ch(c: char) -> KeyChord {
+    KeyChord::plain(KeyCode::Char(c))
+}
+
+#[test]
+fn resolves_context_bindings_then_global() {
+    let keymap = Keymap::default();
+    assert_eq!(
+        keymap.resolve(KeyContext::Normal, &[ch('x')]),
+        Resolution::Action(Action::DeleteChar)
+    );
+    assert_eq!(
+        keymap.resolve(KeyContext::History, &[KeyChord::ctrl('c')]),
+        Resolution::Action(Action::Quit)
+    );
+    assert_eq!(
+        keymap.resolve(KeyContext::Insert, &[ch('x')]),
+        Resolution::Unbound
+    );
+}
+
+#[test]
+fn multi_key_bindings_are_pending_until_complete() {
+    let keymap = Keymap::default();
+    assert_eq!(
+        keymap.resolve(KeyContext::History, &[ch('y')]),
+        Resolution::Pending
+    );
+    assert_eq!(
+        keymap.resolve(KeyContext::History, &[ch('y'), ch('r')]),
+        Resolution::Action(Action::YankResult)
+    );
+}
+
+#[test]
+fn shift_is_ignored_for_characters() {
+    let shifted = KeyChord::new(KeyCode::Char('A'), KeyModifiers::SHIFT);
+    assert_eq!(shifted, ch('A'));
+    assert_eq!(shifted.to_string(), "A");
+    assert_eq!(KeyChord::ctrl('r').to_string(), "Ctrl+r");
+}
+
+#[test]
+fn help_entries_group_keys_by_action() {
+    let mut keymap = Keymap::default();
+    keymap.bind(KeyContext::Normal, vec![ch('H')], Action::LineStart);
+
+    let entries = keymap.help_entries(KeyContext::Normal);
+    assert!(entries.contains(&("Left / h".to_string(), "Move cursor left")));
+    assert!(entries.contains(&("0 / H".to_string(), "Line start")));
+}
+
+#[test]
+fn parses_key_sequences() {
+    assert_eq!(parse_keys("gg"), Ok(vec![ch('g'), ch('g')]));
+    assert_eq!(parse_keys("Ctrl+x"), Ok(vec![KeyChord::ctrl('x')]));
+    assert_eq!(parse_keys("Space"), Ok(vec![ch(' ')]));
+    assert_eq!(
+        parse_keys("Alt+Enter"),
+        Ok(vec![KeyChord::new(KeyCode::Enter, KeyModifiers::ALT)])
+    );
+    assert!(parse_keys("Ctrl+xy").is_err());
+}
+
+#[test]
+fn apply_mapping_binds_named_action() {
+    let mut keymap = Keymap::default();
+    keymap.apply_mapping("normal H line_start").unwrap();
+    assert_eq!(
+        keymap.resolve(KeyContext::Normal, &[ch('H')]),
+        Resolution::Action(Action::LineStart)
+    );
+
+    assert_eq!(
+        keymap.apply_mapping("normal H teleport"),
+        Err("Unknown action: teleport".to_string())
+    );
+    assert!(keymap.apply_mapping("nowhere H line_start").is_err());
+    assert!(keymap.apply_mapping("normal H").is_err());
+}
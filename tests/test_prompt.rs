@@ -0,0 +1,48 @@
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use rustic_calc::{
+    input_editor::{InputEditMode, InputEditor},
+    keymap::default_keymap,
+    prompt::Prompt,
+};
+
+fn key(code: KeyCode) -> KeyEvent {
+    KeyEvent::new(code, KeyModifiers::NONE)
+}
+
+fn type_text(prompt: &mut Prompt, text: &str) {
+    for ch in text.chars() {
+        prompt.handle_key_event_with(key(KeyCode::Char(ch)), default_keymap());
+    }
+}
+
+#[test]
+fn prompt_edits_at_the_cursor_and_stays_in_insert_mode() {
+    let mut prompt = Prompt::new("Name: ");
+    type_text(&mut prompt, "rte");
+    prompt.handle_key_event_with(key(KeyCode::Esc), default_keymap());
+    prompt.handle_key_event_with(key(KeyCode::Left), default_keymap());
+    prompt.handle_key_event_with(key(KeyCode::Left), default_keymap());
+    type_text(&mut prompt, "a");
+    assert_eq!(prompt.text(), "rate");
+    assert_eq!(prompt.cursor(), 2);
+
+    let mut editor = InputEditor::insert_only();
+    editor.switch_to_visual_mode();
+    assert_eq!(editor.mode(), InputEditMode::Insert);
+}
+
+#[test]
+fn prompt_rejects_text_failing_validation() {
+    fn non_empty(text: &str) -> Result<(), String> {
+        if text.trim().is_empty() {
+            Err("Enter a name".to_string())
+        } else {
+            Ok(())
+        }
+    }
+
+    let mut prompt = Prompt::new("Name: ").with_validator(non_empty);
+    assert_eq!(prompt.submit(), Err("Enter a name".to_string()));
+    type_text(&mut prompt, "rate");
+    assert_eq!(prompt.submit(), Ok("rate"));
+}
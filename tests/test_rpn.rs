@@ -0,0 +1,49 @@
+use std::collections::HashMap;
+
+use approx::assert_relative_eq;
+use rustic_calc::{error::CalcError, rpn::RpnStack, types::AngleMode};
+
+#[test]
+fn words_act_on_the_stack() {
+    let mut stack = RpnStack::new();
+    let variables = HashMap::new();
+    stack
+        .apply("5 3 +", &variables, AngleMode::Radians)
+        .unwrap();
+    assert_eq!(stack.values(), [8.0]);
+
+    stack
+        .apply("2 swap / dup * 4", &variables, AngleMode::Radians)
+        .unwrap();
+    assert_eq!(stack.values(), [0.0625, 4.0]);
+
+    stack
+        .apply("drop pi -1 *", &variables, AngleMode::Radians)
+        .unwrap();
+    assert_relative_eq!(stack.top().unwrap(), -std::f64::consts::PI);
+
+    stack
+        .apply("90 sin", &variables, AngleMode::Degrees)
+        .unwrap();
+    assert_relative_eq!(stack.top().unwrap(), 1.0);
+}
+
+#[test]
+fn failing_words_leave_the_stack_unchanged() {
+    let mut stack = RpnStack::new();
+    let variables = HashMap::new();
+    stack.apply("1 2", &variables, AngleMode::Radians).unwrap();
+
+    assert_eq!(
+        stack.apply("+ *", &variables, AngleMode::Radians),
+        Err(CalcError::StackUnderflow {
+            word: "*".to_string(),
+            needed: 2
+        })
+    );
+    assert_eq!(
+        stack.apply("3 q", &variables, AngleMode::Radians),
+        Err(CalcError::UnknownVariable("q".to_string()))
+    );
+    assert_eq!(stack.values(), [1.0, 2.0]);
+}
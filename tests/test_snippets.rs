@@ -0,0 +1,26 @@
+use rustic_calc::snippets::{Snippet, next_placeholder};
+
+#[test]
+fn parses_named_templates_with_placeholders() {
+    let snippet = Snippet::parse("vat = {amount} * 1.19").unwrap();
+    assert_eq!(snippet.name, "vat");
+    assert_eq!(snippet.template, "{amount} * 1.19");
+    assert_eq!(snippet.to_string(), "vat = {amount} * 1.19");
+
+    assert!(Snippet::parse("vat {amount} * 1.19").is_err());
+    assert!(Snippet::parse("1x = 2").is_err());
+    assert!(Snippet::parse("vat = ").is_err());
+    assert!(Snippet::parse("vat = {amount * 1.19").is_err());
+    assert!(Snippet::parse("vat = {} * 1.19").is_err());
+}
+
+#[test]
+fn finds_the_first_placeholder_left() {
+    assert_eq!(
+        next_placeholder("{net} + {net} * {rate}"),
+        Some((0..5, "net"))
+    );
+    assert_eq!(next_placeholder("2 + {rate}"), Some((4..10, "rate")));
+    assert_eq!(next_placeholder("{} + {x"), None);
+    assert_eq!(next_placeholder("2 + 3"), None);
+}
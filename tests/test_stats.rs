@@ -0,0 +1,16 @@
+use approx::assert_relative_eq;
+use rustic_calc::stats::summarize;
+
+#[test]
+fn summarizes_finite_values() {
+    let summary = summarize([2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0, f64::NAN]).unwrap();
+    assert_eq!(summary.count, 8);
+    assert_relative_eq!(summary.sum, 40.0);
+    assert_relative_eq!(summary.mean, 5.0);
+    assert_relative_eq!(summary.min, 2.0);
+    assert_relative_eq!(summary.max, 9.0);
+    assert_relative_eq!(summary.stddev.unwrap(), (32.0f64 / 7.0).sqrt());
+
+    assert_eq!(summarize([3.0]).unwrap().stddev, None);
+    assert_eq!(summarize([]), None);
+}
@@ -0,0 +1,14 @@
+use rustic_calc::{
+    error::CalcError,
+    tape::{TapeOp, parse_tape_line},
+};
+
+#[test]
+fn tape_lines_split_into_operation_and_amount() {
+    assert_eq!(parse_tape_line("12.50"), (TapeOp::Add, "12.50"));
+    assert_eq!(parse_tape_line(" + 3*4"), (TapeOp::Add, "3*4"));
+    assert_eq!(parse_tape_line("-2"), (TapeOp::Sub, "2"));
+    assert_eq!(parse_tape_line("*1.2"), (TapeOp::Mul, "1.2"));
+    assert_eq!(parse_tape_line("/ 4"), (TapeOp::Div, "4"));
+    assert_eq!(TapeOp::Div.apply(3.0, 0.0), Err(CalcError::Undefined));
+}
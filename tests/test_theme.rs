@@ -0,0 +1,26 @@
+use rustic_calc::theme::{Background, Theme, no_color_requested};
+
+#[test]
+fn background_is_read_from_colorfgbg() {
+    assert_eq!(Background::from_colorfgbg(Some("0;15")), Background::Light);
+    assert_eq!(
+        Background::from_colorfgbg(Some("15;default;0")),
+        Background::Dark
+    );
+    assert_eq!(
+        Background::from_colorfgbg(Some("garbage")),
+        Background::Dark
+    );
+    assert_eq!(Background::from_colorfgbg(None), Background::Dark);
+}
+
+#[test]
+fn no_color_overrides_background_theme() {
+    assert!(no_color_requested(Some("1")));
+    assert!(!no_color_requested(Some("")));
+    assert!(!no_color_requested(None));
+
+    assert_eq!(Theme::for_terminal(Background::Light, false), Theme::LIGHT);
+    assert_eq!(Theme::for_terminal(Background::Light, true), Theme::MONO);
+    assert_eq!(Theme::for_terminal(Background::Dark, false), Theme::DEFAULT);
+}
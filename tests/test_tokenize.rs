@@ -1,4 +1,4 @@
-use rustic_calc::tokenize::tokenize;
+use rustic_calc::tokenize::{Lexer, TokenKind, split_statements, tokenize};
 
 #[test]
 fn test_tokenize() {
@@ -39,6 +39,13 @@ fn coefficients_tokenized() {
     assert_eq!(res, vec!["7", "*", "x"]);
 }
 
+#[test]
+fn exponent_literals_tokenized() {
+    assert_eq!(tokenize("1.5e-9x"), vec!["1.5e-9", "*", "x"]);
+    assert_eq!(tokenize("2E20+1"), vec!["2E20", "+", "1"]);
+    assert_eq!(tokenize("2e+x"), vec!["2", "*", "e", "+", "x"]);
+}
+
 #[test]
 fn phrase_tokenized() {
     let res = tokenize("2(a+b)+7");
@@ -47,3 +54,82 @@ fn phrase_tokenized() {
     let res = tokenize("(a+5)/2");
     assert_eq!(res, vec!["(", "a", "+", "5", ")", "/", "2"]);
 }
+
+#[test]
+fn builtin_names_tokenized() {
+    let res = tokenize("2pix");
+    assert_eq!(res, vec!["2", "*", "pi", "*", "x"]);
+
+    let res = tokenize("xsin(t)");
+    assert_eq!(res, vec!["x", "*", "sin", "(", "t", ")"]);
+}
+
+#[test]
+fn history_references_tokenized() {
+    assert_eq!(tokenize("#3+1"), vec!["#3", "+", "1"]);
+    assert_eq!(tokenize("x#3"), vec!["x", "*", "#3"]);
+}
+
+#[test]
+fn lexer_reports_kinds_and_spans() {
+    let tokens: Vec<_> = Lexer::new("2 sin(x)").collect();
+    let kinds: Vec<TokenKind> = tokens.iter().map(|token| token.kind).collect();
+    assert_eq!(
+        kinds,
+        vec![
+            TokenKind::Number,
+            TokenKind::Operator,
+            TokenKind::Function,
+            TokenKind::LParen,
+            TokenKind::Identifier,
+            TokenKind::RParen,
+        ]
+    );
+    assert!(tokens[1].is_implicit());
+    assert_eq!(tokens[1].span, 2..2);
+    assert_eq!(tokens[2].span, 2..5);
+    assert_eq!(tokens[4].span, 6..7);
+}
+
+#[test]
+fn statements_split_on_semicolons() {
+    assert_eq!(split_statements("a=2; b=3;; a*b ;"), ["a=2", "b=3", "a*b"]);
+    assert_eq!(split_statements("1+1"), ["1+1"]);
+    assert_eq!(
+        split_statements("a=2 # rate; per hour\n# only a comment\na*3"),
+        ["a=2 # rate; per hour", "a*3"]
+    );
+}
+
+#[test]
+fn comments_are_skipped_but_history_references_are_not() {
+    assert_eq!(tokenize("2*x # width\n+1"), vec!["2", "*", "x", "+", "1"]);
+    assert_eq!(tokenize("#3+1"), vec!["#3", "+", "1"]);
+}
+
+#[test]
+fn implicit_multiplication_around_parentheses() {
+    assert_eq!(tokenize("(2+3)4"), ["(", "2", "+", "3", ")", "*", "4"]);
+    assert_eq!(tokenize("(2)(3)"), ["(", "2", ")", "*", "(", "3", ")"]);
+    assert_eq!(
+        tokenize("2(x+1)(x-1)"),
+        [
+            "2", "*", "(", "x", "+", "1", ")", "*", "(", "x", "-", "1", ")"
+        ]
+    );
+    assert_eq!(tokenize("(2)x"), ["(", "2", ")", "*", "x"]);
+    assert_eq!(
+        tokenize("(2)sin(1)"),
+        ["(", "2", ")", "*", "sin", "(", "1", ")"]
+    );
+}
+
+#[test]
+fn double_star_is_read_as_power() {
+    assert_eq!(tokenize("2**3"), ["2", "^", "3"]);
+    assert_eq!(tokenize("2 ** 3 * 4"), ["2", "^", "3", "*", "4"]);
+
+    let power = Lexer::new("x**2").nth(1).unwrap();
+    assert_eq!(power.kind, TokenKind::Operator);
+    assert_eq!(power.span, 1..3);
+}
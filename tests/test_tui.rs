@@ -1,15 +1,47 @@
-use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+#[path = "common/home_guard.rs"]
+mod home_guard;
+#[path = "common/temp_home.rs"]
+mod temp_home;
+
+use std::time::{Duration, Instant};
+
+use ratatui::{
+    Terminal,
+    backend::TestBackend,
+    crossterm::event::{KeyCode, KeyEvent, KeyModifiers},
+    layout::Rect,
+};
 use rustic_calc::{
-    tui_app::{App, InputEditMode},
-    types::Focus,
+    commands::Command,
+    events::{AppEvent, ScriptedEvents},
+    io::{session_state_path, snapshot_path},
+    keymap::Action,
+    msg::Msg,
+    snippets::Snippet,
+    theme::Theme,
+    tui_app::{App, InputEditMode, render_app},
+    types::{
+        AppState, ClearTarget, Focus, HistoryKind, LayoutPreset, LayoutState, Overlay, PlotStyle,
+        ValueFormat,
+    },
 };
 
+use home_guard::HomeGuard;
+use temp_home::temp_home_dir;
+
 fn key_event(code: KeyCode) -> KeyEvent {
     KeyEvent::new(code, KeyModifiers::NONE)
 }
 
+/// Points HOME at a fresh directory for the rest of the test, so saving state
+/// and input history never touches the real config directory.
+fn isolated_home() -> HomeGuard {
+    HomeGuard::set(&temp_home_dir("tui"))
+}
+
 #[test]
 fn app_starts_with_empty_state() {
+    let _home = isolated_home();
     let app = App::new();
     assert_eq!(app.input, "");
     assert_eq!(app.character_index, 0);
@@ -20,6 +52,7 @@ fn app_starts_with_empty_state() {
 
 #[test]
 fn enter_char_and_cursor_movement_work() {
+    let _home = isolated_home();
     let mut app = App::new();
     app.enter_char('1');
     app.enter_char('+');
@@ -37,6 +70,7 @@ fn enter_char_and_cursor_movement_work() {
 
 #[test]
 fn delete_char_removes_character_before_cursor() {
+    let _home = isolated_home();
     let mut app = App::new();
     app.input = "12+3".to_string();
     app.character_index = 2;
@@ -49,6 +83,7 @@ fn delete_char_removes_character_before_cursor() {
 
 #[test]
 fn submit_message_records_success_and_clears_input() {
+    let _home = isolated_home();
     let mut app = App::new();
     app.input = "2+2".to_string();
     app.character_index = 3;
@@ -65,6 +100,7 @@ fn submit_message_records_success_and_clears_input() {
 
 #[test]
 fn submit_message_records_error_and_clears_input() {
+    let _home = isolated_home();
     let mut app = App::new();
     app.input = "asdf".to_string();
     app.character_index = 4;
@@ -84,6 +120,7 @@ fn submit_message_records_error_and_clears_input() {
 
 #[test]
 fn up_arrow_recalls_last_expression_in_insert_mode() {
+    let _home = isolated_home();
     let mut app = App::new();
     app.input = "1+1".to_string();
     app.submit_message();
@@ -96,13 +133,29 @@ fn up_arrow_recalls_last_expression_in_insert_mode() {
 
 #[test]
 fn ctrl_c_returns_quit_signal() {
+    let _home = isolated_home();
     let mut app = App::new();
     let quit = app.handle_key_event(KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL));
     assert!(quit);
 }
 
+#[test]
+fn q_in_list_panes_and_colon_q_quit() {
+    let _home = isolated_home();
+    let mut app = App::new();
+    app.handle_key_event(key_event(KeyCode::Esc));
+    app.handle_key_event(key_event(KeyCode::Tab));
+    assert_eq!(app.focus, Focus::History);
+    assert!(app.handle_key_event(key_event(KeyCode::Char('q'))));
+
+    let mut app = App::new();
+    app.input = ":q".to_string();
+    assert!(app.handle_key_event(key_event(KeyCode::Enter)));
+}
+
 #[test]
 fn esc_in_insert_switches_to_normal_mode() {
+    let _home = isolated_home();
     let mut app = App::new();
 
     app.handle_key_event(key_event(KeyCode::Esc));
@@ -113,6 +166,7 @@ fn esc_in_insert_switches_to_normal_mode() {
 
 #[test]
 fn tab_cycles_focus_forward_across_all_panes() {
+    let _home = isolated_home();
     let mut app = App::new();
 
     app.handle_key_event(key_event(KeyCode::Esc)); // Insert -> Normal (Input focused)
@@ -130,6 +184,7 @@ fn tab_cycles_focus_forward_across_all_panes() {
 
 #[test]
 fn backtab_cycles_focus_backward_across_all_panes() {
+    let _home = isolated_home();
     let mut app = App::new();
 
     app.handle_key_event(key_event(KeyCode::Esc)); // Insert -> Normal (Input focused)
@@ -147,6 +202,7 @@ fn backtab_cycles_focus_backward_across_all_panes() {
 
 #[test]
 fn pressing_i_while_not_in_input_mode_re_enters_input_insert_mode() {
+    let _home = isolated_home();
     let mut app = App::new();
     app.input = "x=2".to_string();
     app.submit_message();
@@ -163,6 +219,7 @@ fn pressing_i_while_not_in_input_mode_re_enters_input_insert_mode() {
 
 #[test]
 fn enter_on_history_populates_input_from_selected_item() {
+    let _home = isolated_home();
     let mut app = App::new();
     app.input = "1+1".to_string();
     app.submit_message();
@@ -182,6 +239,7 @@ fn enter_on_history_populates_input_from_selected_item() {
 
 #[test]
 fn enter_on_variables_populates_input_from_selected_variable_expression() {
+    let _home = isolated_home();
     let mut app = App::new();
     app.input = "x=2".to_string();
     app.submit_message();
@@ -201,6 +259,7 @@ fn enter_on_variables_populates_input_from_selected_variable_expression() {
 
 #[test]
 fn normal_mode_y_and_yy_no_longer_copy() {
+    let _home = isolated_home();
     let mut app = App::new();
     app.input = "abc".to_string();
     app.character_index = app.input.chars().count();
@@ -219,6 +278,7 @@ fn normal_mode_y_and_yy_no_longer_copy() {
 
 #[test]
 fn visual_mode_v_toggles_and_esc_returns_to_normal() {
+    let _home = isolated_home();
     let mut app = App::new();
     app.input = "abcd".to_string();
     app.character_index = app.input.chars().count();
@@ -239,6 +299,7 @@ fn visual_mode_v_toggles_and_esc_returns_to_normal() {
 
 #[test]
 fn visual_mode_yank_then_paste_works() {
+    let _home = isolated_home();
     let mut app = App::new();
     app.input = "abcde".to_string();
     app.character_index = app.input.chars().count();
@@ -257,6 +318,7 @@ fn visual_mode_yank_then_paste_works() {
 
 #[test]
 fn visual_mode_delete_selection_works() {
+    let _home = isolated_home();
     let mut app = App::new();
     app.input = "abcde".to_string();
     app.character_index = app.input.chars().count();
@@ -274,6 +336,7 @@ fn visual_mode_delete_selection_works() {
 
 #[test]
 fn save_variable() {
+    let _home = isolated_home();
     let mut app = App::new();
     app.input = "x=2".to_string();
     app.character_index = 3;
@@ -282,7 +345,10 @@ fn save_variable() {
 
     assert_eq!(app.input, "");
     assert_eq!(app.character_index, 0);
-    assert_eq!(app.history.len(), 0);
+    assert_eq!(app.history.len(), 1);
+    assert_eq!(app.history[0].kind, HistoryKind::Assignment);
+    assert_eq!(app.history[0].expression, "x=2");
+    assert_eq!(app.history[0].result, Some(2.0));
     assert_eq!(
         app.variables.get("x").unwrap().expression,
         "x=2".to_string()
@@ -292,6 +358,7 @@ fn save_variable() {
 
 #[test]
 fn plot_expression() {
+    let _home = isolated_home();
     let mut app = App::new();
     app.input = "7x+1".to_string();
     app.character_index = 4;
@@ -307,3 +374,1171 @@ fn plot_expression() {
     assert_eq!(plot_data[0], (-10.0, -69.0));
     assert_eq!(plot_data[20], (10.0, 71.0));
 }
+
+#[test]
+fn ctrl_r_searches_history_and_inserts_selected_expression() {
+    let _home = isolated_home();
+    let mut app = App::new();
+    for expression in ["3*4", "1+1", "3+3"] {
+        app.input = expression.to_string();
+        app.submit_message();
+    }
+
+    let ctrl_r = KeyEvent::new(KeyCode::Char('r'), KeyModifiers::CONTROL);
+    app.handle_key_event(ctrl_r);
+    assert!(app.search.is_some());
+
+    app.handle_key_event(key_event(KeyCode::Char('3')));
+    assert_eq!(app.current_search_match(), Some("3+3"));
+
+    app.handle_key_event(ctrl_r);
+    assert_eq!(app.current_search_match(), Some("3*4"));
+
+    app.handle_key_event(key_event(KeyCode::Enter));
+    assert!(app.search.is_none());
+    assert_eq!(app.input, "3*4");
+    assert_eq!(app.character_index, 3);
+    assert_eq!(app.input_edit_mode, InputEditMode::Insert);
+}
+
+#[test]
+fn ctrl_f_inserts_a_function_with_the_cursor_inside() {
+    let _home = isolated_home();
+    let mut app = App::new();
+    app.input = "f(x) = x+1".to_string();
+    app.submit_message();
+    app.input = "2*".to_string();
+    app.character_index = 2;
+
+    app.handle_key_event(KeyEvent::new(KeyCode::Char('f'), KeyModifiers::CONTROL));
+    assert_eq!(app.overlay, Some(Overlay::FunctionMenu { selected: 0 }));
+    app.handle_key_event(key_event(KeyCode::Down));
+    app.handle_key_event(key_event(KeyCode::Enter));
+    assert_eq!(app.overlay, None);
+    assert_eq!(app.input, "2*cos()");
+    assert_eq!(app.character_index, 6);
+
+    // User-defined functions are listed after the built-in ones.
+    app.input.clear();
+    app.character_index = 0;
+    app.handle_key_event(KeyEvent::new(KeyCode::Char('f'), KeyModifiers::CONTROL));
+    for _ in 0..20 {
+        app.handle_key_event(key_event(KeyCode::Down));
+    }
+    app.handle_key_event(key_event(KeyCode::Enter));
+    assert_eq!(app.input, "f()");
+}
+
+#[test]
+fn snippet_is_inserted_and_tab_moves_through_its_fields() {
+    let _home = isolated_home();
+    let mut app = App::new();
+    app.snippets = vec![Snippet::parse("interest = {principal} * {rate} / 100").unwrap()];
+    app.input = ":snippet interest".to_string();
+    app.submit_message();
+    assert_eq!(app.input, " * {rate} / 100");
+    assert_eq!(app.character_index, 0);
+
+    for ch in "500".chars() {
+        app.handle_key_event(key_event(KeyCode::Char(ch)));
+    }
+    app.handle_key_event(key_event(KeyCode::Tab));
+    assert_eq!(app.input, "500 *  / 100");
+    assert_eq!(app.character_index, 6);
+    app.handle_key_event(key_event(KeyCode::Char('4')));
+    app.submit_message();
+    assert_eq!(app.history.last().unwrap().result, Some(20.0));
+
+    app.input = ":snippet".to_string();
+    app.submit_message();
+    assert_eq!(app.overlay, Some(Overlay::SnippetPicker { selected: 0 }));
+    app.handle_key_event(key_event(KeyCode::Enter));
+    assert_eq!(app.input, " * {rate} / 100");
+}
+
+#[test]
+fn esc_cancels_search_without_touching_input() {
+    let _home = isolated_home();
+    let mut app = App::new();
+    app.input = "1+1".to_string();
+    app.submit_message();
+    app.input = "7".to_string();
+    app.character_index = 1;
+
+    app.handle_key_event(KeyEvent::new(KeyCode::Char('r'), KeyModifiers::CONTROL));
+    app.handle_key_event(key_event(KeyCode::Char('1')));
+    app.handle_key_event(key_event(KeyCode::Esc));
+
+    assert!(app.search.is_none());
+    assert_eq!(app.input, "7");
+}
+
+#[test]
+fn note_command_labels_history_entry() {
+    let _home = isolated_home();
+    let mut app = App::new();
+    app.input = "1200+300".to_string();
+    app.submit_message();
+
+    app.input = ":note 1 monthly budget".to_string();
+    app.submit_message();
+
+    assert_eq!(app.history.len(), 1);
+    assert_eq!(app.history[0].note.as_deref(), Some("monthly budget"));
+    assert_eq!(app.input, "");
+}
+
+#[test]
+fn n_on_history_prefills_note_command_for_selected_entry() {
+    let _home = isolated_home();
+    let mut app = App::new();
+    app.input = "1+1".to_string();
+    app.submit_message();
+    app.input = "2+2".to_string();
+    app.submit_message();
+
+    app.handle_key_event(key_event(KeyCode::Esc)); // Insert -> Normal
+    app.handle_key_event(key_event(KeyCode::Tab)); // Normal -> History
+    app.handle_key_event(key_event(KeyCode::Char('n')));
+
+    assert_eq!(app.focus, Focus::Input);
+    assert_eq!(app.input, ":note 2 ");
+}
+
+#[test]
+fn o_on_history_opens_result_detail_with_used_variables() {
+    let _home = isolated_home();
+    let mut app = App::new();
+    app.input = "a=4".to_string();
+    app.submit_message();
+    app.input = "a/3".to_string();
+    app.submit_message();
+
+    assert_eq!(app.history[1].variables.get("a"), Some(&4.0));
+
+    app.handle_key_event(key_event(KeyCode::Esc)); // Insert -> Normal
+    app.handle_key_event(key_event(KeyCode::Tab)); // Normal -> History
+    app.handle_key_event(key_event(KeyCode::Char('o')));
+    assert_eq!(app.overlay, Some(Overlay::ResultDetail(1)));
+    app.handle_key_event(key_event(KeyCode::Char('s')));
+    assert!(app.detail_steps);
+
+    app.handle_key_event(key_event(KeyCode::Esc));
+    assert_eq!(app.overlay, None);
+    assert_eq!(app.focus, Focus::History);
+}
+
+#[test]
+fn ye_and_yr_yank_expression_or_result_from_history() {
+    let _home = isolated_home();
+    let mut app = App::new();
+    app.input = "6*7".to_string();
+    app.submit_message();
+
+    app.handle_key_event(key_event(KeyCode::Esc)); // Insert -> Normal
+    app.handle_key_event(key_event(KeyCode::Tab)); // Normal -> History
+    app.handle_key_event(key_event(KeyCode::Char('y')));
+    app.handle_key_event(key_event(KeyCode::Char('r')));
+    app.handle_key_event(key_event(KeyCode::BackTab)); // History -> Input
+    app.handle_key_event(key_event(KeyCode::Char('p')));
+    assert_eq!(app.input, "42");
+
+    app.handle_key_event(key_event(KeyCode::Tab)); // Input -> History
+    app.handle_key_event(key_event(KeyCode::Char('y')));
+    app.handle_key_event(key_event(KeyCode::Char('e')));
+    app.handle_key_event(key_event(KeyCode::BackTab)); // History -> Input
+    app.handle_key_event(key_event(KeyCode::Char('p')));
+    assert_eq!(app.input, "426*7");
+}
+
+#[test]
+fn question_mark_opens_scrollable_help_overlay() {
+    let _home = isolated_home();
+    let mut app = App::new();
+    app.handle_key_event(key_event(KeyCode::Esc)); // Insert -> Normal
+    app.handle_key_event(key_event(KeyCode::Char('?')));
+    assert_eq!(app.overlay, Some(Overlay::Help { scroll: 0 }));
+
+    app.handle_key_event(key_event(KeyCode::Char('j')));
+    assert_eq!(app.overlay, Some(Overlay::Help { scroll: 1 }));
+
+    app.handle_key_event(key_event(KeyCode::Esc));
+    assert_eq!(app.overlay, None);
+    assert_eq!(app.input_edit_mode, InputEditMode::Normal);
+}
+
+#[test]
+fn question_mark_is_typed_in_insert_mode() {
+    let _home = isolated_home();
+    let mut app = App::new();
+    app.handle_key_event(key_event(KeyCode::Char('?')));
+    assert_eq!(app.overlay, None);
+    assert_eq!(app.input, "?");
+}
+
+#[test]
+fn precision_command_changes_settings_without_history_entry() {
+    let _home = isolated_home();
+    let mut app = App::new();
+    app.input = ":precision 2".to_string();
+    app.submit_message();
+
+    assert_eq!(app.settings.precision, Some(2));
+    assert!(app.history.is_empty());
+    assert_eq!(
+        app.toast.as_ref().map(|toast| toast.message.as_str()),
+        Some("Precision: 2 decimals")
+    );
+}
+
+#[test]
+fn layout_command_switches_preset() {
+    let _home = isolated_home();
+    let mut app = App::new();
+    app.input = ":layout minimal".to_string();
+    app.submit_message();
+
+    assert_eq!(app.layout.preset, LayoutPreset::Minimal);
+    assert!(!app.layout.variables_visible());
+
+    app.input = ":layout sideways".to_string();
+    app.submit_message();
+    assert_eq!(app.layout.preset, LayoutPreset::Minimal);
+    assert_eq!(
+        app.history.last().and_then(|entry| entry.error.as_deref()),
+        Some("Usage: :layout classic|plot|minimal")
+    );
+}
+
+#[test]
+fn theme_command_selects_or_cycles_builtin_themes() {
+    let _home = isolated_home();
+    let mut app = App::new();
+    app.input = ":theme gruvbox".to_string();
+    app.submit_message();
+    assert_eq!(app.theme, Theme::GRUVBOX);
+
+    app.input = ":theme".to_string();
+    app.submit_message();
+    assert_eq!(app.theme, Theme::MONO);
+
+    app.input = ":theme neon".to_string();
+    app.submit_message();
+    assert_eq!(app.theme, Theme::MONO);
+    assert!(
+        app.history[0]
+            .error
+            .as_deref()
+            .unwrap()
+            .starts_with("Unknown theme: neon")
+    );
+}
+
+#[test]
+fn ctrl_arrows_resize_panes_within_bounds() {
+    let _home = isolated_home();
+    let mut app = App::new();
+    let ctrl = |code| KeyEvent::new(code, KeyModifiers::CONTROL);
+
+    app.handle_key_event(ctrl(KeyCode::Right));
+    assert_eq!(app.layout.history_width, 55);
+    assert!(app.unsaved);
+
+    for _ in 0..20 {
+        app.handle_key_event(ctrl(KeyCode::Left));
+    }
+    assert_eq!(app.layout.history_width, LayoutState::MIN_HISTORY_WIDTH);
+
+    app.handle_key_event(ctrl(KeyCode::Down));
+    assert_eq!(app.layout.input_height, 4);
+    assert_eq!(app.to_state().layout, app.layout);
+}
+
+#[test]
+fn hiding_variables_pane_skips_it_in_focus_cycle() {
+    let _home = isolated_home();
+    let mut app = App::new();
+    app.handle_key_event(KeyEvent::new(KeyCode::Char('v'), KeyModifiers::ALT));
+    assert!(!app.layout.show_variables);
+
+    app.handle_key_event(key_event(KeyCode::Esc)); // Insert -> Normal
+    app.handle_key_event(key_event(KeyCode::Tab)); // Input -> History
+    assert_eq!(app.focus, Focus::History);
+    app.handle_key_event(key_event(KeyCode::Tab)); // History -> Input (Variables hidden)
+    assert_eq!(app.focus, Focus::Input);
+
+    app.handle_key_event(KeyEvent::new(KeyCode::Char('p'), KeyModifiers::ALT));
+    assert!(!app.layout.show_plot);
+}
+
+#[test]
+fn compact_layout_skips_plot_in_focus_cycle() {
+    let _home = isolated_home();
+    assert!(LayoutState::is_compact(50, 40));
+    assert!(LayoutState::is_compact(120, 10));
+    assert!(!LayoutState::is_compact(120, 40));
+
+    let mut app = App::new();
+    app.input = "2x".to_string();
+    app.submit_message();
+    app.compact = true;
+
+    app.handle_key_event(key_event(KeyCode::Esc)); // Insert -> Normal
+    app.handle_key_event(KeyEvent::new(KeyCode::BackTab, KeyModifiers::SHIFT));
+    assert_eq!(app.focus, Focus::Variables);
+}
+
+#[test]
+fn plot_pane_joins_focus_cycle_once_there_is_plot_data() {
+    let _home = isolated_home();
+    let mut app = App::new();
+    app.handle_key_event(key_event(KeyCode::Esc)); // Insert -> Normal
+    app.handle_key_event(KeyEvent::new(KeyCode::BackTab, KeyModifiers::SHIFT));
+    assert_eq!(app.focus, Focus::Variables); // no plot yet
+
+    app.handle_key_event(key_event(KeyCode::Char('i')));
+    app.input = "2x".to_string();
+    app.submit_message();
+    assert_eq!(app.plot_data.as_ref().map(Vec::len), Some(21));
+
+    app.handle_key_event(key_event(KeyCode::Esc));
+    app.handle_key_event(KeyEvent::new(KeyCode::BackTab, KeyModifiers::SHIFT));
+    assert_eq!(app.focus, Focus::Plot);
+
+    app.handle_key_event(key_event(KeyCode::Char('s')));
+    assert_eq!(app.plot_style, PlotStyle::Line);
+    assert_eq!(app.to_state().plot_style, PlotStyle::Line);
+
+    app.handle_key_event(KeyEvent::new(KeyCode::Char('p'), KeyModifiers::ALT));
+    assert_eq!(app.focus, Focus::History);
+}
+
+#[test]
+fn plot_cursor_starts_mid_curve_and_stays_in_bounds() {
+    let _home = isolated_home();
+    let mut app = App::new();
+    app.input = "x^2".to_string();
+    app.submit_message();
+    app.handle_key_event(key_event(KeyCode::Esc));
+    app.handle_key_event(KeyEvent::new(KeyCode::BackTab, KeyModifiers::SHIFT));
+    assert_eq!(app.focus, Focus::Plot);
+
+    app.handle_key_event(key_event(KeyCode::Right));
+    assert_eq!(app.plot_cursor, Some(10)); // x = 0
+    app.handle_key_event(key_event(KeyCode::Char('l')));
+    assert_eq!(app.plot_cursor, Some(11));
+
+    for _ in 0..30 {
+        app.handle_key_event(key_event(KeyCode::Left));
+    }
+    assert_eq!(app.plot_cursor, Some(0));
+}
+
+#[test]
+fn polar_plot_command_samples_curve_and_logs_it() {
+    let _home = isolated_home();
+    let mut app = App::new();
+    app.input = "plot polar r=2".to_string();
+    app.submit_message();
+
+    let plot_data = app
+        .plot_data
+        .as_ref()
+        .expect("polar plot should be sampled");
+    assert!(
+        plot_data
+            .iter()
+            .all(|(x, y)| ((x * x + y * y).sqrt() - 2.0).abs() < 1e-9)
+    );
+    assert_eq!(app.history.len(), 1);
+    assert_eq!(app.history[0].expression, "plot polar r=2");
+}
+
+#[test]
+fn chart_vars_shows_variables_in_plot_pane() {
+    let _home = isolated_home();
+    let mut app = App::new();
+    app.input = "a=1200".to_string();
+    app.submit_message();
+    app.input = "chart vars".to_string();
+    app.submit_message();
+    assert!(app.variable_chart);
+
+    app.handle_key_event(key_event(KeyCode::Esc));
+    app.handle_key_event(KeyEvent::new(KeyCode::BackTab, KeyModifiers::SHIFT));
+    assert_eq!(app.focus, Focus::Plot);
+
+    app.handle_key_event(key_event(KeyCode::Char('i')));
+    app.input = "2x".to_string();
+    app.submit_message();
+    assert!(!app.variable_chart);
+}
+
+#[test]
+fn plot_is_resampled_when_referenced_variable_changes() {
+    let _home = isolated_home();
+    let mut app = App::new();
+    app.input = "a=1".to_string();
+    app.submit_message();
+    app.input = "x+a".to_string();
+    app.submit_message();
+    assert_eq!(app.plot_data.as_ref().unwrap()[10], (0.0, 1.0));
+
+    app.input = "a=5".to_string();
+    app.submit_message();
+    assert_eq!(app.plot_data.as_ref().unwrap()[10], (0.0, 5.0));
+}
+
+#[test]
+fn session_command_switches_and_restores_state() {
+    let _home = isolated_home();
+    let first = format!("test-switch-a-{}", std::process::id());
+    let second = format!("test-switch-b-{}", std::process::id());
+    let mut app = App::new();
+    app.input = format!(":session {first}");
+    app.submit_message();
+    app.input = "a=3".to_string();
+    app.submit_message();
+
+    app.input = format!(":session {second}");
+    app.submit_message();
+    assert_eq!(app.session, second);
+    assert!(app.history.is_empty());
+    assert!(!app.variables.contains_key("a"));
+
+    app.input = format!(":session {first}");
+    app.submit_message();
+    assert_eq!(app.history.len(), 1);
+    assert_eq!(app.variables.get("a").unwrap().value, 3.0);
+
+    for session in [first, second] {
+        let _ = std::fs::remove_file(session_state_path(&session).unwrap());
+    }
+}
+
+#[test]
+fn slash_clear_removes_only_the_active_session_file() {
+    let _home = isolated_home();
+    let mut app = App::new();
+    app.input = "a=1".to_string();
+    app.submit_message();
+    app.input = ":session work".to_string();
+    app.submit_message();
+    app.input = "b=2".to_string();
+    app.submit_message();
+    assert!(session_state_path("work").unwrap().exists());
+
+    app.input = "/clear".to_string();
+    app.submit_message();
+    assert!(!session_state_path("work").unwrap().exists());
+    assert!(session_state_path("default").unwrap().exists());
+}
+
+#[test]
+fn session_command_rejects_invalid_names() {
+    let _home = isolated_home();
+    let mut app = App::new();
+    app.input = ":session ../evil".to_string();
+    app.submit_message();
+    assert_eq!(app.session, "default");
+    assert!(app.history[0].error.is_some());
+}
+
+#[test]
+fn snapshot_restore_brings_back_saved_state() {
+    let _home = isolated_home();
+    let name = format!("test-snapshot-{}", std::process::id());
+    let mut app = App::new();
+    app.input = "a=1".to_string();
+    app.submit_message();
+    app.input = format!(":snapshot save {name}");
+    app.submit_message();
+
+    app.input = "a=5".to_string();
+    app.submit_message();
+    app.input = format!(":snapshot restore {name}");
+    app.submit_message();
+    assert_eq!(app.variables.get("a").unwrap().value, 1.0);
+    assert_eq!(app.history.len(), 1);
+
+    app.input = ":snapshot restore missing-snapshot-name".to_string();
+    app.submit_message();
+    assert_eq!(
+        app.history.last().unwrap().error.as_deref(),
+        Some("No snapshot named missing-snapshot-name")
+    );
+
+    let _ = std::fs::remove_file(snapshot_path(&name).unwrap());
+}
+
+#[test]
+fn changes_are_autosaved_after_a_pause() {
+    let _home = isolated_home();
+    let mut app = App::new();
+    app.session = format!("test-autosave-{}", std::process::id());
+    app.handle_key_event(KeyEvent::new(KeyCode::Char('v'), KeyModifiers::ALT));
+    assert!(app.unsaved);
+
+    app.autosave_if_due(Instant::now());
+    assert!(app.unsaved, "saving waits for the debounce delay");
+
+    app.autosave_if_due(Instant::now() + Duration::from_secs(5));
+    assert!(!app.unsaved);
+
+    let _ = std::fs::remove_file(session_state_path(&app.session).unwrap());
+}
+
+#[test]
+fn clear_vars_asks_for_confirmation() {
+    let _home = isolated_home();
+    let mut app = App::new();
+    app.input = "a=1".to_string();
+    app.submit_message();
+    app.input = ":clear vars".to_string();
+    app.submit_message();
+    assert_eq!(
+        app.overlay,
+        Some(Overlay::ConfirmClear(ClearTarget::Variables))
+    );
+
+    app.handle_key_event(key_event(KeyCode::Char('n')));
+    assert_eq!(app.overlay, None);
+    assert!(app.variables.contains_key("a"));
+
+    app.input = ":clear vars".to_string();
+    app.submit_message();
+    app.handle_key_event(key_event(KeyCode::Char('y')));
+    assert!(app.variables.is_empty());
+    assert_eq!(app.history.len(), 1);
+}
+
+#[test]
+fn tabs_keep_separate_history_and_variables() {
+    let _home = isolated_home();
+    let mut app = App::new();
+    app.input = "a=1".to_string();
+    app.submit_message();
+    app.input = ":tab new".to_string();
+    app.submit_message();
+    assert_eq!(app.workspace, 1);
+    assert!(app.history.is_empty() && app.variables.is_empty());
+    app.input = "b=2".to_string();
+    app.submit_message();
+
+    app.handle_key_event(key_event(KeyCode::Esc));
+    app.handle_key_event(key_event(KeyCode::Char('g')));
+    app.handle_key_event(key_event(KeyCode::Char('t')));
+    assert_eq!(app.workspace, 0);
+    assert!(app.variables.contains_key("a") && !app.variables.contains_key("b"));
+
+    app.handle_key_event(KeyEvent::new(KeyCode::Char('2'), KeyModifiers::ALT));
+    assert_eq!(app.workspace, 1);
+    assert_eq!(app.history.len(), 1);
+
+    let saved = serde_json::to_string(&app.to_state()).unwrap();
+    let state: AppState = serde_json::from_str(&saved).unwrap();
+    let mut reloaded = App::from(&state);
+    assert_eq!(reloaded.workspace, 1);
+    assert!(reloaded.variables.contains_key("b"));
+
+    reloaded.input = ":tab close".to_string();
+    reloaded.submit_message();
+    assert_eq!(reloaded.workspace, 0);
+    assert!(reloaded.workspaces.is_empty());
+    assert!(reloaded.variables.contains_key("a"));
+}
+
+#[test]
+fn undo_restores_cleared_and_overwritten_state() {
+    let _home = isolated_home();
+    let mut app = App::new();
+    app.input = "a=1".to_string();
+    app.submit_message();
+    app.input = "a=2".to_string();
+    app.submit_message();
+    app.input = ":clear history".to_string();
+    app.submit_message();
+    app.handle_key_event(key_event(KeyCode::Char('y')));
+    assert!(app.history.is_empty());
+
+    app.input = ":undo".to_string();
+    app.submit_message();
+    assert_eq!(app.history.len(), 2);
+    assert_eq!(app.variables["a"].value, 2.0);
+
+    app.input = ":undo".to_string();
+    app.submit_message();
+    assert_eq!(app.variables["a"].value, 1.0);
+    assert_eq!(app.history.len(), 1);
+
+    app.input = ":undo".to_string();
+    app.submit_message();
+    assert_eq!(
+        app.history.last().and_then(|entry| entry.error.as_deref()),
+        Some("Nothing to undo")
+    );
+}
+
+#[test]
+fn list_jumps_to_first_last_and_by_half_page() {
+    let _home = isolated_home();
+    let mut app = App::new();
+    for n in 0..30 {
+        app.input = format!("{n}+1");
+        app.submit_message();
+    }
+    app.handle_key_event(key_event(KeyCode::Esc));
+    app.handle_key_event(key_event(KeyCode::Tab));
+    assert_eq!(app.focus, Focus::History);
+
+    app.handle_key_event(key_event(KeyCode::Char('G')));
+    assert_eq!(app.history_state.selected(), Some(29));
+    app.handle_key_event(key_event(KeyCode::Char('g')));
+    app.handle_key_event(key_event(KeyCode::Char('g')));
+    assert_eq!(app.history_state.selected(), Some(0));
+
+    app.handle_key_event(KeyEvent::new(KeyCode::Char('d'), KeyModifiers::CONTROL));
+    assert_eq!(app.history_state.selected(), Some(5));
+    app.handle_key_event(KeyEvent::new(KeyCode::Char('u'), KeyModifiers::CONTROL));
+    assert_eq!(app.history_state.selected(), Some(0));
+
+    app.handle_key_event(key_event(KeyCode::End));
+    assert_eq!(app.history_state.selected(), Some(29));
+    app.handle_key_event(key_event(KeyCode::Home));
+    assert_eq!(app.history_state.selected(), Some(0));
+}
+
+#[test]
+fn hjkl_navigate_list_panes() {
+    let _home = isolated_home();
+    let mut app = App::new();
+    for input in ["a=1", "b=2", "3+4"] {
+        app.input = input.to_string();
+        app.submit_message();
+    }
+    app.handle_key_event(key_event(KeyCode::Esc));
+    app.handle_key_event(key_event(KeyCode::Tab));
+    app.handle_key_event(key_event(KeyCode::Char('j')));
+    app.handle_key_event(key_event(KeyCode::Char('j')));
+    assert_eq!(app.history_state.selected(), Some(2));
+    app.handle_key_event(key_event(KeyCode::Char('k')));
+    assert_eq!(app.history_state.selected(), Some(1));
+
+    app.handle_key_event(key_event(KeyCode::Char('l')));
+    assert_eq!(app.focus, Focus::Variables);
+    app.handle_key_event(key_event(KeyCode::Char('h')));
+    assert_eq!(app.focus, Focus::History);
+}
+
+#[test]
+fn history_entries_can_be_recalled_by_number() {
+    let _home = isolated_home();
+    let mut app = App::new();
+    for input in ["1+1", "2+2", "3+3"] {
+        app.input = input.to_string();
+        app.submit_message();
+    }
+
+    app.input = "#2".to_string();
+    app.submit_message();
+    assert_eq!(app.input, "2+2");
+
+    app.input.clear();
+    app.handle_key_event(key_event(KeyCode::Esc));
+    app.handle_key_event(key_event(KeyCode::Tab));
+    app.handle_key_event(key_event(KeyCode::Char('1')));
+    app.handle_key_event(key_event(KeyCode::Enter));
+    assert_eq!(app.focus, Focus::Input);
+    assert_eq!(app.input, "1+1");
+}
+
+#[test]
+fn history_results_can_be_referenced_by_number() {
+    let _home = isolated_home();
+    let mut app = App::new();
+    app.input = "20+1".to_string();
+    app.submit_message();
+    app.input = "#1 * 2".to_string();
+    app.submit_message();
+    assert_eq!(app.history[1].result, Some(42.0));
+    assert_eq!(app.history[1].expression, "#1 * 2");
+
+    app.input = "#7+1".to_string();
+    app.submit_message();
+    assert_eq!(app.history[2].error.as_deref(), Some("No history entry #7"));
+}
+
+#[test]
+fn recalling_a_variable_previews_its_current_value() {
+    let _home = isolated_home();
+    let mut app = App::new();
+    for input in ["a=1", "b=a+1", "a=5"] {
+        app.input = input.to_string();
+        app.submit_message();
+    }
+    app.handle_key_event(key_event(KeyCode::Esc));
+    app.handle_key_event(key_event(KeyCode::Tab));
+    app.handle_key_event(key_event(KeyCode::Char('l')));
+    app.handle_key_event(key_event(KeyCode::Down));
+    app.handle_key_event(key_event(KeyCode::Enter));
+    assert_eq!(app.input, "b=a+1");
+    assert_eq!(app.input_preview(), Some("= 6 (stored 2)"));
+
+    app.handle_key_event(key_event(KeyCode::Char('0')));
+    assert_eq!(app.input_preview(), None);
+}
+
+#[test]
+fn format_command_tags_variables() {
+    let _home = isolated_home();
+    let mut app = App::new();
+    app.input = "r=0.2".to_string();
+    app.submit_message();
+    app.input = ":format r percent".to_string();
+    app.submit_message();
+    assert_eq!(app.variables["r"].format, Some(ValueFormat::Percent));
+
+    app.input = "r=0.3".to_string();
+    app.submit_message();
+    assert_eq!(app.variables["r"].format, Some(ValueFormat::Percent));
+
+    app.input = ":format nope currency".to_string();
+    app.submit_message();
+    assert_eq!(
+        app.history.last().unwrap().error.as_deref(),
+        Some("Unknown variable: nope")
+    );
+}
+
+#[test]
+fn unit_command_labels_variables() {
+    let _home = isolated_home();
+    let mut app = App::new();
+    app.input = "s=12.5".to_string();
+    app.submit_message();
+    app.input = ":unit s m/s".to_string();
+    app.submit_message();
+    assert_eq!(app.variables["s"].unit.as_deref(), Some("m/s"));
+
+    app.input = "s=13".to_string();
+    app.submit_message();
+    assert_eq!(app.variables["s"].unit.as_deref(), Some("m/s"));
+
+    app.input = ":unit s none".to_string();
+    app.submit_message();
+    assert_eq!(app.variables["s"].unit, None);
+}
+
+#[test]
+fn map_command_rebinds_keys() {
+    let _home = isolated_home();
+    let mut app = App::new();
+    app.input = ":map insert Ctrl+h line_start".to_string();
+    app.submit_message();
+    assert!(app.history.is_empty());
+
+    app.input = "12".to_string();
+    app.character_index = 2;
+    app.handle_key_event(KeyEvent::new(KeyCode::Char('h'), KeyModifiers::CONTROL));
+    assert_eq!(app.character_index, 0);
+}
+
+#[test]
+fn up_and_down_browse_input_history() {
+    let _home = isolated_home();
+    let mut app = App::new();
+    app.input_history = vec!["1+1".to_string(), "2x".to_string()];
+    app.input = "draft".to_string();
+    app.character_index = 5;
+
+    app.handle_key_event(key_event(KeyCode::Up));
+    assert_eq!(app.input, "2x");
+    app.handle_key_event(key_event(KeyCode::Up));
+    app.handle_key_event(key_event(KeyCode::Up));
+    assert_eq!(app.input, "1+1");
+    app.handle_key_event(key_event(KeyCode::Down));
+    assert_eq!(app.input, "2x");
+    app.handle_key_event(key_event(KeyCode::Down));
+    assert_eq!(app.input, "draft");
+
+    app.input = "3".to_string();
+    app.submit_message();
+    assert_eq!(app.input_history.last().map(String::as_str), Some("3"));
+}
+
+#[test]
+fn tab_cycles_completions_inline() {
+    let _home = isolated_home();
+    let mut app = App::new();
+    app.input = "s=2".to_string();
+    app.submit_message();
+    app.input = "2*s".to_string();
+    app.character_index = 3;
+
+    app.handle_key_event(key_event(KeyCode::Tab));
+    assert_eq!(app.input, "2*s");
+    app.handle_key_event(key_event(KeyCode::Tab));
+    assert_eq!(app.input, "2*sin");
+    app.handle_key_event(key_event(KeyCode::Tab));
+    assert_eq!(app.input, "2*sqrt");
+    assert_eq!(app.character_index, 6);
+    app.handle_key_event(KeyEvent::new(KeyCode::BackTab, KeyModifiers::SHIFT));
+    assert_eq!(app.input, "2*sin");
+    assert_eq!(app.focus, Focus::Input);
+}
+
+#[test]
+fn simplify_command_rewrites_the_input() {
+    let _home = isolated_home();
+    let mut app = App::new();
+    app.input = ":simplify y = 2x*1 + 0".to_string();
+    app.submit_message();
+    assert_eq!(app.input, "y = 2*x");
+    assert!(app.history.is_empty());
+}
+
+#[test]
+fn semicolons_submit_each_statement_in_order() {
+    let _home = isolated_home();
+    let mut app = App::new();
+    app.input = "a=2; b=3; a*b".to_string();
+    app.submit_message();
+
+    let entries: Vec<(&str, Option<f64>)> = app
+        .history
+        .iter()
+        .map(|entry| (entry.expression.as_str(), entry.result))
+        .collect();
+    assert_eq!(
+        entries,
+        [("a=2", Some(2.0)), ("b=3", Some(3.0)), ("a*b", Some(6.0))]
+    );
+    assert_eq!(app.input, "");
+}
+
+#[test]
+fn comments_are_kept_in_history_but_not_evaluated() {
+    let _home = isolated_home();
+    let mut app = App::new();
+    app.input = "w=3 # width in m".to_string();
+    app.submit_message();
+    app.input = "# just a note".to_string();
+    app.submit_message();
+
+    assert_eq!(app.history.len(), 1);
+    assert_eq!(app.history[0].expression, "w=3 # width in m");
+    assert_eq!(app.history[0].result, Some(3.0));
+    assert_eq!(app.input, "");
+}
+
+#[test]
+fn saved_plots_can_be_switched_with_the_picker() {
+    let _home = isolated_home();
+    let mut app = App::new();
+    for input in [
+        "x^2",
+        ":plot save square",
+        "2x",
+        ":plot save line",
+        ":plot show square",
+    ] {
+        app.input = input.to_string();
+        app.submit_message();
+    }
+    assert_eq!(app.plots.len(), 2);
+    assert_eq!(
+        app.plot.as_ref().map(|plot| plot.label()).as_deref(),
+        Some("x^2")
+    );
+    assert_eq!(app.plot_data.as_ref().unwrap()[0], (-10.0, 100.0));
+
+    app.input = ":plot".to_string();
+    app.submit_message();
+    assert_eq!(app.overlay, Some(Overlay::PlotPicker { selected: 0 }));
+    app.handle_key_event(key_event(KeyCode::Down));
+    app.handle_key_event(key_event(KeyCode::Enter));
+    assert_eq!(app.overlay, None);
+    assert_eq!(
+        app.plot.as_ref().map(|plot| plot.label()).as_deref(),
+        Some("2x")
+    );
+}
+
+#[test]
+fn function_definitions_are_recorded_and_callable() {
+    let _home = isolated_home();
+    let mut app = App::new();
+    app.input = "f(x) = 3x".to_string();
+    app.submit_message();
+    assert_eq!(app.history[0].kind, HistoryKind::Definition);
+    assert_eq!(app.history[0].error, None);
+    assert_eq!(app.functions.len(), 1);
+
+    app.input = "f(2) + 1".to_string();
+    app.submit_message();
+    assert_eq!(app.history[1].result, Some(7.0));
+    assert_eq!(app.to_state().functions, app.functions);
+}
+
+#[test]
+fn calls_with_the_wrong_number_of_arguments_are_errors() {
+    let _home = isolated_home();
+    let mut app = App::new();
+    for input in ["sin(1,2)", "f(x) = x+1", "f(1,2)"] {
+        app.input = input.to_string();
+        app.submit_message();
+    }
+    assert_eq!(
+        app.history[0].error.as_deref(),
+        Some("sin takes 1 arguments, got 2")
+    );
+    assert_eq!(
+        app.history[2].error.as_deref(),
+        Some("f takes 1 arguments, got 2")
+    );
+    assert!(app.plot.is_none());
+}
+
+#[test]
+fn redefining_a_function_keeps_its_callers_working() {
+    let _home = isolated_home();
+    let mut app = App::new();
+    for input in ["f(x) = x+1", "g(x) = f(x)+1", "g(1)", "f(x) = 3x", "g(1)"] {
+        app.input = input.to_string();
+        app.submit_message();
+    }
+    assert_eq!(app.history[2].result, Some(3.0));
+    assert_eq!(app.history[3].error, None);
+    assert_eq!(app.history[4].result, Some(4.0));
+    let names: Vec<&str> = app.functions.iter().map(|f| f.name.as_str()).collect();
+    assert_eq!(names, ["f", "g"]);
+
+    app.input = "f(x) = g(x)".to_string();
+    app.submit_message();
+    assert_eq!(
+        app.history[5].error.as_deref(),
+        Some("Circular definition: f → g → f")
+    );
+    assert_eq!(app.functions[0].body, "3x");
+}
+
+#[test]
+fn dedup_moves_resubmitted_inputs_to_the_top() {
+    let _home = isolated_home();
+    let mut app = App::new();
+    for input in [":dedup on", "a = 2", "a*3", "a = 5", "a*3"] {
+        app.input = input.to_string();
+        app.submit_message();
+    }
+    let entries: Vec<(&str, Option<f64>)> = app
+        .history
+        .iter()
+        .map(|entry| (entry.expression.as_str(), entry.result))
+        .collect();
+    assert_eq!(
+        entries,
+        [
+            ("a = 2", Some(2.0)),
+            ("a = 5", Some(5.0)),
+            ("a*3", Some(15.0))
+        ]
+    );
+    assert!(app.settings.dedup_history);
+}
+
+#[test]
+fn rpn_mode_applies_input_to_the_stack() {
+    let _home = isolated_home();
+    let mut app = App::new();
+    for input in [":rpn on", "5 3", "+", "2 *"] {
+        app.input = input.to_string();
+        app.submit_message();
+    }
+    assert_eq!(app.rpn_stack.values(), [16.0]);
+    let last = app.history.last().unwrap();
+    assert_eq!((last.expression.as_str(), last.result), ("2 *", Some(16.0)));
+
+    app.input = ":rpn off".to_string();
+    app.submit_message();
+    app.input = "5+3".to_string();
+    app.submit_message();
+    assert_eq!(app.history.last().unwrap().result, Some(8.0));
+}
+
+#[test]
+fn stats_command_opens_summary() {
+    let _home = isolated_home();
+    let mut app = App::new();
+    app.input = ":stats".to_string();
+    app.submit_message();
+    assert_eq!(app.overlay, Some(Overlay::Stats));
+    app.handle_key_event(key_event(KeyCode::Esc));
+    assert_eq!(app.overlay, None);
+}
+
+#[test]
+fn tape_mode_keeps_a_running_total() {
+    let _home = isolated_home();
+    let mut app = App::new();
+    for input in [":tape on", "12.5", "+ 7.5", "-5", "*2", "/0"] {
+        app.input = input.to_string();
+        app.submit_message();
+    }
+    assert_eq!(app.tape_total, Some(30.0));
+    let tape: Vec<(&str, Option<f64>)> = app
+        .history
+        .iter()
+        .filter(|entry| entry.kind == HistoryKind::Tape)
+        .map(|entry| (entry.expression.as_str(), entry.result))
+        .collect();
+    assert_eq!(
+        tape,
+        [
+            ("+ 12.5", Some(12.5)),
+            ("+ 7.5", Some(20.0)),
+            ("- 5", Some(15.0)),
+            ("* 2", Some(30.0))
+        ]
+    );
+    assert!(app.history.last().unwrap().error.is_some());
+    assert_eq!(app.to_state().tape_total, Some(30.0));
+
+    app.input = ":tape off".to_string();
+    app.submit_message();
+    assert_eq!(app.tape_total, None);
+}
+
+#[test]
+fn imported_csv_column_can_be_summarized_and_plotted() {
+    let _home = isolated_home();
+    let path = std::env::temp_dir().join(format!("rcalc-import-{}.csv", std::process::id()));
+    std::fs::write(&path, "day,price\n1,10\n2,12.5\n3,8\n").unwrap();
+    let mut app = App::new();
+    app.input = format!(":import csv {} column=2 as prices", path.display());
+    app.submit_message();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(app.lists["prices"], [10.0, 12.5, 8.0]);
+    assert_eq!(app.to_state().lists["prices"], [10.0, 12.5, 8.0]);
+
+    app.input = ":stats prices".to_string();
+    app.submit_message();
+    assert_eq!(app.overlay, Some(Overlay::Stats));
+    app.overlay = None;
+
+    app.input = ":plot list prices".to_string();
+    app.submit_message();
+    assert_eq!(
+        app.plot_data,
+        Some(vec![(1.0, 10.0), (2.0, 12.5), (3.0, 8.0)])
+    );
+
+    app.input = ":stats costs".to_string();
+    app.submit_message();
+    assert_eq!(
+        app.history.last().unwrap().error.as_deref(),
+        Some("No list named costs")
+    );
+}
+
+#[test]
+fn render_app_draws_a_frame_without_a_terminal() {
+    let _home = isolated_home();
+    let mut app = App::new();
+    app.input = "x = 6*7".to_string();
+    app.submit_message();
+
+    let area = Rect::new(0, 0, 80, 24);
+    app.update(Msg::Resize(area.width, area.height));
+    let buffer = render_app(&app, area);
+    assert_eq!(buffer.area, area);
+    let rows: Vec<String> = (0..area.height)
+        .map(|y| (0..area.width).map(|x| buffer[(x, y)].symbol()).collect())
+        .collect();
+    assert!(rows.iter().any(|row| row.contains("x = 6*7")));
+    assert!(rows.iter().any(|row| row.contains("42")));
+    assert!(rows.last().unwrap().contains("INSERT"));
+    assert!(rows.iter().any(|row| row.contains("Variables")));
+
+    // Side by side, Variables shows `x`; in a narrow terminal only History does.
+    let narrow = Rect::new(0, 0, 50, 24);
+    app.update(Msg::Resize(narrow.width, narrow.height));
+    let buffer = render_app(&app, narrow);
+    let rows: Vec<String> = (0..narrow.height)
+        .map(|y| (0..narrow.width).map(|x| buffer[(x, y)].symbol()).collect())
+        .collect();
+    assert!(app.compact);
+    assert!(rows.iter().any(|row| row.contains("x = 6*7")));
+    assert!(!rows.iter().any(|row| row.contains("Variables")));
+}
+
+#[test]
+fn resize_messages_fit_the_layout_to_the_terminal() {
+    let _home = isolated_home();
+    let mut app = App::new();
+    app.update(Msg::Submit("2x".to_string()));
+    for n in 0..40 {
+        app.update(Msg::Submit(format!("{n}+1")));
+    }
+    app.update(Msg::Resize(120, 40));
+    assert!(!app.compact);
+    app.focus = Focus::Plot;
+
+    app.update(Msg::Resize(50, 40));
+    assert!(app.compact);
+    assert_eq!(app.focus, Focus::History);
+
+    // History fills the 36 rows under the input, 34 of them inside its border.
+    app.update(Msg::Key(key_event(KeyCode::Char('g'))));
+    app.update(Msg::Key(key_event(KeyCode::Char('g'))));
+    app.update(Msg::Key(KeyEvent::new(
+        KeyCode::Char('d'),
+        KeyModifiers::CONTROL,
+    )));
+    assert_eq!(app.history_state.selected(), Some(17));
+}
+
+#[test]
+fn scripted_events_drive_the_app_until_they_run_out() {
+    let _home = isolated_home();
+    let mut app = App::new();
+    app.session = format!("test-scripted-{}", std::process::id());
+    let mut terminal = Terminal::new(TestBackend::new(80, 24)).unwrap();
+    let mut events = ScriptedEvents::new([
+        AppEvent::Paste("6*7".to_string()),
+        AppEvent::Key(key_event(KeyCode::Enter)),
+        AppEvent::Resize(100, 30),
+        AppEvent::Tick,
+        AppEvent::EvalFinished,
+    ]);
+    app.run_with(&mut terminal, &mut events).unwrap();
+
+    assert_eq!(app.history.last().unwrap().result, Some(42.0));
+    assert!(!app.unsaved);
+    let _ = std::fs::remove_file(session_state_path(&app.session).unwrap());
+}
+
+#[test]
+fn messages_replay_a_session() {
+    let _home = isolated_home();
+    let messages = vec![
+        Msg::Submit("a = 2".to_string()),
+        Msg::Submit("a*21".to_string()),
+        Msg::Command(Command::Precision(Some(1))),
+        Msg::Action(Action::FocusHistory),
+        Msg::Tick(Instant::now() + Duration::from_secs(10)),
+    ];
+    let mut app = App::new();
+    // The tick is late enough for an autosave.
+    app.session = format!("test-replay-{}", std::process::id());
+    for msg in messages {
+        assert!(!app.update(msg));
+    }
+    assert_eq!(app.history.last().unwrap().result, Some(42.0));
+    assert_eq!(app.settings.precision, Some(1));
+    assert_eq!(app.focus, Focus::History);
+    assert!(app.toast.is_none());
+    assert!(!app.unsaved);
+
+    assert!(app.update(Msg::Command(Command::Quit)));
+    let _ = std::fs::remove_file(session_state_path(&app.session).unwrap());
+}
@@ -0,0 +1,64 @@
+use std::collections::HashMap;
+
+use rustic_calc::{
+    error::CalcError,
+    types::VariableEntry,
+    validate::{Diagnostic, validate},
+};
+
+#[test]
+fn validate_reports_problems_with_positions() {
+    let mut variables = HashMap::new();
+    variables.insert(
+        "a".to_string(),
+        VariableEntry {
+            expression: "2".to_string(),
+            value: 2.0,
+            format: None,
+            unit: None,
+        },
+    );
+    assert!(validate("y = 2a + sin(a)", &variables).is_empty());
+
+    assert_eq!(
+        validate("(a + b", &variables),
+        vec![
+            Diagnostic {
+                error: CalcError::UnclosedParen(0),
+                span: Some(0..1),
+            },
+            Diagnostic {
+                error: CalcError::UnknownVariable("b".to_string()),
+                span: Some(5..6),
+            },
+        ]
+    );
+    assert_eq!(
+        validate("a)", &variables),
+        vec![Diagnostic {
+            error: CalcError::UnmatchedParen(1),
+            span: Some(1..2),
+        }]
+    );
+    assert_eq!(
+        validate("a *", &variables),
+        vec![Diagnostic {
+            error: CalcError::MissingRightOperand {
+                operator: "*".to_string(),
+                position: 2,
+            },
+            span: Some(2..3),
+        }]
+    );
+}
+
+#[test]
+fn validate_reports_invalid_assignment_target() {
+    assert_eq!(
+        validate("2 = 5", &HashMap::new()),
+        vec![Diagnostic {
+            error: CalcError::InvalidVariableName("2".to_string()),
+            span: Some(0..1),
+        }]
+    );
+}
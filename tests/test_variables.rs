@@ -1,4 +1,4 @@
-use rustic_calc::variables::parse_variables;
+use rustic_calc::{error::CalcError, tokenize::tokenize, variables::parse_variables};
 
 #[test]
 fn test_parse_variables() {
@@ -13,3 +13,36 @@ fn test_parse_variables_formula() {
     assert_eq!(res.var_name, "x".to_string());
     assert_eq!(res.tokens, vec!["2", "+", "3"]);
 }
+
+#[test]
+fn parse_variables_rejects_invalid_names() {
+    let err = parse_variables(tokenize("2=5")).unwrap_err();
+    assert_eq!(err, CalcError::InvalidVariableName("2".to_string()));
+    assert_eq!(err.to_string(), "'2' is not a valid variable name");
+
+    let err = parse_variables(tokenize("+=3")).unwrap_err();
+    assert_eq!(err, CalcError::InvalidVariableName("+".to_string()));
+
+    let err = parse_variables(tokenize("ab=3")).unwrap_err();
+    assert_eq!(err, CalcError::InvalidAssignmentTarget("a*b".to_string()));
+}
+
+#[test]
+fn parse_variables_recognizes_function_definitions() {
+    let err = parse_variables(tokenize("f(x)=x^2+1")).unwrap_err();
+    assert_eq!(err, CalcError::FunctionDefinition("f".to_string()));
+}
+
+#[test]
+fn parse_variables_rejects_builtin_names() {
+    let err = parse_variables(tokenize("pi=3")).unwrap_err();
+    assert_eq!(err, CalcError::ReservedName("pi".to_string()));
+    assert_eq!(
+        err.to_string(),
+        "'pi' is a built-in name and cannot be redefined"
+    );
+    assert_eq!(
+        parse_variables(tokenize("sqrt=2")).unwrap_err(),
+        CalcError::ReservedName("sqrt".to_string())
+    );
+}
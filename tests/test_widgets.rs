@@ -0,0 +1,348 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    widgets::{ListState, StatefulWidget, Widget},
+};
+use rustic_calc::{
+    dates,
+    input_editor::InputEditMode,
+    theme::Theme,
+    types::{AngleMode, Focus, History, HistoryKind, PlotStyle, Settings},
+    widgets::{
+        detail_popup::render_detail_popup,
+        history_block::{render_history_block, visible_window},
+        input_area::cursor_column,
+        list_scrollbar::list_scrollbar_state,
+        plot_block::{PlotOptions, axis_ticks, render_plot},
+        status_bar::render_status_bar,
+    },
+};
+use time::UtcOffset;
+
+fn render_line(widget: impl Widget, width: u16) -> String {
+    let area = Rect::new(0, 0, width, 1);
+    let mut buf = Buffer::empty(area);
+    widget.render(area, &mut buf);
+    (0..width).map(|x| buf[(x, 0)].symbol()).collect()
+}
+
+#[test]
+fn status_bar_shows_mode_settings_session_and_unsaved_marker() {
+    let settings = Settings {
+        angle_mode: AngleMode::Degrees,
+        precision: Some(3),
+        ..Default::default()
+    };
+
+    let line = render_line(
+        render_status_bar(
+            Focus::Input,
+            InputEditMode::Normal,
+            &settings,
+            "work",
+            true,
+            false,
+            &Theme::default(),
+        ),
+        60,
+    );
+
+    assert!(line.contains("NORMAL"));
+    assert!(line.contains("DEG"));
+    assert!(line.contains("-2^2=-4 ^ right"));
+    assert!(line.contains("3 dp"));
+    assert!(line.contains("work"));
+    assert!(line.contains("●"));
+}
+
+#[test]
+fn status_bar_shows_computing_indicator() {
+    let line = render_line(
+        render_status_bar(
+            Focus::Input,
+            InputEditMode::Insert,
+            &Settings::default(),
+            "default",
+            false,
+            true,
+            &Theme::default(),
+        ),
+        80,
+    );
+
+    assert!(line.contains("computing… (Esc to cancel)"));
+}
+
+#[test]
+fn axis_ticks_use_nice_steps_and_si_prefixes() {
+    assert_eq!(
+        axis_ticks(-9.3, 41.0),
+        (
+            [-20.0, 60.0],
+            vec!["-20", "0", "20", "40", "60"]
+                .into_iter()
+                .map(String::from)
+                .collect()
+        )
+    );
+
+    let (_, labels) = axis_ticks(0.0, 2_500_000.0);
+    assert_eq!(labels, vec!["0M", "1M", "2M", "3M"]);
+
+    let (_, labels) = axis_ticks(0.0, 0.003);
+    assert_eq!(labels, vec!["0m", "1m", "2m", "3m"]);
+
+    let (_, labels) = axis_ticks(0.0, 0.5);
+    assert_eq!(labels, vec!["0.0", "0.1", "0.2", "0.3", "0.4", "0.5"]);
+
+    let (_, labels) = axis_ticks(0.0, 3e20);
+    assert_eq!(labels, vec!["0", "1e20", "2e20", "3e20"]);
+}
+
+#[test]
+fn history_shows_day_headers_for_timestamped_entries() {
+    let entry = |expression: &str, timestamp: Option<i64>| History {
+        expression: expression.to_string(),
+        result: Some(1.0),
+        error: None,
+        note: None,
+        variables: Default::default(),
+        kind: HistoryKind::Expression,
+        timestamp,
+        duration_ms: None,
+    };
+    let now = dates::now();
+    let history = vec![
+        entry("old", None),
+        entry("yesterday", Some(now - 86_400)),
+        entry("today_a", Some(now)),
+        entry("today_b", Some(now)),
+    ];
+
+    let area = Rect::new(0, 0, 40, 10);
+    let mut buf = Buffer::empty(area);
+    Widget::render(
+        render_history_block(
+            &history,
+            &Default::default(),
+            0..history.len(),
+            Focus::Input,
+            None,
+            UtcOffset::UTC,
+            &Theme::default(),
+        ),
+        area,
+        &mut buf,
+    );
+    let rows: Vec<String> = (0..area.height)
+        .map(|y| (0..area.width).map(|x| buf[(x, y)].symbol()).collect())
+        .collect();
+
+    let row_of = |text: &str| rows.iter().position(|row| row.contains(text)).unwrap();
+    assert_eq!(rows.iter().filter(|row| row.contains("Today")).count(), 1);
+    assert!(row_of("Today") < row_of("today_b"));
+    assert!(row_of("today_a") < row_of("Yesterday"));
+    assert!(row_of("Yesterday") < row_of("yesterday"));
+    assert_eq!(row_of("old"), row_of("yesterday") + 1);
+}
+
+#[test]
+fn plain_history_uses_text_markers_instead_of_borders_and_color() {
+    let entry = |expression: &str, error: Option<&str>| History {
+        expression: expression.to_string(),
+        result: error.is_none().then_some(1.0),
+        error: error.map(String::from),
+        note: None,
+        variables: Default::default(),
+        kind: HistoryKind::Expression,
+        timestamp: None,
+        duration_ms: None,
+    };
+    let history = vec![entry("1", None), entry("2/", Some("Unexpected end"))];
+
+    let area = Rect::new(0, 0, 50, 5);
+    let mut buf = Buffer::empty(area);
+    StatefulWidget::render(
+        render_history_block(
+            &history,
+            &Default::default(),
+            0..history.len(),
+            Focus::History,
+            None,
+            UtcOffset::UTC,
+            &Theme::default().with_plain(true),
+        ),
+        area,
+        &mut buf,
+        &mut ListState::default().with_selected(Some(0)),
+    );
+    let rows: Vec<String> = (0..area.height)
+        .map(|y| (0..area.width).map(|x| buf[(x, y)].symbol()).collect())
+        .collect();
+
+    assert!(rows[0].contains("[FOCUSED] History"));
+    assert!(rows[1].contains("[SELECTED] 2 [ERROR]"));
+    assert!(rows.iter().all(|row| row.is_ascii()));
+}
+
+#[test]
+fn history_marks_results_and_errors_with_symbols() {
+    let entry = |expression: &str, error: Option<&str>| History {
+        expression: expression.to_string(),
+        result: error.is_none().then_some(1.0),
+        error: error.map(String::from),
+        note: None,
+        variables: Default::default(),
+        kind: HistoryKind::Expression,
+        timestamp: None,
+        duration_ms: None,
+    };
+    let history = vec![entry("1", None), entry("2/", Some("Unexpected end"))];
+
+    let area = Rect::new(0, 0, 50, 4);
+    let mut buf = Buffer::empty(area);
+    Widget::render(
+        render_history_block(
+            &history,
+            &Default::default(),
+            0..history.len(),
+            Focus::Input,
+            None,
+            UtcOffset::UTC,
+            &Theme::DEUTERANOPIA,
+        ),
+        area,
+        &mut buf,
+    );
+    let rows: Vec<String> = (0..area.height)
+        .map(|y| (0..area.width).map(|x| buf[(x, y)].symbol()).collect())
+        .collect();
+
+    assert!(rows[1].contains("2 ✗ "));
+    assert!(rows[2].contains("1 ✓ 1 = 1"));
+}
+
+#[test]
+fn plot_marks_undrawable_values_with_symbols() {
+    let data = [(0.0, f64::NAN), (1.0, f64::INFINITY)];
+    let title = |theme: &Theme| {
+        let options = PlotOptions {
+            style: PlotStyle::Line,
+            cursor: Some(0),
+            aspect: None,
+        };
+        let chart = render_plot(&data, "1/0".to_string(), options, Focus::Plot, None, theme);
+        render_line(chart, 80)
+    };
+
+    let colored = title(&Theme::PROTANOPIA);
+    assert!(colored.contains("f(x) = ✗ NaN"), "{colored}");
+    assert!(colored.contains("✗ no finite points"), "{colored}");
+    let plain = title(&Theme::default().with_plain(true));
+    assert!(plain.contains("[ERROR] no finite points"), "{plain}");
+}
+
+#[test]
+fn history_shows_duration_of_slow_evaluations() {
+    let entry = |expression: &str, duration_ms: Option<u64>| History {
+        expression: expression.to_string(),
+        result: Some(1.0),
+        error: None,
+        note: None,
+        variables: Default::default(),
+        kind: HistoryKind::Expression,
+        timestamp: None,
+        duration_ms,
+    };
+    let history = vec![entry("quick", None), entry("slow", Some(1_340))];
+
+    let area = Rect::new(0, 0, 40, 4);
+    let mut buf = Buffer::empty(area);
+    Widget::render(
+        render_history_block(
+            &history,
+            &Default::default(),
+            0..history.len(),
+            Focus::Input,
+            None,
+            UtcOffset::UTC,
+            &Theme::default(),
+        ),
+        area,
+        &mut buf,
+    );
+    let rows: Vec<String> = (0..area.height)
+        .map(|y| (0..area.width).map(|x| buf[(x, y)].symbol()).collect())
+        .collect();
+
+    assert!(rows[1].contains("slow = 1  in 1.3 s"));
+    assert!(!rows[2].contains(" in "));
+}
+
+#[test]
+fn history_window_follows_selection() {
+    let rows = |_| 1;
+    assert_eq!(visible_window(10_000, None, 0, 20, rows), 0..20);
+    assert_eq!(visible_window(10_000, Some(25), 0, 20, rows), 6..26);
+    assert_eq!(visible_window(10_000, Some(3), 6, 20, rows), 3..23);
+    assert_eq!(visible_window(5, Some(4), 9, 20, rows), 0..5);
+    assert_eq!(visible_window(0, None, 0, 20, rows), 0..0);
+}
+
+#[test]
+fn history_window_counts_day_headers_as_rows() {
+    // Every fifth item starts a day, taking a header row as well.
+    let rows = |i: usize| if i.is_multiple_of(5) { 2 } else { 1 };
+    assert_eq!(visible_window(100, None, 0, 12, rows), 0..10);
+    assert_eq!(visible_window(100, Some(14), 0, 12, rows), 5..15);
+    assert_eq!(visible_window(100, None, 95, 12, rows), 90..100);
+}
+
+#[test]
+fn cursor_column_counts_wide_characters_twice() {
+    assert_eq!(cursor_column("1+2", 2), 2);
+    assert_eq!(cursor_column("税=5", 1), 2);
+    assert_eq!(cursor_column("a🦀b", 3), 4);
+    assert_eq!(cursor_column("e\u{301}x", 1), 1);
+}
+
+#[test]
+fn list_scrollbar_only_for_lists_longer_than_the_pane() {
+    assert!(list_scrollbar_state(5, 0, 10).is_none());
+    assert!(list_scrollbar_state(10, 0, 10).is_none());
+    assert!(list_scrollbar_state(50, 40, 10).is_some());
+}
+
+#[test]
+fn detail_popup_explains_failed_entries() {
+    let entry = History {
+        expression: "x=a+b".to_string(),
+        result: None,
+        error: Some("Unknown variables: a, b".to_string()),
+        note: None,
+        variables: Default::default(),
+        kind: HistoryKind::Expression,
+        timestamp: None,
+        duration_ms: None,
+    };
+
+    let area = Rect::new(0, 0, 60, 8);
+    let mut buf = Buffer::empty(area);
+    Widget::render(
+        render_detail_popup(&entry, 4, &Default::default(), None, &Theme::default()),
+        area,
+        &mut buf,
+    );
+    let text: String = (0..area.height)
+        .map(|y| {
+            (0..area.width)
+                .map(|x| buf[(x, y)].symbol())
+                .collect::<String>()
+                + "\n"
+        })
+        .collect();
+
+    assert!(text.contains("Error #4"));
+    assert!(text.contains("x · = · a · + · b"));
+    assert!(text.contains("Unknown     a, b"));
+}